@@ -15,7 +15,7 @@ pub fn parse_out_packet_derive(input: TokenStream) -> TokenStream {
     packet_out::expand_parse_out_packet_derive(input)
 }
 
-#[proc_macro_derive(PacketReport, attributes(protocol_id))]
+#[proc_macro_derive(PacketReport, attributes(protocol_id, packet_report))]
 pub fn packet_report_derive(input: TokenStream) -> TokenStream {
     packet_reports::packet_report_derive(input)
 }
@@ -2,7 +2,7 @@ use proc_macro::TokenStream;
 use protocol_version::protocol_version::ProtocolVersion;
 use quote::{format_ident, quote};
 use serde::Deserialize;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::PathBuf;
 use std::str::FromStr;
@@ -33,16 +33,49 @@ struct PacketVariantInfo {
     name: String,
 }
 
+/// Directions of the packets decoded and encoded by the registry.
+struct Perspective {
+    incoming: &'static str,
+    outgoing: &'static str,
+}
+
+impl Perspective {
+    /// The server decodes serverbound packets, unless the enum is marked with `#[packet_report(client)]`.
+    fn from_attributes(attrs: &[syn::Attribute]) -> Self {
+        let is_client = attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("packet_report"))
+            .any(|attr| {
+                attr.parse_args::<Ident>()
+                    .expect("Failed to parse #[packet_report] attribute")
+                    == "client"
+            });
+        if is_client {
+            Self {
+                incoming: "clientbound",
+                outgoing: "serverbound",
+            }
+        } else {
+            Self {
+                incoming: "serverbound",
+                outgoing: "clientbound",
+            }
+        }
+    }
+}
+
 pub fn packet_report_derive(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     let enum_ident = &input.ident;
 
+    let perspective = Perspective::from_attributes(&input.attrs);
     let variants = parse_enum_variants(&input.data);
     let protocol_data = load_all_protocol_data();
 
-    let decode_impl = generate_decode_impl(&variants, &protocol_data);
-    let encode_impl = generate_encode_impl(enum_ident, &variants, &protocol_data);
+    let decode_impl = generate_decode_impl(&variants, &protocol_data, &perspective);
+    let encode_impl = generate_encode_impl(enum_ident, &variants, &protocol_data, &perspective);
     let name_impl = generate_packet_name_impl(&variants);
+    let outgoing_name_impl = generate_outgoing_name_impl(&variants, &protocol_data, &perspective);
 
     let expanded = quote! {
         #[derive(Debug, thiserror::Error)]
@@ -71,6 +104,7 @@ pub fn packet_report_derive(input: TokenStream) -> TokenStream {
             #decode_impl
             #encode_impl
             #name_impl
+            #outgoing_name_impl
         }
     };
 
@@ -205,18 +239,19 @@ fn load_all_protocol_data() -> HashMap<String, RawPacketData> {
 fn generate_decode_impl(
     variants: &[PacketVariantInfo],
     protocol_data: &HashMap<String, RawPacketData>,
+    perspective: &Perspective,
 ) -> proc_macro2::TokenStream {
     let report_arms = protocol_data.iter().map(|(version_name, report)| {
         let version_number = ProtocolVersion::from_str(version_name).expect("Failed to parse version name").version_number();
         let state_arms = variants
             .iter()
-            .filter(|v| v.bound == "serverbound")
+            .filter(|v| v.bound == perspective.incoming)
             .filter_map(|variant_info| {
                 let state_str = &variant_info.state;
                 let packet_name = &variant_info.name;
 
                 let packet_info = report.0.get(state_str)
-                    .and_then(|directions| directions.get("serverbound"))
+                    .and_then(|directions| directions.get(perspective.incoming))
                     .and_then(|packets| packets.get(packet_name));
 
                 if let Some(packet_info) = packet_info {
@@ -273,10 +308,11 @@ fn generate_encode_impl(
     enum_ident: &Ident,
     variants: &[PacketVariantInfo],
     protocol_data: &HashMap<String, RawPacketData>,
+    perspective: &Perspective,
 ) -> proc_macro2::TokenStream {
     let variant_arms = variants
         .iter()
-        .filter(|v| v.bound == "clientbound")
+        .filter(|v| v.bound == perspective.outgoing)
         .map(|variant_info| {
             let variant_ident = &variant_info.variant_ident;
             let packet_name = &variant_info.name;
@@ -289,7 +325,7 @@ fn generate_encode_impl(
                 let packet_info = report
                     .0
                     .get(state_str)
-                    .and_then(|directions| directions.get("clientbound"))
+                    .and_then(|directions| directions.get(perspective.outgoing))
                     .and_then(|packets| packets.get(packet_name));
 
                 if let Some(packet_info) = packet_info {
@@ -345,6 +381,63 @@ fn generate_packet_name_impl(variants: &[PacketVariantInfo]) -> proc_macro2::Tok
     }
 }
 
+/// Names the outgoing packets by their ID, as they are only ever encoded.
+fn generate_outgoing_name_impl(
+    variants: &[PacketVariantInfo],
+    protocol_data: &HashMap<String, RawPacketData>,
+    perspective: &Perspective,
+) -> proc_macro2::TokenStream {
+    let report_arms = protocol_data.iter().map(|(version_name, report)| {
+        let version_number = ProtocolVersion::from_str(version_name)
+            .expect("Failed to parse version name")
+            .version_number();
+        // Several variants may share the same packet
+        let mut seen = HashSet::new();
+        let state_arms = variants
+            .iter()
+            .filter(|v| v.bound == perspective.outgoing)
+            .filter_map(|variant_info| {
+                let state_str = &variant_info.state;
+                let packet_name = &variant_info.name;
+                let packet_info = report
+                    .0
+                    .get(state_str)
+                    .and_then(|directions| directions.get(perspective.outgoing))
+                    .and_then(|packets| packets.get(packet_name))?;
+
+                let id = packet_info.protocol_id;
+                if !seen.insert((state_str, id)) {
+                    return None;
+                }
+                let state_ident = format_ident!("{}", capitalize_first(state_str));
+                Some(quote! {
+                    (State::#state_ident, #id) => Some(#packet_name),
+                })
+            });
+
+        quote! {
+            #version_number => match (state, packet_id) {
+                #(#state_arms)*
+                _ => None,
+            },
+        }
+    });
+
+    let function_ident = format_ident!("{}_packet_name", perspective.outgoing);
+    quote! {
+        pub fn #function_ident(
+            protocol_version: ProtocolVersion,
+            state: State,
+            packet_id: u8,
+        ) -> Option<&'static str> {
+            match protocol_version.packets().version_number() {
+                #(#report_arms)*
+                _ => None,
+            }
+        }
+    }
+}
+
 fn capitalize_first(s: &str) -> String {
     let mut c = s.chars();
     match c.next() {
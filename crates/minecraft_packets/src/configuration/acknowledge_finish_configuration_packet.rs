@@ -1,4 +1,4 @@
 use minecraft_protocol::prelude::*;
 
-#[derive(PacketIn)]
+#[derive(PacketIn, PacketOut)]
 pub struct AcknowledgeConfigurationPacket {}
@@ -1,7 +1,7 @@
 use crate::configuration::data::known_pack::KnownPack;
 use minecraft_protocol::prelude::*;
 
-#[derive(PacketOut)]
+#[derive(PacketIn, PacketOut)]
 pub struct ClientBoundKnownPacksPacket {
     pub known_packs: LengthPaddedVec<KnownPack>,
 }
@@ -17,3 +17,42 @@ impl ClientBoundKnownPacksPacket {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_packs_encode() {
+        // Given
+        let packet = ClientBoundKnownPacksPacket::new(&["1.21.4"]);
+        let mut writer = BinaryWriter::new();
+
+        // When
+        packet
+            .encode(&mut writer, ProtocolVersion::V1_21_4)
+            .unwrap();
+
+        // Then
+        assert_eq!(
+            writer.into_inner(),
+            [&[1, 9][..], b"minecraft", &[4], b"core", &[6], b"1.21.4"].concat()
+        );
+    }
+
+    #[test]
+    fn test_known_packs_decode() {
+        // Given
+        let bytes = [&[1, 9][..], b"minecraft", &[4], b"core", &[6], b"1.21.4"].concat();
+        let mut reader = BinaryReader::new(&bytes);
+
+        // When
+        let packet =
+            ClientBoundKnownPacksPacket::decode(&mut reader, ProtocolVersion::V1_21_4).unwrap();
+
+        // Then
+        let known_packs = packet.known_packs.into_inner();
+        assert_eq!(known_packs.len(), 1);
+        assert!(known_packs[0].is_minecraft_core());
+    }
+}
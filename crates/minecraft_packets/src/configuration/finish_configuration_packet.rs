@@ -1,4 +1,4 @@
 use minecraft_protocol::prelude::*;
 
-#[derive(PacketOut)]
+#[derive(PacketIn, PacketOut)]
 pub struct FinishConfigurationPacket {}
@@ -1,7 +1,7 @@
 use crate::configuration::data::known_pack::KnownPack;
 use minecraft_protocol::prelude::*;
 
-#[derive(PacketIn)]
+#[derive(PacketIn, PacketOut)]
 pub struct ServerBoundKnownPacksPacket {
     known_packs: LengthPaddedVec<KnownPack>,
 }
@@ -20,3 +20,54 @@ impl ServerBoundKnownPacksPacket {
             .any(KnownPack::is_minecraft_core)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_packs_encode() {
+        // Given
+        let packet = ServerBoundKnownPacksPacket::new(vec![KnownPack::new("1.21.4")]);
+        let mut writer = BinaryWriter::new();
+
+        // When
+        packet
+            .encode(&mut writer, ProtocolVersion::V1_21_4)
+            .unwrap();
+
+        // Then
+        assert_eq!(
+            writer.into_inner(),
+            [&[1, 9][..], b"minecraft", &[4], b"core", &[6], b"1.21.4"].concat()
+        );
+    }
+
+    #[test]
+    fn test_known_packs_decode() {
+        // Given
+        let bytes = [&[1, 9][..], b"minecraft", &[4], b"core", &[6], b"1.21.4"].concat();
+        let mut reader = BinaryReader::new(&bytes);
+
+        // When
+        let packet =
+            ServerBoundKnownPacksPacket::decode(&mut reader, ProtocolVersion::V1_21_4).unwrap();
+
+        // Then
+        assert!(packet.has_minecraft_core());
+    }
+
+    #[test]
+    fn test_empty_known_packs_decode() {
+        // Given
+        let bytes = [0];
+        let mut reader = BinaryReader::new(&bytes);
+
+        // When
+        let packet =
+            ServerBoundKnownPacksPacket::decode(&mut reader, ProtocolVersion::V1_21_4).unwrap();
+
+        // Then
+        assert!(!packet.has_minecraft_core());
+    }
+}
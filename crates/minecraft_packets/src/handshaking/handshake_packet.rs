@@ -1,6 +1,6 @@
 use minecraft_protocol::prelude::*;
 
-#[derive(Clone, PacketIn)]
+#[derive(Clone, PacketIn, PacketOut)]
 pub struct HandshakePacket {
     pub protocol: VarInt,
     pub hostname: String,
@@ -23,7 +23,9 @@ impl HandshakePacket {
 #[cfg(test)]
 mod tests {
     use crate::handshaking::handshake_packet::HandshakePacket;
-    use minecraft_protocol::prelude::{BinaryReader, DecodePacket, ProtocolVersion, VarInt};
+    use minecraft_protocol::prelude::{
+        BinaryReader, BinaryWriter, DecodePacket, EncodePacket, ProtocolVersion, VarInt,
+    };
 
     #[test]
     fn test_handshake_packet_decode() {
@@ -44,4 +46,19 @@ mod tests {
         assert_eq!(expected_port, packet.port);
         assert_eq!(expected_next_state, packet.next_state);
     }
+
+    #[test]
+    fn test_handshake_packet_encode() {
+        let packet = HandshakePacket::localhost(769, 1);
+        let mut writer = BinaryWriter::new();
+        packet
+            .encode(&mut writer, ProtocolVersion::V1_21_4)
+            .unwrap();
+        assert_eq!(
+            writer.into_inner(),
+            vec![
+                129, 6, 9, 108, 111, 99, 97, 108, 104, 111, 115, 116, 99, 221, 1
+            ]
+        );
+    }
 }
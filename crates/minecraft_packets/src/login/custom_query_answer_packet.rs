@@ -1,9 +1,75 @@
 use minecraft_protocol::prelude::*;
 
 /// This packet is currently only used to communicate with the Velocity proxy.
-#[derive(PacketIn)]
+#[derive(PacketIn, PacketOut)]
 pub struct CustomQueryAnswerPacket {
     pub message_id: VarInt,
     pub is_present: bool,
     pub data: Vec<u8>,
 }
+
+impl CustomQueryAnswerPacket {
+    /// Answer of a client that does not understand the query.
+    pub fn not_understood(message_id: VarInt) -> Self {
+        Self {
+            message_id,
+            is_present: false,
+            data: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_not_understood_encode() {
+        // Given
+        let packet = CustomQueryAnswerPacket::not_understood(VarInt::new(7));
+        let mut writer = BinaryWriter::new();
+
+        // When
+        packet
+            .encode(&mut writer, ProtocolVersion::V1_21_4)
+            .unwrap();
+
+        // Then
+        assert_eq!(writer.into_inner(), vec![7, 0]);
+    }
+
+    #[test]
+    fn test_custom_query_answer_encode() {
+        // Given
+        let packet = CustomQueryAnswerPacket {
+            message_id: VarInt::new(7),
+            is_present: true,
+            data: vec![1, 2, 3],
+        };
+        let mut writer = BinaryWriter::new();
+
+        // When
+        packet
+            .encode(&mut writer, ProtocolVersion::V1_21_4)
+            .unwrap();
+
+        // Then
+        assert_eq!(writer.into_inner(), vec![7, 1, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_custom_query_answer_decode() {
+        // Given
+        let bytes = [7, 1, 1, 2, 3];
+        let mut reader = BinaryReader::new(&bytes);
+
+        // When
+        let packet =
+            CustomQueryAnswerPacket::decode(&mut reader, ProtocolVersion::V1_21_4).unwrap();
+
+        // Then
+        assert_eq!(packet.message_id, VarInt::new(7));
+        assert!(packet.is_present);
+        assert_eq!(packet.data, vec![1, 2, 3]);
+    }
+}
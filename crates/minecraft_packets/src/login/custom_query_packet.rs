@@ -1,7 +1,7 @@
 use minecraft_protocol::prelude::*;
 
 /// This packet is currently only used to communicate with the Velocity proxy.
-#[derive(PacketOut)]
+#[derive(PacketIn, PacketOut)]
 pub struct CustomQueryPacket {
     pub message_id: VarInt,
     pub channel: Identifier,
@@ -9,11 +9,50 @@ pub struct CustomQueryPacket {
 }
 
 impl CustomQueryPacket {
-    pub fn velocity_info_channel(message_id: i32) -> Self {
+    /// Requests the player information, forwarded with at most the given version.
+    pub fn velocity_info_channel(message_id: i32, forwarding_version: u8) -> Self {
         Self {
             message_id: VarInt::new(message_id),
             channel: Identifier::new_unchecked("velocity", "player_info"),
-            data: Vec::new(),
+            data: vec![forwarding_version],
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_velocity_info_channel_encode() {
+        // Given
+        let packet = CustomQueryPacket::velocity_info_channel(7, 4);
+        let mut writer = BinaryWriter::new();
+
+        // When
+        packet
+            .encode(&mut writer, ProtocolVersion::V1_21_4)
+            .unwrap();
+
+        // Then
+        assert_eq!(
+            writer.into_inner(),
+            [&[7, 20][..], b"velocity:player_info", &[4]].concat()
+        );
+    }
+
+    #[test]
+    fn test_velocity_info_channel_decode() {
+        // Given
+        let bytes = [&[7, 20][..], b"velocity:player_info", &[4]].concat();
+        let mut reader = BinaryReader::new(&bytes);
+
+        // When
+        let packet = CustomQueryPacket::decode(&mut reader, ProtocolVersion::V1_21_4).unwrap();
+
+        // Then
+        assert_eq!(packet.message_id, VarInt::new(7));
+        assert_eq!(packet.channel.to_string(), "velocity:player_info");
+        assert_eq!(packet.data, vec![4]);
+    }
+}
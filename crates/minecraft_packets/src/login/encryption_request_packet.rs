@@ -0,0 +1,62 @@
+use minecraft_protocol::prelude::*;
+
+/// Sent by the server to start the encryption handshake when online mode is enabled.
+#[derive(PacketIn, PacketOut)]
+pub struct EncryptionRequestPacket {
+    /// Always empty since 1.7, but still part of the session server hash.
+    server_id: String,
+    #[pvn(..47)]
+    v1_7_public_key: ShortLengthPaddedVec<u8>,
+    #[pvn(..47)]
+    v1_7_verify_token: ShortLengthPaddedVec<u8>,
+    #[pvn(47..)]
+    public_key: LengthPaddedVec<u8>,
+    #[pvn(47..)]
+    verify_token: LengthPaddedVec<u8>,
+    #[pvn(766..)]
+    should_authenticate: bool,
+}
+
+impl EncryptionRequestPacket {
+    pub fn new(public_key: &[u8], verify_token: &[u8]) -> Self {
+        Self {
+            server_id: String::new(),
+            v1_7_public_key: ShortLengthPaddedVec::new(public_key.to_vec()),
+            v1_7_verify_token: ShortLengthPaddedVec::new(verify_token.to_vec()),
+            public_key: LengthPaddedVec::new(public_key.to_vec()),
+            verify_token: LengthPaddedVec::new(verify_token.to_vec()),
+            should_authenticate: true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encryption_request_v1_7_2() {
+        let packet = EncryptionRequestPacket::new(&[1, 2], &[3]);
+        let mut writer = BinaryWriter::new();
+        packet.encode(&mut writer, ProtocolVersion::V1_7_2).unwrap();
+        assert_eq!(writer.into_inner(), vec![0, 0, 2, 1, 2, 0, 1, 3]);
+    }
+
+    #[test]
+    fn test_encryption_request_v1_8() {
+        let packet = EncryptionRequestPacket::new(&[1, 2], &[3]);
+        let mut writer = BinaryWriter::new();
+        packet.encode(&mut writer, ProtocolVersion::V1_8).unwrap();
+        assert_eq!(writer.into_inner(), vec![0, 2, 1, 2, 1, 3]);
+    }
+
+    #[test]
+    fn test_encryption_request_v1_20_5() {
+        let packet = EncryptionRequestPacket::new(&[1, 2], &[3]);
+        let mut writer = BinaryWriter::new();
+        packet
+            .encode(&mut writer, ProtocolVersion::V1_20_5)
+            .unwrap();
+        assert_eq!(writer.into_inner(), vec![0, 2, 1, 2, 1, 3, 1]);
+    }
+}
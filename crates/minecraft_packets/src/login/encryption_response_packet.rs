@@ -0,0 +1,131 @@
+use minecraft_protocol::prelude::*;
+
+/// Sent by the client with the shared secret and the verify token, both
+/// encrypted with the public key from the encryption request.
+#[derive(PacketIn)]
+pub struct EncryptionResponsePacket {
+    #[pvn(..47)]
+    v1_7_shared_secret: ShortLengthPaddedVec<u8>,
+    #[pvn(..47)]
+    v1_7_verify_token: ShortLengthPaddedVec<u8>,
+    #[pvn(47..)]
+    shared_secret: LengthPaddedVec<u8>,
+    #[pvn(47..759)]
+    v1_8_verify_token: LengthPaddedVec<u8>,
+    #[pvn(759..761)]
+    v1_19_verify_token: VerifyTokenOrSignature,
+    #[pvn(761..)]
+    v1_19_3_verify_token: LengthPaddedVec<u8>,
+}
+
+/// Between 1.19 and 1.19.2, clients with a profile key sign the verify token
+/// instead of encrypting it.
+#[derive(Default)]
+enum VerifyTokenOrSignature {
+    #[default]
+    None,
+    VerifyToken(Vec<u8>),
+    Signature {
+        salt: i64,
+        signature: Vec<u8>,
+    },
+}
+
+impl DecodePacket for VerifyTokenOrSignature {
+    fn decode(
+        reader: &mut BinaryReader,
+        protocol_version: ProtocolVersion,
+    ) -> Result<Self, BinaryReaderError> {
+        if bool::decode(reader, protocol_version)? {
+            let verify_token = LengthPaddedVec::<u8>::decode(reader, protocol_version)?;
+            Ok(Self::VerifyToken(verify_token.into_inner()))
+        } else {
+            let salt = i64::decode(reader, protocol_version)?;
+            let signature = LengthPaddedVec::<u8>::decode(reader, protocol_version)?;
+            Ok(Self::Signature {
+                salt,
+                signature: signature.into_inner(),
+            })
+        }
+    }
+}
+
+impl EncryptionResponsePacket {
+    /// The shared secret, still encrypted with the server's public key.
+    pub fn shared_secret(&self) -> &[u8] {
+        non_empty_or(self.v1_7_shared_secret.inner(), self.shared_secret.inner())
+    }
+
+    /// The verify token, still encrypted with the server's public key.
+    ///
+    /// Returns `None` when a 1.19 client signed the token with its profile key
+    /// instead of encrypting it.
+    pub fn verify_token(&self) -> Option<&[u8]> {
+        match &self.v1_19_verify_token {
+            VerifyTokenOrSignature::VerifyToken(token) => Some(token),
+            VerifyTokenOrSignature::Signature { .. } => None,
+            VerifyTokenOrSignature::None => Some(non_empty_or(
+                self.v1_7_verify_token.inner(),
+                non_empty_or(
+                    self.v1_8_verify_token.inner(),
+                    self.v1_19_3_verify_token.inner(),
+                ),
+            )),
+        }
+    }
+
+    /// The salt and the signature of the verify token, made with the profile key of a 1.19 client.
+    pub fn salt_signature(&self) -> Option<(i64, &[u8])> {
+        match &self.v1_19_verify_token {
+            VerifyTokenOrSignature::Signature { salt, signature } => Some((*salt, signature)),
+            _ => None,
+        }
+    }
+}
+
+/// Only the fields matching the protocol version are decoded, the others stay empty.
+fn non_empty_or<'a>(value: &'a [u8], fallback: &'a [u8]) -> &'a [u8] {
+    if value.is_empty() { fallback } else { value }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encryption_response_v1_7_2() {
+        let bytes = [0, 2, 1, 2, 0, 1, 3];
+        let mut reader = BinaryReader::new(&bytes);
+        let packet =
+            EncryptionResponsePacket::decode(&mut reader, ProtocolVersion::V1_7_2).unwrap();
+        assert_eq!(packet.shared_secret(), &[1, 2]);
+        assert_eq!(packet.verify_token(), Some([3].as_slice()));
+    }
+
+    #[test]
+    fn test_encryption_response_v1_8() {
+        let bytes = [2, 1, 2, 1, 3];
+        let mut reader = BinaryReader::new(&bytes);
+        let packet = EncryptionResponsePacket::decode(&mut reader, ProtocolVersion::V1_8).unwrap();
+        assert_eq!(packet.shared_secret(), &[1, 2]);
+        assert_eq!(packet.verify_token(), Some([3].as_slice()));
+    }
+
+    #[test]
+    fn test_encryption_response_v1_19_with_verify_token() {
+        let bytes = [2, 1, 2, 1, 1, 3];
+        let mut reader = BinaryReader::new(&bytes);
+        let packet = EncryptionResponsePacket::decode(&mut reader, ProtocolVersion::V1_19).unwrap();
+        assert_eq!(packet.verify_token(), Some([3].as_slice()));
+    }
+
+    #[test]
+    fn test_encryption_response_v1_19_with_signature() {
+        let bytes = [2, 1, 2, 0, 0, 0, 0, 0, 0, 0, 0, 42, 1, 9];
+        let mut reader = BinaryReader::new(&bytes);
+        let packet = EncryptionResponsePacket::decode(&mut reader, ProtocolVersion::V1_19).unwrap();
+        assert_eq!(packet.shared_secret(), &[1, 2]);
+        assert_eq!(packet.verify_token(), None);
+        assert_eq!(packet.salt_signature(), Some((42, [9].as_slice())));
+    }
+}
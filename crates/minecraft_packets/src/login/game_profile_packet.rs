@@ -3,7 +3,7 @@ use minecraft_protocol::prelude::*;
 
 /// This is the equivalent of LoginSuccessPacket but for versions before 1.21.2.
 /// For versions prior to 1.20.2, this packet changes the state to Play.
-#[derive(PacketOut)]
+#[derive(PacketIn, PacketOut)]
 pub struct GameProfilePacket {
     uuid: UuidAsString,
     username: String,
@@ -22,4 +22,94 @@ impl GameProfilePacket {
             strict_error_handling: false,
         }
     }
+
+    pub fn uuid(&self) -> Uuid {
+        self.uuid.uuid()
+    }
+
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UUID: Uuid = Uuid::from_u128(0x069a_79f4_44e9_4726_a5be_fca9_0e38_aaf5);
+    const UUID_BYTES: [u8; 16] = [
+        0x06, 0x9A, 0x79, 0xF4, 0x44, 0xE9, 0x47, 0x26, 0xA5, 0xBE, 0xFC, 0xA9, 0x0E, 0x38, 0xAA,
+        0xF5,
+    ];
+
+    #[test]
+    fn test_game_profile_encode_v1_7_2() {
+        // Given
+        let packet = GameProfilePacket::new(UUID, "Notch");
+        let mut writer = BinaryWriter::new();
+
+        // When
+        packet.encode(&mut writer, ProtocolVersion::V1_7_2).unwrap();
+
+        // Then
+        let expected = [
+            &[32][..],
+            b"069a79f444e94726a5befca90e38aaf5",
+            &[5],
+            b"Notch",
+        ]
+        .concat();
+        assert_eq!(writer.into_inner(), expected);
+    }
+
+    #[test]
+    fn test_game_profile_encode_v1_7_6() {
+        // Given
+        let packet = GameProfilePacket::new(UUID, "Notch");
+        let mut writer = BinaryWriter::new();
+
+        // When
+        packet.encode(&mut writer, ProtocolVersion::V1_7_6).unwrap();
+
+        // Then
+        let expected = [
+            &[36][..],
+            b"069a79f4-44e9-4726-a5be-fca90e38aaf5",
+            &[5],
+            b"Notch",
+        ]
+        .concat();
+        assert_eq!(writer.into_inner(), expected);
+    }
+
+    #[test]
+    fn test_game_profile_encode_v1_20_5() {
+        // Given
+        let packet = GameProfilePacket::new(UUID, "Notch");
+        let mut writer = BinaryWriter::new();
+
+        // When
+        packet
+            .encode(&mut writer, ProtocolVersion::V1_20_5)
+            .unwrap();
+
+        // Then
+        let expected = [&UUID_BYTES[..], &[5], b"Notch", &[0, 0]].concat();
+        assert_eq!(writer.into_inner(), expected);
+    }
+
+    #[test]
+    fn test_game_profile_decode_v1_16() {
+        // Given
+        let bytes = [&UUID_BYTES[..], &[5], b"Notch"].concat();
+        let mut reader = BinaryReader::new(&bytes);
+
+        // When
+        let packet = GameProfilePacket::decode(&mut reader, ProtocolVersion::V1_16).unwrap();
+
+        // Then
+        assert_eq!(packet.uuid(), UUID);
+        assert_eq!(packet.username(), "Notch");
+        assert_eq!(reader.remaining(), 0);
+    }
 }
@@ -2,5 +2,5 @@ use minecraft_protocol::prelude::*;
 
 /// This packet was introduced in 1.20.2
 /// This packet changes the state to Configuration.
-#[derive(Default, PacketIn)]
+#[derive(Default, PacketIn, PacketOut)]
 pub struct LoginAcknowledgedPacket {}
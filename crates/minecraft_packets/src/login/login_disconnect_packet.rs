@@ -1,7 +1,7 @@
 use minecraft_protocol::prelude::*;
 use pico_text_component::prelude::Component;
 
-#[derive(PacketOut)]
+#[derive(PacketIn, PacketOut)]
 pub struct LoginDisconnectPacket {
     /// Reason as a JSON Text Component
     pub reason: String,
@@ -17,9 +17,49 @@ impl Default for LoginDisconnectPacket {
 
 impl LoginDisconnectPacket {
     pub fn text(text: impl Into<String>) -> LoginDisconnectPacket {
-        let component = Component::new(text);
+        Self::component(&Component::new(text))
+    }
+
+    pub fn component(component: &Component) -> LoginDisconnectPacket {
         Self {
             reason: component.to_json(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REASON: &[u8] = br#"{"text":"Bye"}"#;
+
+    #[test]
+    fn test_login_disconnect_encode() {
+        // Given
+        let packet = LoginDisconnectPacket {
+            reason: r#"{"text":"Bye"}"#.to_string(),
+        };
+        let mut writer = BinaryWriter::new();
+
+        // When
+        packet
+            .encode(&mut writer, ProtocolVersion::V1_21_4)
+            .unwrap();
+
+        // Then
+        assert_eq!(writer.into_inner(), [&[14][..], REASON].concat());
+    }
+
+    #[test]
+    fn test_login_disconnect_decode() {
+        // Given
+        let bytes = [&[14][..], REASON].concat();
+        let mut reader = BinaryReader::new(&bytes);
+
+        // When
+        let packet = LoginDisconnectPacket::decode(&mut reader, ProtocolVersion::V1_21_4).unwrap();
+
+        // Then
+        assert_eq!(packet.reason, r#"{"text":"Bye"}"#);
+    }
+}
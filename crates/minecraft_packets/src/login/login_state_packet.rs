@@ -1,10 +1,9 @@
 use minecraft_protocol::prelude::*;
 
-#[derive(Default, PacketIn)]
+#[derive(Default, PacketIn, PacketOut)]
 pub struct LoginStartPacket {
     pub name: String,
     #[pvn(759..761)]
-    #[allow(dead_code)]
     sig_data: Optional<SigData>,
     #[pvn(761..764)]
     v1_19_3_player_uuid: Optional<Uuid>, // Really??
@@ -13,6 +12,15 @@ pub struct LoginStartPacket {
 }
 
 impl LoginStartPacket {
+    pub fn new(name: impl ToString, uuid: Uuid) -> Self {
+        Self {
+            name: name.to_string(),
+            sig_data: Optional::None,
+            v1_19_3_player_uuid: Optional::Some(uuid),
+            v1_20_2_player_uuid: uuid,
+        }
+    }
+
     pub fn name(&self) -> String {
         self.name.clone()
     }
@@ -20,15 +28,106 @@ impl LoginStartPacket {
     pub fn uuid(&self) -> Uuid {
         self.v1_19_3_player_uuid.unwrap_or(self.v1_20_2_player_uuid)
     }
+
+    /// The X.509 encoded profile key sent by 1.19 to 1.19.2 clients, used to sign the verify token.
+    pub fn profile_public_key(&self) -> Option<&[u8]> {
+        match &self.sig_data {
+            Optional::Some(sig_data) => Some(sig_data.public_key.inner()),
+            Optional::None => None,
+        }
+    }
 }
 
-#[derive(Default, PacketIn)]
+#[derive(Default, PacketIn, PacketOut)]
 #[allow(dead_code)]
 struct SigData {
     /// When the key data will expire.
     timestamp: i64,
     /// Length of Public Key.
-    public_key: LengthPaddedVec<i8>,
+    public_key: LengthPaddedVec<u8>,
     /// The bytes of the public key signature the client received from Mojang.
     signature: LengthPaddedVec<i8>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UUID: Uuid = Uuid::from_u128(0x069a_79f4_44e9_4726_a5be_fca9_0e38_aaf5);
+    const UUID_BYTES: [u8; 16] = [
+        0x06, 0x9A, 0x79, 0xF4, 0x44, 0xE9, 0x47, 0x26, 0xA5, 0xBE, 0xFC, 0xA9, 0x0E, 0x38, 0xAA,
+        0xF5,
+    ];
+
+    fn encode(packet: &LoginStartPacket, protocol_version: ProtocolVersion) -> Vec<u8> {
+        let mut writer = BinaryWriter::new();
+        packet.encode(&mut writer, protocol_version).unwrap();
+        writer.into_inner()
+    }
+
+    #[test]
+    fn test_login_start_encode() {
+        // Given
+        let packet = LoginStartPacket::new("Notch", UUID);
+
+        // When
+        let v1_8 = encode(&packet, ProtocolVersion::V1_8);
+        let v1_19_3 = encode(&packet, ProtocolVersion::V1_19_3);
+        let v1_20_2 = encode(&packet, ProtocolVersion::V1_20_2);
+
+        // Then
+        assert_eq!(v1_8, [&[5][..], b"Notch"].concat());
+        assert_eq!(v1_19_3, [&[5][..], b"Notch", &[1], &UUID_BYTES].concat());
+        assert_eq!(v1_20_2, [&[5][..], b"Notch", &UUID_BYTES].concat());
+    }
+
+    #[test]
+    fn test_login_start_encode_without_profile_key_v1_19() {
+        // Given
+        let packet = LoginStartPacket::new("Notch", UUID);
+
+        // When
+        let bytes = encode(&packet, ProtocolVersion::V1_19);
+
+        // Then
+        assert_eq!(bytes, [&[5][..], b"Notch", &[0]].concat());
+    }
+
+    #[test]
+    fn test_login_start_decode_with_profile_key_v1_19() {
+        // Given
+        let bytes = [
+            &[5][..],
+            b"Notch",
+            &[1],
+            &[0, 0, 0x01, 0x8B, 0xCF, 0xE5, 0x68, 0x00],
+            &[3, 1, 2, 3],
+            &[2, 4, 5],
+        ]
+        .concat();
+        let mut reader = BinaryReader::new(&bytes);
+
+        // When
+        let packet = LoginStartPacket::decode(&mut reader, ProtocolVersion::V1_19).unwrap();
+
+        // Then
+        assert_eq!(packet.name(), "Notch");
+        assert_eq!(packet.profile_public_key(), Some([1, 2, 3].as_slice()));
+        assert_eq!(reader.remaining(), 0);
+    }
+
+    #[test]
+    fn test_login_start_decode_v1_20_2() {
+        // Given
+        let bytes = [&[5][..], b"Notch", &UUID_BYTES].concat();
+        let mut reader = BinaryReader::new(&bytes);
+
+        // When
+        let packet = LoginStartPacket::decode(&mut reader, ProtocolVersion::V1_20_2).unwrap();
+
+        // Then
+        assert_eq!(packet.name(), "Notch");
+        assert_eq!(packet.uuid(), UUID);
+        assert_eq!(packet.profile_public_key(), None);
+    }
+}
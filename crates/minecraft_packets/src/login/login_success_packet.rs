@@ -2,7 +2,7 @@ use crate::login::Property;
 use minecraft_protocol::prelude::*;
 
 /// This packet was introduced in 1.21.2, previous versions uses the GameProfilePacket.
-#[derive(PacketOut)]
+#[derive(PacketIn, PacketOut)]
 pub struct LoginFinishedPacket {
     uuid: UuidAsString,
     username: String,
@@ -21,4 +21,54 @@ impl LoginFinishedPacket {
             session_id: Uuid::new_v4().into(),
         }
     }
+
+    pub fn uuid(&self) -> Uuid {
+        self.uuid.uuid()
+    }
+
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UUID: Uuid = Uuid::from_u128(0x069a_79f4_44e9_4726_a5be_fca9_0e38_aaf5);
+    const UUID_BYTES: [u8; 16] = [
+        0x06, 0x9A, 0x79, 0xF4, 0x44, 0xE9, 0x47, 0x26, 0xA5, 0xBE, 0xFC, 0xA9, 0x0E, 0x38, 0xAA,
+        0xF5,
+    ];
+
+    #[test]
+    fn test_login_finished_encode_v1_21_2() {
+        // Given
+        let packet = LoginFinishedPacket::new(UUID, "Notch");
+        let mut writer = BinaryWriter::new();
+
+        // When
+        packet
+            .encode(&mut writer, ProtocolVersion::V1_21_2)
+            .unwrap();
+
+        // Then
+        let expected = [&UUID_BYTES[..], &[5], b"Notch", &[0]].concat();
+        assert_eq!(writer.into_inner(), expected);
+    }
+
+    #[test]
+    fn test_login_finished_decode_v26_2() {
+        // Given
+        let bytes = [&UUID_BYTES[..], &[5], b"Notch", &[0], &[0; 16]].concat();
+        let mut reader = BinaryReader::new(&bytes);
+
+        // When
+        let packet = LoginFinishedPacket::decode(&mut reader, ProtocolVersion::V26_2).unwrap();
+
+        // Then
+        assert_eq!(packet.uuid(), UUID);
+        assert_eq!(packet.username(), "Notch");
+        assert_eq!(reader.remaining(), 0);
+    }
 }
@@ -1,6 +1,8 @@
 pub mod custom_query_answer_packet;
 pub mod custom_query_packet;
 mod data;
+pub mod encryption_request_packet;
+pub mod encryption_response_packet;
 pub mod game_profile_packet;
 pub mod login_acknowledged_packet;
 pub mod login_disconnect_packet;
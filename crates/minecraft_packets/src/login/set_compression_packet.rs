@@ -1,6 +1,6 @@
 use minecraft_protocol::prelude::*;
 
-#[derive(PacketOut)]
+#[derive(PacketIn, PacketOut)]
 pub struct SetCompressionPacket {
     threshold: VarInt,
 }
@@ -11,4 +11,39 @@ impl SetCompressionPacket {
             threshold: threshold.into(),
         }
     }
+
+    pub fn threshold(&self) -> i32 {
+        self.threshold.inner()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_compression_encode() {
+        // Given
+        let packet = SetCompressionPacket::new(256);
+        let mut writer = BinaryWriter::new();
+
+        // When
+        packet.encode(&mut writer, ProtocolVersion::V1_8).unwrap();
+
+        // Then
+        assert_eq!(writer.into_inner(), vec![0x80, 0x02]);
+    }
+
+    #[test]
+    fn test_set_compression_decode() {
+        // Given
+        let bytes = [0x80, 0x02];
+        let mut reader = BinaryReader::new(&bytes);
+
+        // When
+        let packet = SetCompressionPacket::decode(&mut reader, ProtocolVersion::V1_8).unwrap();
+
+        // Then
+        assert_eq!(packet.threshold(), 256);
+    }
 }
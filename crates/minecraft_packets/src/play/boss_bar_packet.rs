@@ -9,12 +9,12 @@ pub struct BossBarPacket {
 
 impl BossBarPacket {
     pub fn add(
+        uuid: Uuid,
         title: &Component,
         health: f32,
         color: BossBarColor,
         division: BossBarDivision,
     ) -> Self {
-        let uuid = Uuid::new_v4();
         Self {
             uuid: uuid.into(),
             action: BossBarAction::Add {
@@ -26,6 +26,29 @@ impl BossBarPacket {
             },
         }
     }
+
+    pub fn remove(uuid: Uuid) -> Self {
+        Self {
+            uuid: uuid.into(),
+            action: BossBarAction::Remove,
+        }
+    }
+
+    pub fn update_health(uuid: Uuid, health: f32) -> Self {
+        Self {
+            uuid: uuid.into(),
+            action: BossBarAction::UpdateHealth { health },
+        }
+    }
+
+    pub fn update_title(uuid: Uuid, title: &Component) -> Self {
+        Self {
+            uuid: uuid.into(),
+            action: BossBarAction::UpdateTitle {
+                title: title.clone(),
+            },
+        }
+    }
 }
 
 #[allow(dead_code)]
@@ -54,7 +77,7 @@ enum BossBarAction {
     },
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(i32)]
 pub enum BossBarColor {
     Pink = 0,
@@ -66,7 +89,7 @@ pub enum BossBarColor {
     White = 6,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(i32)]
 pub enum BossBarDivision {
     NoDivision = 0,
@@ -3,7 +3,7 @@ use rand::RngExt;
 use std::num::TryFromIntError;
 
 /// This packet exists for all versions of the game from 1.7.2 to the latest at the time (1.21.4).
-#[derive(PacketOut)]
+#[derive(PacketIn, PacketOut)]
 pub struct ClientBoundKeepAlivePacket {
     #[pvn(340..)]
     v1_12_2_id: i64,
@@ -25,6 +25,21 @@ impl ClientBoundKeepAlivePacket {
     pub fn random() -> Result<Self, TryFromIntError> {
         Self::new(get_random_i32())
     }
+
+    pub const fn id(&self) -> i32 {
+        self.id
+    }
+
+    /// ID of a decoded keep alive, only the field of the protocol version is set.
+    pub fn decoded_id(&self, protocol_version: ProtocolVersion) -> i64 {
+        if protocol_version.is_after_inclusive(ProtocolVersion::V1_12_2) {
+            self.v1_12_2_id
+        } else if protocol_version.is_after_inclusive(ProtocolVersion::V1_8) {
+            i64::from(self.v1_8_id.inner())
+        } else {
+            i64::from(self.id)
+        }
+    }
 }
 
 fn get_random_i32() -> i32 {
@@ -67,4 +82,32 @@ mod tests {
         let encoded_packet = writer.into_inner();
         assert_eq!(encoded_packet, vec![0x00, 0x00, 0x00, 0x00]);
     }
+
+    #[test]
+    fn test_decoded_keep_alive_id_v1_8() {
+        // Given
+        let bytes = [0xAC, 0x02];
+        let mut reader = BinaryReader::new(&bytes);
+
+        // When
+        let packet =
+            ClientBoundKeepAlivePacket::decode(&mut reader, ProtocolVersion::V1_8).unwrap();
+
+        // Then
+        assert_eq!(packet.decoded_id(ProtocolVersion::V1_8), 300);
+    }
+
+    #[test]
+    fn test_decoded_keep_alive_id_v1_12_2() {
+        // Given
+        let bytes = [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFE];
+        let mut reader = BinaryReader::new(&bytes);
+
+        // When
+        let packet =
+            ClientBoundKeepAlivePacket::decode(&mut reader, ProtocolVersion::V1_12_2).unwrap();
+
+        // Then
+        assert_eq!(packet.decoded_id(ProtocolVersion::V1_12_2), -2);
+    }
 }
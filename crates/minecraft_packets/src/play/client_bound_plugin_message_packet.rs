@@ -2,22 +2,63 @@ use minecraft_protocol::prelude::*;
 
 #[derive(PacketOut)]
 pub struct PlayClientBoundPluginMessagePacket {
-    channel: Identifier,
-    data: LengthPaddedVec<i8>,
+    /// Namespaced since 1.13, legacy channels such as `BungeeCord` are plain names.
+    channel: String,
+    #[pvn(..47)]
+    v1_7_data: ShortLengthPaddedVec<u8>,
+    /// Takes the rest of the packet since 1.8.
+    #[pvn(47..)]
+    data: Vec<u8>,
 }
 
 impl PlayClientBoundPluginMessagePacket {
-    pub fn brand(brand: impl ToString) -> Self {
+    pub fn new(channel: impl ToString, data: Vec<u8>) -> Self {
         Self {
-            channel: Identifier::vanilla_unchecked("brand"),
-            data: LengthPaddedVec::new(
-                brand
-                    .to_string()
-                    .as_bytes()
-                    .iter()
-                    .map(|&b| b as i8)
-                    .collect::<Vec<_>>(),
-            ),
+            channel: channel.to_string(),
+            v1_7_data: ShortLengthPaddedVec::new(data.clone()),
+            data,
         }
     }
+
+    pub fn brand(brand: impl ToString) -> Self {
+        let brand = brand.to_string();
+        // The brand is a string prefixed by its length, writing to a vector cannot fail
+        let mut data = VarInt::from(brand.len() as i32)
+            .to_bytes()
+            .unwrap_or_default();
+        data.extend_from_slice(brand.as_bytes());
+        Self::new("minecraft:brand", data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_brand_packet() {
+        let packet = PlayClientBoundPluginMessagePacket::brand("PicoLimbo");
+        let mut writer = BinaryWriter::new();
+        packet.encode(&mut writer, ProtocolVersion::V1_13).unwrap();
+        let bytes = writer.into_inner();
+
+        let mut expected = vec![15];
+        expected.extend_from_slice(b"minecraft:brand");
+        expected.push(9);
+        expected.extend_from_slice(b"PicoLimbo");
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn test_plugin_message_v1_7_2_is_length_prefixed() {
+        let packet = PlayClientBoundPluginMessagePacket::new("BungeeCord", vec![1, 2]);
+        let mut writer = BinaryWriter::new();
+        packet.encode(&mut writer, ProtocolVersion::V1_7_2).unwrap();
+        let bytes = writer.into_inner();
+
+        let mut expected = vec![10];
+        expected.extend_from_slice(b"BungeeCord");
+        expected.extend_from_slice(&[0, 2, 1, 2]);
+        assert_eq!(bytes, expected);
+    }
 }
@@ -2,7 +2,7 @@ use minecraft_protocol::prelude::*;
 use pico_text_component::prelude::Component;
 
 /// This packet can be used in the play and configuration state, the structure remains the same
-#[derive(PacketOut)]
+#[derive(PacketIn, PacketOut)]
 pub struct DisconnectPacket {
     #[pvn(..765)]
     reason: String, // JSON encoded
@@ -12,10 +12,81 @@ pub struct DisconnectPacket {
 
 impl DisconnectPacket {
     pub fn text(text: impl Into<String>) -> DisconnectPacket {
-        let component = Component::new(text);
+        Self::component(&Component::new(text))
+    }
+
+    pub fn component(component: &Component) -> DisconnectPacket {
         Self {
             reason: component.to_json(),
             v1_20_3_reason: component.to_nbt(),
         }
     }
+
+    /// Reason of a decoded packet, as JSON.
+    pub fn reason(&self, protocol_version: ProtocolVersion) -> String {
+        if protocol_version.is_after_inclusive(ProtocolVersion::V1_20_3) {
+            serde_json::to_string(&self.v1_20_3_reason).unwrap_or_default()
+        } else {
+            self.reason.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Nameless root string tag, as sent since 1.20.3.
+    const NBT_REASON: &[u8] = &[0x08, 0x00, 0x06, b'K', b'i', b'c', b'k', b'e', b'd'];
+
+    fn packet() -> DisconnectPacket {
+        DisconnectPacket {
+            reason: r#"{"text":"Kicked"}"#.to_string(),
+            v1_20_3_reason: pico_nbt::Value::String("Kicked".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_disconnect_encode_v1_20_2() {
+        // Given
+        let packet = packet();
+        let mut writer = BinaryWriter::new();
+
+        // When
+        packet
+            .encode(&mut writer, ProtocolVersion::V1_20_2)
+            .unwrap();
+
+        // Then
+        let expected = [&[17][..], br#"{"text":"Kicked"}"#].concat();
+        assert_eq!(writer.into_inner(), expected);
+    }
+
+    #[test]
+    fn test_disconnect_encode_v1_20_3() {
+        // Given
+        let packet = packet();
+        let mut writer = BinaryWriter::new();
+
+        // When
+        packet
+            .encode(&mut writer, ProtocolVersion::V1_20_3)
+            .unwrap();
+
+        // Then
+        assert_eq!(writer.into_inner(), NBT_REASON);
+    }
+
+    #[test]
+    fn test_disconnect_decode_v1_20_3() {
+        // Given
+        let mut reader = BinaryReader::new(NBT_REASON);
+
+        // When
+        let packet = DisconnectPacket::decode(&mut reader, ProtocolVersion::V1_20_3).unwrap();
+
+        // Then
+        assert_eq!(packet.reason(ProtocolVersion::V1_20_3), r#""Kicked""#);
+        assert_eq!(reader.remaining(), 0);
+    }
 }
@@ -14,6 +14,7 @@ pub mod legacy_set_title_packet;
 pub mod login_packet;
 pub mod player_info_update_packet;
 pub mod player_position_packet;
+pub mod server_bound_keep_alive_packet;
 pub mod server_bound_player_abilities_packet;
 pub mod set_action_bar_text_packet;
 pub mod set_chunk_cache_center_packet;
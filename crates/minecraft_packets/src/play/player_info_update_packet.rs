@@ -21,6 +21,24 @@ impl PlayerInfoUpdatePacket {
         Self::new(name, uuid, Vec::new(), listed)
     }
 
+    /// Updates the ping bars of the player in the tab list, latency in milliseconds.
+    pub fn latency(uuid: Uuid, latency: i32) -> Self {
+        let update_latency_action = PlayerActions::UpdateLatency {
+            latency: VarInt::new(latency),
+        };
+        let player_action = Player {
+            uuid: uuid.into(),
+            action: update_latency_action.clone(),
+            actions: vec![update_latency_action.clone()],
+        };
+
+        Self {
+            action: VarInt::new(2),
+            v1_19_3_mask: update_latency_action.get_mask(),
+            players: LengthPaddedVec::new(vec![player_action]),
+        }
+    }
+
     fn new(name: String, uuid: Uuid, properties: Vec<Property>, listed: bool) -> Self {
         let properties = LengthPaddedVec::new(properties);
         let add_player_action = AddPlayer {
@@ -44,7 +62,7 @@ impl PlayerInfoUpdatePacket {
 
         let player_action = Player {
             uuid: uuid.into(),
-            action: add_player_action,
+            action: PlayerActions::AddPlayer(add_player_action),
             actions,
         };
 
@@ -60,7 +78,7 @@ impl PlayerInfoUpdatePacket {
 struct Player {
     uuid: UuidAsLongs,
     #[pvn(..761)]
-    action: AddPlayer,
+    action: PlayerActions,
     #[pvn(761..)]
     actions: Vec<PlayerActions>,
 }
@@ -90,6 +108,7 @@ struct SigData {
 enum PlayerActions {
     AddPlayer(AddPlayer),
     UpdateListed { listed: bool },
+    UpdateLatency { latency: VarInt },
 }
 
 impl PlayerActions {
@@ -97,6 +116,7 @@ impl PlayerActions {
         match self {
             PlayerActions::AddPlayer { .. } => 0x01,
             PlayerActions::UpdateListed { .. } => 0x08,
+            PlayerActions::UpdateLatency { .. } => 0x10,
         }
     }
 }
@@ -116,6 +136,39 @@ impl EncodePacket for PlayerActions {
                 listed.encode(writer, protocol_version)?;
                 Ok(())
             }
+            PlayerActions::UpdateLatency { latency } => {
+                latency.encode(writer, protocol_version)?;
+                Ok(())
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(packet: &PlayerInfoUpdatePacket, protocol_version: ProtocolVersion) -> Vec<u8> {
+        let mut writer = BinaryWriter::new();
+        packet.encode(&mut writer, protocol_version).unwrap();
+        writer.into_inner()
+    }
+
+    #[test]
+    fn test_latency_v1_19_3() {
+        let packet = PlayerInfoUpdatePacket::latency(Uuid::nil(), 300);
+        let mut expected = vec![0x10, 0x01];
+        expected.extend([0; 16]);
+        expected.extend([0xAC, 0x02]);
+        assert_eq!(encode(&packet, ProtocolVersion::V1_19_3), expected);
+    }
+
+    #[test]
+    fn test_latency_v1_8() {
+        let packet = PlayerInfoUpdatePacket::latency(Uuid::nil(), 42);
+        let mut expected = vec![0x02, 0x01];
+        expected.extend([0; 16]);
+        expected.push(42);
+        assert_eq!(encode(&packet, ProtocolVersion::V1_8), expected);
+    }
+}
@@ -0,0 +1,92 @@
+use minecraft_protocol::prelude::*;
+
+/// Answer to a clientbound keep alive, echoing its ID.
+/// The same structure is used in the configuration and play states.
+#[derive(PacketIn, PacketOut)]
+pub struct ServerBoundKeepAlivePacket {
+    #[pvn(340..)]
+    v1_12_2_id: i64,
+    #[pvn(47..340)]
+    v1_8_id: VarInt,
+    #[pvn(..47)]
+    id: i32,
+}
+
+impl ServerBoundKeepAlivePacket {
+    /// Echoes a keep alive ID, IDs sent before 1.12.2 fit in an `i32`.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn new(id: i64) -> Self {
+        Self {
+            v1_12_2_id: id,
+            v1_8_id: VarInt::new(id as i32),
+            id: id as i32,
+        }
+    }
+
+    pub fn id(&self, protocol_version: ProtocolVersion) -> i64 {
+        if protocol_version.is_after_inclusive(ProtocolVersion::V1_12_2) {
+            self.v1_12_2_id
+        } else if protocol_version.is_after_inclusive(ProtocolVersion::V1_8) {
+            i64::from(self.v1_8_id.inner())
+        } else {
+            i64::from(self.id)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(bytes: &[u8], protocol_version: ProtocolVersion) -> i64 {
+        let mut reader = BinaryReader::new(bytes);
+        ServerBoundKeepAlivePacket::decode(&mut reader, protocol_version)
+            .unwrap()
+            .id(protocol_version)
+    }
+
+    #[test]
+    fn test_keep_alive_packet_v1_12_2() {
+        let bytes = [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFE];
+        assert_eq!(decode(&bytes, ProtocolVersion::V1_12_2), -2);
+    }
+
+    #[test]
+    fn test_keep_alive_packet_v1_8() {
+        assert_eq!(decode(&[0xAC, 0x02], ProtocolVersion::V1_8), 300);
+    }
+
+    #[test]
+    fn test_keep_alive_packet_v1_7_2() {
+        assert_eq!(
+            decode(&[0x00, 0x00, 0x01, 0x00], ProtocolVersion::V1_7_2),
+            256
+        );
+    }
+
+    #[test]
+    fn test_keep_alive_encode_v1_8() {
+        // Given
+        let packet = ServerBoundKeepAlivePacket::new(300);
+        let mut writer = BinaryWriter::new();
+
+        // When
+        packet.encode(&mut writer, ProtocolVersion::V1_8).unwrap();
+
+        // Then
+        assert_eq!(writer.into_inner(), vec![0xAC, 0x02]);
+    }
+
+    #[test]
+    fn test_keep_alive_encode_v1_7_2() {
+        // Given
+        let packet = ServerBoundKeepAlivePacket::new(256);
+        let mut writer = BinaryWriter::new();
+
+        // When
+        packet.encode(&mut writer, ProtocolVersion::V1_7_2).unwrap();
+
+        // Then
+        assert_eq!(writer.into_inner(), vec![0x00, 0x00, 0x01, 0x00]);
+    }
+}
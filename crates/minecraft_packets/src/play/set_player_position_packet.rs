@@ -1,6 +1,6 @@
 use minecraft_protocol::prelude::*;
 
-#[derive(PacketIn)]
+#[derive(PacketIn, PacketOut)]
 pub struct SetPlayerPositionPacket {
     pub x: f64,
     pub feet_y: f64,
@@ -12,7 +12,70 @@ pub struct SetPlayerPositionPacket {
 }
 
 impl SetPlayerPositionPacket {
+    pub const fn new(x: f64, feet_y: f64, z: f64, on_ground: bool) -> Self {
+        Self {
+            x,
+            feet_y,
+            z,
+            v1_21_4_flags: if on_ground { 0x01 } else { 0x00 },
+            on_ground,
+        }
+    }
+
     pub fn position(&self) -> (f64, f64, f64) {
         (self.x, self.feet_y, self.z)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position_bytes() -> Vec<u8> {
+        [
+            1.5_f64.to_be_bytes(),
+            64.0_f64.to_be_bytes(),
+            (-2.5_f64).to_be_bytes(),
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn test_set_player_position_encode() {
+        // Given
+        let packet = SetPlayerPositionPacket::new(1.5, 64.0, -2.5, true);
+
+        // When
+        let mut v1_21_2 = BinaryWriter::new();
+        packet
+            .encode(&mut v1_21_2, ProtocolVersion::V1_21_2)
+            .unwrap();
+        let mut v1_21_4 = BinaryWriter::new();
+        packet
+            .encode(&mut v1_21_4, ProtocolVersion::V1_21_4)
+            .unwrap();
+
+        // Then
+        assert_eq!(v1_21_2.into_inner(), [position_bytes(), vec![1]].concat());
+        assert_eq!(
+            v1_21_4.into_inner(),
+            [position_bytes(), vec![0x01]].concat()
+        );
+    }
+
+    #[test]
+    fn test_set_player_position_decode_v1_21_4() {
+        // Given
+        let bytes = [position_bytes(), vec![0x01]].concat();
+        let mut reader = BinaryReader::new(&bytes);
+
+        // When
+        let packet =
+            SetPlayerPositionPacket::decode(&mut reader, ProtocolVersion::V1_21_4).unwrap();
+
+        // Then
+        assert_eq!(packet.position(), (1.5, 64.0, -2.5));
+        assert_eq!(packet.v1_21_4_flags, 0x01);
+        assert_eq!(reader.remaining(), 0);
+    }
+}
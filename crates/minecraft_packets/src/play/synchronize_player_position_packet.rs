@@ -1,6 +1,6 @@
 use minecraft_protocol::prelude::*;
 
-#[derive(PacketOut)]
+#[derive(PacketIn, PacketOut)]
 pub struct SynchronizePlayerPositionPacket {
     #[pvn(768..)]
     pub v_1_21_2_teleport_id: VarInt,
@@ -54,3 +54,74 @@ impl SynchronizePlayerPositionPacket {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position_bytes() -> Vec<u8> {
+        [
+            1.5_f64.to_be_bytes(),
+            64.0_f64.to_be_bytes(),
+            (-2.5_f64).to_be_bytes(),
+        ]
+        .concat()
+    }
+
+    fn rotation_bytes() -> Vec<u8> {
+        [90.0_f32.to_be_bytes(), 45.0_f32.to_be_bytes()].concat()
+    }
+
+    #[test]
+    fn test_synchronize_player_position_encode_v1_8() {
+        // Given
+        let packet = SynchronizePlayerPositionPacket::new(1.5, 64.0, -2.5, 90.0, 45.0);
+        let mut writer = BinaryWriter::new();
+
+        // When
+        packet.encode(&mut writer, ProtocolVersion::V1_8).unwrap();
+
+        // Then
+        let expected = [position_bytes(), rotation_bytes(), vec![0]].concat();
+        assert_eq!(writer.into_inner(), expected);
+    }
+
+    #[test]
+    fn test_synchronize_player_position_encode_v1_21_2() {
+        // Given
+        let packet = SynchronizePlayerPositionPacket::new(1.5, 64.0, -2.5, 90.0, 45.0);
+        let mut writer = BinaryWriter::new();
+
+        // When
+        packet
+            .encode(&mut writer, ProtocolVersion::V1_21_2)
+            .unwrap();
+
+        // Then
+        let expected = [
+            vec![0],
+            position_bytes(),
+            vec![0; 24],
+            rotation_bytes(),
+            vec![0; 4],
+        ]
+        .concat();
+        assert_eq!(writer.into_inner(), expected);
+    }
+
+    #[test]
+    fn test_synchronize_player_position_decode_v1_17() {
+        // Given
+        let bytes = [position_bytes(), rotation_bytes(), vec![0, 0, 0]].concat();
+        let mut reader = BinaryReader::new(&bytes);
+
+        // When
+        let packet =
+            SynchronizePlayerPositionPacket::decode(&mut reader, ProtocolVersion::V1_17).unwrap();
+
+        // Then
+        assert_eq!((packet.x, packet.y, packet.z), (1.5, 64.0, -2.5));
+        assert_eq!((packet.yaw, packet.pitch), (90.0, 45.0));
+        assert_eq!(reader.remaining(), 0);
+    }
+}
@@ -1,5 +1,5 @@
-use crate::prelude::EncodePacket;
-use pico_binutils::prelude::{BinaryWriter, BinaryWriterError};
+use crate::prelude::{DecodePacket, EncodePacket};
+use pico_binutils::prelude::{BinaryReader, BinaryReaderError, BinaryWriter, BinaryWriterError};
 use pico_nbt::{NbtOptions, Value};
 use protocol_version::protocol_version::ProtocolVersion;
 
@@ -17,6 +17,18 @@ impl EncodePacket for Value {
     }
 }
 
+impl DecodePacket for Value {
+    fn decode(
+        reader: &mut BinaryReader,
+        protocol_version: ProtocolVersion,
+    ) -> Result<Self, BinaryReaderError> {
+        let (_, value) =
+            pico_nbt::from_reader_with_options(reader, from_protocol_version(protocol_version))
+                .map_err(|_| BinaryReaderError::Custom)?;
+        Ok(value)
+    }
+}
+
 fn from_protocol_version(value: ProtocolVersion) -> NbtOptions {
     NbtOptions::new()
         .nameless_root(value.is_after_inclusive(ProtocolVersion::V1_20_2))
@@ -1,12 +1,15 @@
 use crate::prelude::{DecodePacket, EncodePacket, ProtocolVersion};
 use pico_binutils::prelude::{
     BinaryReader, BinaryReaderError, BinaryWriter, BinaryWriterError, Prefixed, ReadLengthPrefix,
-    VarIntPrefixed, WriteLengthPrefix,
+    ShortPrefixed, VarIntPrefixed, WriteLengthPrefix,
 };
 
 /// A wrapper around a Vec that adds the length as a VarInt before the Vec itself.
 pub type LengthPaddedVec<T> = VarIntPrefixed<Vec<T>>;
 
+/// A wrapper around a Vec that adds the length as a Short before the Vec itself, used by 1.7.
+pub type ShortLengthPaddedVec<T> = ShortPrefixed<Vec<T>>;
+
 impl<L, T> DecodePacket for Prefixed<L, Vec<T>>
 where
     L: ReadLengthPrefix,
@@ -18,6 +18,20 @@ impl DecodePacket for Uuid {
     }
 }
 
+impl EncodePacket for Uuid {
+    fn encode(
+        &self,
+        writer: &mut BinaryWriter,
+        protocol_version: ProtocolVersion,
+    ) -> Result<(), BinaryWriterError> {
+        if protocol_version >= ProtocolVersion::V1_16 {
+            writer.write(self)
+        } else {
+            Err(BinaryWriterError::UnsupportedOperation)
+        }
+    }
+}
+
 /// A UUID wrapper that encodes as a string representation based on protocol version.
 ///
 /// - Before 1.7.6: Encoded as a string without dashes (e.g., "550e8400e29b41d4a716446655440000")
@@ -30,6 +44,10 @@ impl UuidAsString {
     pub fn new(uuid: Uuid) -> Self {
         Self(uuid)
     }
+
+    pub const fn uuid(&self) -> Uuid {
+        self.0
+    }
 }
 
 impl From<Uuid> for UuidAsString {
@@ -62,10 +80,27 @@ impl EncodePacket for UuidAsString {
     }
 }
 
+impl DecodePacket for UuidAsString {
+    fn decode(
+        reader: &mut BinaryReader,
+        protocol_version: ProtocolVersion,
+    ) -> Result<Self, BinaryReaderError> {
+        if protocol_version.is_after_inclusive(ProtocolVersion::V1_16) {
+            return Ok(Self(reader.read::<Uuid>()?));
+        }
+        // Parses the strings with or without dashes
+        let string = reader.read::<VarIntPrefixedString>()?.into_inner();
+        Uuid::parse_str(&string)
+            .map(Self)
+            .map_err(|_| BinaryReaderError::Custom)
+    }
+}
+
 /// A UUID wrapper that encodes as either raw bytes or a pair of 64-bit integers.
 ///
 /// - Before 1.16: Encoded as two i64 values (most significant bits, least significant bits)
 /// - 1.16+: Encoded as raw 16-byte array
+#[derive(Default)]
 pub struct UuidAsLongs(Uuid);
 
 impl UuidAsLongs {
@@ -100,6 +135,21 @@ impl EncodePacket for UuidAsLongs {
     }
 }
 
+impl DecodePacket for UuidAsLongs {
+    fn decode(
+        reader: &mut BinaryReader,
+        protocol_version: ProtocolVersion,
+    ) -> Result<Self, BinaryReaderError> {
+        if protocol_version.is_after_inclusive(ProtocolVersion::V1_16) {
+            Ok(Self(reader.read::<Uuid>()?))
+        } else {
+            let most_sig = reader.read::<u64>()?;
+            let least_sig = reader.read::<u64>()?;
+            Ok(Self(Uuid::from_u64_pair(most_sig, least_sig)))
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,60 @@
+use aes::Aes128;
+use cfb8::cipher::InvalidLength;
+use cfb8::cipher::KeyIvInit;
+
+type Aes128Cfb8Enc = cfb8::Encryptor<Aes128>;
+type Aes128Cfb8Dec = cfb8::Decryptor<Aes128>;
+
+/// AES-128 in CFB8 mode, keyed and initialised with the shared secret
+/// negotiated during login. Both directions keep their own running state.
+pub(crate) struct PacketCipher {
+    encryptor: Aes128Cfb8Enc,
+    decryptor: Aes128Cfb8Dec,
+}
+
+impl PacketCipher {
+    pub(crate) fn new(shared_secret: &[u8]) -> Result<Self, InvalidLength> {
+        Ok(Self {
+            encryptor: Aes128Cfb8Enc::new_from_slices(shared_secret, shared_secret)?,
+            decryptor: Aes128Cfb8Dec::new_from_slices(shared_secret, shared_secret)?,
+        })
+    }
+
+    pub(crate) fn encrypt(&mut self, buf: &mut [u8]) {
+        self.encryptor.encrypt(buf);
+    }
+
+    pub(crate) fn decrypt(&mut self, buf: &mut [u8]) {
+        self.decryptor.decrypt(buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_across_multiple_calls() {
+        // Given
+        let secret = [0x42; 16];
+        let mut sender = PacketCipher::new(&secret).unwrap();
+        let mut receiver = PacketCipher::new(&secret).unwrap();
+        let mut first = b"hello".to_vec();
+        let mut second = b" world".to_vec();
+
+        // When
+        sender.encrypt(&mut first);
+        sender.encrypt(&mut second);
+        receiver.decrypt(&mut first);
+        receiver.decrypt(&mut second);
+
+        // Then
+        assert_eq!(first, b"hello");
+        assert_eq!(second, b" world");
+    }
+
+    #[test]
+    fn test_invalid_secret_length() {
+        assert!(PacketCipher::new(&[0; 8]).is_err());
+    }
+}
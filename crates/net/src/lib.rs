@@ -1,3 +1,4 @@
+mod cipher;
 mod get_packet_length;
 pub mod packet_stream;
 pub mod raw_packet;
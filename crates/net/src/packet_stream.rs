@@ -1,3 +1,4 @@
+use crate::cipher::PacketCipher;
 use crate::get_packet_length::{MAXIMUM_PACKET_LENGTH, PacketLengthParseError, get_packet_length};
 use crate::raw_packet::RawPacket;
 use flate2::Compression;
@@ -9,6 +10,11 @@ use std::num::TryFromIntError;
 use thiserror::Error;
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
+/// Maximum number of bytes read from the stream at once.
+const READ_CHUNK_SIZE: usize = 4096;
+/// Maximum size of the `VarInt` prefixing the length of a packet.
+const MAXIMUM_VAR_INT_LENGTH: usize = 5;
+
 #[derive(Clone)]
 struct CompressionSettings {
     threshold: usize,
@@ -21,6 +27,11 @@ where
 {
     stream: Stream,
     compression_settings: Option<CompressionSettings>,
+    cipher: Option<PacketCipher>,
+    /// Decrypted bytes received but not read as a packet yet.
+    read_buffer: Vec<u8>,
+    bytes_written: u64,
+    bytes_read: u64,
 }
 
 impl<Stream> PacketStream<Stream>
@@ -32,6 +43,20 @@ where
         Self {
             stream,
             compression_settings: None,
+            cipher: None,
+            read_buffer: Vec::new(),
+            bytes_written: 0,
+            bytes_read: 0,
+        }
+    }
+
+    /// Creates a new `PacketStream` without compression, whose first bytes were
+    /// already read from the stream.
+    pub fn with_received_bytes(stream: Stream, received_bytes: Vec<u8>) -> Self {
+        Self {
+            bytes_read: received_bytes.len() as u64,
+            read_buffer: received_bytes,
+            ..Self::new(stream)
         }
     }
 
@@ -47,11 +72,34 @@ where
         });
     }
 
+    /// Enables AES/CFB8 encryption of the stream using the shared secret sent
+    /// by the client in the encryption response.
+    ///
+    /// Every byte read or written after this call goes through the cipher, so
+    /// it must be called right after the encryption response was handled.
+    pub fn set_encryption(&mut self, shared_secret: &[u8]) -> Result<(), PacketStreamError> {
+        let mut cipher =
+            PacketCipher::new(shared_secret).map_err(|_| PacketStreamError::InvalidSharedSecret)?;
+        // The bytes received after the encryption response were already encrypted
+        cipher.decrypt(&mut self.read_buffer);
+        self.cipher = Some(cipher);
+        Ok(())
+    }
+
     /// Reads a single packet from the stream, handling decompression if enabled.
+    ///
+    /// This method is cancel safe: the bytes of a packet received partially are kept
+    /// until the rest of the packet is received by the next call.
     pub async fn read_packet(&mut self) -> Result<RawPacket, PacketStreamError> {
-        match self.compression_settings {
-            None => self.read_uncompressed_packet().await,
-            Some(_) => self.read_compressed_packet_format().await,
+        loop {
+            if let Some(packet_content) = self.take_packet_content()? {
+                return match self.compression_settings {
+                    None => RawPacket::new(packet_content)
+                        .map_err(|_| PacketStreamError::MissingPacketId),
+                    Some(_) => decode_compressed_packet_format(&packet_content),
+                };
+            }
+            self.fill_read_buffer().await?;
         }
     }
 
@@ -63,50 +111,30 @@ where
         }
     }
 
-    fn compression_settings(&self) -> Option<CompressionSettings> {
-        self.compression_settings.clone()
+    /// Returns whether packets are compressed.
+    pub const fn is_compression_enabled(&self) -> bool {
+        self.compression_settings.is_some()
     }
 
-    /// Gets a mutable reference to the underlying stream.
-    pub const fn get_stream(&mut self) -> &mut Stream {
-        &mut self.stream
+    /// Returns the number of bytes written to the stream, including the length
+    /// prefixes and after compression.
+    pub const fn bytes_written(&self) -> u64 {
+        self.bytes_written
     }
 
-    async fn read_uncompressed_packet(&mut self) -> Result<RawPacket, PacketStreamError> {
-        let packet_length = self.read_and_validate_packet_length().await?;
-
-        if packet_length == 0 {
-            return Err(PacketStreamError::ZeroLengthPacket);
-        }
-
-        let mut data = vec![0u8; packet_length];
-        self.stream.read_exact(&mut data).await?;
-
-        RawPacket::new(data).map_err(|_| PacketStreamError::MissingPacketId)
+    /// Returns the number of bytes read from the stream, including the length
+    /// prefixes and before decompression.
+    pub const fn bytes_read(&self) -> u64 {
+        self.bytes_read
     }
 
-    async fn read_compressed_packet_format(&mut self) -> Result<RawPacket, PacketStreamError> {
-        let packet_length = self.read_and_validate_packet_length().await?;
-
-        if packet_length == 0 {
-            return Err(PacketStreamError::ZeroLengthPacket);
-        }
-
-        let mut packet_content_buf = vec![0u8; packet_length];
-        self.stream.read_exact(&mut packet_content_buf).await?;
-
-        let mut reader = BinaryReader::new(&packet_content_buf);
-        let data_length = usize::try_from(reader.read::<VarInt>()?.inner())?;
-        let payload_bytes = reader.remaining_bytes()?;
-
-        let packet_data = if data_length > 0 {
-            decompress_data(&payload_bytes, data_length)?
-        } else {
-            // Data length of 0 means the packet is not compressed.
-            payload_bytes
-        };
+    fn compression_settings(&self) -> Option<CompressionSettings> {
+        self.compression_settings.clone()
+    }
 
-        RawPacket::new(packet_data).map_err(|_| PacketStreamError::MissingPacketId)
+    /// Gets a mutable reference to the underlying stream.
+    pub const fn get_stream(&mut self) -> &mut Stream {
+        &mut self.stream
     }
 
     async fn write_uncompressed_packet(
@@ -119,8 +147,8 @@ where
         }
 
         let packet_length_bytes = VarInt::new(i32::try_from(packet_length)?).to_bytes()?;
-        self.stream.write_all(&packet_length_bytes).await?;
-        self.stream.write_all(packet.bytes()).await?;
+        self.write_all(&packet_length_bytes).await?;
+        self.write_all(packet.bytes()).await?;
         self.stream.flush().await?;
 
         Ok(())
@@ -150,18 +178,23 @@ where
         let packet_length = data_length_bytes.len() + final_payload.len();
         let packet_length_bytes = VarInt::new(i32::try_from(packet_length)?).to_bytes()?;
 
-        self.stream.write_all(&packet_length_bytes).await?;
-        self.stream.write_all(&data_length_bytes).await?;
-        self.stream.write_all(&final_payload).await?;
+        self.write_all(&packet_length_bytes).await?;
+        self.write_all(&data_length_bytes).await?;
+        self.write_all(&final_payload).await?;
         self.stream.flush().await?;
 
         Ok(())
     }
 
-    async fn read_and_validate_packet_length(&mut self) -> Result<usize, PacketStreamError> {
-        let packet_length = usize::try_from(self.read_var_int().await?)?;
+    /// Takes the content of the first packet of the read buffer, without its length prefix,
+    /// returns `None` until the whole packet was received.
+    fn take_packet_content(&mut self) -> Result<Option<Vec<u8>>, PacketStreamError> {
+        let Some((packet_length, prefix_length)) = self.parse_length_prefix()? else {
+            return Ok(None);
+        };
 
         if packet_length > MAXIMUM_PACKET_LENGTH {
+            self.read_buffer.drain(..prefix_length);
             return Err(PacketStreamError::PacketTooLarge {
                 size: packet_length,
                 max: MAXIMUM_PACKET_LENGTH,
@@ -169,31 +202,72 @@ where
         }
 
         if packet_length == 0 {
+            self.read_buffer.drain(..prefix_length);
             return Err(PacketStreamError::ZeroLengthPacket);
         }
 
-        Ok(packet_length)
-    }
+        let packet_end = prefix_length + packet_length;
+        if self.read_buffer.len() < packet_end {
+            return Ok(None);
+        }
 
-    async fn read_var_int(&mut self) -> Result<i32, PacketStreamError> {
-        let mut var_int_buf = Vec::with_capacity(5);
+        let packet_content = self.read_buffer[prefix_length..packet_end].to_vec();
+        self.read_buffer.drain(..packet_end);
+        Ok(Some(packet_content))
+    }
 
-        for _ in 0..5 {
-            let mut byte = [0u8; 1];
-            self.stream.read_exact(&mut byte).await?;
-            var_int_buf.push(byte[0]);
+    /// Returns the packet length and the size of the `VarInt` prefixing it,
+    /// or `None` when the `VarInt` was not entirely received yet.
+    fn parse_length_prefix(&mut self) -> Result<Option<(usize, usize)>, PacketStreamError> {
+        for prefix_length in 1..=MAXIMUM_VAR_INT_LENGTH {
+            let Some(prefix) = self.read_buffer.get(..prefix_length) else {
+                return Ok(None);
+            };
 
             // Try to parse, but continue if more data is needed.
-            match get_packet_length(&var_int_buf) {
-                Ok(length) => return Ok(i32::try_from(length)?),
+            match get_packet_length(prefix) {
+                Ok(length) => return Ok(Some((length, prefix_length))),
                 Err(PacketLengthParseError::BinaryReader(BinaryReaderError::UnexpectedEof)) => {}
-                Err(e) => return Err(e.into()),
+                Err(e) => {
+                    self.read_buffer.drain(..prefix_length);
+                    return Err(e.into());
+                }
             }
         }
 
-        // If we loop 5 times and still need more data, the VarInt is too long.
+        // If 5 bytes were received and more data is still needed, the VarInt is too long.
+        self.read_buffer.drain(..MAXIMUM_VAR_INT_LENGTH);
         Err(PacketLengthParseError::VarIntTooLong.into())
     }
+
+    /// Appends the next bytes received to the read buffer, nothing is lost if cancelled.
+    async fn fill_read_buffer(&mut self) -> Result<(), PacketStreamError> {
+        let mut chunk = [0u8; READ_CHUNK_SIZE];
+        let bytes_read = self.stream.read(&mut chunk).await?;
+        if bytes_read == 0 {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+
+        let chunk = &mut chunk[..bytes_read];
+        if let Some(cipher) = self.cipher.as_mut() {
+            cipher.decrypt(chunk);
+        }
+        self.bytes_read += bytes_read as u64;
+        self.read_buffer.extend_from_slice(chunk);
+        Ok(())
+    }
+
+    async fn write_all(&mut self, buf: &[u8]) -> Result<(), PacketStreamError> {
+        if let Some(cipher) = self.cipher.as_mut() {
+            let mut encrypted = buf.to_vec();
+            cipher.encrypt(&mut encrypted);
+            self.stream.write_all(&encrypted).await?;
+        } else {
+            self.stream.write_all(buf).await?;
+        }
+        self.bytes_written += buf.len() as u64;
+        Ok(())
+    }
 }
 
 #[derive(Error, Debug)]
@@ -216,6 +290,23 @@ pub enum PacketStreamError {
     DecompressionSizeMismatch { expected: usize, actual: usize },
     #[error(transparent)]
     TryFromInt(#[from] TryFromIntError),
+    #[error("shared secret must be 16 bytes long")]
+    InvalidSharedSecret,
+}
+
+fn decode_compressed_packet_format(packet_content: &[u8]) -> Result<RawPacket, PacketStreamError> {
+    let mut reader = BinaryReader::new(packet_content);
+    let data_length = usize::try_from(reader.read::<VarInt>()?.inner())?;
+    let payload_bytes = reader.remaining_bytes()?;
+
+    let packet_data = if data_length > 0 {
+        decompress_data(&payload_bytes, data_length)?
+    } else {
+        // Data length of 0 means the packet is not compressed.
+        payload_bytes
+    };
+
+    RawPacket::new(packet_data).map_err(|_| PacketStreamError::MissingPacketId)
 }
 
 fn compress_data(data: &[u8], compression_level: Compression) -> io::Result<Vec<u8>> {
@@ -312,6 +403,21 @@ mod tests {
         assert_eq!(packet.data(), &[84]);
     }
 
+    #[tokio::test]
+    async fn test_read_packet_started_by_received_bytes() {
+        // Given
+        let reader = tokio_test::io::Builder::new().read(&[42]).build();
+        let mut packet_stream = PacketStream::with_received_bytes(reader, vec![2, 7]);
+
+        // When
+        let packet = packet_stream.read_packet().await.unwrap();
+
+        // Then
+        assert_eq!(packet.packet_id(), Some(7));
+        assert_eq!(packet.data(), &[42]);
+        assert_eq!(packet_stream.bytes_read(), 3);
+    }
+
     #[tokio::test]
     async fn test_two_packets() {
         // Given
@@ -331,6 +437,40 @@ mod tests {
         assert_eq!(packet_2.data(), &[84]);
     }
 
+    #[tokio::test]
+    async fn test_cancelled_read_keeps_partial_packet() {
+        // Given
+        let (mut client, server) = tokio::io::duplex(64);
+        let mut packet_stream = PacketStream::new(server);
+        client.write_all(&[3, 42]).await.unwrap();
+
+        // When
+        let cancelled =
+            tokio::time::timeout(Duration::from_millis(10), packet_stream.read_packet()).await;
+        client.write_all(&[1, 2]).await.unwrap();
+        let packet = packet_stream.read_packet().await.unwrap();
+
+        // Then
+        assert!(cancelled.is_err());
+        assert_eq!(packet.packet_id(), Some(42));
+        assert_eq!(packet.data(), &[1, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_zero_length_packet_is_skipped() {
+        // Given
+        let reader = tokio_test::io::Builder::new().read(&[0, 1, 42]).build();
+        let mut packet_stream = PacketStream::new(reader);
+
+        // When
+        let result = packet_stream.read_packet().await;
+        let packet = packet_stream.read_packet().await.unwrap();
+
+        // Then
+        assert!(matches!(result, Err(PacketStreamError::ZeroLengthPacket)));
+        assert_eq!(packet.packet_id(), Some(42));
+    }
+
     // Uncompressed Write Tests
 
     #[tokio::test]
@@ -346,4 +486,138 @@ mod tests {
         // When / Then
         packet_stream.write_packet(packet).await.unwrap();
     }
+
+    #[tokio::test]
+    async fn test_bytes_written_after_compression() {
+        // Given
+        let (client, _server) = tokio::io::duplex(1024);
+        let mut packet_stream = PacketStream::new(client);
+        packet_stream.set_compression(16, 6);
+
+        // When
+        packet_stream
+            .write_packet(RawPacket::new(vec![42; 256]).unwrap())
+            .await
+            .unwrap();
+
+        // Then
+        assert!(packet_stream.is_compression_enabled());
+        assert!(packet_stream.bytes_written() > 0);
+        assert!(packet_stream.bytes_written() < 256);
+    }
+
+    #[tokio::test]
+    async fn test_bytes_read_before_decompression() {
+        // Given
+        let (client, server) = tokio::io::duplex(1024);
+        let mut writer = PacketStream::new(client);
+        let mut reader = PacketStream::new(server);
+        writer.set_compression(16, 6);
+        reader.set_compression(16, 6);
+        writer
+            .write_packet(RawPacket::new(vec![42; 256]).unwrap())
+            .await
+            .unwrap();
+
+        // When
+        let packet = reader.read_packet().await.unwrap();
+
+        // Then
+        assert_eq!(packet.size(), 256);
+        assert_eq!(reader.bytes_read(), writer.bytes_written());
+    }
+
+    // Encryption Tests
+
+    #[tokio::test]
+    async fn test_encrypted_round_trip() {
+        // Given
+        let secret = [7u8; 16];
+        let (client, server) = tokio::io::duplex(64);
+        let mut writer = PacketStream::new(client);
+        let mut reader = PacketStream::new(server);
+        writer.set_encryption(&secret).unwrap();
+        reader.set_encryption(&secret).unwrap();
+
+        // When
+        writer
+            .write_packet(RawPacket::new(vec![42, 84]).unwrap())
+            .await
+            .unwrap();
+        writer
+            .write_packet(RawPacket::new(vec![80, 1, 2, 3]).unwrap())
+            .await
+            .unwrap();
+        let packet_1 = reader.read_packet().await.unwrap();
+        let packet_2 = reader.read_packet().await.unwrap();
+
+        // Then
+        assert_eq!(packet_1.packet_id(), Some(42));
+        assert_eq!(packet_1.data(), &[84]);
+        assert_eq!(packet_2.packet_id(), Some(80));
+        assert_eq!(packet_2.data(), &[1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_bytes_differ_from_plaintext() {
+        // Given
+        let (client, mut server) = tokio::io::duplex(64);
+        let mut writer = PacketStream::new(client);
+        writer.set_encryption(&[7u8; 16]).unwrap();
+
+        // When
+        writer
+            .write_packet(RawPacket::new(vec![42, 84]).unwrap())
+            .await
+            .unwrap();
+        let mut bytes = [0u8; 3];
+        server.read_exact(&mut bytes).await.unwrap();
+
+        // Then
+        assert_ne!(bytes, [2, 42, 84]);
+    }
+
+    #[tokio::test]
+    async fn test_encryption_enabled_after_bytes_were_received() {
+        // Given
+        let secret = [7u8; 16];
+        let (client, server) = tokio::io::duplex(64);
+        let mut writer = PacketStream::new(client);
+        let mut reader = PacketStream::new(server);
+        writer
+            .write_packet(RawPacket::new(vec![1, 2]).unwrap())
+            .await
+            .unwrap();
+        writer.set_encryption(&secret).unwrap();
+        writer
+            .write_packet(RawPacket::new(vec![42, 84]).unwrap())
+            .await
+            .unwrap();
+
+        // When
+        let packet_1 = reader.read_packet().await.unwrap();
+        reader.set_encryption(&secret).unwrap();
+        let packet_2 = reader.read_packet().await.unwrap();
+
+        // Then
+        assert_eq!(packet_1.packet_id(), Some(1));
+        assert_eq!(packet_2.packet_id(), Some(42));
+        assert_eq!(packet_2.data(), &[84]);
+    }
+
+    #[test]
+    fn test_set_encryption_rejects_invalid_secret() {
+        // Given
+        let stream = tokio_test::io::Builder::new().build();
+        let mut packet_stream = PacketStream::new(stream);
+
+        // When
+        let result = packet_stream.set_encryption(&[0u8; 4]);
+
+        // Then
+        assert!(matches!(
+            result,
+            Err(PacketStreamError::InvalidSharedSecret)
+        ));
+    }
 }
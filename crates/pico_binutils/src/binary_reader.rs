@@ -38,6 +38,13 @@ impl<'a> BinaryReader<'a> {
     }
 }
 
+/// Lets decoders of nested formats consume exactly the bytes they need.
+impl Read for BinaryReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum BinaryReaderError {
     #[error("unexpected eof")]
@@ -142,4 +149,16 @@ mod tests {
 
         assert_eq!(parsed.into_inner(), "HELLO");
     }
+
+    #[test]
+    fn test_read_trait_consumes_only_the_requested_bytes() {
+        let data = [0x01, 0x02, 0x03, 0x7F];
+        let mut reader = BinaryReader::new(&data);
+        let mut buf = [0u8; 3];
+        Read::read_exact(&mut reader, &mut buf).unwrap();
+
+        assert_eq!(buf, [0x01, 0x02, 0x03]);
+        assert_eq!(reader.read::<i8>().unwrap(), 127);
+        assert_eq!(reader.remaining(), 0);
+    }
 }
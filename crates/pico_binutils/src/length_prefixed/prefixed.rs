@@ -39,3 +39,6 @@ pub type IntPrefixed<T> = Prefixed<i32, T>;
 
 /// Strings in NBT format are prefixed with their length as a UShort
 pub type UShortPrefixed<T> = Prefixed<u16, T>;
+
+/// Byte arrays in the 1.7 network format are prefixed with their length as a Short
+pub type ShortPrefixed<T> = Prefixed<i16, T>;
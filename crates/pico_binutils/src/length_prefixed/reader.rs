@@ -70,3 +70,10 @@ impl ReadLengthPrefix for u16 {
         Ok(reader.read::<u16>()?.into())
     }
 }
+
+impl ReadLengthPrefix for i16 {
+    fn read_to_usize(reader: &mut BinaryReader) -> Result<usize, BinaryReaderError> {
+        let len = reader.read::<i16>()?;
+        from_i32(len.into())
+    }
+}
@@ -87,3 +87,15 @@ impl WriteLengthPrefix for u16 {
         writer.write(&len_u16)
     }
 }
+
+impl WriteLengthPrefix for i16 {
+    fn write_from_usize(writer: &mut BinaryWriter, len: usize) -> Result<(), BinaryWriterError> {
+        let len_i16: i16 = len.try_into().map_err(|_| {
+            BinaryWriterError::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Length exceeds i16::MAX",
+            ))
+        })?;
+        writer.write(&len_i16)
+    }
+}
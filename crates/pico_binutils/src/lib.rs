@@ -19,7 +19,9 @@ pub mod prelude {
     #[cfg(feature = "binary_writer")]
     pub use crate::binary_writer::{BinaryWriter, BinaryWriterError, WriteBytes};
     #[cfg(feature = "length_prefixed")]
-    pub use crate::length_prefixed::prefixed::{IntPrefixed, Prefixed, UShortPrefixed};
+    pub use crate::length_prefixed::prefixed::{
+        IntPrefixed, Prefixed, ShortPrefixed, UShortPrefixed,
+    };
     #[cfg(feature = "length_prefixed")]
     pub use crate::length_prefixed::reader::ReadLengthPrefix;
     #[cfg(all(feature = "length_prefixed", feature = "var_int"))]
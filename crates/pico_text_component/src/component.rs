@@ -57,6 +57,20 @@ impl Component {
         .unwrap_or_default()
     }
 
+    /// Text with `§` formatting codes, as understood by pre-1.7 clients.
+    pub fn to_legacy_text(&self) -> String {
+        self.to_legacy_impl(true)
+    }
+
+    /// Text without any formatting.
+    pub fn to_plain_text(&self) -> String {
+        let mut s = self.text.clone();
+        for extra in &self.extra {
+            s.push_str(&extra.to_plain_text());
+        }
+        s
+    }
+
     fn to_legacy_impl(&self, is_root: bool) -> String {
         let mut s = String::new();
 
@@ -37,17 +37,29 @@ export default defineConfig({
 				text: "Configuration",
 				items: [
 					{ text: "Introduction", link: "/config/introduction.html" },
+					{ text: "AFK and Session Limit", link: "/config/afk-and-session-limit.html" },
+					{ text: "Announcements", link: "/config/announcements.html" },
+					{ text: "Backend Watcher", link: "/config/backend-watcher.html" },
 					{ text: "Boss Bar", link: "/config/boss-bar.html" },
 					{ text: "Commands", link: "/config/commands.html" },
 					{ text: "Compression", link: "/config/compression.html" },
 					{ text: "Connection", link: "/config/connection.html" },
+					{ text: "Connection Limits", link: "/config/connection-limits.html" },
 					{ text: "Fly", link: "/config/fly.html" },
+					{ text: "Metrics", link: "/config/metrics.html" },
+					{ text: "Online Mode", link: "/config/online-mode.html" },
 					{ text: "Proxy Integration", link: "/config/proxy-integration.html" },
+					{ text: "PROXY Protocol", link: "/config/proxy-protocol.html" },
+					{ text: "Query", link: "/config/query.html" },
+					{ text: "Queue", link: "/config/queue.html" },
+					{ text: "RCON", link: "/config/rcon.html" },
+					{ text: "Recording", link: "/config/recording.html" },
 					{ text: "Schematic Loading", link: "/config/schematic-loading.html" },
 					{ text: "Server List", link: "/config/server-list.html" },
 					{ text: "Server Settings", link: "/config/server-settings.html" },
 					{ text: "Tab List", link: "/config/tab-list.html" },
 					{ text: "Title", link: "/config/title.html" },
+					{ text: "Whitelist and Bans", link: "/config/whitelist-and-bans.html" },
 					{ text: "World", link: "/config/world.html" },
 					{
 						text: "Default Configuration",
@@ -74,6 +86,7 @@ export default defineConfig({
 				items: [
 					{ text: "Customizing Server Brand", link: "/developer/customizing-server-brand.html" },
 					{ text: "Building Java Wrapper", link: "/developer/building-java-wrapper.html" },
+					{ text: "Load Testing", link: "/developer/load-testing.html" },
 				],
 			},
 		],
@@ -0,0 +1,378 @@
+use crate::packet_registry::{
+    PacketRegistry, PacketRegistryDecodeError, PacketRegistryEncodeError,
+};
+use minecraft_packets::configuration::acknowledge_finish_configuration_packet::AcknowledgeConfigurationPacket;
+use minecraft_packets::configuration::server_bound_known_packs_packet::ServerBoundKnownPacksPacket;
+use minecraft_packets::handshaking::handshake_packet::HandshakePacket;
+use minecraft_packets::login::custom_query_answer_packet::CustomQueryAnswerPacket;
+use minecraft_packets::login::login_acknowledged_packet::LoginAcknowledgedPacket;
+use minecraft_packets::login::login_state_packet::LoginStartPacket;
+use minecraft_packets::play::server_bound_keep_alive_packet::ServerBoundKeepAlivePacket;
+use minecraft_packets::play::set_player_position_packet::SetPlayerPositionPacket;
+use minecraft_protocol::prelude::{ProtocolVersion, State, Uuid, VarInt};
+use net::packet_stream::{PacketStream, PacketStreamError};
+use net::raw_packet::RawPacket;
+use std::f64::consts::TAU;
+use std::io;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncWrite, BufStream};
+use tokio::net::TcpStream;
+use tokio::time::{Instant, MissedTickBehavior, interval, sleep_until};
+
+/// Compression level of the packets sent once the server enabled compression.
+const COMPRESSION_LEVEL: u32 = 6;
+/// Interval between two positions sent while walking, one game tick.
+const WALK_INTERVAL: Duration = Duration::from_millis(50);
+const WALK_RADIUS: f64 = 3.0;
+/// Walking speed of a player, in blocks per second.
+const WALK_SPEED: f64 = 4.317;
+
+pub struct BotSettings {
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    pub protocol_version: ProtocolVersion,
+    pub login_timeout: Duration,
+    pub duration: Duration,
+    pub walk: bool,
+}
+
+/// Outcome of a single bot.
+pub struct BotReport {
+    pub protocol_version: ProtocolVersion,
+    /// Time from the connection to the first position sent by the server, once joined.
+    pub login_latency: Option<Duration>,
+    pub failure: Option<String>,
+    pub bytes_received: u64,
+}
+
+#[derive(Debug, Error)]
+pub enum BotError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Stream(#[from] PacketStreamError),
+    #[error(transparent)]
+    Encode(#[from] PacketRegistryEncodeError),
+    #[error(transparent)]
+    Decode(#[from] PacketRegistryDecodeError),
+    #[error("the server is in online mode")]
+    OnlineMode,
+    #[error("disconnected: {0}")]
+    Disconnected(String),
+    #[error("connection closed by the server")]
+    ConnectionClosed,
+    #[error("did not join the game in time")]
+    LoginTimedOut,
+}
+
+/// Connects a bot, keeps it in game for the configured duration and reports how it went.
+pub async fn run(settings: BotSettings) -> BotReport {
+    let started_at = Instant::now();
+    let protocol_version = settings.protocol_version;
+    match TcpStream::connect((settings.host.as_str(), settings.port)).await {
+        Ok(stream) => {
+            let mut bot = Bot::new(stream, protocol_version, started_at);
+            let result = bot.play(&settings).await;
+            BotReport {
+                protocol_version,
+                login_latency: bot.login_latency,
+                failure: result.err().map(|err| err.to_string()),
+                bytes_received: bot.stream.bytes_read(),
+            }
+        }
+        Err(err) => BotReport {
+            protocol_version,
+            login_latency: None,
+            failure: Some(err.to_string()),
+            bytes_received: 0,
+        },
+    }
+}
+
+enum Event {
+    Packet(Result<RawPacket, PacketStreamError>),
+    Walk,
+    Deadline,
+}
+
+struct Bot<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    stream: PacketStream<BufStream<S>>,
+    protocol_version: ProtocolVersion,
+    state: State,
+    started_at: Instant,
+    login_latency: Option<Duration>,
+    spawn_position: Option<(f64, f64, f64)>,
+}
+
+impl<S> Bot<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    fn new(stream: S, protocol_version: ProtocolVersion, started_at: Instant) -> Self {
+        Self {
+            // Writes each packet at once like real clients, instead of its length apart
+            stream: PacketStream::new(BufStream::new(stream)),
+            protocol_version,
+            state: State::Handshake,
+            started_at,
+            login_latency: None,
+            spawn_position: None,
+        }
+    }
+
+    /// Logs in, then answers the server until the duration elapsed.
+    async fn play(&mut self, settings: &BotSettings) -> Result<(), BotError> {
+        self.login(settings).await?;
+
+        let mut walk = interval(WALK_INTERVAL);
+        walk.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        let mut walked_ticks = 0_u32;
+
+        loop {
+            let is_walking = settings.walk && self.spawn_position.is_some();
+            let deadline = self
+                .login_latency
+                .map_or(self.started_at + settings.login_timeout, |login_latency| {
+                    self.started_at + login_latency + settings.duration
+                });
+
+            // Reading a packet is cancel safe, a packet received halfway is kept for the next read
+            let event = tokio::select! {
+                raw_packet = self.stream.read_packet() => Event::Packet(raw_packet),
+                _ = walk.tick(), if is_walking => Event::Walk,
+                () = sleep_until(deadline) => Event::Deadline,
+            };
+
+            match event {
+                Event::Packet(Err(PacketStreamError::Io(err)))
+                    if err.kind() == io::ErrorKind::UnexpectedEof =>
+                {
+                    return Err(BotError::ConnectionClosed);
+                }
+                Event::Packet(raw_packet) => self.handle_packet(raw_packet?).await?,
+                Event::Walk => {
+                    walked_ticks = walked_ticks.wrapping_add(1);
+                    self.walk(walked_ticks).await?;
+                }
+                Event::Deadline if self.login_latency.is_some() => return Ok(()),
+                Event::Deadline => return Err(BotError::LoginTimedOut),
+            }
+        }
+    }
+
+    async fn login(&mut self, settings: &BotSettings) -> Result<(), BotError> {
+        self.send(PacketRegistry::Handshake(HandshakePacket {
+            protocol: VarInt::new(self.protocol_version.version_number()),
+            hostname: settings.host.clone(),
+            port: settings.port,
+            next_state: VarInt::new(2),
+        }))
+        .await?;
+        self.state = State::Login;
+        self.send(PacketRegistry::LoginStart(LoginStartPacket::new(
+            &settings.username,
+            Uuid::new_v4(),
+        )))
+        .await
+    }
+
+    async fn handle_packet(&mut self, raw_packet: RawPacket) -> Result<(), BotError> {
+        let packet =
+            match PacketRegistry::decode_packet(self.protocol_version, self.state, raw_packet) {
+                Ok(packet) => packet,
+                // Packets the bot does not need to understand
+                Err(PacketRegistryDecodeError::NoCorrespondingPacket(..)) => return Ok(()),
+                Err(err) => return Err(err.into()),
+            };
+
+        match packet {
+            PacketRegistry::EncryptionRequest(_) => return Err(BotError::OnlineMode),
+            PacketRegistry::CustomQuery(packet) => {
+                let answer = CustomQueryAnswerPacket::not_understood(packet.message_id);
+                self.send(PacketRegistry::CustomQueryAnswer(answer)).await?;
+            }
+            PacketRegistry::SetCompression(packet) => {
+                if let Ok(threshold) = usize::try_from(packet.threshold()) {
+                    self.stream.set_compression(threshold, COMPRESSION_LEVEL);
+                }
+            }
+            PacketRegistry::LoginFinished(_) | PacketRegistry::GameProfile(_) => {
+                if self.protocol_version.supports_configuration_state() {
+                    self.send(PacketRegistry::LoginAcknowledged(
+                        LoginAcknowledgedPacket {},
+                    ))
+                    .await?;
+                    self.state = State::Configuration;
+                } else {
+                    self.state = State::Play;
+                }
+            }
+            PacketRegistry::ClientBoundKnownPacks(packet) => {
+                let known_packs = ServerBoundKnownPacksPacket::new(packet.known_packs.into_inner());
+                self.send(PacketRegistry::ServerBoundKnownPacks(known_packs))
+                    .await?;
+            }
+            PacketRegistry::ConfigurationClientBoundKeepAlive(packet) => {
+                let answer =
+                    ServerBoundKeepAlivePacket::new(packet.decoded_id(self.protocol_version));
+                self.send(PacketRegistry::ConfigurationServerBoundKeepAlive(answer))
+                    .await?;
+            }
+            PacketRegistry::FinishConfiguration(_) => {
+                self.send(PacketRegistry::AcknowledgeConfiguration(
+                    AcknowledgeConfigurationPacket {},
+                ))
+                .await?;
+                self.state = State::Play;
+            }
+            PacketRegistry::SynchronizePlayerPosition(packet) => {
+                if self.login_latency.is_none() {
+                    self.login_latency = Some(self.started_at.elapsed());
+                }
+                self.spawn_position = Some((packet.x, packet.y, packet.z));
+            }
+            PacketRegistry::ClientBoundKeepAlive(packet) => {
+                let answer =
+                    ServerBoundKeepAlivePacket::new(packet.decoded_id(self.protocol_version));
+                self.send(PacketRegistry::ServerBoundKeepAlive(answer))
+                    .await?;
+            }
+            PacketRegistry::LoginDisconnect(packet) => {
+                return Err(BotError::Disconnected(packet.reason));
+            }
+            PacketRegistry::ConfigurationDisconnect(packet)
+            | PacketRegistry::PlayDisconnect(packet) => {
+                return Err(BotError::Disconnected(packet.reason(self.protocol_version)));
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Walks in a circle starting from the spawn, at the walking speed of a player.
+    async fn walk(&mut self, walked_ticks: u32) -> Result<(), BotError> {
+        let Some((x, y, z)) = self.spawn_position else {
+            return Ok(());
+        };
+        let distance = WALK_SPEED * WALK_INTERVAL.as_secs_f64() * f64::from(walked_ticks);
+        let angle = (distance / WALK_RADIUS) % TAU;
+        let position = SetPlayerPositionPacket::new(
+            WALK_RADIUS.mul_add(angle.cos(), x - WALK_RADIUS),
+            y,
+            WALK_RADIUS.mul_add(angle.sin(), z),
+            true,
+        );
+        self.send(PacketRegistry::SetPlayerPosition(position)).await
+    }
+
+    async fn send(&mut self, packet: PacketRegistry) -> Result<(), BotError> {
+        let raw_packet = packet.encode_packet(self.protocol_version)?;
+        self.stream.write_packet(raw_packet).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use minecraft_packets::login::game_profile_packet::GameProfilePacket;
+    use minecraft_packets::login::login_disconnect_packet::LoginDisconnectPacket;
+    use minecraft_packets::play::disconnect_packet::DisconnectPacket;
+    use minecraft_protocol::prelude::{BinaryWriter, EncodePacket};
+    use tokio::io::DuplexStream;
+
+    const LOGIN_DISCONNECT_PACKET_ID: u8 = 0x00;
+    const GAME_PROFILE_PACKET_ID_V1_8: u8 = 0x02;
+    const PLAY_DISCONNECT_PACKET_ID_V1_8: u8 = 0x40;
+
+    fn settings(protocol_version: ProtocolVersion) -> BotSettings {
+        BotSettings {
+            host: "localhost".to_string(),
+            port: 25565,
+            username: "Bot".to_string(),
+            protocol_version,
+            login_timeout: Duration::from_secs(10),
+            duration: Duration::from_secs(10),
+            walk: false,
+        }
+    }
+
+    fn connect(
+        protocol_version: ProtocolVersion,
+    ) -> (Bot<DuplexStream>, PacketStream<DuplexStream>) {
+        let (client, server) = tokio::io::duplex(1024);
+        let bot = Bot::new(client, protocol_version, Instant::now());
+        (bot, PacketStream::new(server))
+    }
+
+    fn encode(
+        packet_id: u8,
+        packet: &impl EncodePacket,
+        protocol_version: ProtocolVersion,
+    ) -> RawPacket {
+        let mut writer = BinaryWriter::new();
+        packet.encode(&mut writer, protocol_version).unwrap();
+        RawPacket::from_bytes(packet_id, &writer.into_inner())
+    }
+
+    #[tokio::test]
+    async fn test_login_disconnect_is_reported() {
+        // Given
+        let protocol_version = ProtocolVersion::V1_21_4;
+        let (mut bot, mut server) = connect(protocol_version);
+        let packet = LoginDisconnectPacket::text("Server is full");
+        let raw_packet = encode(LOGIN_DISCONNECT_PACKET_ID, &packet, protocol_version);
+        server.write_packet(raw_packet).await.unwrap();
+
+        // When
+        let result = bot.play(&settings(protocol_version)).await;
+
+        // Then
+        assert!(matches!(result, Err(BotError::Disconnected(reason)) if reason == packet.reason));
+        assert!(bot.login_latency.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_kick_in_game_is_reported() {
+        // Given
+        let protocol_version = ProtocolVersion::V1_8;
+        let (mut bot, mut server) = connect(protocol_version);
+        let game_profile = GameProfilePacket::new(Uuid::nil(), "Bot");
+        let raw_packet = encode(GAME_PROFILE_PACKET_ID_V1_8, &game_profile, protocol_version);
+        server.write_packet(raw_packet).await.unwrap();
+        let packet = DisconnectPacket::text("Kicked");
+        let raw_packet = encode(PLAY_DISCONNECT_PACKET_ID_V1_8, &packet, protocol_version);
+        server.write_packet(raw_packet).await.unwrap();
+
+        // When
+        let result = bot.play(&settings(protocol_version)).await;
+
+        // Then
+        let expected_reason = packet.reason(protocol_version);
+        assert!(matches!(result, Err(BotError::Disconnected(reason)) if reason == expected_reason));
+        assert_eq!(bot.state, State::Play);
+    }
+
+    #[tokio::test]
+    async fn test_closed_connection_is_reported() {
+        // Given
+        let protocol_version = ProtocolVersion::V1_21_4;
+        let (mut bot, mut server) = connect(protocol_version);
+        let settings = settings(protocol_version);
+        let close_after_login = async move {
+            server.read_packet().await.unwrap();
+            server.read_packet().await.unwrap();
+        };
+
+        // When
+        let (result, ()) = tokio::join!(bot.play(&settings), close_after_login);
+
+        // Then
+        assert!(matches!(result, Err(BotError::ConnectionClosed)));
+    }
+}
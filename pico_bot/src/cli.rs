@@ -0,0 +1,95 @@
+use clap::Parser;
+use minecraft_protocol::prelude::ProtocolVersion;
+
+#[derive(Parser, Clone)]
+#[command(
+    version,
+    about = "Headless bots joining a Minecraft server, to measure how it handles many players"
+)]
+pub struct Cli {
+    /// Address of the server to join
+    #[arg(
+        value_name = "ADDRESS",
+        default_value = "127.0.0.1:25565",
+        help = "Address of the server, as host:port"
+    )]
+    pub address: String,
+
+    /// Number of bots joining the server
+    #[arg(
+        short = 'n',
+        long = "bots",
+        value_name = "COUNT",
+        default_value_t = 10,
+        help = "Number of bots joining the server"
+    )]
+    pub bots: usize,
+
+    /// Protocol versions of the bots, assigned in turn
+    #[arg(
+        long = "versions",
+        value_name = "VERSIONS",
+        value_delimiter = ',',
+        value_parser = parse_protocol_version,
+        default_value = ProtocolVersion::latest().humanize(),
+        help = "Comma separated versions of the bots, such as 1.8,1.21.4"
+    )]
+    pub versions: Vec<ProtocolVersion>,
+
+    /// Delay between two bots joining, in milliseconds
+    #[arg(
+        long = "join-delay",
+        value_name = "MILLISECONDS",
+        default_value_t = 100,
+        help = "Delay between two bots joining, in milliseconds"
+    )]
+    pub join_delay_millis: u64,
+
+    /// Time each bot stays in game once joined, in seconds
+    #[arg(
+        short = 'd',
+        long = "duration",
+        value_name = "SECONDS",
+        default_value_t = 30,
+        help = "Time each bot stays in game, in seconds"
+    )]
+    pub duration_secs: u64,
+
+    /// Time a bot has to join the game before giving up, in seconds
+    #[arg(
+        long = "login-timeout",
+        value_name = "SECONDS",
+        default_value_t = 10,
+        help = "Time a bot has to join the game, in seconds"
+    )]
+    pub login_timeout_secs: u64,
+
+    /// If set to true, bots walk in circles around the spawn
+    #[arg(
+        long = "walk",
+        help = "Walk in circles around the spawn",
+        default_value_t = false
+    )]
+    pub walk: bool,
+
+    /// Prefix of the usernames, followed by the number of the bot
+    #[arg(
+        long = "username-prefix",
+        value_name = "PREFIX",
+        default_value = "Bot",
+        help = "Prefix of the usernames of the bots"
+    )]
+    pub username_prefix: String,
+}
+
+/// Accepts a version name, such as `1.21.4`, or a protocol version number, such as `769`.
+fn parse_protocol_version(value: &str) -> Result<ProtocolVersion, String> {
+    ProtocolVersion::ALL_VERSION
+        .iter()
+        .copied()
+        .filter(|version| !version.is_any() && !version.is_unsupported())
+        .find(|version| {
+            version.humanize() == value || version.version_number().to_string() == value
+        })
+        .ok_or_else(|| format!("unsupported version {value}"))
+}
@@ -0,0 +1,57 @@
+#![warn(clippy::all, clippy::pedantic, clippy::nursery)]
+mod bot;
+mod cli;
+mod packet_registry;
+mod report;
+
+use crate::bot::BotSettings;
+use crate::cli::Cli;
+use crate::report::Summary;
+use clap::Parser;
+use std::process::ExitCode;
+use std::time::Duration;
+use tokio::task::JoinSet;
+use tokio::time::sleep;
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let Some((host, port)) = cli
+        .address
+        .rsplit_once(':')
+        .and_then(|(host, port)| Some((host, port.parse::<u16>().ok()?)))
+    else {
+        eprintln!("Invalid address {}, expected host:port", cli.address);
+        return ExitCode::FAILURE;
+    };
+
+    println!(
+        "Joining {} with {} bots, one every {}ms",
+        cli.address, cli.bots, cli.join_delay_millis
+    );
+    let mut bots = JoinSet::new();
+    for (index, protocol_version) in cli.versions.iter().cycle().take(cli.bots).enumerate() {
+        if index > 0 {
+            sleep(Duration::from_millis(cli.join_delay_millis)).await;
+        }
+        bots.spawn(bot::run(BotSettings {
+            host: host.to_string(),
+            port,
+            username: format!("{}{index}", cli.username_prefix),
+            protocol_version: *protocol_version,
+            login_timeout: Duration::from_secs(cli.login_timeout_secs),
+            duration: Duration::from_secs(cli.duration_secs),
+            walk: cli.walk,
+        }));
+    }
+
+    let reports = bots.join_all().await;
+    let summary = Summary::new(&reports);
+    println!("{summary}");
+
+    if reports.iter().all(|report| report.failure.is_none()) {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
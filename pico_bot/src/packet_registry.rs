@@ -0,0 +1,171 @@
+use macros::PacketReport;
+use minecraft_packets::configuration::acknowledge_finish_configuration_packet::AcknowledgeConfigurationPacket;
+use minecraft_packets::configuration::client_bound_known_packs_packet::ClientBoundKnownPacksPacket;
+use minecraft_packets::configuration::finish_configuration_packet::FinishConfigurationPacket;
+use minecraft_packets::configuration::server_bound_known_packs_packet::ServerBoundKnownPacksPacket;
+use minecraft_packets::handshaking::handshake_packet::HandshakePacket;
+use minecraft_packets::login::custom_query_answer_packet::CustomQueryAnswerPacket;
+use minecraft_packets::login::custom_query_packet::CustomQueryPacket;
+use minecraft_packets::login::encryption_request_packet::EncryptionRequestPacket;
+use minecraft_packets::login::game_profile_packet::GameProfilePacket;
+use minecraft_packets::login::login_acknowledged_packet::LoginAcknowledgedPacket;
+use minecraft_packets::login::login_disconnect_packet::LoginDisconnectPacket;
+use minecraft_packets::login::login_state_packet::LoginStartPacket;
+use minecraft_packets::login::login_success_packet::LoginFinishedPacket;
+use minecraft_packets::login::set_compression_packet::SetCompressionPacket;
+use minecraft_packets::play::client_bound_keep_alive_packet::ClientBoundKeepAlivePacket;
+use minecraft_packets::play::disconnect_packet::DisconnectPacket;
+use minecraft_packets::play::server_bound_keep_alive_packet::ServerBoundKeepAlivePacket;
+use minecraft_packets::play::set_player_position_packet::SetPlayerPositionPacket;
+use minecraft_packets::play::synchronize_player_position_packet::SynchronizePlayerPositionPacket;
+use minecraft_protocol::prelude::*;
+use net::raw_packet::RawPacket;
+
+/// Packets exchanged by the bot, clientbound packets are decoded and serverbound packets encoded.
+#[derive(PacketReport)]
+#[packet_report(client)]
+pub enum PacketRegistry {
+    // Handshake packets
+    #[protocol_id(
+        state = "handshake",
+        bound = "serverbound",
+        name = "minecraft:intention"
+    )]
+    Handshake(HandshakePacket),
+
+    // Login packets
+    #[protocol_id(state = "login", bound = "serverbound", name = "minecraft:hello")]
+    LoginStart(LoginStartPacket),
+
+    #[protocol_id(
+        state = "login",
+        bound = "serverbound",
+        name = "minecraft:login_acknowledged"
+    )]
+    LoginAcknowledged(LoginAcknowledgedPacket),
+
+    #[protocol_id(
+        state = "login",
+        bound = "serverbound",
+        name = "minecraft:custom_query_answer"
+    )]
+    CustomQueryAnswer(CustomQueryAnswerPacket),
+
+    // Only the arrival of the packet matters
+    #[allow(dead_code)]
+    #[protocol_id(state = "login", bound = "clientbound", name = "minecraft:hello")]
+    EncryptionRequest(EncryptionRequestPacket),
+
+    #[protocol_id(
+        state = "login",
+        bound = "clientbound",
+        name = "minecraft:custom_query"
+    )]
+    CustomQuery(CustomQueryPacket),
+
+    // Only the arrival of the packet matters
+    #[allow(dead_code)]
+    #[protocol_id(
+        state = "login",
+        bound = "clientbound",
+        name = "minecraft:login_finished"
+    )]
+    LoginFinished(LoginFinishedPacket),
+
+    // Only the arrival of the packet matters
+    #[allow(dead_code)]
+    #[protocol_id(
+        state = "login",
+        bound = "clientbound",
+        name = "minecraft:game_profile"
+    )]
+    GameProfile(GameProfilePacket),
+
+    #[protocol_id(
+        state = "login",
+        bound = "clientbound",
+        name = "minecraft:login_disconnect"
+    )]
+    LoginDisconnect(LoginDisconnectPacket),
+
+    #[protocol_id(
+        state = "login",
+        bound = "clientbound",
+        name = "minecraft:login_compression"
+    )]
+    SetCompression(SetCompressionPacket),
+
+    // Configuration packets
+    #[protocol_id(
+        state = "configuration",
+        bound = "serverbound",
+        name = "minecraft:finish_configuration"
+    )]
+    AcknowledgeConfiguration(AcknowledgeConfigurationPacket),
+
+    #[protocol_id(
+        state = "configuration",
+        bound = "serverbound",
+        name = "minecraft:select_known_packs"
+    )]
+    ServerBoundKnownPacks(ServerBoundKnownPacksPacket),
+
+    #[protocol_id(
+        state = "configuration",
+        bound = "serverbound",
+        name = "minecraft:keep_alive"
+    )]
+    ConfigurationServerBoundKeepAlive(ServerBoundKeepAlivePacket),
+
+    #[protocol_id(
+        state = "configuration",
+        bound = "clientbound",
+        name = "minecraft:select_known_packs"
+    )]
+    ClientBoundKnownPacks(ClientBoundKnownPacksPacket),
+
+    #[protocol_id(
+        state = "configuration",
+        bound = "clientbound",
+        name = "minecraft:keep_alive"
+    )]
+    ConfigurationClientBoundKeepAlive(ClientBoundKeepAlivePacket),
+
+    #[protocol_id(
+        state = "configuration",
+        bound = "clientbound",
+        name = "minecraft:finish_configuration"
+    )]
+    FinishConfiguration(FinishConfigurationPacket),
+
+    #[protocol_id(
+        state = "configuration",
+        bound = "clientbound",
+        name = "minecraft:disconnect"
+    )]
+    ConfigurationDisconnect(DisconnectPacket),
+
+    // Play packets
+    #[protocol_id(
+        state = "play",
+        bound = "clientbound",
+        name = "minecraft:player_position"
+    )]
+    SynchronizePlayerPosition(SynchronizePlayerPositionPacket),
+
+    #[protocol_id(state = "play", bound = "clientbound", name = "minecraft:keep_alive")]
+    ClientBoundKeepAlive(ClientBoundKeepAlivePacket),
+
+    #[protocol_id(state = "play", bound = "clientbound", name = "minecraft:disconnect")]
+    PlayDisconnect(DisconnectPacket),
+
+    #[protocol_id(state = "play", bound = "serverbound", name = "minecraft:keep_alive")]
+    ServerBoundKeepAlive(ServerBoundKeepAlivePacket),
+
+    #[protocol_id(
+        state = "play",
+        bound = "serverbound",
+        name = "minecraft:move_player_pos"
+    )]
+    SetPlayerPosition(SetPlayerPositionPacket),
+}
@@ -0,0 +1,156 @@
+use crate::bot::BotReport;
+use minecraft_protocol::prelude::ProtocolVersion;
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+use std::time::Duration;
+
+/// Aggregated outcome of every bot.
+pub struct Summary {
+    bots: usize,
+    bots_per_version: BTreeMap<ProtocolVersion, usize>,
+    /// Sorted login latencies of the bots who joined.
+    login_latencies: Vec<Duration>,
+    disconnected_after_joining: usize,
+    bytes_received: u64,
+    /// Number of bots per failure.
+    failures: BTreeMap<String, usize>,
+}
+
+impl Summary {
+    pub fn new(reports: &[BotReport]) -> Self {
+        let mut bots_per_version = BTreeMap::new();
+        let mut login_latencies = Vec::new();
+        let mut disconnected_after_joining = 0;
+        let mut failures = BTreeMap::new();
+
+        for report in reports {
+            *bots_per_version.entry(report.protocol_version).or_insert(0) += 1;
+            if let Some(login_latency) = report.login_latency {
+                login_latencies.push(login_latency);
+                if report.failure.is_some() {
+                    disconnected_after_joining += 1;
+                }
+            }
+            if let Some(failure) = &report.failure {
+                *failures.entry(failure.clone()).or_insert(0) += 1;
+            }
+        }
+        login_latencies.sort_unstable();
+
+        Self {
+            bots: reports.len(),
+            bots_per_version,
+            login_latencies,
+            disconnected_after_joining,
+            bytes_received: reports.iter().map(|report| report.bytes_received).sum(),
+            failures,
+        }
+    }
+
+    pub const fn joined(&self) -> usize {
+        self.login_latencies.len()
+    }
+
+    /// Nearest-rank percentile of the login latencies.
+    fn login_latency_percentile(&self, percentile: usize) -> Option<Duration> {
+        let rank = (percentile * self.login_latencies.len()).div_ceil(100);
+        self.login_latencies.get(rank.saturating_sub(1)).copied()
+    }
+
+    fn average_login_latency(&self) -> Option<Duration> {
+        let joined = u32::try_from(self.joined())
+            .ok()
+            .filter(|joined| *joined > 0)?;
+        Some(self.login_latencies.iter().sum::<Duration>() / joined)
+    }
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let versions = self
+            .bots_per_version
+            .iter()
+            .map(|(version, bots)| format!("{}: {bots}", version.humanize()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        writeln!(f, "Bots: {} ({versions})", self.bots)?;
+        writeln!(
+            f,
+            "Joined: {}, failed to join: {}, disconnected after joining: {}",
+            self.joined(),
+            self.bots - self.joined(),
+            self.disconnected_after_joining
+        )?;
+
+        if let (Some(min), Some(average), Some(p50), Some(p95), Some(max)) = (
+            self.login_latencies.first(),
+            self.average_login_latency(),
+            self.login_latency_percentile(50),
+            self.login_latency_percentile(95),
+            self.login_latencies.last(),
+        ) {
+            writeln!(
+                f,
+                "Login latency: min {min:.1?}, average {average:.1?}, p50 {p50:.1?}, p95 {p95:.1?}, max {max:.1?}"
+            )?;
+        }
+
+        let bytes_per_bot = self
+            .bytes_received
+            .checked_div(self.bots as u64)
+            .unwrap_or(0);
+        write!(
+            f,
+            "Bytes received: {} ({bytes_per_bot} per bot)",
+            self.bytes_received
+        )?;
+
+        if !self.failures.is_empty() {
+            write!(f, "\nFailures:")?;
+            for (failure, bots) in &self.failures {
+                write!(f, "\n  {bots} × {failure}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(login_latency_millis: Option<u64>, failure: Option<&str>) -> BotReport {
+        BotReport {
+            protocol_version: ProtocolVersion::V1_21_4,
+            login_latency: login_latency_millis.map(Duration::from_millis),
+            failure: failure.map(String::from),
+            bytes_received: 1000,
+        }
+    }
+
+    #[test]
+    fn test_summary_of_bots() {
+        // Given
+        let mut reports: Vec<BotReport> = (1..=19)
+            .map(|millis| report(Some(millis * 10), None))
+            .collect();
+        reports.push(report(Some(1000), Some("disconnected: Kicked")));
+        reports.push(report(None, Some("connection closed by the server")));
+        reports.push(report(None, Some("connection closed by the server")));
+
+        // When
+        let summary = Summary::new(&reports);
+
+        // Then
+        assert_eq!(
+            summary.to_string(),
+            "Bots: 22 (1.21.4: 22)\n\
+             Joined: 20, failed to join: 2, disconnected after joining: 1\n\
+             Login latency: min 10.0ms, average 145.0ms, p50 100.0ms, p95 190.0ms, max 1.0s\n\
+             Bytes received: 22000 (1000 per bot)\n\
+             Failures:\n  \
+             2 × connection closed by the server\n  \
+             1 × disconnected: Kicked"
+        );
+    }
+}
@@ -171,6 +171,13 @@ impl Value {
     }
 }
 
+/// An empty compound, the usual root of a NBT document.
+impl Default for Value {
+    fn default() -> Self {
+        Self::Compound(IndexMap::new())
+    }
+}
+
 impl From<i8> for Value {
     fn from(v: i8) -> Self {
         Self::Byte(v)
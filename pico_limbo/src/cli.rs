@@ -1,4 +1,4 @@
-use clap::Parser;
+use clap::{Args, Parser, Subcommand};
 use std::path::PathBuf;
 
 #[derive(Parser, Clone)]
@@ -26,7 +26,7 @@ pub struct Cli {
     )]
     pub config_path: PathBuf,
 
-    /// Port to listen on. Defaults back to the port defined in the configuration file if not specified
+    /// Port to listen on, for every bind address. Defaults back to the ports defined in the configuration file if not specified
     #[arg(
         short = 'p',
         long = "port",
@@ -43,4 +43,36 @@ pub struct Cli {
         default_value_t = false
     )]
     pub skip_banner: bool,
+
+    /// If set to true, commands will not be read from the standard input
+    #[arg(
+        long = "no-console",
+        help = "Do not read console commands from the standard input",
+        default_value_t = false
+    )]
+    pub no_console: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Clone)]
+pub enum Command {
+    /// Print the packets of a connection recording
+    Replay(ReplayArgs),
+}
+
+#[derive(Args, Clone)]
+pub struct ReplayArgs {
+    /// Path to the recording to print
+    #[arg(value_name = "RECORDING_PATH")]
+    pub recording_path: PathBuf,
+
+    /// If set to true, the data of every packet will be printed in hexadecimal
+    #[arg(
+        long = "hex",
+        help = "Print the data of every packet in hexadecimal",
+        default_value_t = false
+    )]
+    pub hex: bool,
 }
@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+pub struct WhitelistConfig {
+    /// If set to true, only the players listed in the whitelist file can join.
+    pub enabled: bool,
+
+    /// JSON file listing the UUIDs or names of the allowed players.
+    pub file: PathBuf,
+
+    /// Kick message sent to players missing from the whitelist.
+    pub kick_message: String,
+}
+
+impl Default for WhitelistConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            file: PathBuf::from("whitelist.json"),
+            kick_message: "You are not whitelisted on this server!".into(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+pub struct BansConfig {
+    /// If set to true, the players and addresses listed in the ban files cannot join.
+    pub enabled: bool,
+
+    /// JSON file listing the banned UUIDs or names.
+    pub players_file: PathBuf,
+
+    /// JSON file listing the banned addresses or CIDR ranges.
+    pub ips_file: PathBuf,
+
+    /// Kick message sent to banned players, `{reason}` and `{expires}` are replaced.
+    pub kick_message: String,
+
+    /// Kick message sent to players joining from a banned address, `{reason}` and `{expires}` are replaced.
+    pub ip_kick_message: String,
+}
+
+impl Default for BansConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            players_file: PathBuf::from("banned-players.json"),
+            ips_file: PathBuf::from("banned-ips.json"),
+            kick_message:
+                "<red>You are banned from this server.</red>\nReason: {reason}\nExpires: {expires}"
+                    .into(),
+            ip_kick_message:
+                "<red>Your IP address is banned from this server.</red>\nReason: {reason}\nExpires: {expires}"
+                    .into(),
+        }
+    }
+}
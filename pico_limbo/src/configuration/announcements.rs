@@ -0,0 +1,96 @@
+use crate::configuration::boss_bar::{BossBarColorConfig, BossBarDivisionConfig};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+pub struct AnnouncementsConfig {
+    /// If set to true, the entries are announced to the players in game.
+    pub enabled: bool,
+
+    pub entries: Vec<AnnouncementConfig>,
+}
+
+impl Default for AnnouncementsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            entries: vec![AnnouncementConfig {
+                interval_seconds: Some(300),
+                schedule: None,
+                target: AnnouncementTargetConfig::Chat,
+                rotation: RotationConfig::Sequential,
+                messages: vec![
+                    "<gray>You are in the waiting room, please stay a moment.</gray>".into(),
+                ],
+                fade_in: None,
+                stay: None,
+                fade_out: None,
+                color: None,
+                division: None,
+                duration_seconds: None,
+            }],
+        }
+    }
+}
+
+/// Messages announced on a schedule, either every `interval_seconds` or following a cron expression.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AnnouncementConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interval_seconds: Option<u64>,
+
+    /// Cron expression with 5 fields: minute, hour, day of month, month and day of week, in UTC.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<String>,
+
+    #[serde(default)]
+    pub target: AnnouncementTargetConfig,
+
+    #[serde(default)]
+    pub rotation: RotationConfig,
+
+    /// One message is announced each time, a title message is split in a title and a subtitle on the first line break.
+    pub messages: Vec<String>,
+
+    /// Title fade in, in ticks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fade_in: Option<i32>,
+
+    /// Title stay, in ticks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stay: Option<i32>,
+
+    /// Title fade out, in ticks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fade_out: Option<i32>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<BossBarColorConfig>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub division: Option<BossBarDivisionConfig>,
+
+    /// How long the boss bar stays displayed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_seconds: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnouncementTargetConfig {
+    #[default]
+    Chat,
+    ActionBar,
+    Title,
+    BossBar,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum RotationConfig {
+    #[default]
+    Sequential,
+    Random,
+}
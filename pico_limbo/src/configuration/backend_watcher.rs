@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+pub struct BackendWatcherConfig {
+    /// If set to true, the targets are pinged and players are moved to the first one online.
+    pub enabled: bool,
+
+    /// How often the targets are pinged.
+    pub interval_seconds: u64,
+
+    /// How many players are moved each second once a target is online.
+    pub transfers_per_second: u32,
+
+    /// How players prior to 1.20.5, which cannot be transferred, are moved.
+    pub older_clients: OlderClientsConfig,
+
+    /// Chat message sent to players who cannot be moved, `{host}` and `{port}` are replaced.
+    pub chat_prompt: String,
+
+    /// Targets ordered by priority.
+    pub targets: Vec<BackendTargetConfig>,
+}
+
+impl Default for BackendWatcherConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_seconds: 10,
+            transfers_per_second: 5,
+            older_clients: OlderClientsConfig::default(),
+            chat_prompt: "<green>The server is back online, reconnect to {host}:{port}!</green>"
+                .into(),
+            targets: vec![BackendTargetConfig {
+                host: "127.0.0.1".into(),
+                port: 25565,
+                proxy_server: None,
+            }],
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BackendTargetConfig {
+    pub host: String,
+
+    pub port: u16,
+
+    /// Name of the server in the proxy configuration, used to move older clients through the proxy.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy_server: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum OlderClientsConfig {
+    /// Sends the chat prompt.
+    #[default]
+    Chat,
+    /// Asks the `BungeeCord` or Velocity proxy to connect the player to the `proxy_server` of the target.
+    ProxyConnect,
+}
@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+
+/// One address, or a list of addresses served by the same server.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum BindConfig {
+    Single(String),
+    Multiple(Vec<String>),
+}
+
+impl BindConfig {
+    pub fn addresses(&self) -> Vec<&str> {
+        match self {
+            Self::Single(address) => vec![address.as_str()],
+            Self::Multiple(addresses) => addresses.iter().map(String::as_str).collect(),
+        }
+    }
+}
+
+impl Default for BindConfig {
+    fn default() -> Self {
+        Self::Single("0.0.0.0:25565".into())
+    }
+}
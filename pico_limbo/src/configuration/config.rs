@@ -1,14 +1,28 @@
+use crate::configuration::access_lists::{BansConfig, WhitelistConfig};
+use crate::configuration::announcements::AnnouncementsConfig;
+use crate::configuration::backend_watcher::BackendWatcherConfig;
+use crate::configuration::bind::BindConfig;
 use crate::configuration::boss_bar::BossBarConfig;
 use crate::configuration::commands::CommandsConfig;
 use crate::configuration::compression::CompressionConfig;
 use crate::configuration::connection_config::ConnectionConfig;
+use crate::configuration::connection_limits::ConnectionLimitsConfig;
 use crate::configuration::env_placeholders::{EnvPlaceholderError, expand_env_placeholders};
 use crate::configuration::fly_config::FlyConfig;
 use crate::configuration::forwarding::ForwardingConfig;
 use crate::configuration::game_mode_config::GameModeConfig;
+use crate::configuration::metrics::MetricsConfig;
+use crate::configuration::online_mode::OnlineModeConfig;
+use crate::configuration::proxy_protocol::ProxyProtocolConfig;
+use crate::configuration::query::QueryConfig;
+use crate::configuration::queue::QueueConfig;
+use crate::configuration::rcon::RconConfig;
+use crate::configuration::recording::RecordingConfig;
 use crate::configuration::server_list::ServerListConfig;
+use crate::configuration::session_timeouts::{AfkConfig, SessionLimitConfig};
 use crate::configuration::tab_list::TabListConfig;
 use crate::configuration::title::TitleConfig;
+use crate::configuration::unix_socket::UnixSocketConfig;
 use crate::configuration::world_config::WorldConfig;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
@@ -38,12 +52,18 @@ pub enum ConfigError {
 pub struct Config {
     /// Server listening address and port.
     ///
-    /// Specify the IP address and port the server should bind to.
+    /// Specify the IP address and port the server should bind to, or a list of them.
     /// Use 0.0.0.0 to listen on all network interfaces.
-    pub bind: String,
+    pub bind: BindConfig,
 
     pub forwarding: ForwardingConfig,
 
+    pub online_mode: OnlineModeConfig,
+
+    pub proxy_protocol: ProxyProtocolConfig,
+
+    pub connection_limits: ConnectionLimitsConfig,
+
     pub world: WorldConfig,
 
     pub server_list: ServerListConfig,
@@ -74,22 +94,54 @@ pub struct Config {
 
     pub accept_transfers: bool,
 
+    /// Reload the configuration when this file is modified.
+    ///
+    /// The configuration can also be reloaded by sending SIGHUP to the process.
+    pub watch_config_file: bool,
+
     pub boss_bar: BossBarConfig,
 
     pub title: TitleConfig,
 
     pub commands: CommandsConfig,
+
+    pub metrics: MetricsConfig,
+
+    pub query: QueryConfig,
+
+    pub rcon: RconConfig,
+
+    pub unix_socket: UnixSocketConfig,
+
+    pub whitelist: WhitelistConfig,
+
+    pub bans: BansConfig,
+
+    pub announcements: AnnouncementsConfig,
+
+    pub backend_watcher: BackendWatcherConfig,
+
+    pub queue: QueueConfig,
+
+    pub afk: AfkConfig,
+
+    pub session_limit: SessionLimitConfig,
+
+    pub recording: RecordingConfig,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
-            bind: "0.0.0.0:25565".into(),
+            bind: BindConfig::default(),
             server_list: ServerListConfig::default(),
             connection: ConnectionConfig::default(),
             welcome_message: "Welcome to PicoLimbo!".into(),
             action_bar: "Welcome to PicoLimbo!".into(),
             forwarding: ForwardingConfig::default(),
+            online_mode: OnlineModeConfig::default(),
+            proxy_protocol: ProxyProtocolConfig::default(),
+            connection_limits: ConnectionLimitsConfig::default(),
             default_game_mode: GameModeConfig::default(),
             world: WorldConfig::default(),
             hardcore: false,
@@ -101,7 +153,20 @@ impl Default for Config {
             title: TitleConfig::default(),
             fly: FlyConfig::default(),
             accept_transfers: false,
+            watch_config_file: false,
             commands: CommandsConfig::default(),
+            metrics: MetricsConfig::default(),
+            query: QueryConfig::default(),
+            rcon: RconConfig::default(),
+            unix_socket: UnixSocketConfig::default(),
+            whitelist: WhitelistConfig::default(),
+            bans: BansConfig::default(),
+            announcements: AnnouncementsConfig::default(),
+            backend_watcher: BackendWatcherConfig::default(),
+            queue: QueueConfig::default(),
+            afk: AfkConfig::default(),
+            session_limit: SessionLimitConfig::default(),
+            recording: RecordingConfig::default(),
         }
     }
 }
@@ -118,9 +183,7 @@ pub fn load_or_create<P: AsRef<Path>>(path: P) -> Result<Config, ConfigError> {
         if raw_toml_str.trim().is_empty() {
             create_default_config(path)
         } else {
-            let expanded_toml_str = expand_env_placeholders(&raw_toml_str)?;
-            let cfg: Config = toml::from_str(expanded_toml_str.as_ref())?;
-            Ok(cfg)
+            parse(&raw_toml_str)
         }
     } else {
         if let Some(dir) = path.parent() {
@@ -130,6 +193,18 @@ pub fn load_or_create<P: AsRef<Path>>(path: P) -> Result<Config, ConfigError> {
     }
 }
 
+/// Loads a `Config` from an existing file, without ever writing to it.
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Config, ConfigError> {
+    let raw_toml_str = fs::read_to_string(path)?;
+    parse(&raw_toml_str)
+}
+
+fn parse(raw_toml_str: &str) -> Result<Config, ConfigError> {
+    let expanded_toml_str = expand_env_placeholders(raw_toml_str)?;
+    let cfg: Config = toml::from_str(expanded_toml_str.as_ref())?;
+    Ok(cfg)
+}
+
 fn create_default_config<P: AsRef<Path>>(path: P) -> Result<Config, ConfigError> {
     let cfg = Config::default();
     let toml_str = toml::to_string_pretty(&cfg)?;
@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Serialize)]
+#[serde(default)]
 #[serde(deny_unknown_fields)]
 pub struct ConnectionConfig {
     /// Interval between two `minecraft:keep_alive` packets sent to a client
@@ -14,6 +15,12 @@ pub struct ConnectionConfig {
     /// If set to true, `PicoLimbo` will attempt to use the latest protocol
     /// version for unsupported versions. Useful for snapshots.
     pub allow_unsupported_versions: bool,
+
+    /// Number of keep alive packets in a row a client may leave unanswered
+    /// before being disconnected. Set to 0 to never disconnect.
+    pub max_missed_keep_alives: u32,
+
+    pub timeouts: TimeoutsConfig,
 }
 
 impl Default for ConnectionConfig {
@@ -21,6 +28,43 @@ impl Default for ConnectionConfig {
         Self {
             keep_alive_interval_seconds: 15,
             allow_unsupported_versions: false,
+            max_missed_keep_alives: 3,
+            timeouts: TimeoutsConfig::default(),
+        }
+    }
+}
+
+/// Maximum time, in seconds, a client may spend in each state before being
+/// disconnected. Set a value to 0 to disable the matching timeout.
+#[derive(Deserialize, Serialize)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+pub struct TimeoutsConfig {
+    /// Time allowed to send the handshake after connecting.
+    pub handshake: u64,
+
+    /// Time allowed to complete a server list ping.
+    pub status: u64,
+
+    /// Time allowed to complete the login, including proxy forwarding and
+    /// session server authentication.
+    pub login: u64,
+
+    /// Time allowed to complete the configuration state (1.20.2+).
+    pub configuration: u64,
+
+    /// Time without receiving any packet from a player in PLAY state.
+    pub play_read: u64,
+}
+
+impl Default for TimeoutsConfig {
+    fn default() -> Self {
+        Self {
+            handshake: 5,
+            status: 10,
+            login: 30,
+            configuration: 30,
+            play_read: 30,
         }
     }
 }
@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+pub struct ConnectionLimitsConfig {
+    /// Throttle incoming connections before any packet is processed.
+    pub enabled: bool,
+
+    /// Length of the window used to count new connections per address, in seconds.
+    pub window_seconds: u64,
+
+    /// Maximum number of new connections per address during a window.
+    /// Set to 0 to disable the rate limit.
+    pub connections_per_window: u32,
+
+    /// Maximum number of simultaneous connections per address.
+    /// Set to 0 to disable the limit.
+    pub max_connections_per_address: u32,
+
+    /// Maximum number of connections that did not reach the PLAY state yet,
+    /// across all addresses. Set to 0 to disable the limit.
+    pub max_pending_connections: usize,
+}
+
+impl Default for ConnectionLimitsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            window_seconds: 10,
+            connections_per_window: 10,
+            max_connections_per_address: 5,
+            max_pending_connections: 256,
+        }
+    }
+}
@@ -1,9 +1,15 @@
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
 #[derive(Serialize, Deserialize, Default)]
 pub struct ModernForwardingConfig {
     enabled: bool,
+    #[serde(default)]
     secret: String,
+    #[serde(default)]
+    secrets: Vec<String>,
+    #[serde(default)]
+    secret_files: Vec<PathBuf>,
 }
 
 #[derive(Serialize, Deserialize, Default)]
@@ -33,7 +39,19 @@ pub enum TaggedForwarding {
     BungeeGuard { tokens: Vec<String> },
 
     #[serde(alias = "modern")]
-    Modern { secret: String },
+    Modern {
+        #[serde(default)]
+        secret: String,
+        /// Secrets accepted in addition to `secret`, to rotate them without downtime.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        secrets: Vec<String>,
+        /// Files containing a secret each, like the `forwarding.secret` file of Velocity.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        secret_files: Vec<PathBuf>,
+        /// Tokens of the `BungeeGuard` proxies accepted alongside the Velocity ones.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        bungee_guard_tokens: Vec<String>,
+    },
 }
 
 #[derive(Serialize, Deserialize)]
@@ -55,8 +73,17 @@ impl From<ForwardingConfig> for TaggedForwarding {
             ForwardingConfig::Tagged(forwarding) => forwarding,
             ForwardingConfig::Structured(forwarding) => {
                 if forwarding.velocity.enabled {
+                    let bungee_guard_tokens =
+                        if forwarding.bungee_cord.enabled && forwarding.bungee_cord.bungee_guard {
+                            forwarding.bungee_cord.tokens
+                        } else {
+                            Vec::new()
+                        };
                     Self::Modern {
                         secret: forwarding.velocity.secret,
+                        secrets: forwarding.velocity.secrets,
+                        secret_files: forwarding.velocity.secret_files,
+                        bungee_guard_tokens,
                     }
                 } else if forwarding.bungee_cord.enabled {
                     if forwarding.bungee_cord.bungee_guard {
@@ -1,16 +1,30 @@
+pub mod access_lists;
+pub mod announcements;
+pub mod backend_watcher;
+pub mod bind;
 pub mod boss_bar;
 pub mod commands;
 mod compression;
 pub mod config;
-mod connection_config;
+pub mod connection_config;
+mod connection_limits;
 mod env_placeholders;
 pub mod fly_config;
 mod forwarding;
 mod game_mode_config;
+mod metrics;
+mod online_mode;
+mod proxy_protocol;
+mod query;
+pub mod queue;
+mod rcon;
+mod recording;
 mod require_boolean;
 mod server_list;
+pub mod session_timeouts;
 pub mod tab_list;
 pub mod title;
+mod unix_socket;
 pub mod world_config;
 
 pub use forwarding::TaggedForwarding;
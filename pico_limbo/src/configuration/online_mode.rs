@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+pub struct OnlineModeConfig {
+    /// Authenticates players against the session server and encrypts the connection.
+    /// Only used when forwarding is disabled, proxies already authenticate players.
+    pub enabled: bool,
+
+    /// URL of the session server's `hasJoined` endpoint.
+    pub has_joined_url: String,
+}
+
+impl Default for OnlineModeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            has_joined_url: "https://sessionserver.mojang.com/session/minecraft/hasJoined".into(),
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+pub struct ProxyProtocolConfig {
+    /// Expect a PROXY protocol v1 or v2 header from trusted sources.
+    pub enabled: bool,
+
+    /// Addresses or CIDR ranges allowed to send a PROXY protocol header.
+    /// Connections from other addresses are treated as direct connections.
+    pub trusted_sources: Vec<String>,
+}
+
+impl Default for ProxyProtocolConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            trusted_sources: vec!["127.0.0.1/32".into(), "::1/128".into()],
+        }
+    }
+}
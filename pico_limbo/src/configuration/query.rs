@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+pub struct QueryConfig {
+    /// Answer the `GameSpy4` UDP query protocol used by server lists and monitoring tools.
+    pub enabled: bool,
+
+    /// Address and UDP port of the query listener.
+    /// It can share the port of the server, since the server listens on TCP.
+    pub bind: String,
+}
+
+impl Default for QueryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind: "0.0.0.0:25565".into(),
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use crate::configuration::boss_bar::BossBarColorConfig;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+pub struct QueueConfig {
+    /// If set to true, players wait in a queue and are released in batches to the target.
+    pub enabled: bool,
+
+    /// Server the players are released to, also pinged before each batch.
+    pub host: String,
+
+    pub port: u16,
+
+    /// Name of the target in the proxy configuration, required by the `proxy_connect` method.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy_server: Option<String>,
+
+    pub method: QueueMethodConfig,
+
+    /// How many players are released at once.
+    pub batch_size: u32,
+
+    /// Delay between two batches.
+    pub batch_interval_seconds: u64,
+
+    pub display: QueueDisplayConfig,
+
+    /// Displayed to each player, `{position}`, `{size}` and `{eta}` are replaced.
+    pub message: String,
+
+    /// Color of the boss bar when displayed in the boss bar.
+    pub color: BossBarColorConfig,
+
+    /// Kick message releasing the players who can neither be transferred nor moved by the proxy, `{host}` and `{port}` are replaced.
+    pub kick_message: String,
+
+    /// Players listed in a tier are queued ahead of the following tiers and of everyone else.
+    pub tiers: Vec<QueueTierConfig>,
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: "127.0.0.1".into(),
+            port: 25565,
+            proxy_server: None,
+            method: QueueMethodConfig::default(),
+            batch_size: 5,
+            batch_interval_seconds: 10,
+            display: QueueDisplayConfig::default(),
+            message: "<gold>Position in queue: <bold>{position}</bold>/{size}</gold> <gray>Estimated time: {eta}</gray>".into(),
+            color: BossBarColorConfig::Yellow,
+            kick_message: "<green>It is your turn!</green>\nConnect to {host}:{port}".into(),
+            tiers: Vec::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct QueueTierConfig {
+    /// UUIDs of the players in this tier.
+    pub uuids: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueMethodConfig {
+    /// Transfers players since 1.20.5, older clients go through the proxy when `proxy_server` is set.
+    #[default]
+    Transfer,
+    /// Asks the `BungeeCord` or Velocity proxy to connect every player to `proxy_server`.
+    ProxyConnect,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueDisplayConfig {
+    #[default]
+    ActionBar,
+    BossBar,
+}
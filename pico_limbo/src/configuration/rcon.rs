@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+pub struct RconConfig {
+    /// Accept remote commands through the Source RCON protocol.
+    pub enabled: bool,
+
+    /// Address and port of the RCON listener.
+    pub bind: String,
+
+    /// Password required to log in, RCON is not started when it is empty.
+    pub password: String,
+}
+
+impl Default for RconConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind: "0.0.0.0:25575".into(),
+            password: String::new(),
+        }
+    }
+}
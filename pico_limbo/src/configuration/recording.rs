@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+pub struct RecordingConfig {
+    /// Record every packet exchanged with each connection, to replay them with `pico_limbo replay`.
+    pub enabled: bool,
+
+    /// Directory where a recording file is created for each connection.
+    pub directory: String,
+}
+
+impl Default for RecordingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            directory: "recordings".into(),
+        }
+    }
+}
+
+/// Directory of the recordings, when enabled.
+impl From<RecordingConfig> for Option<PathBuf> {
+    fn from(config: RecordingConfig) -> Self {
+        config.enabled.then(|| PathBuf::from(config.directory))
+    }
+}
@@ -10,6 +10,15 @@ pub struct ServerListConfig {
     /// Maximum amount of player displayed in the server list.
     pub max_players: u32,
 
+    /// If set to true, players are kicked at login once `max_players` are online.
+    pub enforce_max_players: bool,
+
+    /// Kick message sent to players joining a full server.
+    pub full_server_message: String,
+
+    /// UUIDs or usernames allowed to join a full server.
+    pub max_players_bypass: Vec<String>,
+
     /// Description of the server displayed in the server list.
     pub message_of_the_day: String,
 
@@ -24,6 +33,9 @@ impl Default for ServerListConfig {
         Self {
             reply_to_status: true,
             max_players: 20,
+            enforce_max_players: false,
+            full_server_message: "The server is full!".into(),
+            max_players_bypass: Vec::new(),
             message_of_the_day: "A PicoLimbo Server".into(),
             show_online_player_count: true,
             server_icon: PathBuf::from("server-icon.png"),
@@ -0,0 +1,121 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+pub struct AfkConfig {
+    /// If set to true, players who neither move, look around nor chat are kicked or transferred.
+    pub enabled: bool,
+
+    /// How long a player can stay idle.
+    pub timeout_seconds: u64,
+
+    /// How long before the timeout the warning is displayed, 0 disables the warning.
+    pub warning_seconds: u64,
+
+    pub warning_display: WarningDisplayConfig,
+
+    /// Warning displayed to the player, `{seconds}` is replaced by the seconds left.
+    pub warning_message: String,
+
+    pub action: TimeoutActionConfig,
+
+    /// Kick message, also used for the players who cannot be transferred.
+    pub kick_message: String,
+
+    /// Server the players are transferred to by the `transfer` action.
+    pub host: String,
+
+    pub port: u16,
+
+    /// Name of the server in the proxy configuration, used to move clients prior to 1.20.5 through the proxy.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy_server: Option<String>,
+}
+
+impl Default for AfkConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            timeout_seconds: 300,
+            warning_seconds: 30,
+            warning_display: WarningDisplayConfig::default(),
+            warning_message:
+                "<yellow>You are idle and will be disconnected in {seconds} seconds.</yellow>"
+                    .into(),
+            action: TimeoutActionConfig::default(),
+            kick_message: "<red>You were idle for too long.</red>".into(),
+            host: "127.0.0.1".into(),
+            port: 25565,
+            proxy_server: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+pub struct SessionLimitConfig {
+    /// If set to true, players are kicked or transferred once they stayed for the maximum duration.
+    pub enabled: bool,
+
+    /// How long a player can stay in game.
+    pub max_duration_seconds: u64,
+
+    /// How long before the end of the session the warning is displayed, 0 disables the warning.
+    pub warning_seconds: u64,
+
+    pub warning_display: WarningDisplayConfig,
+
+    /// Warning displayed to the player, `{seconds}` is replaced by the seconds left.
+    pub warning_message: String,
+
+    pub action: TimeoutActionConfig,
+
+    /// Kick message, also used for the players who cannot be transferred.
+    pub kick_message: String,
+
+    /// Server the players are transferred to by the `transfer` action.
+    pub host: String,
+
+    pub port: u16,
+
+    /// Name of the server in the proxy configuration, used to move clients prior to 1.20.5 through the proxy.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy_server: Option<String>,
+}
+
+impl Default for SessionLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_duration_seconds: 3600,
+            warning_seconds: 60,
+            warning_display: WarningDisplayConfig::default(),
+            warning_message: "<yellow>Your session ends in {seconds} seconds.</yellow>".into(),
+            action: TimeoutActionConfig::default(),
+            kick_message: "<red>Your session has ended.</red>".into(),
+            host: "127.0.0.1".into(),
+            port: 25565,
+            proxy_server: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum WarningDisplayConfig {
+    #[default]
+    Chat,
+    /// Displayed in the chat to clients prior to 1.8.
+    Title,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeoutActionConfig {
+    #[default]
+    Kick,
+    /// Transfers players since 1.20.5, older clients go through the proxy when `proxy_server` is set and are kicked otherwise.
+    Transfer,
+}
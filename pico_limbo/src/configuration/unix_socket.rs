@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+pub struct UnixSocketConfig {
+    /// Octal permissions of the Unix domain sockets listed in `bind`.
+    pub permissions: String,
+}
+
+impl Default for UnixSocketConfig {
+    fn default() -> Self {
+        Self {
+            permissions: "660".into(),
+        }
+    }
+}
+
+impl UnixSocketConfig {
+    pub fn mode(&self) -> Option<u32> {
+        u32::from_str_radix(&self.permissions, 8)
+            .ok()
+            .filter(|mode| *mode <= 0o777)
+    }
+}
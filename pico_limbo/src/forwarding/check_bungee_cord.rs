@@ -1,4 +1,5 @@
 use crate::forwarding::forwarding_result::LegacyForwardingResult;
+use crate::forwarding::parse_forwarded_address;
 use crate::server_state::ServerState;
 use minecraft_packets::login::Property;
 use minecraft_protocol::prelude::Uuid;
@@ -11,6 +12,7 @@ const BUNGEE_GUARD_TOKEN_PROPERTY_NAME: &str = "bungeeguard-token";
 /// `BungeeCord` sends 3 or 4 parts in online mode where Velocity always sends 4
 /// Parts are the following: hostname, client IP, player unique ID, properties
 /// When using `BungeeGuard`, we must have 4 parts as we need the last one
+/// When `BungeeGuard` is accepted alongside modern forwarding, handshakes without parts are left to the latter
 pub fn check_bungee_cord(state: &ServerState, hostname: &str) -> LegacyForwardingResult {
     if !state.is_legacy_forwarding() && !state.is_bungee_guard_forwarding() {
         return LegacyForwardingResult::NoForwarding;
@@ -57,7 +59,13 @@ pub fn check_bungee_cord(state: &ServerState, hostname: &str) -> LegacyForwardin
         LegacyForwardingResult::Anonymous {
             textures,
             player_uuid,
+            player_address: parts
+                .get(1)
+                .and_then(|address| parse_forwarded_address(address)),
         }
+    } else if state.is_modern_forwarding() {
+        // The handshake of Velocity modern forwarding carries no player information
+        LegacyForwardingResult::NoForwarding
     } else {
         LegacyForwardingResult::Invalid
     }
@@ -90,6 +98,13 @@ mod tests {
         server_state_builder.build().unwrap()
     }
 
+    fn modern_and_bungee_guard() -> ServerState {
+        let mut server_state_builder = ServerState::builder();
+        server_state_builder
+            .enable_modern_and_bungee_guard_forwarding(["secret"], vec![String::from("the_token")]);
+        server_state_builder.build().unwrap()
+    }
+
     #[test]
     fn test_valid_no_forwarding() {
         // Given
@@ -117,11 +132,13 @@ mod tests {
             LegacyForwardingResult::Anonymous {
                 player_uuid,
                 textures,
+                player_address,
             } => {
                 let expected_player_uuid =
                     Uuid::from_str("6856201a-9c1f-4997-8608-371019daf15e").unwrap();
                 assert_eq!(player_uuid, expected_player_uuid);
                 assert!(textures.is_none());
+                assert_eq!(player_address, Some([127, 0, 0, 1].into()));
             }
             _ => panic!("Expected LegacyForwardingResult::Anonymous"),
         }
@@ -141,6 +158,7 @@ mod tests {
             LegacyForwardingResult::Anonymous {
                 player_uuid,
                 textures,
+                ..
             } => {
                 let expected_player_uuid =
                     Uuid::from_str("6856201a-9c1f-4997-8608-371019daf15e").unwrap();
@@ -167,6 +185,7 @@ mod tests {
             LegacyForwardingResult::Anonymous {
                 player_uuid,
                 textures,
+                ..
             } => {
                 let expected_player_uuid =
                     Uuid::from_str("6856201a-9c1f-4997-8608-371019daf15e").unwrap();
@@ -206,6 +225,7 @@ mod tests {
             LegacyForwardingResult::Anonymous {
                 player_uuid,
                 textures,
+                ..
             } => {
                 let expected_player_uuid =
                     Uuid::from_str("6856201a-9c1f-4997-8608-371019daf15e").unwrap();
@@ -256,6 +276,7 @@ mod tests {
             LegacyForwardingResult::Anonymous {
                 player_uuid,
                 textures,
+                ..
             } => {
                 let expected_player_uuid =
                     Uuid::from_str("6856201a-9c1f-4997-8608-371019daf15e").unwrap();
@@ -282,6 +303,7 @@ mod tests {
             LegacyForwardingResult::Anonymous {
                 player_uuid,
                 textures,
+                ..
             } => {
                 let expected_player_uuid =
                     Uuid::from_str("6856201a-9c1f-4997-8608-371019daf15e").unwrap();
@@ -293,4 +315,46 @@ mod tests {
             _ => panic!("Expected LegacyForwardingResult::Anonymous"),
         }
     }
+
+    #[test]
+    fn test_modern_handshake_is_left_to_modern_forwarding() {
+        // Given
+        let server_state = modern_and_bungee_guard();
+        let hostname = "localhost";
+
+        // When
+        let validation = check_bungee_cord(&server_state, hostname);
+
+        // Then
+        assert!(matches!(validation, LegacyForwardingResult::NoForwarding));
+    }
+
+    #[test]
+    fn test_bungee_guard_handshake_is_accepted_alongside_modern_forwarding() {
+        // Given
+        let server_state = modern_and_bungee_guard();
+        let hostname = "localhost\x00127.0.0.1\x006856201a9c1f49978608371019daf15e\x00[{\"name\":\"bungeeguard-token\",\"value\":\"the_token\"}]";
+
+        // When
+        let validation = check_bungee_cord(&server_state, hostname);
+
+        // Then
+        assert!(matches!(
+            validation,
+            LegacyForwardingResult::Anonymous { .. }
+        ));
+    }
+
+    #[test]
+    fn test_bungee_guard_handshake_with_invalid_token_is_rejected_alongside_modern_forwarding() {
+        // Given
+        let server_state = modern_and_bungee_guard();
+        let hostname = "localhost\x00127.0.0.1\x006856201a9c1f49978608371019daf15e\x00[{\"name\":\"bungeeguard-token\",\"value\":\"wrong_token\"}]";
+
+        // When
+        let validation = check_bungee_cord(&server_state, hostname);
+
+        // Then
+        assert!(matches!(validation, LegacyForwardingResult::Invalid));
+    }
 }
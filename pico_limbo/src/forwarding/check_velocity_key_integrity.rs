@@ -1,14 +1,17 @@
 use crate::forwarding::forwarding_result::ModernForwardingResult;
+use crate::forwarding::modern_forwarding_version::ModernForwardingVersion;
+use crate::forwarding::parse_forwarded_address;
+use crate::server::game_profile::PlayerPublicKey;
 use hmac::KeyInit;
 use hmac::digest::InvalidLength;
 use hmac::{Hmac, Mac};
 use minecraft_packets::login::Property;
 use minecraft_protocol::prelude::{
-    BinaryReader, BinaryReaderError, DecodePacket, Optional, ProtocolVersion, Uuid, VarInt,
+    BinaryReader, BinaryReaderError, DecodePacket, LengthPaddedVec, ProtocolVersion, Uuid, VarInt,
     VarIntPrefixedString,
 };
 use sha2::Sha256;
-use subtle::ConstantTimeEq;
+use subtle::{Choice, ConstantTimeEq};
 use thiserror::Error;
 
 // Type alias for HMAC-SHA256.
@@ -30,29 +33,36 @@ impl From<InvalidLength> for VelocityKeyIntegrityError {
     }
 }
 
-pub fn read_velocity_key(reader: &mut BinaryReader, secret_key: &[u8]) -> ModernForwardingResult {
-    check_velocity_key_integrity(reader, secret_key).unwrap_or(ModernForwardingResult::Invalid)
+pub fn read_velocity_key(
+    reader: &mut BinaryReader,
+    secret_keys: &[Vec<u8>],
+    requested_version: ModernForwardingVersion,
+) -> ModernForwardingResult {
+    check_velocity_key_integrity(reader, secret_keys, requested_version)
+        .unwrap_or(ModernForwardingResult::Invalid)
 }
 
 /// Checks the integrity of the forwarded message using an HMAC signature.
 ///
 /// The input `buf` is expected to have the first 32 bytes as the HMAC signature,
 /// followed by the payload. The HMAC is computed over the entire payload. After verifying
-/// the HMAC, the function reads a varint from the beginning of the payload and checks if it equals 1.
+/// the HMAC, the function reads a varint from the beginning of the payload and checks it is
+/// a known version no higher than the requested one, since the proxy may answer with a lower version.
 ///
 /// # Arguments
 ///
 /// * `buf` - A byte slice containing the full message. The first 32 bytes are the HMAC signature.
-/// * `secret_key` - A byte slice containing the secret key used for HMAC computation.
+/// * `secret_keys` - The accepted secret keys, every one of them is tried to not leak which one matched.
+/// * `requested_version` - The version requested in the login plugin request.
 ///
 /// # Returns
 ///
-/// * `Ok(true)` if the HMAC is valid and the version equals 1.
-/// * `Ok(false)` if the computed signature does not match the provided signature.
-/// * An error if the input buffer is malformed or the version is unsupported.
+/// * `Ok(ModernForwardingResult::Valid)` if the HMAC and the version are valid.
+/// * An error if the signature does not match, the input buffer is malformed or the version is unsupported.
 fn check_velocity_key_integrity(
     reader: &mut BinaryReader,
-    secret_key: &[u8],
+    secret_keys: &[Vec<u8>],
+    requested_version: ModernForwardingVersion,
 ) -> Result<ModernForwardingResult, VelocityKeyIntegrityError> {
     let remaining = reader.remaining();
     if remaining < 32 {
@@ -66,35 +76,44 @@ fn check_velocity_key_integrity(
     let mut payload = vec![0u8; reader.remaining()];
     reader.read_bytes(&mut payload)?;
 
-    // Compute HMAC-SHA256 over the payload.
-    let mut mac = HmacSha256::new_from_slice(secret_key)?;
-    mac.update(&payload);
-    let computed_signature = mac.finalize().into_bytes();
+    // Compute HMAC-SHA256 over the payload with every secret key, and use constant-time
+    // equality to compare signatures.
+    let mut is_valid = Choice::from(0);
+    for secret_key in secret_keys {
+        let mut mac = HmacSha256::new_from_slice(secret_key)?;
+        mac.update(&payload);
+        let computed_signature = mac.finalize().into_bytes();
+        is_valid |= signature.ct_eq(&computed_signature);
+    }
 
-    // Use constant-time equality to compare signatures.
-    if signature.ct_eq(&computed_signature).unwrap_u8() != 1 {
+    if is_valid.unwrap_u8() != 1 {
         return Err(VelocityKeyIntegrityError);
     }
 
     // Read the version from the beginning of the payload.
     let mut payload_reader = BinaryReader::new(&payload);
-    let version = payload_reader.read::<VarInt>()?.inner();
-    if version != 1 {
-        return Err(VelocityKeyIntegrityError);
-    }
+    let version = ModernForwardingVersion::from_id(payload_reader.read::<VarInt>()?.inner())
+        .filter(|version| *version <= requested_version)
+        .ok_or(VelocityKeyIntegrityError)?;
 
-    Ok(read_payload(&mut payload_reader)?)
+    Ok(read_payload(&mut payload_reader, version)?)
 }
 
-fn read_payload(reader: &mut BinaryReader) -> Result<ModernForwardingResult, BinaryReaderError> {
-    let _address = reader.read::<VarIntPrefixedString>()?;
+fn read_payload(
+    reader: &mut BinaryReader,
+    version: ModernForwardingVersion,
+) -> Result<ModernForwardingResult, BinaryReaderError> {
+    let player_address =
+        parse_forwarded_address(&reader.read::<VarIntPrefixedString>()?.into_inner());
     let player_uuid = reader.read::<Uuid>()?;
     let player_name = reader.read::<VarIntPrefixedString>()?.into_inner();
-    let property: Option<Property> =
-        Optional::<Property>::decode(reader, ProtocolVersion::Any)?.into();
+    let textures = LengthPaddedVec::<Property>::decode(reader, ProtocolVersion::Any)?
+        .into_inner()
+        .into_iter()
+        .find(Property::is_textures);
 
-    let property = if let Some(ref prop) = property {
-        if prop.is_textures() { property } else { None }
+    let public_key = if version.has_public_key() {
+        Some(Box::new(read_public_key(reader, version)?))
     } else {
         None
     };
@@ -102,6 +121,222 @@ fn read_payload(reader: &mut BinaryReader) -> Result<ModernForwardingResult, Bin
     Ok(ModernForwardingResult::Valid {
         player_uuid,
         player_name,
-        textures: property,
+        textures,
+        player_address,
+        public_key,
     })
 }
+
+fn read_public_key(
+    reader: &mut BinaryReader,
+    version: ModernForwardingVersion,
+) -> Result<PlayerPublicKey, BinaryReaderError> {
+    let expires_at = reader.read::<i64>()?;
+    let key = LengthPaddedVec::<u8>::decode(reader, ProtocolVersion::Any)?.into_inner();
+    let signature = LengthPaddedVec::<u8>::decode(reader, ProtocolVersion::Any)?.into_inner();
+    let signature_holder =
+        if version.has_signature_holder() && bool::decode(reader, ProtocolVersion::Any)? {
+            Some(reader.read::<Uuid>()?)
+        } else {
+            None
+        };
+
+    Ok(PlayerPublicKey {
+        expires_at,
+        key,
+        signature,
+        signature_holder,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use minecraft_protocol::prelude::{BinaryWriter, EncodePacket};
+
+    const SECRET: &[u8] = b"secret";
+    const PLAYER_UUID: Uuid = Uuid::from_u128(0x069a_79f4_44e9_4726_a5be_fca9_0e38_aaf5);
+    const SIGNATURE_HOLDER: Uuid = Uuid::from_u128(0x0123_4567_89ab_cdef_0123_4567_89ab_cdef);
+
+    fn payload(version: i32, properties: Vec<Property>) -> BinaryWriter {
+        let mut writer = BinaryWriter::new();
+        writer.write(&VarInt::new(version)).unwrap();
+        "203.0.113.7"
+            .to_string()
+            .encode(&mut writer, ProtocolVersion::Any)
+            .unwrap();
+        writer.write(&PLAYER_UUID).unwrap();
+        "Notch"
+            .to_string()
+            .encode(&mut writer, ProtocolVersion::Any)
+            .unwrap();
+        LengthPaddedVec::new(properties)
+            .encode(&mut writer, ProtocolVersion::Any)
+            .unwrap();
+        writer
+    }
+
+    fn write_public_key(writer: &mut BinaryWriter, signature_holder: Option<Uuid>) {
+        writer.write(&1_700_000_000_000_i64).unwrap();
+        LengthPaddedVec::new(vec![1_u8, 2, 3])
+            .encode(writer, ProtocolVersion::Any)
+            .unwrap();
+        LengthPaddedVec::new(vec![4_u8, 5])
+            .encode(writer, ProtocolVersion::Any)
+            .unwrap();
+        if let Some(signature_holder) = signature_holder {
+            true.encode(writer, ProtocolVersion::Any).unwrap();
+            writer.write(&signature_holder).unwrap();
+        }
+    }
+
+    fn sign(payload: BinaryWriter) -> Vec<u8> {
+        let payload = payload.into_inner();
+        let mut mac = HmacSha256::new_from_slice(SECRET).unwrap();
+        mac.update(&payload);
+        let mut message = mac.finalize().into_bytes().to_vec();
+        message.extend(payload);
+        message
+    }
+
+    fn read(message: &[u8], requested_version: ModernForwardingVersion) -> ModernForwardingResult {
+        read_velocity_key(
+            &mut BinaryReader::new(message),
+            &[SECRET.to_vec()],
+            requested_version,
+        )
+    }
+
+    #[test]
+    fn test_default_version_is_accepted_when_a_higher_version_was_requested() {
+        // Given
+        let message = sign(payload(1, Vec::new()));
+
+        // When
+        let result = read(&message, ModernForwardingVersion::LazySession);
+
+        // Then
+        assert!(matches!(
+            result,
+            ModernForwardingResult::Valid {
+                player_uuid: PLAYER_UUID,
+                public_key: None,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_version_higher_than_requested_is_rejected() {
+        // Given
+        let message = sign(payload(4, Vec::new()));
+
+        // When
+        let result = read(&message, ModernForwardingVersion::WithKeyV2);
+
+        // Then
+        assert!(matches!(result, ModernForwardingResult::Invalid));
+    }
+
+    #[test]
+    fn test_public_key_is_read_with_key_version() {
+        // Given
+        let mut payload = payload(2, Vec::new());
+        write_public_key(&mut payload, None);
+        let message = sign(payload);
+
+        // When
+        let result = read(&message, ModernForwardingVersion::WithKey);
+
+        // Then
+        let ModernForwardingResult::Valid { public_key, .. } = result else {
+            panic!("the forwarded information should be valid");
+        };
+        assert_eq!(
+            public_key.map(|public_key| *public_key),
+            Some(PlayerPublicKey {
+                expires_at: 1_700_000_000_000,
+                key: vec![1, 2, 3],
+                signature: vec![4, 5],
+                signature_holder: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_signature_holder_is_read_with_key_v2_version() {
+        // Given
+        let properties = vec![
+            Property::new("foo".to_string(), "bar".to_string(), None),
+            Property::textures("textures", Some("signature")),
+        ];
+        let mut payload = payload(3, properties);
+        write_public_key(&mut payload, Some(SIGNATURE_HOLDER));
+        let message = sign(payload);
+
+        // When
+        let result = read(&message, ModernForwardingVersion::WithKeyV2);
+
+        // Then
+        let ModernForwardingResult::Valid {
+            textures,
+            public_key,
+            ..
+        } = result
+        else {
+            panic!("the forwarded information should be valid");
+        };
+        assert!(textures.is_some_and(|textures| textures.is_textures()));
+        assert_eq!(
+            public_key.and_then(|public_key| public_key.signature_holder),
+            Some(SIGNATURE_HOLDER)
+        );
+    }
+
+    #[test]
+    fn test_tampered_payload_is_rejected() {
+        // Given
+        let mut message = sign(payload(1, Vec::new()));
+        *message.last_mut().unwrap() ^= 1;
+
+        // When
+        let result = read(&message, ModernForwardingVersion::Default);
+
+        // Then
+        assert!(matches!(result, ModernForwardingResult::Invalid));
+    }
+
+    #[test]
+    fn test_any_of_the_secrets_is_accepted() {
+        // Given
+        let message = sign(payload(1, Vec::new()));
+        let secret_keys = [b"old".to_vec(), SECRET.to_vec(), b"new".to_vec()];
+
+        // When
+        let result = read_velocity_key(
+            &mut BinaryReader::new(&message),
+            &secret_keys,
+            ModernForwardingVersion::Default,
+        );
+
+        // Then
+        assert!(matches!(result, ModernForwardingResult::Valid { .. }));
+    }
+
+    #[test]
+    fn test_unknown_secret_is_rejected() {
+        // Given
+        let message = sign(payload(1, Vec::new()));
+        let secret_keys = [b"old".to_vec(), b"new".to_vec()];
+
+        // When
+        let result = read_velocity_key(
+            &mut BinaryReader::new(&message),
+            &secret_keys,
+            ModernForwardingVersion::Default,
+        );
+
+        // Then
+        assert!(matches!(result, ModernForwardingResult::Invalid));
+    }
+}
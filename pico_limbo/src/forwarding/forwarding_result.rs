@@ -1,5 +1,7 @@
+use crate::server::game_profile::PlayerPublicKey;
 use minecraft_packets::login::Property;
 use minecraft_protocol::prelude::Uuid;
+use std::net::IpAddr;
 
 pub enum ModernForwardingResult {
     Invalid,
@@ -7,6 +9,9 @@ pub enum ModernForwardingResult {
         player_uuid: Uuid,
         player_name: String,
         textures: Option<Property>,
+        /// Address of the player as seen by the proxy.
+        player_address: Option<IpAddr>,
+        public_key: Option<Box<PlayerPublicKey>>,
     },
 }
 
@@ -15,6 +20,8 @@ pub enum LegacyForwardingResult {
     Anonymous {
         player_uuid: Uuid,
         textures: Option<Property>,
+        /// Address of the player as seen by the proxy.
+        player_address: Option<IpAddr>,
     },
     NoForwarding,
 }
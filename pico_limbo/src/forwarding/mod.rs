@@ -1,3 +1,15 @@
 pub mod check_bungee_cord;
 pub mod check_velocity_key_integrity;
 pub mod forwarding_result;
+pub mod modern_forwarding_version;
+
+use std::net::IpAddr;
+
+/// Parses the address of the player forwarded by a proxy, IPv6 addresses may be enclosed in brackets.
+fn parse_forwarded_address(address: &str) -> Option<IpAddr> {
+    address
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse()
+        .ok()
+}
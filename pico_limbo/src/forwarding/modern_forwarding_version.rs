@@ -0,0 +1,76 @@
+use minecraft_protocol::prelude::ProtocolVersion;
+
+/// Versions of the player information forwarded by Velocity, each one extending the previous one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ModernForwardingVersion {
+    /// Address, UUID, username and properties of the player.
+    Default = 1,
+    /// Adds the chat signing key of 1.19 players.
+    WithKey = 2,
+    /// Adds the UUID of the holder of the chat signing key of 1.19.1 and 1.19.2 players.
+    WithKeyV2 = 3,
+    /// The chat session of 1.19.3+ players is sent in game instead of being forwarded.
+    LazySession = 4,
+}
+
+impl ModernForwardingVersion {
+    /// Highest version Velocity can forward the information of a player with.
+    pub fn highest_supported(protocol_version: ProtocolVersion) -> Self {
+        if protocol_version.is_after_inclusive(ProtocolVersion::V1_19_3) {
+            Self::LazySession
+        } else if protocol_version.is_after_inclusive(ProtocolVersion::V1_19_1) {
+            Self::WithKeyV2
+        } else if protocol_version.is_after_inclusive(ProtocolVersion::V1_19) {
+            Self::WithKey
+        } else {
+            Self::Default
+        }
+    }
+
+    pub const fn from_id(id: i32) -> Option<Self> {
+        match id {
+            1 => Some(Self::Default),
+            2 => Some(Self::WithKey),
+            3 => Some(Self::WithKeyV2),
+            4 => Some(Self::LazySession),
+            _ => None,
+        }
+    }
+
+    pub const fn id(self) -> u8 {
+        self as u8
+    }
+
+    pub const fn has_public_key(self) -> bool {
+        matches!(self, Self::WithKey | Self::WithKeyV2)
+    }
+
+    pub const fn has_signature_holder(self) -> bool {
+        matches!(self, Self::WithKeyV2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highest_supported_version() {
+        assert_eq!(
+            ModernForwardingVersion::highest_supported(ProtocolVersion::V1_18_2),
+            ModernForwardingVersion::Default
+        );
+        assert_eq!(
+            ModernForwardingVersion::highest_supported(ProtocolVersion::V1_19),
+            ModernForwardingVersion::WithKey
+        );
+        assert_eq!(
+            ModernForwardingVersion::highest_supported(ProtocolVersion::V1_19_1),
+            ModernForwardingVersion::WithKeyV2
+        );
+        assert_eq!(
+            ModernForwardingVersion::highest_supported(ProtocolVersion::V1_21_4),
+            ModernForwardingVersion::LazySession
+        );
+    }
+}
@@ -6,7 +6,9 @@ use crate::server::game_mode::GameMode;
 use crate::server::packet_handler::{PacketHandler, PacketHandlerError};
 use crate::server::packet_registry::PacketRegistry;
 use crate::server_brand::SERVER_BRAND;
-use crate::server_state::{ServerCommand, ServerState, TabList, Title, TitleType};
+use crate::server_state::{
+    ReloadedSections, ServerCommand, ServerState, TabList, Title, TitleType,
+};
 use minecraft_packets::configuration::acknowledge_finish_configuration_packet::AcknowledgeConfigurationPacket;
 use minecraft_packets::login::Property;
 use minecraft_packets::play::boss_bar_packet::BossBarPacket;
@@ -31,7 +33,7 @@ use minecraft_packets::play::synchronize_player_position_packet::SynchronizePlay
 use minecraft_packets::play::system_chat_message_packet::SystemChatMessagePacket;
 use minecraft_packets::play::tab_list_packet::TabListPacket;
 use minecraft_packets::play::update_time_packet::UpdateTimePacket;
-use minecraft_protocol::prelude::{Dimension as ProtocolDimension, ProtocolVersion, State};
+use minecraft_protocol::prelude::{Dimension as ProtocolDimension, ProtocolVersion, State, Uuid};
 use pico_precomputed_registries::PrecomputedRegistries;
 use pico_registries::Identifier;
 use pico_registries::registry_provider::Dimension as RegistryDimension;
@@ -215,7 +217,7 @@ pub fn send_play_packets(
         send_title_text_packets(batch, server_state, protocol_version);
     }
     if protocol_version.is_after_inclusive(ProtocolVersion::V1_9) {
-        send_boss_bar_packets(batch, server_state);
+        send_boss_bar_packets(batch, client_state, server_state);
     }
 
     if protocol_version.is_after_inclusive(ProtocolVersion::V1_16) {
@@ -252,6 +254,38 @@ pub fn send_play_packets(
     Ok(())
 }
 
+/// Pushes the sections that changed after a reload to a player already in game.
+pub fn send_reloaded_packets(
+    batch: &mut Batch,
+    client_state: &mut ClientState,
+    server_state: &ServerState,
+    sections: ReloadedSections,
+) {
+    let protocol_version = client_state.protocol_version();
+    if protocol_version.is_after_inclusive(ProtocolVersion::V1_8) {
+        if sections.tab_list {
+            if server_state.tab_list().is_some() {
+                send_tab_list_packets(batch, server_state);
+            } else {
+                let empty = Component::default();
+                let packet = TabListPacket::new(&empty, &empty);
+                batch.queue(|| PacketRegistry::TabList(packet));
+            }
+        }
+        if sections.title {
+            send_title_text_packets(batch, server_state, protocol_version);
+        }
+    }
+    if sections.boss_bar && protocol_version.is_after_inclusive(ProtocolVersion::V1_9) {
+        if let Some(uuid) = client_state.boss_bar_uuid() {
+            let packet = BossBarPacket::remove(uuid);
+            batch.queue(|| PacketRegistry::BossBar(packet));
+            client_state.set_boss_bar_uuid(None);
+        }
+        send_boss_bar_packets(batch, client_state, server_state);
+    }
+}
+
 fn send_tab_list_packets(batch: &mut Batch, server_state: &ServerState) {
     if let Some(TabList { header, footer }) = server_state.tab_list() {
         let packet = TabListPacket::new(header, footer);
@@ -259,15 +293,22 @@ fn send_tab_list_packets(batch: &mut Batch, server_state: &ServerState) {
     }
 }
 
-fn send_boss_bar_packets(batch: &mut Batch, server_state: &ServerState) {
+fn send_boss_bar_packets(
+    batch: &mut Batch,
+    client_state: &mut ClientState,
+    server_state: &ServerState,
+) {
     if let Some(boss_bar) = server_state.boss_bar() {
+        let uuid = Uuid::new_v4();
         let packet = BossBarPacket::add(
+            uuid,
             &boss_bar.title,
             boss_bar.health,
             boss_bar.color,
             boss_bar.division,
         );
         batch.queue(|| PacketRegistry::BossBar(packet));
+        client_state.set_boss_bar_uuid(Some(uuid));
     }
 }
 
@@ -276,54 +317,67 @@ fn send_title_text_packets(
     server_state: &ServerState,
     protocol_version: ProtocolVersion,
 ) {
-    if let Some(Title {
+    if let Some(title) = server_state.title() {
+        for packet in title_packets(title, protocol_version) {
+            batch.queue(|| packet);
+        }
+    }
+}
+
+/// Builds the packets displaying a title, for clients on 1.8+.
+pub fn title_packets(title: &Title, protocol_version: ProtocolVersion) -> Vec<PacketRegistry> {
+    let Title {
         content,
         fade_in,
         stay,
         fade_out,
-    }) = server_state.title()
-    {
-        if protocol_version.is_after_inclusive(ProtocolVersion::V1_17) {
-            let animation_packet = SetTitlesAnimationPacket::new(*fade_in, *stay, *fade_out);
-            batch.queue(|| PacketRegistry::SetTitlesAnimation(animation_packet));
-
-            match content {
-                TitleType::Title(title) => {
-                    let title_packet = SetTitleTextPacket::new(title);
-                    batch.queue(|| PacketRegistry::SetTitleText(title_packet));
-                }
-                TitleType::Subtitle(subtitle) => {
-                    let subtitle_packet = SetSubtitleTextPacket::new(subtitle);
-                    batch.queue(|| PacketRegistry::SetSubtitleText(subtitle_packet));
-                }
-                TitleType::Both { title, subtitle } => {
-                    let title_packet = SetTitleTextPacket::new(title);
-                    batch.queue(|| PacketRegistry::SetTitleText(title_packet));
-                    let subtitle_packet = SetSubtitleTextPacket::new(subtitle);
-                    batch.queue(|| PacketRegistry::SetSubtitleText(subtitle_packet));
-                }
+    } = title;
+    if protocol_version.is_after_inclusive(ProtocolVersion::V1_17) {
+        let mut packets = vec![PacketRegistry::SetTitlesAnimation(
+            SetTitlesAnimationPacket::new(*fade_in, *stay, *fade_out),
+        )];
+        match content {
+            TitleType::Title(title) => {
+                packets.push(PacketRegistry::SetTitleText(SetTitleTextPacket::new(title)));
             }
-        } else {
-            let animation_packet = LegacySetTitlePacket::set_animation(*fade_in, *stay, *fade_out);
-            batch.queue(|| PacketRegistry::LegacySetTitle(animation_packet));
-
-            match content {
-                TitleType::Title(title) => {
-                    let title_packet = LegacySetTitlePacket::set_title(title);
-                    batch.queue(|| PacketRegistry::LegacySetTitle(title_packet));
-                }
-                TitleType::Subtitle(subtitle) => {
-                    let subtitle_packet = LegacySetTitlePacket::set_subtitle(subtitle);
-                    batch.queue(|| PacketRegistry::LegacySetTitle(subtitle_packet));
-                }
-                TitleType::Both { title, subtitle } => {
-                    let title_packet = LegacySetTitlePacket::set_title(title);
-                    batch.queue(|| PacketRegistry::LegacySetTitle(title_packet));
-                    let subtitle_packet = LegacySetTitlePacket::set_subtitle(subtitle);
-                    batch.queue(|| PacketRegistry::LegacySetTitle(subtitle_packet));
-                }
+            TitleType::Subtitle(subtitle) => {
+                packets.push(PacketRegistry::SetSubtitleText(SetSubtitleTextPacket::new(
+                    subtitle,
+                )));
+            }
+            TitleType::Both { title, subtitle } => {
+                packets.push(PacketRegistry::SetTitleText(SetTitleTextPacket::new(title)));
+                packets.push(PacketRegistry::SetSubtitleText(SetSubtitleTextPacket::new(
+                    subtitle,
+                )));
+            }
+        }
+        packets
+    } else {
+        let mut packets = vec![PacketRegistry::LegacySetTitle(
+            LegacySetTitlePacket::set_animation(*fade_in, *stay, *fade_out),
+        )];
+        match content {
+            TitleType::Title(title) => {
+                packets.push(PacketRegistry::LegacySetTitle(
+                    LegacySetTitlePacket::set_title(title),
+                ));
+            }
+            TitleType::Subtitle(subtitle) => {
+                packets.push(PacketRegistry::LegacySetTitle(
+                    LegacySetTitlePacket::set_subtitle(subtitle),
+                ));
+            }
+            TitleType::Both { title, subtitle } => {
+                packets.push(PacketRegistry::LegacySetTitle(
+                    LegacySetTitlePacket::set_title(title),
+                ));
+                packets.push(PacketRegistry::LegacySetTitle(
+                    LegacySetTitlePacket::set_subtitle(subtitle),
+                ));
             }
         }
+        packets
     }
 }
 
@@ -333,16 +387,22 @@ fn send_action_bar_packet(
     protocol_version: ProtocolVersion,
 ) {
     if let Some(action_bar) = server_state.action_bar() {
-        if protocol_version.is_after_inclusive(ProtocolVersion::V1_17) {
-            let packet = SetActionBarTextPacket::new(action_bar);
-            batch.queue(|| PacketRegistry::SetActionBarText(packet));
-        } else if protocol_version.is_after_inclusive(ProtocolVersion::V1_11) {
-            let packet = LegacySetTitlePacket::action_bar(action_bar);
-            batch.queue(|| PacketRegistry::LegacySetTitle(packet));
-        } else {
-            let packet = LegacyChatMessagePacket::game_info(action_bar);
-            batch.queue(|| PacketRegistry::LegacyChatMessage(packet));
-        }
+        let packet = action_bar_packet(action_bar, protocol_version);
+        batch.queue(|| packet);
+    }
+}
+
+/// Builds the packet displaying a message above the hotbar.
+pub fn action_bar_packet(
+    component: &Component,
+    protocol_version: ProtocolVersion,
+) -> PacketRegistry {
+    if protocol_version.is_after_inclusive(ProtocolVersion::V1_17) {
+        PacketRegistry::SetActionBarText(SetActionBarTextPacket::new(component))
+    } else if protocol_version.is_after_inclusive(ProtocolVersion::V1_11) {
+        PacketRegistry::LegacySetTitle(LegacySetTitlePacket::action_bar(component))
+    } else {
+        PacketRegistry::LegacyChatMessage(LegacyChatMessagePacket::game_info(component))
     }
 }
 
@@ -432,18 +492,23 @@ impl From<TryFromIntError> for PacketHandlerError {
 }
 
 pub fn send_message(batch: &mut Batch, component: &Component, protocol_version: ProtocolVersion) {
+    let packet = message_packet(component, protocol_version);
+    batch.queue(|| packet);
+}
+
+/// Builds the packet displaying a system message in the chat.
+pub fn message_packet(component: &Component, protocol_version: ProtocolVersion) -> PacketRegistry {
     if protocol_version.is_after_inclusive(ProtocolVersion::V1_19) {
-        let packet = SystemChatMessagePacket::component(component);
-        batch.queue(|| PacketRegistry::SystemChatMessage(packet));
+        PacketRegistry::SystemChatMessage(SystemChatMessagePacket::component(component))
     } else {
-        let packet = LegacyChatMessagePacket::system(component);
-        batch.queue(|| PacketRegistry::LegacyChatMessage(packet));
+        PacketRegistry::LegacyChatMessage(LegacyChatMessagePacket::system(component))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::configuration::boss_bar::BossBarConfig;
     use crate::server::batch::BatchStream;
     use futures::StreamExt;
     use minecraft_protocol::prelude::Direction;
@@ -679,4 +744,46 @@ mod tests {
         ));
         assert!(batch.next().await.is_none());
     }
+
+    #[tokio::test]
+    async fn test_reloaded_boss_bar_replaces_previous_one() {
+        // Given
+        let mut client_state = client(ProtocolVersion::V1_21_2);
+        let previous_uuid = Uuid::new_v4();
+        client_state.set_boss_bar_uuid(Some(previous_uuid));
+        let server_state = {
+            let mut builder = ServerState::builder();
+            if let BossBarConfig::Enabled(boss_bar) = BossBarConfig::default() {
+                builder.boss_bar(boss_bar).unwrap();
+            }
+            builder.build().unwrap()
+        };
+        let sections = ReloadedSections {
+            tab_list: true,
+            boss_bar: true,
+            title: false,
+        };
+        let mut batch = Batch::new();
+
+        // When
+        send_reloaded_packets(&mut batch, &mut client_state, &server_state, sections);
+        let mut batch = batch.into_stream();
+
+        // Then
+        assert!(matches!(
+            batch.next().await.unwrap().unwrap_packet(),
+            PacketRegistry::TabList(_)
+        ));
+        assert!(matches!(
+            batch.next().await.unwrap().unwrap_packet(),
+            PacketRegistry::BossBar(_)
+        ));
+        assert!(matches!(
+            batch.next().await.unwrap().unwrap_packet(),
+            PacketRegistry::BossBar(_)
+        ));
+        assert!(batch.next().await.is_none());
+        assert!(client_state.boss_bar_uuid().is_some());
+        assert_ne!(client_state.boss_bar_uuid(), Some(previous_uuid));
+    }
 }
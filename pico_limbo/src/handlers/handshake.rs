@@ -9,6 +9,7 @@ use crate::server_state::ServerState;
 use minecraft_packets::handshaking::handshake_packet::HandshakePacket;
 use minecraft_protocol::prelude::{ProtocolVersion, State};
 use thiserror::Error;
+use tracing::info;
 
 impl PacketHandler for HandshakePacket {
     fn handle(
@@ -82,12 +83,40 @@ fn begin_login(
         LegacyForwardingResult::Anonymous {
             player_uuid,
             textures,
+            player_address,
         } => {
+            client_state.set_forwarded_by_handshake();
+            if let Some(player_address) = player_address {
+                client_state.set_forwarded_address(player_address);
+            }
             let game_profile = GameProfile::anonymous(player_uuid, textures);
+            kick_if_banned(client_state, server_state, Some(&game_profile));
             client_state.set_game_profile(game_profile);
             Ok(())
         }
-        LegacyForwardingResult::NoForwarding => Ok(()),
+        LegacyForwardingResult::NoForwarding => {
+            kick_if_banned(client_state, server_state, None);
+            Ok(())
+        }
+    }
+}
+
+/// Refuses banned addresses, and banned UUIDs when forwarded by the proxy,
+/// before the player is authenticated.
+fn kick_if_banned(
+    client_state: &mut ClientState,
+    server_state: &ServerState,
+    game_profile: Option<&GameProfile>,
+) {
+    let address = client_state.remote_address().map(|address| address.ip());
+    if let Some((kick_message, reason)) = server_state
+        .access_lists()
+        .check_bans(game_profile, address)
+    {
+        if let Some(address) = address {
+            info!("Refused the connection from {address}: {reason}");
+        }
+        client_state.kick_component(kick_message, reason);
     }
 }
 
@@ -129,6 +158,7 @@ impl GetStateProtocol for HandshakePacket {
 mod tests {
     use super::*;
     use minecraft_protocol::prelude::VarInt;
+    use pico_text_component::prelude::Component;
 
     fn server_state() -> ServerState {
         let mut server_state_builder = ServerState::builder();
@@ -248,6 +278,7 @@ mod tests {
         // Then
         batch.assert_client_state(State::Login).await;
         batch.assert_server_state(State::Login).await;
+        assert!(client_state.is_forwarded_by_handshake());
     }
 
     #[test]
@@ -267,7 +298,7 @@ mod tests {
         // Then
         assert_eq!(
             client_state.should_kick(),
-            Some(PROXY_REQUIRED_KICK_MESSAGE.to_string())
+            Some(Component::new(PROXY_REQUIRED_KICK_MESSAGE))
         );
         assert!(matches!(
             result,
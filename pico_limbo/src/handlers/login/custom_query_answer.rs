@@ -1,5 +1,6 @@
 use crate::forwarding::check_velocity_key_integrity::read_velocity_key;
 use crate::forwarding::forwarding_result::ModernForwardingResult;
+use crate::forwarding::modern_forwarding_version::ModernForwardingVersion;
 use crate::handlers::login::login_start::fire_login_success;
 use crate::kick_messages::PROXY_REQUIRED_KICK_MESSAGE;
 use crate::server::batch::Batch;
@@ -20,19 +21,27 @@ impl PacketHandler for CustomQueryAnswerPacket {
         let client_message_id = client_state.get_velocity_login_message_id();
 
         if server_state.is_modern_forwarding() && self.message_id.inner() == client_message_id {
-            let secret_key = server_state
-                .secret_key()
+            let secret_keys = server_state
+                .secret_keys()
                 .map_err(|_| PacketHandlerError::custom("No secret key"))?;
             let mut reader = BinaryReader::new(&self.data);
-            let velocity_key = read_velocity_key(&mut reader, &secret_key);
+            let requested_version =
+                ModernForwardingVersion::highest_supported(client_state.protocol_version());
+            let velocity_key = read_velocity_key(&mut reader, secret_keys, requested_version);
 
             match velocity_key {
                 ModernForwardingResult::Valid {
                     player_uuid,
                     player_name,
                     textures,
+                    player_address,
+                    public_key,
                 } => {
-                    let game_profile = GameProfile::new(&player_name, player_uuid, textures);
+                    if let Some(player_address) = player_address {
+                        client_state.set_forwarded_address(player_address);
+                    }
+                    let mut game_profile = GameProfile::new(&player_name, player_uuid, textures);
+                    game_profile.set_public_key(public_key.map(|public_key| *public_key));
                     fire_login_success(&mut batch, client_state, server_state, game_profile)?;
                 }
                 ModernForwardingResult::Invalid => {
@@ -49,10 +58,11 @@ mod tests {
     use super::*;
     use futures::StreamExt;
     use minecraft_protocol::prelude::{ProtocolVersion, VarInt};
+    use pico_text_component::prelude::Component;
 
     fn velocity() -> ServerState {
         let mut builder = ServerState::builder();
-        builder.enable_modern_forwarding("foo");
+        builder.enable_modern_forwarding(["foo"]);
         builder.build().unwrap()
     }
 
@@ -87,7 +97,7 @@ mod tests {
         // Then
         assert_eq!(
             client_state.should_kick(),
-            Some(PROXY_REQUIRED_KICK_MESSAGE.to_string())
+            Some(Component::new(PROXY_REQUIRED_KICK_MESSAGE))
         );
         assert!(batch.into_stream().next().await.is_none());
     }
@@ -0,0 +1,241 @@
+use crate::server::batch::Batch;
+use crate::server::client_state::ClientState;
+use crate::server::packet_handler::{PacketHandler, PacketHandlerError};
+use crate::server_state::{ServerState, verify_token_signature};
+use minecraft_packets::login::encryption_response_packet::EncryptionResponsePacket;
+
+/// AES-128 keys are 16 bytes long.
+const SHARED_SECRET_LENGTH: usize = 16;
+
+impl PacketHandler for EncryptionResponsePacket {
+    fn handle(
+        &self,
+        client_state: &mut ClientState,
+        server_state: &ServerState,
+    ) -> Result<Batch, PacketHandlerError> {
+        let mut batch = Batch::new();
+        let Some(online_mode) = server_state.online_mode() else {
+            return Err(PacketHandlerError::invalid_state(
+                "Received an encryption response while online mode is disabled",
+            ));
+        };
+        let Some((username, expected_verify_token)) = client_state.take_encryption_request() else {
+            return Err(PacketHandlerError::invalid_state(
+                "Received an encryption response without an encryption request",
+            ));
+        };
+
+        // Clients between 1.19 and 1.19.2 may sign the verify token with their profile key instead.
+        // The session server still binds the shared secret to our public key in that case.
+        if let Some((salt, signature)) = self.salt_signature() {
+            let is_valid = client_state
+                .profile_public_key()
+                .is_some_and(|profile_public_key| {
+                    verify_token_signature(
+                        profile_public_key,
+                        &expected_verify_token,
+                        salt,
+                        signature,
+                    )
+                });
+            if !is_valid {
+                return Err(PacketHandlerError::invalid_state(
+                    "Invalid verify token signature",
+                ));
+            }
+        } else if let Some(verify_token) = self.verify_token() {
+            let verify_token = online_mode
+                .decrypt(verify_token)
+                .map_err(|_| PacketHandlerError::invalid_state("Failed to decrypt verify token"))?;
+            if verify_token != expected_verify_token {
+                return Err(PacketHandlerError::invalid_state("Invalid verify token"));
+            }
+        }
+
+        let shared_secret = online_mode
+            .decrypt(self.shared_secret())
+            .map_err(|_| PacketHandlerError::invalid_state("Failed to decrypt shared secret"))?;
+        if shared_secret.len() != SHARED_SECRET_LENGTH {
+            return Err(PacketHandlerError::invalid_state(
+                "Invalid shared secret length",
+            ));
+        }
+
+        let server_hash = online_mode.server_hash(&shared_secret);
+        client_state.set_pending_authentication(username, server_hash);
+        batch.queue_enable_encryption(shared_secret);
+        Ok(batch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::batch::BatchItem;
+    use crate::server_state::sign_verify_token;
+    use futures::StreamExt;
+    use minecraft_protocol::prelude::{
+        BinaryReader, DecodePacket, Direction, ProtocolVersion, State, VarInt,
+    };
+    use rsa::pkcs8::{DecodePublicKey, EncodePublicKey};
+    use rsa::rand_core::OsRng;
+    use rsa::{Pkcs1v15Encrypt, RsaPrivateKey, RsaPublicKey};
+
+    const SHARED_SECRET: [u8; 16] = [7; 16];
+    const VERIFY_TOKEN: [u8; 4] = [1, 2, 3, 4];
+    const SALT: i64 = 42;
+
+    fn online_mode() -> ServerState {
+        let mut builder = ServerState::builder();
+        builder.enable_online_mode("http://localhost/hasJoined");
+        builder.build().unwrap()
+    }
+
+    fn client() -> ClientState {
+        let mut cs = ClientState::default();
+        cs.set_protocol_version(ProtocolVersion::V1_21_2);
+        cs.set_state(Direction::Clientbound, State::Login);
+        cs.set_state(Direction::Serverbound, State::Login);
+        cs.set_encryption_request("Notch".to_string(), VERIFY_TOKEN.to_vec());
+        cs
+    }
+
+    fn signed_packet(server_state: &ServerState, signature: &[u8]) -> EncryptionResponsePacket {
+        let public_key_der = server_state.online_mode().unwrap().public_key();
+        let public_key = RsaPublicKey::from_public_key_der(public_key_der).unwrap();
+        let encrypted = public_key
+            .encrypt(&mut OsRng, Pkcs1v15Encrypt, &SHARED_SECRET)
+            .unwrap();
+        let mut bytes = Vec::new();
+        let length = VarInt::new(i32::try_from(encrypted.len()).unwrap());
+        bytes.extend(length.to_bytes().unwrap());
+        bytes.extend(encrypted);
+        bytes.push(0);
+        bytes.extend(SALT.to_be_bytes());
+        let length = VarInt::new(i32::try_from(signature.len()).unwrap());
+        bytes.extend(length.to_bytes().unwrap());
+        bytes.extend(signature);
+        let mut reader = BinaryReader::new(&bytes);
+        EncryptionResponsePacket::decode(&mut reader, ProtocolVersion::V1_19).unwrap()
+    }
+
+    fn packet(server_state: &ServerState, verify_token: &[u8]) -> EncryptionResponsePacket {
+        let public_key_der = server_state.online_mode().unwrap().public_key();
+        let public_key = RsaPublicKey::from_public_key_der(public_key_der).unwrap();
+        let mut bytes = Vec::new();
+        for value in [SHARED_SECRET.as_slice(), verify_token] {
+            let encrypted = public_key
+                .encrypt(&mut OsRng, Pkcs1v15Encrypt, value)
+                .unwrap();
+            let length = VarInt::new(i32::try_from(encrypted.len()).unwrap());
+            bytes.extend(length.to_bytes().unwrap());
+            bytes.extend(encrypted);
+        }
+        let mut reader = BinaryReader::new(&bytes);
+        EncryptionResponsePacket::decode(&mut reader, ProtocolVersion::V1_21_2).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_encryption_response_enables_encryption() {
+        // Given
+        let server_state = online_mode();
+        let mut client_state = client();
+        let pkt = packet(&server_state, &VERIFY_TOKEN);
+
+        // When
+        let batch = pkt.handle(&mut client_state, &server_state).unwrap();
+        let mut batch = batch.into_stream();
+
+        // Then
+        assert!(matches!(
+            batch.next().await.unwrap(),
+            BatchItem::EnableEncryption(secret) if secret == SHARED_SECRET
+        ));
+        assert!(batch.next().await.is_none());
+        let pending_authentication = client_state.take_pending_authentication().unwrap();
+        assert_eq!(pending_authentication.username, "Notch");
+        assert_eq!(
+            pending_authentication.server_hash,
+            server_state
+                .online_mode()
+                .unwrap()
+                .server_hash(&SHARED_SECRET)
+        );
+    }
+
+    #[test]
+    fn test_encryption_response_rejects_invalid_verify_token() {
+        // Given
+        let server_state = online_mode();
+        let mut client_state = client();
+        let pkt = packet(&server_state, &[4, 3, 2, 1]);
+
+        // When
+        let result = pkt.handle(&mut client_state, &server_state);
+
+        // Then
+        assert!(matches!(
+            result,
+            Err(PacketHandlerError::InvalidState(_, _))
+        ));
+        assert!(client_state.take_pending_authentication().is_none());
+    }
+
+    #[test]
+    fn test_encryption_response_without_encryption_request() {
+        // Given
+        let server_state = online_mode();
+        let mut client_state = client();
+        client_state.take_encryption_request();
+        let pkt = packet(&server_state, &VERIFY_TOKEN);
+
+        // When
+        let result = pkt.handle(&mut client_state, &server_state);
+
+        // Then
+        assert!(matches!(
+            result,
+            Err(PacketHandlerError::InvalidState(_, _))
+        ));
+    }
+
+    #[test]
+    fn test_encryption_response_accepts_verify_token_signed_with_profile_key() {
+        // Given
+        let server_state = online_mode();
+        let mut client_state = client();
+        let profile_key = RsaPrivateKey::new(&mut OsRng, 1024).unwrap();
+        let profile_public_key = RsaPublicKey::from(&profile_key)
+            .to_public_key_der()
+            .unwrap()
+            .into_vec();
+        client_state.set_profile_public_key(profile_public_key);
+        let signature = sign_verify_token(&profile_key, &VERIFY_TOKEN, SALT);
+        let pkt = signed_packet(&server_state, &signature);
+
+        // When
+        let result = pkt.handle(&mut client_state, &server_state);
+
+        // Then
+        assert!(result.is_ok());
+        assert!(client_state.take_pending_authentication().is_some());
+    }
+
+    #[test]
+    fn test_encryption_response_rejects_signature_without_profile_key() {
+        // Given
+        let server_state = online_mode();
+        let mut client_state = client();
+        let pkt = signed_packet(&server_state, &[0; 128]);
+
+        // When
+        let result = pkt.handle(&mut client_state, &server_state);
+
+        // Then
+        assert!(matches!(
+            result,
+            Err(PacketHandlerError::InvalidState(_, _))
+        ));
+        assert!(client_state.take_pending_authentication().is_none());
+    }
+}
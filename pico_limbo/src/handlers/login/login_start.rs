@@ -1,18 +1,24 @@
+use crate::forwarding::modern_forwarding_version::ModernForwardingVersion;
 use crate::handlers::configuration::send_play_packets;
-use crate::kick_messages::CLIENT_MODERN_FORWARDING_NOT_SUPPORTED_KICK_MESSAGE;
+use crate::kick_messages::{
+    CLIENT_MODERN_FORWARDING_NOT_SUPPORTED_KICK_MESSAGE, SERVER_FULL_KICK_REASON,
+};
 use crate::server::batch::Batch;
 use crate::server::client_state::ClientState;
 use crate::server::game_profile::GameProfile;
+use crate::server::metrics::metrics;
 use crate::server::packet_handler::{PacketHandler, PacketHandlerError};
 use crate::server::packet_registry::PacketRegistry;
-use crate::server_state::ServerState;
+use crate::server_state::{OnlineMode, ServerState};
 use minecraft_packets::login::custom_query_packet::CustomQueryPacket;
+use minecraft_packets::login::encryption_request_packet::EncryptionRequestPacket;
 use minecraft_packets::login::game_profile_packet::GameProfilePacket;
 use minecraft_packets::login::login_state_packet::LoginStartPacket;
 use minecraft_packets::login::login_success_packet::LoginFinishedPacket;
 use minecraft_packets::login::set_compression_packet::SetCompressionPacket;
 use minecraft_protocol::prelude::ProtocolVersion;
 use rand::RngExt;
+use tracing::info;
 
 impl PacketHandler for LoginStartPacket {
     fn handle(
@@ -21,12 +27,18 @@ impl PacketHandler for LoginStartPacket {
         server_state: &ServerState,
     ) -> Result<Batch, PacketHandlerError> {
         let mut batch = Batch::new();
-        if server_state.is_modern_forwarding() {
+        // Players forwarded by BungeeGuard alongside modern forwarding already have a profile
+        if server_state.is_modern_forwarding() && client_state.game_profile().is_none() {
             if client_state.protocol_version().is_modern() {
                 login_start_velocity(&mut batch, client_state);
             } else {
                 client_state.kick(CLIENT_MODERN_FORWARDING_NOT_SUPPORTED_KICK_MESSAGE);
             }
+        } else if let Some(online_mode) = server_state.online_mode() {
+            if let Some(profile_public_key) = self.profile_public_key() {
+                client_state.set_profile_public_key(profile_public_key.to_vec());
+            }
+            login_start_online_mode(&mut batch, client_state, online_mode, self.name());
         } else {
             let game_profile: GameProfile = self.into();
             fire_login_success(&mut batch, client_state, server_state, game_profile)?;
@@ -41,16 +53,65 @@ fn login_start_velocity(batch: &mut Batch, client_state: &mut ClientState) {
         rng.random()
     };
     client_state.set_velocity_login_message_id(message_id);
-    let packet = CustomQueryPacket::velocity_info_channel(message_id);
+    let version = ModernForwardingVersion::highest_supported(client_state.protocol_version());
+    let packet = CustomQueryPacket::velocity_info_channel(message_id, version.id());
     batch.queue(|| PacketRegistry::CustomQuery(packet));
 }
 
+fn login_start_online_mode(
+    batch: &mut Batch,
+    client_state: &mut ClientState,
+    online_mode: &OnlineMode,
+    username: String,
+) {
+    let verify_token: [u8; 4] = {
+        let mut rng = rand::rng();
+        rng.random()
+    };
+    client_state.set_encryption_request(username, verify_token.to_vec());
+    let packet = EncryptionRequestPacket::new(online_mode.public_key(), &verify_token);
+    batch.queue(|| PacketRegistry::EncryptionRequest(packet));
+}
+
 pub fn fire_login_success(
     batch: &mut Batch,
     client_state: &mut ClientState,
     server_state: &ServerState,
     game_profile: GameProfile,
 ) -> Result<(), PacketHandlerError> {
+    // The profile forwarded by a legacy proxy keeps its UUID and textures, with the username of the login start
+    let identity = match client_state.game_profile() {
+        Some(mut forwarded) => {
+            forwarded.set_name(&game_profile.username());
+            forwarded
+        }
+        None => game_profile,
+    };
+    let address = client_state.remote_address().map(|address| address.ip());
+    let access_lists = server_state.access_lists();
+    if let Some((kick_message, reason)) = access_lists
+        .check_bans(Some(&identity), address)
+        .or_else(|| access_lists.check_whitelist(&identity))
+    {
+        info!("{} may not join: {reason}", identity.username());
+        client_state.kick_component(kick_message, reason);
+        return Ok(());
+    }
+
+    match server_state.reserve_player_slot(&identity) {
+        Ok(player_slot) => client_state.set_player_slot(player_slot),
+        Err(kick_message) => {
+            info!("{} tried to join a full server", identity.username());
+            client_state.kick_component(kick_message.clone(), SERVER_FULL_KICK_REASON);
+            return Ok(());
+        }
+    }
+
+    let forwarding = server_state
+        .forwarding_mode()
+        .login_name(client_state.is_forwarded_by_handshake());
+    metrics().login(forwarding);
+
     let protocol_version = client_state.protocol_version();
 
     if protocol_version.is_after_inclusive(ProtocolVersion::V1_8)
@@ -63,14 +124,14 @@ pub fn fire_login_success(
     }
 
     if protocol_version.is_after_inclusive(ProtocolVersion::V1_21_2) {
-        let packet = LoginFinishedPacket::new(game_profile.uuid(), game_profile.username());
+        let packet = LoginFinishedPacket::new(identity.uuid(), identity.username());
         batch.queue(|| PacketRegistry::LoginFinished(packet));
     } else {
-        let packet = GameProfilePacket::new(game_profile.uuid(), game_profile.username());
+        let packet = GameProfilePacket::new(identity.uuid(), identity.username());
         batch.queue(|| PacketRegistry::GameProfile(packet));
     }
 
-    client_state.set_game_profile(game_profile);
+    client_state.set_game_profile(identity);
 
     if !protocol_version.supports_configuration_state() {
         client_state.set_keep_alive_should_enable();
@@ -83,7 +144,10 @@ pub fn fire_login_success(
 mod tests {
     use super::*;
     use futures::StreamExt;
-    use minecraft_protocol::prelude::{Direction, ProtocolVersion, State};
+    use minecraft_packets::login::Property;
+    use minecraft_protocol::prelude::{Direction, ProtocolVersion, State, Uuid};
+    use pico_text_component::prelude::Component;
+    use tempfile::TempDir;
 
     fn vanilla() -> ServerState {
         ServerState::builder().build().unwrap()
@@ -92,7 +156,13 @@ mod tests {
     fn velocity() -> ServerState {
         let mut builder = ServerState::builder();
         let secret = "foo";
-        builder.enable_modern_forwarding(secret);
+        builder.enable_modern_forwarding([secret]);
+        builder.build().unwrap()
+    }
+
+    fn online_mode() -> ServerState {
+        let mut builder = ServerState::builder();
+        builder.enable_online_mode("http://localhost/hasJoined");
         builder.build().unwrap()
     }
 
@@ -133,6 +203,31 @@ mod tests {
         assert!(batch.next().await.is_none());
     }
 
+    #[tokio::test]
+    async fn test_login_start_skips_velocity_query_when_forwarded_by_bungee_guard() {
+        // Given
+        let mut builder = ServerState::builder();
+        builder.enable_modern_and_bungee_guard_forwarding(["foo"], vec!["bar".to_string()]);
+        let server_state = builder.build().unwrap();
+        let mut client_state = client(ProtocolVersion::V1_12_2);
+        client_state.set_game_profile(GameProfile::anonymous(Uuid::new_v4(), None));
+        let pkt = packet();
+
+        // When
+        let batch = pkt.handle(&mut client_state, &server_state).unwrap();
+        let mut batch = batch.into_stream();
+
+        // Then
+        assert!(
+            matches!(
+                batch.next().await.unwrap().unwrap_packet(),
+                PacketRegistry::GameProfile(_)
+            ),
+            "first packet should be GameProfile for < 1.21.2"
+        );
+        assert!(client_state.should_kick().is_none());
+    }
+
     #[tokio::test]
     async fn test_login_start_velocity_kicks_old_client() {
         // Given
@@ -147,8 +242,212 @@ mod tests {
         assert!(result.is_ok());
         assert_eq!(
             client_state.should_kick(),
-            Some(CLIENT_MODERN_FORWARDING_NOT_SUPPORTED_KICK_MESSAGE.to_string())
+            Some(Component::new(
+                CLIENT_MODERN_FORWARDING_NOT_SUPPORTED_KICK_MESSAGE
+            ))
+        );
+    }
+
+    // online mode
+    #[tokio::test]
+    async fn test_login_start_online_mode_sends_encryption_request() {
+        // Given
+        let server_state = online_mode();
+        let mut client_state = client(ProtocolVersion::V1_21_2);
+        let pkt = packet();
+
+        // When
+        let batch = pkt.handle(&mut client_state, &server_state).unwrap();
+        let mut batch = batch.into_stream();
+
+        // Then
+        assert!(
+            matches!(
+                batch.next().await.unwrap().unwrap_packet(),
+                PacketRegistry::EncryptionRequest(_)
+            ),
+            "first packet should be the EncryptionRequest"
+        );
+        assert!(batch.next().await.is_none());
+        assert!(client_state.take_encryption_request().is_some());
+        assert!(client_state.game_profile().is_none());
+    }
+
+    // max players
+    fn full_server(bypass: &[&str]) -> ServerState {
+        let mut builder = ServerState::builder();
+        builder.max_players(1);
+        builder
+            .enforce_max_players("<red>The server is full!</red>", bypass)
+            .unwrap();
+        let server_state = builder.build().unwrap();
+        server_state.increment();
+        server_state
+    }
+
+    #[tokio::test]
+    async fn test_login_start_kicks_when_server_is_full() {
+        // Given
+        let server_state = full_server(&[]);
+        let mut client_state = client(ProtocolVersion::V1_21_2);
+        let pkt = packet();
+
+        // When
+        let batch = pkt.handle(&mut client_state, &server_state).unwrap();
+        let mut batch = batch.into_stream();
+
+        // Then
+        assert!(batch.next().await.is_none());
+        assert_eq!(
+            client_state.should_kick().map(|kick| kick.to_plain_text()),
+            Some("The server is full!".to_string())
+        );
+        assert!(client_state.game_profile().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_login_start_bypasses_full_server() {
+        // Given
+        let server_state = full_server(&["Notch", "069a79f4-44e9-4726-a5be-fca90e38aaf5"]);
+        let mut client_state = client(ProtocolVersion::V1_21_2);
+        let pkt = LoginStartPacket::default();
+        let mut game_profile: GameProfile = (&pkt).into();
+        game_profile.set_name(&"notch");
+
+        // When
+        fire_login_success(
+            &mut Batch::new(),
+            &mut client_state,
+            &server_state,
+            game_profile,
+        )
+        .unwrap();
+
+        // Then
+        assert!(client_state.should_kick().is_none());
+        assert!(client_state.game_profile().is_some());
+    }
+
+    #[test]
+    fn test_forwarded_uuid_bypasses_full_server() {
+        // Given
+        let server_state = full_server(&["069a79f4-44e9-4726-a5be-fca90e38aaf5"]);
+        let mut client_state = client(ProtocolVersion::V1_21_2);
+        let forwarded_uuid = Uuid::from_u128(0x069a_79f4_44e9_4726_a5be_fca9_0e38_aaf5);
+        client_state.set_game_profile(GameProfile::anonymous(forwarded_uuid, None));
+        let game_profile = GameProfile::new("Notch", Uuid::new_v4(), None);
+
+        // When
+        fire_login_success(
+            &mut Batch::new(),
+            &mut client_state,
+            &server_state,
+            game_profile,
+        )
+        .unwrap();
+
+        // Then
+        assert!(client_state.should_kick().is_none());
+    }
+
+    #[test]
+    fn test_concurrent_logins_do_not_exceed_max_players() {
+        // Given
+        let mut builder = ServerState::builder();
+        builder.max_players(1).show_online_player_count(true);
+        builder
+            .enforce_max_players("<red>The server is full!</red>", &[] as &[&str])
+            .unwrap();
+        let server_state = builder.build().unwrap();
+
+        // When
+        let client_states: Vec<ClientState> = std::thread::scope(|scope| {
+            let logins: Vec<_> = (0..8)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut client_state = client(ProtocolVersion::V1_21_2);
+                        let game_profile: GameProfile = (&packet()).into();
+                        fire_login_success(
+                            &mut Batch::new(),
+                            &mut client_state,
+                            &server_state,
+                            game_profile,
+                        )
+                        .unwrap();
+                        client_state
+                    })
+                })
+                .collect();
+            logins
+                .into_iter()
+                .map(|login| login.join().unwrap())
+                .collect()
+        });
+        let joined = client_states
+            .iter()
+            .filter(|client_state| client_state.should_kick().is_none())
+            .count();
+        let online_while_joined = server_state.online_players();
+        drop(client_states);
+
+        // Then
+        assert_eq!(joined, 1);
+        assert_eq!(online_while_joined, 1);
+        assert_eq!(server_state.online_players(), 0);
+    }
+
+    // access lists
+    #[test]
+    fn test_forwarded_profile_is_kept_with_the_login_start_username() {
+        // Given
+        let server_state = vanilla();
+        let mut client_state = client(ProtocolVersion::V1_21_2);
+        let forwarded_uuid = Uuid::from_u128(0x069a_79f4_44e9_4726_a5be_fca9_0e38_aaf5);
+        let textures = Property::textures("skin", Some("signature"));
+        client_state.set_game_profile(GameProfile::anonymous(forwarded_uuid, Some(textures)));
+        let game_profile = GameProfile::new("Notch", Uuid::new_v4(), None);
+
+        // When
+        fire_login_success(
+            &mut Batch::new(),
+            &mut client_state,
+            &server_state,
+            game_profile,
+        )
+        .unwrap();
+
+        // Then
+        assert_eq!(client_state.get_username(), "Notch");
+        assert_eq!(client_state.get_unique_id(), forwarded_uuid);
+        assert!(client_state.get_textures().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_login_start_kicks_banned_address() {
+        // Given
+        let directory = TempDir::new().unwrap();
+        let players_file = directory.path().join("players.json");
+        let ips_file = directory.path().join("ips.json");
+        std::fs::write(&ips_file, r#"[{"ip": "10.0.0.0/8", "reason": "Spam"}]"#).unwrap();
+        let mut builder = ServerState::builder();
+        builder
+            .bans(&players_file, &ips_file, "Banned", "IP banned: {reason}")
+            .unwrap();
+        let server_state = builder.build().unwrap();
+        let mut client_state = client(ProtocolVersion::V1_21_2);
+        client_state.set_remote_address("10.1.2.3:50000".parse().unwrap());
+
+        // When
+        let batch = packet().handle(&mut client_state, &server_state).unwrap();
+        let mut batch = batch.into_stream();
+
+        // Then
+        assert!(batch.next().await.is_none());
+        assert_eq!(
+            client_state.should_kick().map(|kick| kick.to_plain_text()),
+            Some("IP banned: Spam".to_string())
         );
+        assert_eq!(client_state.kick_reason(), "ip_banned");
     }
 
     // vanilla login
@@ -1,3 +1,5 @@
 mod custom_query_answer;
+mod encryption_response;
 mod login_acknowledged;
-mod login_start;
+pub mod login_start;
+pub mod session_server;
@@ -0,0 +1,89 @@
+use crate::handlers::play::fetch_minecraft_profile::Profile;
+use crate::server::game_profile::GameProfile;
+use minecraft_packets::login::Property;
+use minecraft_protocol::prelude::Uuid;
+use reqwest::{StatusCode, Url};
+use serde::Deserialize;
+use std::time::Duration;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SessionServerError {
+    #[error("invalid session server URL: {0}")]
+    InvalidUrl(String),
+    #[error(transparent)]
+    Request(#[from] reqwest::Error),
+    #[error("session server returned an invalid UUID: {0}")]
+    InvalidUuid(#[from] uuid::Error),
+    #[error("session server did not answer within {} seconds", .0.as_secs_f32())]
+    Timeout(Duration),
+}
+
+#[derive(Deserialize)]
+struct HasJoinedResponse {
+    id: String,
+    name: String,
+    #[serde(flatten)]
+    profile: Profile,
+}
+
+/// Asks the session server whether the player joined using the given server hash.
+/// Returns `None` when the session server does not know about this login.
+pub async fn has_joined(
+    has_joined_url: &str,
+    username: &str,
+    server_hash: &str,
+    timeout: Duration,
+) -> Result<Option<GameProfile>, SessionServerError> {
+    tokio::time::timeout(
+        timeout,
+        request_has_joined(has_joined_url, username, server_hash),
+    )
+    .await
+    .map_err(|_| SessionServerError::Timeout(timeout))?
+}
+
+async fn request_has_joined(
+    has_joined_url: &str,
+    username: &str,
+    server_hash: &str,
+) -> Result<Option<GameProfile>, SessionServerError> {
+    let url = Url::parse_with_params(
+        has_joined_url,
+        &[("username", username), ("serverId", server_hash)],
+    )
+    .map_err(|err| SessionServerError::InvalidUrl(err.to_string()))?;
+    let response = reqwest::get(url).await?.error_for_status()?;
+    if response.status() == StatusCode::NO_CONTENT {
+        return Ok(None);
+    }
+
+    let response = response.json::<HasJoinedResponse>().await?;
+    let uuid = Uuid::parse_str(&response.id)?;
+    let textures = response.profile.try_get_textures().map(Property::from);
+    Ok(Some(GameProfile::new(&response.name, uuid, textures)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_has_joined_times_out_when_the_session_server_never_answers() {
+        // Given
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hasJoined", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (_socket, _) = listener.accept().await.unwrap();
+            std::future::pending::<()>().await;
+        });
+
+        // When
+        let result = has_joined(&url, "Notch", "hash", Duration::from_millis(100)).await;
+
+        // Then
+        assert!(matches!(result, Err(SessionServerError::Timeout(_))));
+        server.abort();
+    }
+}
@@ -9,6 +9,7 @@ use minecraft_packets::play::chat_message_packet::ChatMessagePacket;
 use minecraft_packets::play::client_bound_player_abilities_packet::ClientBoundPlayerAbilitiesPacket;
 use minecraft_packets::play::transfer_packet::TransferPacket;
 use minecraft_protocol::prelude::{ProtocolVersion, VarInt};
+use std::time::Instant;
 use thiserror::Error;
 use tracing::{info, warn};
 
@@ -19,6 +20,7 @@ impl PacketHandler for ChatCommandPacket {
         server_state: &ServerState,
     ) -> Result<Batch, PacketHandlerError> {
         let mut batch = Batch::new();
+        client_state.record_activity(Instant::now());
         run_command(client_state, server_state, self.get_command(), &mut batch);
         Ok(batch)
     }
@@ -31,6 +33,7 @@ impl PacketHandler for ChatMessagePacket {
         server_state: &ServerState,
     ) -> Result<Batch, PacketHandlerError> {
         let mut batch = Batch::new();
+        client_state.record_activity(Instant::now());
         if let Some(command) = self.get_command() {
             run_command(client_state, server_state, command, &mut batch);
         } else {
@@ -51,7 +54,24 @@ fn run_command(
         client_state.get_username(),
         command
     );
+    execute_command(client_state, server_state, command, batch);
+}
 
+/// Checks a command before running it on behalf of a player.
+pub fn validate_command(
+    server_commands: &ServerCommands,
+    command: &str,
+) -> Result<(), ParseCommandError> {
+    Command::parse(server_commands, command).map(|_| ())
+}
+
+/// Runs a command as if it was typed by the player.
+pub fn execute_command(
+    client_state: &mut ClientState,
+    server_state: &ServerState,
+    command: &str,
+    batch: &mut Batch,
+) {
     if let Ok(parsed_command) = Command::parse(server_state.server_commands(), command) {
         match parsed_command {
             Command::Spawn => {
@@ -0,0 +1,132 @@
+use crate::server::batch::Batch;
+use crate::server::client_state::ClientState;
+use crate::server::metrics::metrics;
+use crate::server::packet_handler::{PacketHandler, PacketHandlerError};
+use crate::server::packet_registry::PacketRegistry;
+use crate::server_state::ServerState;
+use minecraft_packets::play::player_info_update_packet::PlayerInfoUpdatePacket;
+use minecraft_packets::play::server_bound_keep_alive_packet::ServerBoundKeepAlivePacket;
+use minecraft_protocol::prelude::{ProtocolVersion, State};
+use std::time::Instant;
+use tracing::debug;
+
+impl PacketHandler for ServerBoundKeepAlivePacket {
+    fn handle(
+        &self,
+        client_state: &mut ClientState,
+        _server_state: &ServerState,
+    ) -> Result<Batch, PacketHandlerError> {
+        let mut batch = Batch::new();
+        let protocol_version = client_state.protocol_version();
+        let id = self.id(protocol_version);
+
+        let Some(latency) = client_state.keep_alive_received(id, Instant::now()) else {
+            debug!("Ignoring keep alive with unexpected id {}", id);
+            return Ok(batch);
+        };
+        metrics().keep_alive_latency(latency);
+
+        let latency = i32::try_from(latency.as_millis()).unwrap_or(i32::MAX);
+        debug!(
+            "{} has a latency of {} ms",
+            client_state.get_username(),
+            latency
+        );
+
+        // The player is only added to the tab list from 1.8, and only once the client
+        // acknowledged the end of the configuration and received the login packet
+        let unique_id = client_state.get_unique_id();
+        if client_state.serverbound_state() == State::Play
+            && protocol_version.is_after_inclusive(ProtocolVersion::V1_8)
+            && !unique_id.is_nil()
+        {
+            let packet = PlayerInfoUpdatePacket::latency(unique_id, latency);
+            batch.queue(|| PacketRegistry::PlayerInfoUpdate(packet));
+        }
+
+        Ok(batch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::game_profile::GameProfile;
+    use futures::StreamExt;
+    use minecraft_packets::login::login_state_packet::LoginStartPacket;
+    use minecraft_protocol::prelude::{BinaryReader, DecodePacket, Direction};
+
+    fn client(protocol_version: ProtocolVersion) -> ClientState {
+        let mut client_state = ClientState::default();
+        client_state.set_protocol_version(protocol_version);
+        client_state.set_state(Direction::Clientbound, State::Play);
+        client_state.set_state(Direction::Serverbound, State::Play);
+        let mut game_profile: GameProfile = (&LoginStartPacket::default()).into();
+        game_profile.set_name(&"Player");
+        client_state.set_game_profile(game_profile);
+        client_state
+    }
+
+    fn packet(id: i64) -> ServerBoundKeepAlivePacket {
+        let bytes = id.to_be_bytes();
+        let mut reader = BinaryReader::new(&bytes);
+        ServerBoundKeepAlivePacket::decode(&mut reader, ProtocolVersion::V1_21_2).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_keep_alive_updates_latency_in_tab_list() {
+        // Given
+        let mut client_state = client(ProtocolVersion::V1_21_2);
+        client_state.keep_alive_sent(42, Instant::now());
+
+        // When
+        let batch = packet(42)
+            .handle(&mut client_state, &ServerState::default())
+            .unwrap();
+        let mut batch = batch.into_stream();
+
+        // Then
+        assert!(client_state.latency().is_some());
+        assert!(matches!(
+            batch.next().await.unwrap().unwrap_packet(),
+            PacketRegistry::PlayerInfoUpdate(_)
+        ));
+        assert!(batch.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_keep_alive_answered_during_configuration_does_not_update_tab_list() {
+        // Given
+        let mut client_state = client(ProtocolVersion::V1_21_2);
+        client_state.set_state(Direction::Serverbound, State::Configuration);
+        client_state.keep_alive_sent(42, Instant::now());
+
+        // When
+        let batch = packet(42)
+            .handle(&mut client_state, &ServerState::default())
+            .unwrap();
+        let mut batch = batch.into_stream();
+
+        // Then
+        assert!(client_state.latency().is_some());
+        assert!(batch.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_keep_alive_ignores_unexpected_id() {
+        // Given
+        let mut client_state = client(ProtocolVersion::V1_21_2);
+        client_state.keep_alive_sent(42, Instant::now());
+
+        // When
+        let batch = packet(7)
+            .handle(&mut client_state, &ServerState::default())
+            .unwrap();
+        let mut batch = batch.into_stream();
+
+        // Then
+        assert!(client_state.latency().is_none());
+        assert!(batch.next().await.is_none());
+        assert_eq!(client_state.keep_alive_sent(43, Instant::now()), 1);
+    }
+}
@@ -1,5 +1,6 @@
-mod commands;
+pub mod commands;
 pub mod fetch_minecraft_profile;
+mod keep_alive;
 mod player_abilities;
 pub mod send_chunks_circularly;
 mod set_player_pos;
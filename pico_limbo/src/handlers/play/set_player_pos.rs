@@ -4,6 +4,7 @@ use crate::server::client_state::ClientState;
 use crate::server::packet_handler::{PacketHandler, PacketHandlerError};
 use crate::server_state::ServerState;
 use minecraft_packets::play::set_player_position_packet::SetPlayerPositionPacket;
+use std::time::Instant;
 
 impl PacketHandler for SetPlayerPositionPacket {
     fn handle(
@@ -11,6 +12,7 @@ impl PacketHandler for SetPlayerPositionPacket {
         client_state: &mut ClientState,
         server_state: &ServerState,
     ) -> Result<Batch, PacketHandlerError> {
+        client_state.record_position(self.x, self.z, Instant::now());
         Ok(teleport_player_to_spawn_out_of_bounds(
             client_state,
             server_state,
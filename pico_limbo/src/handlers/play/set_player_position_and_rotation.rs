@@ -6,6 +6,7 @@ use crate::server::packet_registry::PacketRegistry;
 use crate::server_state::{Boundaries, ServerState};
 use minecraft_packets::play::set_player_position_and_rotation_packet::SetPlayerPositionAndRotationPacket;
 use minecraft_packets::play::synchronize_player_position_packet::SynchronizePlayerPositionPacket;
+use std::time::Instant;
 
 const FALL_SPEED: f64 = 3.8855;
 
@@ -15,6 +16,9 @@ impl PacketHandler for SetPlayerPositionAndRotationPacket {
         client_state: &mut ClientState,
         server_state: &ServerState,
     ) -> Result<Batch, PacketHandlerError> {
+        let now = Instant::now();
+        client_state.record_position(self.x, self.z, now);
+        client_state.record_rotation(self.yaw, self.pitch, now);
         Ok(teleport_player_to_spawn_out_of_bounds(
             client_state,
             server_state,
@@ -1,3 +1,35 @@
 pub const PROXY_REQUIRED_KICK_MESSAGE: &str = "You must connect through a proxy.";
 pub const CLIENT_MODERN_FORWARDING_NOT_SUPPORTED_KICK_MESSAGE: &str =
     "This server is only compatible with Minecraft 1.13 and above.";
+pub const FAILED_TO_VERIFY_USERNAME_KICK_MESSAGE: &str = "Failed to verify username!";
+pub const AUTHENTICATION_SERVERS_DOWN_KICK_MESSAGE: &str =
+    "Authentication servers are down. Please try again later, sorry!";
+pub const CONNECTION_THROTTLED_KICK_MESSAGE: &str =
+    "Connection throttled! Please wait before reconnecting.";
+pub const DUPLICATE_LOGIN_KICK_MESSAGE: &str = "You logged in from another location";
+pub const TIMED_OUT_KICK_MESSAGE: &str = "Timed out";
+pub const TOO_MANY_CONNECTIONS_KICK_MESSAGE: &str = "Too many connections from your address.";
+
+pub const SERVER_FULL_KICK_REASON: &str = "server_full";
+pub const OPERATOR_KICK_REASON: &str = "operator";
+pub const BANNED_KICK_REASON: &str = "banned";
+pub const IP_BANNED_KICK_REASON: &str = "ip_banned";
+pub const NOT_WHITELISTED_KICK_REASON: &str = "not_whitelisted";
+pub const QUEUE_RELEASED_KICK_REASON: &str = "queue_released";
+pub const AFK_KICK_REASON: &str = "afk";
+pub const SESSION_LIMIT_KICK_REASON: &str = "session_limit";
+
+/// Short name of a kick message, used to label the kicks in the metrics.
+pub fn kick_reason(kick_message: &str) -> &'static str {
+    match kick_message {
+        PROXY_REQUIRED_KICK_MESSAGE => "proxy_required",
+        CLIENT_MODERN_FORWARDING_NOT_SUPPORTED_KICK_MESSAGE => "unsupported_version",
+        FAILED_TO_VERIFY_USERNAME_KICK_MESSAGE => "failed_to_verify_username",
+        AUTHENTICATION_SERVERS_DOWN_KICK_MESSAGE => "authentication_servers_down",
+        CONNECTION_THROTTLED_KICK_MESSAGE => "connection_throttled",
+        DUPLICATE_LOGIN_KICK_MESSAGE => "duplicate_login",
+        TIMED_OUT_KICK_MESSAGE => "timed_out",
+        TOO_MANY_CONNECTIONS_KICK_MESSAGE => "too_many_connections",
+        _ => "other",
+    }
+}
@@ -8,7 +8,7 @@ mod server;
 mod server_brand;
 mod server_state;
 
-use crate::cli::Cli;
+use crate::cli::{Cli, Command};
 use clap::Parser;
 use std::ffi::{CStr, c_char, c_int};
 use std::slice;
@@ -80,6 +80,12 @@ pub unsafe extern "C" fn start_app(
     }
 
     match Cli::try_parse_from(&rust_args) {
+        Ok(Cli {
+            command: Some(Command::Replay(args)),
+            ..
+        }) => {
+            server::replay::replay(&args);
+        }
         Ok(cli) => {
             let cancellation_token = unsafe { &*token_ptr };
 
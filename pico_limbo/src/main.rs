@@ -9,12 +9,15 @@ mod server;
 mod server_brand;
 mod server_state;
 
-use crate::cli::Cli;
+use crate::cli::{Cli, Command};
 use clap::Parser;
 use std::process::ExitCode;
 
 #[tokio::main]
 async fn main() -> ExitCode {
     let cli = Cli::parse();
-    server::start_server::start_server(&cli, None).await
+    match &cli.command {
+        Some(Command::Replay(args)) => server::replay::replay(args),
+        None => server::start_server::start_server(&cli, None).await,
+    }
 }
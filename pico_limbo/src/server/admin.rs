@@ -0,0 +1,429 @@
+use crate::handlers::configuration::message_packet;
+use crate::handlers::play::commands::{ParseCommandError, validate_command};
+use crate::kick_messages::OPERATOR_KICK_REASON;
+use crate::server::packet_registry::PacketRegistry;
+use crate::server::player_registry::{PlayerHandle, PlayerRegistry};
+use crate::server::reload::{ConfigReloader, ReloadError};
+use crate::server_state::ServerState;
+use minecraft_packets::play::transfer_packet::TransferPacket;
+use minecraft_protocol::prelude::{ProtocolVersion, Uuid, VarInt};
+use pico_text_component::prelude::{Component, parse_mini_message};
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::sync::RwLock;
+use tokio_util::sync::CancellationToken;
+
+pub const COMMANDS: [&str; 6] = ["list", "kick", "broadcast", "transfer", "reload", "stop"];
+const DEFAULT_KICK_REASON: &str = "Kicked by an operator";
+const TRANSFER_USAGE: &str = "transfer <player> <host> [port]";
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ParseAdminCommandError {
+    #[error(
+        "unknown command, available commands are: {}, or an in-game command followed by a player",
+        COMMANDS.join(", ")
+    )]
+    Unknown,
+    #[error("usage: {0}")]
+    Usage(&'static str),
+    #[error("invalid port")]
+    InvalidPort,
+}
+
+#[derive(Debug, Error)]
+pub enum AdminError {
+    #[error(transparent)]
+    Parse(#[from] ParseAdminCommandError),
+    #[error("{0}")]
+    Command(ParseCommandError),
+    #[error("{0} is not online")]
+    NotOnline(String),
+    #[error("{username} cannot be transferred on version {version}")]
+    UnsupportedVersion {
+        username: String,
+        version: &'static str,
+    },
+    #[error("invalid message: {0}")]
+    InvalidMessage(String),
+    #[error("failed to reload the configuration, keeping the current one: {0}")]
+    Reload(#[from] ReloadError),
+}
+
+impl From<ParseCommandError> for AdminError {
+    fn from(err: ParseCommandError) -> Self {
+        match err {
+            ParseCommandError::Unknown => Self::Parse(ParseAdminCommandError::Unknown),
+            err => Self::Command(err),
+        }
+    }
+}
+
+/// Command typed by an operator, either on the console or through RCON.
+#[derive(Debug, PartialEq, Eq)]
+pub enum AdminCommand {
+    List,
+    Kick {
+        username: String,
+        reason: String,
+    },
+    Broadcast(String),
+    Transfer {
+        username: String,
+        host: String,
+        port: i32,
+    },
+    Reload,
+    Stop,
+    /// In-game command run on behalf of a player, such as `fly Notch`.
+    Player {
+        username: String,
+        command: String,
+    },
+}
+
+impl AdminCommand {
+    pub fn parse(input: &str) -> Result<Option<Self>, ParseAdminCommandError> {
+        let input = input.trim();
+        let (command, arguments) = input.split_once(' ').unwrap_or((input, ""));
+        let arguments = arguments.trim();
+        let mut parts = arguments.split_whitespace();
+        let parsed = match command {
+            "" => return Ok(None),
+            "list" => Self::List,
+            "kick" => {
+                let username = parts
+                    .next()
+                    .ok_or(ParseAdminCommandError::Usage("kick <player> [reason]"))?;
+                let reason = arguments[username.len()..].trim();
+                Self::Kick {
+                    username: username.to_string(),
+                    reason: if reason.is_empty() {
+                        DEFAULT_KICK_REASON.to_string()
+                    } else {
+                        reason.to_string()
+                    },
+                }
+            }
+            "broadcast" => {
+                if arguments.is_empty() {
+                    return Err(ParseAdminCommandError::Usage("broadcast <message>"));
+                }
+                Self::Broadcast(arguments.to_string())
+            }
+            "transfer" => {
+                let username = parts
+                    .next()
+                    .ok_or(ParseAdminCommandError::Usage(TRANSFER_USAGE))?;
+                let host = parts
+                    .next()
+                    .ok_or(ParseAdminCommandError::Usage(TRANSFER_USAGE))?;
+                let port = parts
+                    .next()
+                    .unwrap_or("25565")
+                    .parse::<u16>()
+                    .map_err(|_| ParseAdminCommandError::InvalidPort)?;
+                Self::Transfer {
+                    username: username.to_string(),
+                    host: host.to_string(),
+                    port: i32::from(port),
+                }
+            }
+            "reload" => Self::Reload,
+            "stop" => Self::Stop,
+            // The aliases of the in-game commands are only known once the state is read
+            _ => {
+                let username = parts.next().ok_or(ParseAdminCommandError::Unknown)?;
+                let command_arguments = arguments[username.len()..].trim();
+                Self::Player {
+                    username: username.to_string(),
+                    command: format!("{command} {command_arguments}").trim().to_string(),
+                }
+            }
+        };
+        Ok(Some(parsed))
+    }
+}
+
+/// Runs the commands of the operators and tells them the outcome.
+pub struct Admin {
+    state: Arc<RwLock<ServerState>>,
+    players: Arc<PlayerRegistry>,
+    reloader: Arc<ConfigReloader>,
+    stop: CancellationToken,
+}
+
+impl Admin {
+    pub fn new(
+        state: Arc<RwLock<ServerState>>,
+        players: Arc<PlayerRegistry>,
+        reloader: Arc<ConfigReloader>,
+        stop: CancellationToken,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            state,
+            players,
+            reloader,
+            stop,
+        })
+    }
+
+    pub fn players(&self) -> Arc<PlayerRegistry> {
+        Arc::clone(&self.players)
+    }
+
+    /// Parses and runs a command, returns `None` when the input is blank.
+    pub async fn run(&self, input: &str) -> Option<Result<Component, AdminError>> {
+        match AdminCommand::parse(input) {
+            Ok(Some(command)) => Some(self.execute(command).await),
+            Ok(None) => None,
+            Err(err) => Some(Err(err.into())),
+        }
+    }
+
+    pub async fn execute(&self, command: AdminCommand) -> Result<Component, AdminError> {
+        let reply = match command {
+            AdminCommand::List => {
+                let usernames = self.players.usernames();
+                if usernames.is_empty() {
+                    "There are no players online".to_string()
+                } else {
+                    format!(
+                        "Players online ({}): {}",
+                        usernames.len(),
+                        usernames.join(", ")
+                    )
+                }
+            }
+            AdminCommand::Kick { username, reason } => {
+                let player = self.find_player(&username)?;
+                let component =
+                    parse_mini_message(&reason).unwrap_or_else(|_| Component::new(&reason));
+                player.kick(component, OPERATOR_KICK_REASON);
+                format!("Kicked {}: {}", player.username(), reason)
+            }
+            AdminCommand::Broadcast(message) => {
+                let component = parse_mini_message(&message)
+                    .map_err(|err| AdminError::InvalidMessage(err.to_string()))?;
+                for player in self.players.all() {
+                    player.queue(message_packet(&component, player.protocol_version()));
+                }
+                format!("[Broadcast] {}", component.to_plain_text())
+            }
+            AdminCommand::Transfer {
+                username,
+                host,
+                port,
+            } => self.transfer(&username, host, port)?,
+            AdminCommand::Reload => {
+                self.reloader.reload().await?;
+                "Configuration reloaded".to_string()
+            }
+            AdminCommand::Stop => {
+                self.stop.cancel();
+                "Stopping the server".to_string()
+            }
+            AdminCommand::Player { username, command } => {
+                validate_command(self.state.read().await.server_commands(), &command)?;
+                let player = self.find_player(&username)?;
+                player.run_command(command.clone());
+                format!("Ran /{} as {}", command, player.username())
+            }
+        };
+        Ok(Component::new(reply))
+    }
+
+    /// Finds a player by username, or by UUID.
+    fn find_player(&self, player: &str) -> Result<PlayerHandle, AdminError> {
+        Uuid::parse_str(player)
+            .map_or_else(|_| self.players.find(player), |uuid| self.players.get(uuid))
+            .ok_or_else(|| AdminError::NotOnline(player.to_string()))
+    }
+
+    fn transfer(&self, username: &str, host: String, port: i32) -> Result<String, AdminError> {
+        let player = self.find_player(username)?;
+        if !player
+            .protocol_version()
+            .is_after_inclusive(ProtocolVersion::V1_20_5)
+        {
+            return Err(AdminError::UnsupportedVersion {
+                username: player.username().to_string(),
+                version: player.protocol_version().humanize(),
+            });
+        }
+        let reply = format!("Transferring {} to {}:{}", player.username(), host, port);
+        player.queue(PacketRegistry::Transfer(TransferPacket {
+            host,
+            port: VarInt::from(port),
+        }));
+        Ok(reply)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configuration::commands::CommandsConfig;
+    use crate::server::player_registry::Outbound;
+    use std::path::PathBuf;
+    use tokio::sync::broadcast;
+
+    fn admin() -> Arc<Admin> {
+        let mut builder = ServerState::builder();
+        builder.server_commands(CommandsConfig::default());
+        let state = Arc::new(RwLock::new(builder.build().unwrap()));
+        let (reloads, _) = broadcast::channel(1);
+        let reloader = ConfigReloader::new(
+            PathBuf::from("server.toml"),
+            Arc::clone(&state),
+            reloads,
+            false,
+        );
+        Admin::new(
+            state,
+            PlayerRegistry::new(),
+            reloader,
+            CancellationToken::new(),
+        )
+    }
+
+    #[test]
+    fn test_parse_kick_with_reason() {
+        // Given
+        let input = "kick Notch <red>Go away</red> now";
+
+        // When
+        let command = AdminCommand::parse(input).unwrap();
+
+        // Then
+        assert_eq!(
+            command,
+            Some(AdminCommand::Kick {
+                username: "Notch".to_string(),
+                reason: "<red>Go away</red> now".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_kick_without_reason() {
+        // Given
+        let input = "kick Notch";
+
+        // When
+        let command = AdminCommand::parse(input).unwrap();
+
+        // Then
+        assert_eq!(
+            command,
+            Some(AdminCommand::Kick {
+                username: "Notch".to_string(),
+                reason: DEFAULT_KICK_REASON.to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_transfer_default_port() {
+        // Given
+        let input = "transfer Notch lobby.example.com";
+
+        // When
+        let command = AdminCommand::parse(input).unwrap();
+
+        // Then
+        assert_eq!(
+            command,
+            Some(AdminCommand::Transfer {
+                username: "Notch".to_string(),
+                host: "lobby.example.com".to_string(),
+                port: 25565,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_player_command() {
+        // Given
+        let input = "flyspeed Notch 0.2";
+
+        // When
+        let command = AdminCommand::parse(input).unwrap();
+
+        // Then
+        assert_eq!(
+            command,
+            Some(AdminCommand::Player {
+                username: "Notch".to_string(),
+                command: "flyspeed 0.2".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(AdminCommand::parse("   "), Ok(None));
+        assert_eq!(
+            AdminCommand::parse("op"),
+            Err(ParseAdminCommandError::Unknown)
+        );
+        assert_eq!(
+            AdminCommand::parse("broadcast"),
+            Err(ParseAdminCommandError::Usage("broadcast <message>"))
+        );
+        assert_eq!(
+            AdminCommand::parse("transfer Notch localhost 70000"),
+            Err(ParseAdminCommandError::InvalidPort)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_player_command_is_queued_to_the_player() {
+        // Given
+        let admin = admin();
+        let (_registration, mut receiver) =
+            admin
+                .players()
+                .register(Uuid::from_u128(1), "Notch", ProtocolVersion::V1_21_2);
+
+        // When
+        let reply = admin.run("fly notch").await.unwrap().unwrap();
+
+        // Then
+        assert_eq!(reply.to_plain_text(), "Ran /fly as Notch");
+        assert!(matches!(
+            receiver.try_recv().unwrap(),
+            Outbound::Command(command) if command == "fly"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_player_command_is_rejected() {
+        // Given
+        let admin = admin();
+
+        // When
+        let result = admin.run("op Notch").await.unwrap();
+
+        // Then
+        assert!(matches!(
+            result,
+            Err(AdminError::Parse(ParseAdminCommandError::Unknown))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_list_players() {
+        // Given
+        let admin = admin();
+        let _notch =
+            admin
+                .players()
+                .register(Uuid::from_u128(1), "Notch", ProtocolVersion::V1_21_2);
+
+        // When
+        let reply = admin.execute(AdminCommand::List).await.unwrap();
+
+        // Then
+        assert_eq!(reply.to_plain_text(), "Players online (1): Notch");
+    }
+}
@@ -0,0 +1,262 @@
+use crate::handlers::configuration::{action_bar_packet, message_packet, title_packets};
+use crate::server::packet_registry::PacketRegistry;
+use crate::server::player_registry::PlayerRegistry;
+use crate::server_state::{Announcement, AnnouncementMessage, Rotation, ServerState, TitleType};
+use minecraft_packets::play::boss_bar_packet::BossBarPacket;
+use minecraft_protocol::prelude::{ProtocolVersion, Uuid};
+use rand::RngExt;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::RwLock;
+
+/// How often the schedule is checked, announcements are delivered to the second.
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Sends the scheduled announcements to every player in game.
+pub struct Announcer {
+    state: Arc<RwLock<ServerState>>,
+    players: Arc<PlayerRegistry>,
+}
+
+/// Schedule of an announcement, restarted when the announcements are reloaded.
+struct Scheduled {
+    next: Option<SystemTime>,
+    position: usize,
+}
+
+impl Announcer {
+    pub const fn new(state: Arc<RwLock<ServerState>>, players: Arc<PlayerRegistry>) -> Self {
+        Self { state, players }
+    }
+
+    /// Announces until the task is aborted.
+    pub async fn run(self) {
+        let mut interval = tokio::time::interval(TICK_INTERVAL);
+        let mut announcements: Arc<[Announcement]> = Arc::default();
+        let mut schedule: Vec<Scheduled> = Vec::new();
+        let mut boss_bars: Vec<(Uuid, Instant)> = Vec::new();
+
+        loop {
+            interval.tick().await;
+            let now = SystemTime::now();
+            let current = self.state.read().await.announcements();
+            if !Arc::ptr_eq(&current, &announcements) {
+                schedule = current
+                    .iter()
+                    .map(|announcement| Scheduled {
+                        next: announcement.schedule.next_after(now),
+                        position: 0,
+                    })
+                    .collect();
+                announcements = current;
+            }
+
+            for (announcement, scheduled) in announcements.iter().zip(&mut schedule) {
+                if scheduled.next.is_some_and(|next| next <= now) {
+                    let message = next_message(announcement, &mut scheduled.position);
+                    self.announce(message, &mut boss_bars);
+                    scheduled.next = announcement.schedule.next_after(now);
+                }
+            }
+
+            let instant = Instant::now();
+            boss_bars.retain(|(uuid, until)| {
+                let is_displayed = *until > instant;
+                if !is_displayed {
+                    self.remove_boss_bar(*uuid);
+                }
+                is_displayed
+            });
+        }
+    }
+
+    fn announce(&self, message: &AnnouncementMessage, boss_bars: &mut Vec<(Uuid, Instant)>) {
+        let boss_bar_uuid = Uuid::new_v4();
+        if let AnnouncementMessage::BossBar(_, duration) = message {
+            boss_bars.push((boss_bar_uuid, Instant::now() + *duration));
+        }
+        for player in self.players.all() {
+            for packet in announcement_packets(message, player.protocol_version(), boss_bar_uuid) {
+                player.queue(packet);
+            }
+        }
+    }
+
+    fn remove_boss_bar(&self, uuid: Uuid) {
+        for player in self.players.all() {
+            if player
+                .protocol_version()
+                .is_after_inclusive(ProtocolVersion::V1_9)
+            {
+                player.queue(PacketRegistry::BossBar(BossBarPacket::remove(uuid)));
+            }
+        }
+    }
+}
+
+fn next_message<'a>(
+    announcement: &'a Announcement,
+    position: &mut usize,
+) -> &'a AnnouncementMessage {
+    let index = match announcement.rotation {
+        Rotation::Sequential => {
+            let index = *position % announcement.messages.len();
+            *position = index + 1;
+            index
+        }
+        Rotation::Random => rand::rng().random_range(0..announcement.messages.len()),
+    };
+    &announcement.messages[index]
+}
+
+/// Builds the packets of an announcement, titles fall back to the chat before 1.8
+/// and boss bars to the action bar before 1.9.
+fn announcement_packets(
+    message: &AnnouncementMessage,
+    protocol_version: ProtocolVersion,
+    boss_bar_uuid: Uuid,
+) -> Vec<PacketRegistry> {
+    match message {
+        AnnouncementMessage::Chat(component) => vec![message_packet(component, protocol_version)],
+        AnnouncementMessage::ActionBar(component) => {
+            vec![action_bar_packet(component, protocol_version)]
+        }
+        AnnouncementMessage::Title(title) => {
+            if protocol_version.is_after_inclusive(ProtocolVersion::V1_8) {
+                title_packets(title, protocol_version)
+            } else {
+                match &title.content {
+                    TitleType::Title(component) | TitleType::Subtitle(component) => {
+                        vec![message_packet(component, protocol_version)]
+                    }
+                    TitleType::Both { title, subtitle } => vec![
+                        message_packet(title, protocol_version),
+                        message_packet(subtitle, protocol_version),
+                    ],
+                }
+            }
+        }
+        AnnouncementMessage::BossBar(boss_bar, _) => {
+            if protocol_version.is_after_inclusive(ProtocolVersion::V1_9) {
+                vec![PacketRegistry::BossBar(BossBarPacket::add(
+                    boss_bar_uuid,
+                    &boss_bar.title,
+                    boss_bar.health,
+                    boss_bar.color,
+                    boss_bar.division,
+                ))]
+            } else {
+                vec![action_bar_packet(&boss_bar.title, protocol_version)]
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configuration::announcements::{
+        AnnouncementConfig, AnnouncementTargetConfig, RotationConfig,
+    };
+    use crate::server::player_registry::Outbound;
+
+    fn announcement(target: AnnouncementTargetConfig, messages: &[&str]) -> Announcement {
+        Announcement::from_config(AnnouncementConfig {
+            interval_seconds: Some(60),
+            schedule: None,
+            target,
+            rotation: RotationConfig::Sequential,
+            messages: messages.iter().map(ToString::to_string).collect(),
+            fade_in: None,
+            stay: None,
+            fade_out: None,
+            color: None,
+            division: None,
+            duration_seconds: None,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_sequential_rotation_wraps_around() {
+        // Given
+        let announcement = announcement(AnnouncementTargetConfig::Chat, &["first", "second"]);
+        let mut position = 0;
+
+        // When
+        let messages: Vec<&AnnouncementMessage> = (0..3)
+            .map(|_| next_message(&announcement, &mut position))
+            .collect();
+
+        // Then
+        assert!(messages[0] == &announcement.messages[0]);
+        assert!(messages[1] == &announcement.messages[1]);
+        assert!(messages[2] == &announcement.messages[0]);
+    }
+
+    #[test]
+    fn test_older_clients_get_a_fallback() {
+        // Given
+        let title = announcement(AnnouncementTargetConfig::Title, &["Title\nSubtitle"]);
+        let boss_bar = announcement(AnnouncementTargetConfig::BossBar, &["Boss bar"]);
+        let uuid = Uuid::new_v4();
+
+        // When
+        let title_1_7 = announcement_packets(&title.messages[0], ProtocolVersion::V1_7_2, uuid);
+        let title_1_21 = announcement_packets(&title.messages[0], ProtocolVersion::V1_21_2, uuid);
+        let boss_bar_1_8 = announcement_packets(&boss_bar.messages[0], ProtocolVersion::V1_8, uuid);
+        let boss_bar_1_9 = announcement_packets(&boss_bar.messages[0], ProtocolVersion::V1_9, uuid);
+
+        // Then
+        assert!(matches!(
+            title_1_7.as_slice(),
+            [
+                PacketRegistry::LegacyChatMessage(_),
+                PacketRegistry::LegacyChatMessage(_)
+            ]
+        ));
+        assert!(matches!(
+            title_1_21.as_slice(),
+            [
+                PacketRegistry::SetTitlesAnimation(_),
+                PacketRegistry::SetTitleText(_),
+                PacketRegistry::SetSubtitleText(_)
+            ]
+        ));
+        assert!(matches!(
+            boss_bar_1_8.as_slice(),
+            [PacketRegistry::LegacyChatMessage(_)]
+        ));
+        assert!(matches!(
+            boss_bar_1_9.as_slice(),
+            [PacketRegistry::BossBar(_)]
+        ));
+    }
+
+    #[test]
+    fn test_announces_to_every_player() {
+        // Given
+        let players = PlayerRegistry::new();
+        let (_notch, mut notch) =
+            players.register(Uuid::from_u128(1), "Notch", ProtocolVersion::V1_21_2);
+        let (_jeb, mut jeb) = players.register(Uuid::from_u128(2), "jeb_", ProtocolVersion::V1_8);
+        let state = Arc::new(RwLock::new(ServerState::builder().build().unwrap()));
+        let announcer = Announcer::new(state, players);
+        let announcement = announcement(AnnouncementTargetConfig::Chat, &["Hello"]);
+        let mut boss_bars = Vec::new();
+
+        // When
+        announcer.announce(&announcement.messages[0], &mut boss_bars);
+
+        // Then
+        assert!(matches!(
+            notch.try_recv().unwrap(),
+            Outbound::Packet(packet) if matches!(*packet, PacketRegistry::SystemChatMessage(_))
+        ));
+        assert!(matches!(
+            jeb.try_recv().unwrap(),
+            Outbound::Packet(packet) if matches!(*packet, PacketRegistry::LegacyChatMessage(_))
+        ));
+        assert!(boss_bars.is_empty());
+    }
+}
@@ -0,0 +1,243 @@
+use crate::handlers::configuration::message_packet;
+use crate::server::packet_registry::PacketRegistry;
+use crate::server::player_registry::PlayerRegistry;
+use crate::server::server_ping::ping;
+use crate::server_state::{BackendTarget, BackendTransfer, OlderClients, ServerState};
+use minecraft_packets::play::client_bound_plugin_message_packet::PlayClientBoundPluginMessagePacket;
+use minecraft_packets::play::transfer_packet::TransferPacket;
+use minecraft_protocol::prelude::{ProtocolVersion, Uuid, VarInt};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tokio::time::{Instant, sleep, sleep_until};
+use tracing::{debug, info};
+
+/// How often the state is checked for a backend watcher enabled by a reload.
+const DISABLED_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Pings the configured backend servers and moves the players to the first one online.
+pub struct BackendWatcher {
+    state: Arc<RwLock<ServerState>>,
+    players: Arc<PlayerRegistry>,
+}
+
+impl BackendWatcher {
+    pub const fn new(state: Arc<RwLock<ServerState>>, players: Arc<PlayerRegistry>) -> Self {
+        Self { state, players }
+    }
+
+    /// Watches until the task is aborted.
+    pub async fn run(self) {
+        // Players already moved, or prompted, to the target currently online
+        let mut moved: HashSet<Uuid> = HashSet::new();
+        let mut online: Option<(String, u16)> = None;
+
+        loop {
+            let Some(backend_transfer) = self.state.read().await.backend_transfer() else {
+                moved.clear();
+                online = None;
+                sleep(DISABLED_INTERVAL).await;
+                continue;
+            };
+            let deadline = Instant::now() + backend_transfer.interval;
+
+            let target = first_online(&backend_transfer).await;
+            let address = target.map(|target| (target.host.clone(), target.port));
+            if address != online {
+                match (&address, &online) {
+                    (Some((host, port)), _) => {
+                        info!("Backend server {host}:{port} is online, moving the players");
+                    }
+                    (None, Some((host, port))) => info!("Backend server {host}:{port} is offline"),
+                    (None, None) => {}
+                }
+                moved.clear();
+                online = address;
+            }
+
+            if let Some(target) = target {
+                self.move_players(&backend_transfer, target, &mut moved, deadline)
+                    .await;
+            }
+            sleep_until(deadline).await;
+        }
+    }
+
+    /// Moves the players one at a time, the targets are pinged again once the deadline is reached.
+    async fn move_players(
+        &self,
+        backend_transfer: &BackendTransfer,
+        target: &BackendTarget,
+        moved: &mut HashSet<Uuid>,
+        deadline: Instant,
+    ) {
+        let players = self.players.all();
+        moved.retain(|uuid| players.iter().any(|player| player.uuid() == *uuid));
+        for player in players {
+            if Instant::now() >= deadline {
+                break;
+            }
+            if !moved.insert(player.uuid()) {
+                continue;
+            }
+            debug!(
+                "Moving {} to {}:{}",
+                player.username(),
+                target.host,
+                target.port
+            );
+            player.queue(move_packet(
+                target,
+                backend_transfer.older_clients,
+                player.protocol_version(),
+            ));
+            sleep(backend_transfer.transfer_delay).await;
+        }
+    }
+}
+
+async fn first_online(backend_transfer: &BackendTransfer) -> Option<&BackendTarget> {
+    for target in &backend_transfer.targets {
+        match ping(&target.host, target.port).await {
+            Ok(()) => return Some(target),
+            Err(err) => debug!(
+                "Backend server {}:{} did not answer: {err}",
+                target.host, target.port
+            ),
+        }
+    }
+    None
+}
+
+/// Transfers clients since 1.20.5, older clients are either moved by the proxy or prompted in the chat.
+fn move_packet(
+    target: &BackendTarget,
+    older_clients: OlderClients,
+    protocol_version: ProtocolVersion,
+) -> PacketRegistry {
+    if protocol_version.is_after_inclusive(ProtocolVersion::V1_20_5) {
+        return PacketRegistry::Transfer(TransferPacket::new(
+            &target.host,
+            &VarInt::from(i32::from(target.port)),
+        ));
+    }
+    match (older_clients, &target.proxy_connect) {
+        (OlderClients::ProxyConnect, Some(payload)) => {
+            proxy_connect_packet(payload, protocol_version)
+        }
+        _ => message_packet(&target.chat_prompt, protocol_version),
+    }
+}
+
+/// Sends the `Connect` payload on the `BungeeCord` channel, which the proxy intercepts.
+pub fn proxy_connect_packet(payload: &[u8], protocol_version: ProtocolVersion) -> PacketRegistry {
+    let channel = if protocol_version.is_after_inclusive(ProtocolVersion::V1_13) {
+        "bungeecord:main"
+    } else {
+        "BungeeCord"
+    };
+    PacketRegistry::PlayClientBoundPluginMessage(PlayClientBoundPluginMessagePacket::new(
+        channel,
+        payload.to_vec(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configuration::backend_watcher::{
+        BackendTargetConfig, BackendWatcherConfig, OlderClientsConfig,
+    };
+    use crate::server::player_registry::Outbound;
+    use tokio::net::TcpListener;
+
+    fn backend_transfer(older_clients: OlderClientsConfig, port: u16) -> BackendTransfer {
+        BackendTransfer::from_config(BackendWatcherConfig {
+            enabled: true,
+            older_clients,
+            targets: vec![BackendTargetConfig {
+                host: "127.0.0.1".into(),
+                port,
+                proxy_server: Some("lobby".into()),
+            }],
+            ..BackendWatcherConfig::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_move_packet_depends_on_the_version() {
+        // Given
+        let chat = backend_transfer(OlderClientsConfig::Chat, 25565);
+        let proxy_connect = backend_transfer(OlderClientsConfig::ProxyConnect, 25565);
+
+        // When
+        let transfer = move_packet(
+            &chat.targets[0],
+            chat.older_clients,
+            ProtocolVersion::V1_20_5,
+        );
+        let prompt = move_packet(&chat.targets[0], chat.older_clients, ProtocolVersion::V1_8);
+        let connect = move_packet(
+            &proxy_connect.targets[0],
+            proxy_connect.older_clients,
+            ProtocolVersion::V1_8,
+        );
+
+        // Then
+        assert!(matches!(transfer, PacketRegistry::Transfer(_)));
+        assert!(matches!(prompt, PacketRegistry::LegacyChatMessage(_)));
+        assert!(matches!(
+            connect,
+            PacketRegistry::PlayClientBoundPluginMessage(_)
+        ));
+        assert_eq!(
+            proxy_connect.targets[0].proxy_connect.as_deref(),
+            Some(b"\0\x07Connect\0\x05lobby".as_slice())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_players_are_moved_once() {
+        // Given
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+        let backend_transfer = backend_transfer(OlderClientsConfig::Chat, port);
+        let players = PlayerRegistry::new();
+        let (_notch, mut notch) =
+            players.register(Uuid::from_u128(1), "Notch", ProtocolVersion::V1_21_2);
+        let state = Arc::new(RwLock::new(ServerState::builder().build().unwrap()));
+        let watcher = BackendWatcher::new(state, players);
+        let mut moved = HashSet::new();
+        let deadline = Instant::now() + Duration::from_secs(10);
+
+        // When
+        let online = first_online(&backend_transfer).await;
+        watcher
+            .move_players(
+                &backend_transfer,
+                &backend_transfer.targets[0],
+                &mut moved,
+                deadline,
+            )
+            .await;
+        watcher
+            .move_players(
+                &backend_transfer,
+                &backend_transfer.targets[0],
+                &mut moved,
+                deadline,
+            )
+            .await;
+
+        // Then
+        assert!(online.is_none());
+        assert!(matches!(
+            notch.try_recv().unwrap(),
+            Outbound::Packet(packet) if matches!(*packet, PacketRegistry::Transfer(_))
+        ));
+        assert!(notch.try_recv().is_err());
+    }
+}
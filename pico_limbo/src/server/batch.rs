@@ -15,6 +15,7 @@ enum Producer {
     Iterator(Box<dyn Iterator<Item = PacketRegistry> + Send + 'static>),
     StateChange(Direction, State),
     EnableCompression,
+    EnableEncryption(Vec<u8>),
 }
 
 pub struct Batch {
@@ -32,6 +33,12 @@ impl Batch {
         self.producers.push_back(Producer::EnableCompression);
     }
 
+    /// Queue the activation of the stream cipher. All packets after this one are encrypted.
+    pub fn queue_enable_encryption(&mut self, shared_secret: Vec<u8>) {
+        self.producers
+            .push_back(Producer::EnableEncryption(shared_secret));
+    }
+
     /// Queue a state change for both directions. All received and sent packets will use the new state.
     pub fn queue_both_state_change(&mut self, new_state: State) {
         self.queue_clientbound_state_change(new_state);
@@ -103,6 +110,7 @@ pub enum BatchItem {
     Packet(PacketRegistry),
     StateChange(Direction, State),
     EnableCompression,
+    EnableEncryption(Vec<u8>),
 }
 
 impl Stream for BatchStream {
@@ -138,6 +146,9 @@ impl Stream for BatchStream {
                     Some(Producer::EnableCompression) => {
                         return Poll::Ready(Some(BatchItem::EnableCompression));
                     }
+                    Some(Producer::EnableEncryption(shared_secret)) => {
+                        return Poll::Ready(Some(BatchItem::EnableEncryption(shared_secret)));
+                    }
                     Some(Producer::AsyncClosure(f)) => {
                         this.current = Current::Future(f());
                     }
@@ -164,6 +175,9 @@ impl BatchItem {
             Self::EnableCompression => {
                 panic!("tried to unwrap a packet, but got a compression instead")
             }
+            Self::EnableEncryption(_) => {
+                panic!("tried to unwrap a packet, but got an encryption instead")
+            }
         }
     }
 
@@ -174,6 +188,9 @@ impl BatchItem {
             Self::EnableCompression => {
                 panic!("tried to unwrap a packet, but got a compression instead")
             }
+            Self::EnableEncryption(_) => {
+                panic!("tried to unwrap a packet, but got an encryption instead")
+            }
         }
     }
 }
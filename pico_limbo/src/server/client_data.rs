@@ -1,27 +1,52 @@
 use crate::server::client_state::ClientState;
 use crate::server::controllable_interval::ControllableInterval;
-use minecraft_protocol::prelude::ProtocolVersion;
+use crate::server::listener::Transport;
+use crate::server::metrics::metrics;
+use crate::server::player_registry::Outbound;
+use crate::server::recording::ConnectionRecorder;
+use crate::server_state::{ReloadedSections, Timeouts};
+use minecraft_protocol::prelude::{Direction, ProtocolVersion, State};
 use net::packet_stream::{PacketStream, PacketStreamError};
 use net::raw_packet::RawPacket;
+use std::net::SocketAddr;
 use std::ops::Add;
-use std::sync::Arc;
+use std::sync::{Arc, PoisonError};
 use std::time::Duration;
-use tokio::io::AsyncWriteExt;
-use tokio::net::TcpStream;
+use tokio::io::{AsyncWriteExt, BufReader};
 use tokio::sync::Mutex;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::mpsc;
 use tokio::time::Instant;
+use tracing::warn;
 
-pub struct ClientData {
+const SESSION_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+pub struct ClientData<S: Transport> {
     client_state: Arc<Mutex<ClientState>>,
-    packet_stream: Arc<Mutex<PacketStream<TcpStream>>>,
+    packet_stream: Arc<Mutex<PacketStream<BufReader<S>>>>,
     interval: Arc<Mutex<ControllableInterval>>,
     keep_alive_interval: Duration,
+    timeout: Arc<Mutex<ControllableInterval>>,
+    timeout_state: Arc<Mutex<Option<State>>>,
+    timeouts: Timeouts,
+    session_interval: Arc<Mutex<ControllableInterval>>,
+    reloads: Arc<Mutex<broadcast::Receiver<ReloadedSections>>>,
+    outbound: Arc<Mutex<Option<mpsc::UnboundedReceiver<Outbound>>>>,
+    recorder: Arc<std::sync::Mutex<Option<ConnectionRecorder>>>,
 }
 
-impl ClientData {
-    pub fn new(socket: TcpStream, keep_alive_interval: Duration) -> Self {
-        let client_state = ClientState::default();
-        let packet_stream = PacketStream::new(socket);
+impl<S: Transport> ClientData<S> {
+    pub fn new(
+        socket: BufReader<S>,
+        received_bytes: Vec<u8>,
+        remote_address: SocketAddr,
+        keep_alive_interval: Duration,
+        timeouts: Timeouts,
+        reloads: broadcast::Receiver<ReloadedSections>,
+    ) -> Self {
+        let mut client_state = ClientState::default();
+        client_state.set_remote_address(remote_address);
+        let packet_stream = PacketStream::with_received_bytes(socket, received_bytes);
         let interval = ControllableInterval::new();
 
         Self {
@@ -29,6 +54,13 @@ impl ClientData {
             packet_stream: Arc::new(Mutex::new(packet_stream)),
             interval: Arc::new(Mutex::new(interval)),
             keep_alive_interval,
+            timeout: Arc::new(Mutex::new(ControllableInterval::new())),
+            timeout_state: Arc::new(Mutex::new(None)),
+            timeouts,
+            session_interval: Arc::new(Mutex::new(ControllableInterval::new())),
+            reloads: Arc::new(Mutex::new(reloads)),
+            outbound: Arc::new(Mutex::new(None)),
+            recorder: Arc::new(std::sync::Mutex::new(None)),
         }
     }
 
@@ -45,24 +77,93 @@ impl ClientData {
 
     // Stream
 
-    pub async fn stream(&self) -> tokio::sync::MutexGuard<'_, PacketStream<TcpStream>> {
+    pub async fn stream(&self) -> tokio::sync::MutexGuard<'_, PacketStream<BufReader<S>>> {
         self.packet_stream.lock().await
     }
 
     pub async fn write_packet(&self, raw_packet: RawPacket) -> Result<(), PacketStreamError> {
-        self.stream().await.write_packet(raw_packet).await
+        let (state, protocol_version) = {
+            let client = self.client().await;
+            (client.clientbound_state(), client.protocol_version())
+        };
+        self.record(Direction::Clientbound, state, protocol_version, &raw_packet);
+        let size = raw_packet.size();
+        let mut stream = self.stream().await;
+        let is_compression_enabled = stream.is_compression_enabled();
+        let bytes_written = stream.bytes_written();
+        stream.write_packet(raw_packet).await?;
+        let compressed_size = stream.bytes_written() - bytes_written;
+        drop(stream);
+
+        metrics().packet_sent(state, size);
+        if is_compression_enabled {
+            metrics().compressed_packet_sent(size, compressed_size);
+        }
+        Ok(())
     }
 
+    /// Reads the next packet, cancel safe so it can race the other events of the connection.
     pub async fn read_packet(&self) -> Result<RawPacket, PacketStreamError> {
         self.stream().await.read_packet().await
     }
 
+    /// Records a packet read from the client.
+    pub async fn record_received(&self, raw_packet: &RawPacket) {
+        if self.is_recording() {
+            let (state, protocol_version) = {
+                let client = self.client().await;
+                (client.serverbound_state(), client.protocol_version())
+            };
+            self.record(Direction::Serverbound, state, protocol_version, raw_packet);
+        }
+    }
+
     pub async fn shutdown(&self) -> Result<(), PacketStreamError> {
         self.stream().await.get_stream().shutdown().await?;
         self.interval().await.clear_interval().await;
+        self.timeout().await.clear_interval().await;
+        self.session_interval().await.clear_interval().await;
+        if let Some(mut recorder) = self.recorder().take()
+            && let Err(err) = recorder.flush()
+        {
+            warn!("Failed to save the recording: {err}");
+        }
         Ok(())
     }
 
+    // Recording
+
+    /// Records every packet read and written from now on.
+    pub fn enable_recording(&self, recorder: ConnectionRecorder) {
+        self.recorder().replace(recorder);
+    }
+
+    fn is_recording(&self) -> bool {
+        self.recorder().is_some()
+    }
+
+    /// Stops recording when the file cannot be written, rather than failing the connection.
+    fn record(
+        &self,
+        direction: Direction,
+        state: State,
+        protocol_version: ProtocolVersion,
+        raw_packet: &RawPacket,
+    ) {
+        let mut recorder = self.recorder();
+        if let Some(connection_recorder) = recorder.as_mut()
+            && let Err(err) =
+                connection_recorder.record(direction, state, protocol_version, raw_packet)
+        {
+            warn!("Failed to record a packet, the recording is stopped: {err}");
+            recorder.take();
+        }
+    }
+
+    fn recorder(&self) -> std::sync::MutexGuard<'_, Option<ConnectionRecorder>> {
+        self.recorder.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
     // Keep alive
 
     pub async fn enable_keep_alive_if_needed(&self) {
@@ -89,6 +190,92 @@ impl ClientData {
         self.interval().await.tick().await;
     }
 
+    // Timeout
+
+    /// Starts the timeout of the current state when the client just entered it.
+    /// In PLAY state, the timeout restarts every time a packet is received.
+    pub async fn refresh_timeout(&self) {
+        let state = self.client().await.serverbound_state();
+        let previous_state = self.timeout_state.lock().await.replace(state);
+        if previous_state == Some(state) && state != State::Play {
+            return;
+        }
+
+        if let Some(duration) = self.timeouts.for_state(state) {
+            self.timeout()
+                .await
+                .set_interval_at(Instant::now().add(duration), duration)
+                .await;
+        } else {
+            self.timeout().await.clear_interval().await;
+        }
+    }
+
+    pub async fn timeout_tick(&self) {
+        self.timeout().await.tick().await;
+    }
+
+    // Session timeouts
+
+    /// Starts checking the AFK timeout and the session limit every second once the player joined.
+    pub async fn enable_session_checks(&self) {
+        self.client().await.start_session(std::time::Instant::now());
+        self.session_interval()
+            .await
+            .set_interval_at(
+                Instant::now().add(SESSION_CHECK_INTERVAL),
+                SESSION_CHECK_INTERVAL,
+            )
+            .await;
+    }
+
+    pub async fn session_tick(&self) {
+        self.session_interval().await.tick().await;
+    }
+
+    // Reload
+
+    /// Waits for the next configuration reload, never completes once the server stopped.
+    pub async fn next_reload(&self) -> ReloadedSections {
+        let result = self.reloads.lock().await.recv().await;
+        match result {
+            Ok(sections) => sections,
+            Err(RecvError::Lagged(_)) => ReloadedSections::all(),
+            Err(RecvError::Closed) => std::future::pending().await,
+        }
+    }
+
+    // Outbound
+
+    /// Starts receiving the packets and kicks queued for this player by other tasks.
+    pub async fn set_outbound(&self, receiver: mpsc::UnboundedReceiver<Outbound>) {
+        self.outbound.lock().await.replace(receiver);
+    }
+
+    /// Waits for the next packet or kick queued by another task, never completes before the player joined.
+    pub async fn next_outbound(&self) -> Outbound {
+        let mut outbound = self.outbound.lock().await;
+        let next = match outbound.as_mut() {
+            Some(receiver) => receiver.recv().await,
+            None => None,
+        };
+        drop(outbound);
+        match next {
+            Some(next) => next,
+            None => std::future::pending().await,
+        }
+    }
+
+    #[inline]
+    async fn timeout(&self) -> tokio::sync::MutexGuard<'_, ControllableInterval> {
+        self.timeout.lock().await
+    }
+
+    #[inline]
+    async fn session_interval(&self) -> tokio::sync::MutexGuard<'_, ControllableInterval> {
+        self.session_interval.lock().await
+    }
+
     #[inline]
     async fn interval(&self) -> tokio::sync::MutexGuard<'_, ControllableInterval> {
         self.interval.lock().await
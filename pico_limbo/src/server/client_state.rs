@@ -1,7 +1,29 @@
+use crate::kick_messages::kick_reason;
 use crate::server::game_profile::GameProfile;
+use crate::server_state::PlayerSlot;
 use minecraft_packets::login::Property;
 use minecraft_protocol::prelude::{Direction, ProtocolVersion, State, Uuid};
-use tracing::info;
+use pico_text_component::prelude::Component;
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+use tracing::{debug, info};
+
+/// Player who completed the encryption handshake and still has to be
+/// confirmed by the session server.
+pub struct PendingAuthentication {
+    pub username: String,
+    pub server_hash: String,
+}
+
+/// Time spent in game and since the last meaningful movement or chat message.
+struct Activity {
+    joined_at: Option<Instant>,
+    last_active_at: Instant,
+    last_horizontal_position: Option<(f64, f64)>,
+    last_rotation: Option<(f32, f32)>,
+    afk_warned: bool,
+    session_limit_warned: bool,
+}
 
 #[derive(PartialEq, Eq)]
 pub enum KeepAliveStatus {
@@ -16,14 +38,33 @@ impl Default for ClientState {
             clientbound_state: State::Handshake,
             serverbound_state: State::Handshake,
             protocol_version: ProtocolVersion::Any,
+            remote_address: None,
+            forwarded_by_handshake: false,
             kick_message: None,
+            kick_reason: "other",
             message_id: -1,
+            encryption_request: None,
+            profile_public_key: None,
+            pending_authentication: None,
             game_profile: None,
+            player_slot: None,
             keep_alive_enabled: KeepAliveStatus::Disabled,
+            pending_keep_alive: None,
+            missed_keep_alives: 0,
+            latency: None,
             feet_y: 0.0,
             is_flight_allowed: false,
             is_flying: false,
             flying_speed: 0.05,
+            boss_bar_uuid: None,
+            activity: Activity {
+                joined_at: None,
+                last_active_at: Instant::now(),
+                last_horizontal_position: None,
+                last_rotation: None,
+                afk_warned: false,
+                session_limit_warned: false,
+            },
         }
     }
 }
@@ -32,14 +73,26 @@ pub struct ClientState {
     clientbound_state: State,
     serverbound_state: State,
     protocol_version: ProtocolVersion,
-    kick_message: Option<String>,
+    remote_address: Option<SocketAddr>,
+    forwarded_by_handshake: bool,
+    kick_message: Option<Component>,
+    kick_reason: &'static str,
     message_id: i32,
+    encryption_request: Option<(String, Vec<u8>)>,
+    profile_public_key: Option<Vec<u8>>,
+    pending_authentication: Option<PendingAuthentication>,
     game_profile: Option<GameProfile>,
+    player_slot: Option<PlayerSlot>,
     keep_alive_enabled: KeepAliveStatus,
+    pending_keep_alive: Option<(i64, Instant)>,
+    missed_keep_alives: u32,
+    latency: Option<Duration>,
     feet_y: f64,
     is_flight_allowed: bool,
     is_flying: bool,
     flying_speed: f32,
+    boss_bar_uuid: Option<Uuid>,
+    activity: Activity,
 }
 
 impl ClientState {
@@ -48,13 +101,24 @@ impl ClientState {
     // Kick
 
     pub fn kick(&mut self, kick_message: &str) {
-        self.kick_message = Some(kick_message.to_string());
+        self.kick_message = Some(Component::new(kick_message));
+        self.kick_reason = kick_reason(kick_message);
+    }
+
+    /// Kicks with a custom message, the reason labels the kick in the metrics.
+    pub fn kick_component(&mut self, kick_message: Component, reason: &'static str) {
+        self.kick_message = Some(kick_message);
+        self.kick_reason = reason;
     }
 
-    pub fn should_kick(&self) -> Option<String> {
+    pub fn should_kick(&self) -> Option<Component> {
         self.kick_message.clone()
     }
 
+    pub const fn kick_reason(&self) -> &'static str {
+        self.kick_reason
+    }
+
     // State
 
     pub const fn clientbound_state(&self) -> State {
@@ -86,6 +150,34 @@ impl ClientState {
         self.protocol_version = new_protocol_version;
     }
 
+    // Remote address
+
+    /// The address of the player, as told by the PROXY protocol header when one was received.
+    pub const fn remote_address(&self) -> Option<SocketAddr> {
+        self.remote_address
+    }
+
+    pub const fn set_remote_address(&mut self, remote_address: SocketAddr) {
+        self.remote_address = Some(remote_address);
+    }
+
+    /// Replaces the address with the one of the player forwarded by the proxy.
+    pub fn set_forwarded_address(&mut self, address: IpAddr) {
+        let port = self
+            .remote_address
+            .map_or(0, |remote_address| remote_address.port());
+        self.remote_address = Some(SocketAddr::new(address, port));
+    }
+
+    /// Whether the player information was forwarded in the handshake by a legacy or `BungeeGuard` proxy.
+    pub const fn is_forwarded_by_handshake(&self) -> bool {
+        self.forwarded_by_handshake
+    }
+
+    pub const fn set_forwarded_by_handshake(&mut self) {
+        self.forwarded_by_handshake = true;
+    }
+
     // Velocity
 
     pub const fn set_velocity_login_message_id(&mut self, message_id: i32) {
@@ -96,6 +188,44 @@ impl ClientState {
         self.message_id
     }
 
+    // Online mode
+
+    /// Remembers the username and the verify token sent in the encryption request.
+    pub fn set_encryption_request(&mut self, username: String, verify_token: Vec<u8>) {
+        self.encryption_request = Some((username, verify_token));
+    }
+
+    pub const fn take_encryption_request(&mut self) -> Option<(String, Vec<u8>)> {
+        self.encryption_request.take()
+    }
+
+    /// Remembers the profile key of a 1.19 to 1.19.2 client, which may sign the verify token with it.
+    pub fn set_profile_public_key(&mut self, profile_public_key: Vec<u8>) {
+        self.profile_public_key = Some(profile_public_key);
+    }
+
+    pub fn profile_public_key(&self) -> Option<&[u8]> {
+        self.profile_public_key.as_deref()
+    }
+
+    pub fn set_pending_authentication(&mut self, username: String, server_hash: String) {
+        self.pending_authentication = Some(PendingAuthentication {
+            username,
+            server_hash,
+        });
+    }
+
+    pub const fn take_pending_authentication(&mut self) -> Option<PendingAuthentication> {
+        self.pending_authentication.take()
+    }
+
+    // Player slot
+
+    /// Keeps the player counted as online until the client state is dropped.
+    pub fn set_player_slot(&mut self, player_slot: PlayerSlot) {
+        self.player_slot = Some(player_slot);
+    }
+
     // Game profile
 
     pub fn set_game_profile(&mut self, game_profile: GameProfile) {
@@ -113,6 +243,13 @@ impl ClientState {
                 existing_game_profile.username(),
                 existing_game_profile.uuid()
             );
+            if let Some(public_key) = existing_game_profile.public_key() {
+                debug!(
+                    "Chat signing key of player {} expires at {} ms since the epoch",
+                    existing_game_profile.username(),
+                    public_key.expires_at
+                );
+            }
         }
     }
 
@@ -155,6 +292,34 @@ impl ClientState {
         }
     }
 
+    /// Remembers the keep alive sent to the client, returns how many keep
+    /// alive in a row were left unanswered.
+    pub const fn keep_alive_sent(&mut self, id: i64, sent_at: Instant) -> u32 {
+        if self.pending_keep_alive.is_some() {
+            self.missed_keep_alives += 1;
+        }
+        self.pending_keep_alive = Some((id, sent_at));
+        self.missed_keep_alives
+    }
+
+    /// Matches the echoed ID with the pending keep alive, returns the round-trip latency.
+    pub fn keep_alive_received(&mut self, id: i64, received_at: Instant) -> Option<Duration> {
+        let (pending_id, sent_at) = self.pending_keep_alive?;
+        if pending_id != id {
+            return None;
+        }
+        let latency = received_at.saturating_duration_since(sent_at);
+        self.pending_keep_alive = None;
+        self.missed_keep_alives = 0;
+        self.latency = Some(latency);
+        Some(latency)
+    }
+
+    /// Last measured round-trip latency, `None` before the first keep alive answer.
+    pub const fn latency(&self) -> Option<Duration> {
+        self.latency
+    }
+
     // Position
 
     pub const fn get_y_position(&self) -> f64 {
@@ -190,4 +355,73 @@ impl ClientState {
     pub const fn set_flying_speed(&mut self, flying_speed: f32) {
         self.flying_speed = flying_speed;
     }
+
+    // Boss bar
+
+    /// Identifier of the boss bar currently displayed to the player.
+    pub const fn boss_bar_uuid(&self) -> Option<Uuid> {
+        self.boss_bar_uuid
+    }
+
+    pub const fn set_boss_bar_uuid(&mut self, boss_bar_uuid: Option<Uuid>) {
+        self.boss_bar_uuid = boss_bar_uuid;
+    }
+
+    // Activity
+
+    /// Starts counting the session and the idle time once the player entered the game,
+    /// or again after a transfer that may have failed.
+    pub const fn start_session(&mut self, now: Instant) {
+        self.activity.joined_at = Some(now);
+        self.activity.session_limit_warned = false;
+        self.record_activity(now);
+    }
+
+    /// Time spent in game, `None` before the player entered the game.
+    pub fn session_duration(&self, now: Instant) -> Option<Duration> {
+        self.activity
+            .joined_at
+            .map(|joined_at| now.saturating_duration_since(joined_at))
+    }
+
+    pub fn idle_duration(&self, now: Instant) -> Duration {
+        now.saturating_duration_since(self.activity.last_active_at)
+    }
+
+    /// Only a horizontal movement is meaningful, so that falling or jumping in place does not count.
+    pub fn record_position(&mut self, x: f64, z: f64, now: Instant) {
+        let previous = self.activity.last_horizontal_position.replace((x, z));
+        if previous.is_some_and(|previous| previous != (x, z)) {
+            self.record_activity(now);
+        }
+    }
+
+    pub fn record_rotation(&mut self, yaw: f32, pitch: f32, now: Instant) {
+        let previous = self.activity.last_rotation.replace((yaw, pitch));
+        if previous.is_some_and(|previous| previous != (yaw, pitch)) {
+            self.record_activity(now);
+        }
+    }
+
+    /// Marks the player as active, also on a chat message or a command.
+    pub const fn record_activity(&mut self, now: Instant) {
+        self.activity.last_active_at = now;
+        self.activity.afk_warned = false;
+    }
+
+    pub const fn is_afk_warned(&self) -> bool {
+        self.activity.afk_warned
+    }
+
+    pub const fn set_afk_warned(&mut self) {
+        self.activity.afk_warned = true;
+    }
+
+    pub const fn is_session_limit_warned(&self) -> bool {
+        self.activity.session_limit_warned
+    }
+
+    pub const fn set_session_limit_warned(&mut self) {
+        self.activity.session_limit_warned = true;
+    }
 }
@@ -0,0 +1,642 @@
+//! Drives a full handshake, login, configuration and play sequence over an in-memory
+//! connection for every supported protocol version, to catch version regressions.
+
+use crate::forwarding::modern_forwarding_version::ModernForwardingVersion;
+use crate::server::connection_limiter::ConnectionLimiter;
+use crate::server::game_profile::GameProfile;
+use crate::server::network::handle_connection;
+use crate::server::packet_registry::PacketRegistry;
+use crate::server::player_registry::PlayerRegistry;
+use crate::server_state::ServerState;
+use hmac::{Hmac, KeyInit, Mac};
+use macros::PacketReport;
+use minecraft_packets::configuration::acknowledge_finish_configuration_packet::AcknowledgeConfigurationPacket;
+use minecraft_packets::configuration::client_bound_known_packs_packet::ClientBoundKnownPacksPacket;
+use minecraft_packets::configuration::finish_configuration_packet::FinishConfigurationPacket;
+use minecraft_packets::configuration::server_bound_known_packs_packet::ServerBoundKnownPacksPacket;
+use minecraft_packets::handshaking::handshake_packet::HandshakePacket;
+use minecraft_packets::login::custom_query_answer_packet::CustomQueryAnswerPacket;
+use minecraft_packets::login::custom_query_packet::CustomQueryPacket;
+use minecraft_packets::login::game_profile_packet::GameProfilePacket;
+use minecraft_packets::login::login_acknowledged_packet::LoginAcknowledgedPacket;
+use minecraft_packets::login::login_disconnect_packet::LoginDisconnectPacket;
+use minecraft_packets::login::login_state_packet::LoginStartPacket;
+use minecraft_packets::login::login_success_packet::LoginFinishedPacket;
+use minecraft_packets::login::set_compression_packet::SetCompressionPacket;
+use minecraft_packets::play::client_bound_keep_alive_packet::ClientBoundKeepAlivePacket;
+use minecraft_packets::play::disconnect_packet::DisconnectPacket;
+use minecraft_packets::play::server_bound_keep_alive_packet::ServerBoundKeepAlivePacket;
+use minecraft_packets::play::synchronize_player_position_packet::SynchronizePlayerPositionPacket;
+use minecraft_protocol::prelude::*;
+use net::packet_stream::PacketStream;
+use net::raw_packet::RawPacket;
+use pico_precomputed_registries::PrecomputedRegistries;
+use pico_registries::registry_provider::RegistryProvider;
+use sha2::Sha256;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::DuplexStream;
+use tokio::sync::{RwLock, broadcast};
+use tokio::time::timeout;
+
+const USERNAME: &str = "Notch";
+const FORWARDED_UUID: Uuid = Uuid::from_u128(0x069a_79f4_44e9_4726_a5be_fca9_0e38_aaf5);
+const PLAYER_ADDRESS: &str = "203.0.113.7";
+const SECRET: &str = "secret";
+const BUNGEE_GUARD_TOKEN: &str = "token";
+const VIEW_DISTANCE: i32 = 2;
+const KEY_EXPIRES_AT: i64 = 1_700_000_000_000;
+const KEY_HOLDER_UUID: Uuid = Uuid::from_u128(0x0123_4567_89ab_cdef_0123_4567_89ab_cdef);
+/// The clock is paused, waiting only advances it once both sides are idle.
+const JOIN_TIMEOUT: Duration = Duration::from_mins(1);
+
+/// Packets of the client side, clientbound packets are decoded and serverbound packets encoded.
+#[derive(PacketReport)]
+#[packet_report(client)]
+enum ClientPacket {
+    #[protocol_id(
+        state = "handshake",
+        bound = "serverbound",
+        name = "minecraft:intention"
+    )]
+    Handshake(HandshakePacket),
+
+    #[protocol_id(state = "login", bound = "serverbound", name = "minecraft:hello")]
+    LoginStart(LoginStartPacket),
+
+    #[protocol_id(
+        state = "login",
+        bound = "serverbound",
+        name = "minecraft:login_acknowledged"
+    )]
+    LoginAcknowledged(LoginAcknowledgedPacket),
+
+    #[protocol_id(
+        state = "login",
+        bound = "serverbound",
+        name = "minecraft:custom_query_answer"
+    )]
+    CustomQueryAnswer(CustomQueryAnswerPacket),
+
+    #[protocol_id(
+        state = "login",
+        bound = "clientbound",
+        name = "minecraft:custom_query"
+    )]
+    CustomQuery(CustomQueryPacket),
+
+    #[protocol_id(
+        state = "login",
+        bound = "clientbound",
+        name = "minecraft:login_finished"
+    )]
+    LoginFinished(LoginFinishedPacket),
+
+    #[protocol_id(
+        state = "login",
+        bound = "clientbound",
+        name = "minecraft:game_profile"
+    )]
+    GameProfile(GameProfilePacket),
+
+    // Only the arrival of the packet matters
+    #[allow(dead_code)]
+    #[protocol_id(
+        state = "login",
+        bound = "clientbound",
+        name = "minecraft:login_disconnect"
+    )]
+    LoginDisconnect(LoginDisconnectPacket),
+
+    #[protocol_id(
+        state = "login",
+        bound = "clientbound",
+        name = "minecraft:login_compression"
+    )]
+    SetCompression(SetCompressionPacket),
+
+    #[protocol_id(
+        state = "configuration",
+        bound = "serverbound",
+        name = "minecraft:finish_configuration"
+    )]
+    AcknowledgeConfiguration(AcknowledgeConfigurationPacket),
+
+    #[protocol_id(
+        state = "configuration",
+        bound = "serverbound",
+        name = "minecraft:select_known_packs"
+    )]
+    ServerBoundKnownPacks(ServerBoundKnownPacksPacket),
+
+    #[protocol_id(
+        state = "configuration",
+        bound = "clientbound",
+        name = "minecraft:select_known_packs"
+    )]
+    ClientBoundKnownPacks(ClientBoundKnownPacksPacket),
+
+    #[protocol_id(
+        state = "configuration",
+        bound = "serverbound",
+        name = "minecraft:keep_alive"
+    )]
+    ConfigurationServerBoundKeepAlive(ServerBoundKeepAlivePacket),
+
+    #[protocol_id(
+        state = "configuration",
+        bound = "clientbound",
+        name = "minecraft:keep_alive"
+    )]
+    ConfigurationClientBoundKeepAlive(ClientBoundKeepAlivePacket),
+
+    // Only the arrival of the packet matters
+    #[allow(dead_code)]
+    #[protocol_id(
+        state = "configuration",
+        bound = "clientbound",
+        name = "minecraft:finish_configuration"
+    )]
+    FinishConfiguration(FinishConfigurationPacket),
+
+    // Only the arrival of the packet matters
+    #[allow(dead_code)]
+    #[protocol_id(
+        state = "configuration",
+        bound = "clientbound",
+        name = "minecraft:disconnect"
+    )]
+    ConfigurationDisconnect(DisconnectPacket),
+
+    #[protocol_id(
+        state = "play",
+        bound = "clientbound",
+        name = "minecraft:player_position"
+    )]
+    SynchronizePlayerPosition(SynchronizePlayerPositionPacket),
+
+    // Only the arrival of the packet matters
+    #[allow(dead_code)]
+    #[protocol_id(state = "play", bound = "clientbound", name = "minecraft:keep_alive")]
+    ClientBoundKeepAlive(ClientBoundKeepAlivePacket),
+
+    // Only the arrival of the packet matters
+    #[allow(dead_code)]
+    #[protocol_id(state = "play", bound = "clientbound", name = "minecraft:disconnect")]
+    PlayDisconnect(DisconnectPacket),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Forwarding {
+    Disabled,
+    Legacy,
+    BungeeGuard,
+    Modern,
+}
+
+#[derive(Clone, Copy)]
+struct Scenario {
+    forwarding: Forwarding,
+    compression: bool,
+    schematic: bool,
+    /// Whether the proxy forwards the chat signing key of 1.19 to 1.19.2 players.
+    chat_signing_key: bool,
+}
+
+impl Scenario {
+    const fn new(forwarding: Forwarding) -> Self {
+        Self {
+            forwarding,
+            compression: false,
+            schematic: false,
+            chat_signing_key: false,
+        }
+    }
+
+    const fn with_compression(mut self) -> Self {
+        self.compression = true;
+        self
+    }
+
+    const fn with_schematic(mut self) -> Self {
+        self.schematic = true;
+        self
+    }
+
+    const fn with_chat_signing_key(mut self) -> Self {
+        self.chat_signing_key = true;
+        self
+    }
+
+    fn server_state(self) -> ServerState {
+        let mut builder = ServerState::builder();
+        builder.view_distance(VIEW_DISTANCE);
+        match self.forwarding {
+            Forwarding::Disabled => builder.disable_forwarding(),
+            Forwarding::Legacy => builder.enable_legacy_forwarding(),
+            Forwarding::BungeeGuard => {
+                builder.enable_bungee_guard_forwarding(vec![BUNGEE_GUARD_TOKEN.to_string()])
+            }
+            Forwarding::Modern => builder.enable_modern_forwarding([SECRET]),
+        };
+        if self.compression {
+            // Low enough for the registries and chunks to be compressed
+            builder.enable_compression(64, 6).unwrap();
+        }
+        if self.schematic {
+            builder.schematic(
+                concat!(
+                    env!("CARGO_MANIFEST_DIR"),
+                    "/../data/schematics/spawn.schem"
+                )
+                .to_string(),
+            );
+        }
+        builder.build().unwrap()
+    }
+
+    /// Hostname of the handshake, carrying the player information when a legacy proxy forwards it.
+    fn hostname(self) -> String {
+        match self.forwarding {
+            Forwarding::Disabled | Forwarding::Modern => "localhost".to_string(),
+            Forwarding::Legacy => {
+                format!("localhost\0{PLAYER_ADDRESS}\0{}", FORWARDED_UUID.simple())
+            }
+            Forwarding::BungeeGuard => format!(
+                "localhost\0{PLAYER_ADDRESS}\0{}\0[{{\"name\":\"bungeeguard-token\",\"value\":\"{BUNGEE_GUARD_TOKEN}\"}}]",
+                FORWARDED_UUID.simple()
+            ),
+        }
+    }
+
+    /// Every clientbound packet expected until the first keep alive in game, keep alives excluded.
+    fn expected_packets(self, protocol_version: ProtocolVersion) -> Vec<&'static str> {
+        let mut packets = Vec::new();
+        let is_at_least = |version| protocol_version.is_after_inclusive(version);
+
+        if self.forwarding == Forwarding::Modern {
+            if !protocol_version.is_modern() {
+                return vec!["minecraft:login_disconnect"];
+            }
+            packets.push("minecraft:custom_query");
+        }
+        if self.compression && is_at_least(ProtocolVersion::V1_8) {
+            packets.push("minecraft:login_compression");
+        }
+        if is_at_least(ProtocolVersion::V1_21_2) {
+            packets.push("minecraft:login_finished");
+        } else {
+            packets.push("minecraft:game_profile");
+        }
+
+        if protocol_version.supports_configuration_state() {
+            packets.push("minecraft:custom_payload");
+            if is_at_least(ProtocolVersion::V1_20_5) {
+                packets.push("minecraft:select_known_packs");
+                let registries = PrecomputedRegistries::new(protocol_version)
+                    .get_registry_data_v1_20_5()
+                    .unwrap()
+                    .len();
+                packets.extend(std::iter::repeat_n("minecraft:registry_data", registries));
+            } else {
+                packets.push("minecraft:registry_data");
+            }
+            if is_at_least(ProtocolVersion::V1_21_6) {
+                packets.push("minecraft:update_tags");
+            }
+            packets.push("minecraft:finish_configuration");
+        }
+
+        packets.push("minecraft:login");
+        packets.push("minecraft:player_abilities");
+        if is_at_least(ProtocolVersion::V1_19) {
+            packets.push("minecraft:set_default_spawn_position");
+        }
+        packets.push("minecraft:player_position");
+        if is_at_least(ProtocolVersion::V1_13) {
+            packets.push("minecraft:commands");
+        }
+        if protocol_version.between_inclusive(ProtocolVersion::V1_13, ProtocolVersion::V1_20) {
+            packets.push("minecraft:custom_payload");
+        }
+        packets.push("minecraft:set_time");
+        if is_at_least(ProtocolVersion::V1_8) {
+            packets.push("minecraft:player_info_update");
+            packets.push("minecraft:set_entity_data");
+        }
+        if is_at_least(ProtocolVersion::V1_16) {
+            if is_at_least(ProtocolVersion::V1_20_3) {
+                packets.push("minecraft:game_event");
+            }
+            if is_at_least(ProtocolVersion::V1_19) {
+                packets.push("minecraft:set_chunk_cache_center");
+            }
+            let chunks = usize::try_from((2 * VIEW_DISTANCE + 1).pow(2)).unwrap();
+            packets.extend(std::iter::repeat_n(
+                "minecraft:level_chunk_with_light",
+                chunks,
+            ));
+        }
+        packets
+    }
+}
+
+/// Client side of a connection, answering the server like a vanilla client would.
+struct Client {
+    stream: PacketStream<DuplexStream>,
+    scenario: Scenario,
+    protocol_version: ProtocolVersion,
+    state: State,
+    /// Name of every clientbound packet, in the order they were received.
+    received: Vec<&'static str>,
+    /// UUID of the login success packet.
+    unique_id: Option<Uuid>,
+}
+
+impl Client {
+    fn connect(
+        server_state: &Arc<RwLock<ServerState>>,
+        scenario: Scenario,
+        protocol_version: ProtocolVersion,
+    ) -> Self {
+        let (client, server) = tokio::io::duplex(64 * 1024);
+        let (_, reloads) = broadcast::channel(1);
+        tokio::spawn(handle_connection(
+            server,
+            "127.0.0.1:50000".parse().unwrap(),
+            Arc::clone(server_state),
+            ConnectionLimiter::new(),
+            reloads,
+            PlayerRegistry::new(),
+        ));
+        Self {
+            stream: PacketStream::new(client),
+            scenario,
+            protocol_version,
+            state: State::Handshake,
+            received: Vec::new(),
+            unique_id: None,
+        }
+    }
+
+    async fn send(&mut self, packet: ClientPacket) {
+        let raw_packet = packet.encode_packet(self.protocol_version).unwrap();
+        self.stream.write_packet(raw_packet).await.unwrap();
+    }
+
+    /// Joins the game and waits for the first keep alive in game, or until the server disconnects the client.
+    async fn join(&mut self) {
+        self.send(ClientPacket::Handshake(HandshakePacket {
+            protocol: VarInt::new(self.protocol_version.version_number()),
+            hostname: self.scenario.hostname(),
+            port: 25565,
+            next_state: VarInt::new(2),
+        }))
+        .await;
+        self.state = State::Login;
+        self.send(ClientPacket::LoginStart(LoginStartPacket::new(
+            USERNAME,
+            Uuid::nil(),
+        )))
+        .await;
+
+        loop {
+            let raw_packet = self.stream.read_packet().await.unwrap();
+            if !self.handle_packet(raw_packet).await {
+                return;
+            }
+        }
+    }
+
+    /// Returns whether the client keeps reading packets.
+    async fn handle_packet(&mut self, raw_packet: RawPacket) -> bool {
+        let version = self.protocol_version.humanize();
+        let packet_id = raw_packet.packet_id().unwrap();
+        let name =
+            PacketRegistry::clientbound_packet_name(self.protocol_version, self.state, packet_id)
+                .unwrap_or_else(|| {
+                    panic!(
+                        "{version}: unknown packet 0x{packet_id:02X} in {} state",
+                        self.state
+                    )
+                });
+        // Keep alives are sent on a timer, their position in the sequence is not deterministic
+        if name != "minecraft:keep_alive" {
+            self.received.push(name);
+        }
+
+        let packet =
+            match ClientPacket::decode_packet(self.protocol_version, self.state, raw_packet) {
+                Ok(packet) => packet,
+                Err(PacketRegistryDecodeError::NoCorrespondingPacket(..)) => return true,
+                Err(err) => panic!("{version}: failed to decode {name}: {err}"),
+            };
+
+        match packet {
+            ClientPacket::SetCompression(packet) => {
+                let threshold = usize::try_from(packet.threshold()).unwrap();
+                self.stream.set_compression(threshold, 6);
+            }
+            ClientPacket::CustomQuery(packet) => {
+                let answer = velocity_answer(
+                    packet.message_id,
+                    &packet.data,
+                    self.scenario.chat_signing_key,
+                );
+                self.send(ClientPacket::CustomQueryAnswer(answer)).await;
+            }
+            ClientPacket::LoginFinished(packet) => {
+                assert_eq!(packet.username(), USERNAME, "{version}");
+                self.finish_login(packet.uuid()).await;
+            }
+            ClientPacket::GameProfile(packet) => {
+                assert_eq!(packet.username(), USERNAME, "{version}");
+                self.finish_login(packet.uuid()).await;
+            }
+            ClientPacket::ClientBoundKnownPacks(packet) => {
+                let known_packs = ServerBoundKnownPacksPacket::new(packet.known_packs.into_inner());
+                self.send(ClientPacket::ServerBoundKnownPacks(known_packs))
+                    .await;
+            }
+            ClientPacket::FinishConfiguration(_) => {
+                self.send(ClientPacket::AcknowledgeConfiguration(
+                    AcknowledgeConfigurationPacket {},
+                ))
+                .await;
+                self.state = State::Play;
+            }
+            ClientPacket::SynchronizePlayerPosition(packet) => {
+                assert_eq!((packet.x, packet.y, packet.z), (0.0, 0.0, 0.0), "{version}");
+            }
+            ClientPacket::ConfigurationClientBoundKeepAlive(packet) => {
+                let answer =
+                    ServerBoundKeepAlivePacket::new(packet.decoded_id(self.protocol_version));
+                self.send(ClientPacket::ConfigurationServerBoundKeepAlive(answer))
+                    .await;
+            }
+            // The keep alive may be sent between the end of the configuration and the login packet
+            ClientPacket::ClientBoundKeepAlive(_) => {
+                return !self.received.contains(&"minecraft:login");
+            }
+            ClientPacket::LoginDisconnect(_)
+            | ClientPacket::ConfigurationDisconnect(_)
+            | ClientPacket::PlayDisconnect(_) => return false,
+            _ => {}
+        }
+        true
+    }
+
+    async fn finish_login(&mut self, unique_id: Uuid) {
+        self.unique_id = Some(unique_id);
+        if self.protocol_version.supports_configuration_state() {
+            self.send(ClientPacket::LoginAcknowledged(LoginAcknowledgedPacket {}))
+                .await;
+            self.state = State::Configuration;
+        } else {
+            self.state = State::Play;
+        }
+    }
+}
+
+/// Player information signed with the secret, as sent by Velocity.
+fn velocity_answer(
+    message_id: VarInt,
+    data: &[u8],
+    chat_signing_key: bool,
+) -> CustomQueryAnswerPacket {
+    let requested_version = ModernForwardingVersion::from_id(i32::from(data[0])).unwrap();
+    // Players without a chat signing key fall back to the default version
+    let version = if requested_version.has_public_key() && !chat_signing_key {
+        ModernForwardingVersion::Default
+    } else {
+        requested_version
+    };
+    // UUIDs are only encoded as bytes since 1.16
+    let protocol_version = ProtocolVersion::latest();
+    let mut writer = BinaryWriter::new();
+    VarInt::new(i32::from(version.id()))
+        .encode(&mut writer, protocol_version)
+        .unwrap();
+    PLAYER_ADDRESS
+        .to_string()
+        .encode(&mut writer, protocol_version)
+        .unwrap();
+    FORWARDED_UUID
+        .encode(&mut writer, protocol_version)
+        .unwrap();
+    USERNAME
+        .to_string()
+        .encode(&mut writer, protocol_version)
+        .unwrap();
+    // No properties
+    VarInt::new(0)
+        .encode(&mut writer, protocol_version)
+        .unwrap();
+    if version.has_public_key() {
+        writer.write(&KEY_EXPIRES_AT).unwrap();
+        LengthPaddedVec::new(vec![1_u8, 2, 3])
+            .encode(&mut writer, protocol_version)
+            .unwrap();
+        LengthPaddedVec::new(vec![4_u8, 5])
+            .encode(&mut writer, protocol_version)
+            .unwrap();
+        if version.has_signature_holder() {
+            true.encode(&mut writer, protocol_version).unwrap();
+            KEY_HOLDER_UUID
+                .encode(&mut writer, protocol_version)
+                .unwrap();
+        }
+    }
+    let payload = writer.into_inner();
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(SECRET.as_bytes()).unwrap();
+    mac.update(&payload);
+    let mut data = mac.finalize().into_bytes().to_vec();
+    data.extend(payload);
+    CustomQueryAnswerPacket {
+        message_id,
+        is_present: true,
+        data,
+    }
+}
+
+fn offline_unique_id() -> Uuid {
+    GameProfile::from(&LoginStartPacket::new(USERNAME, Uuid::nil())).uuid()
+}
+
+fn supported_versions() -> impl Iterator<Item = ProtocolVersion> {
+    ProtocolVersion::ALL_VERSION
+        .iter()
+        .copied()
+        .filter(|version| !version.is_any() && !version.is_unsupported())
+}
+
+/// Joins with every supported version and checks the exact order of the packets received.
+async fn assert_every_version_joins(scenario: Scenario) {
+    let server_state = Arc::new(RwLock::new(scenario.server_state()));
+    for protocol_version in supported_versions() {
+        let version = protocol_version.humanize();
+        let mut client = Client::connect(&server_state, scenario, protocol_version);
+
+        let joined = timeout(JOIN_TIMEOUT, client.join()).await;
+
+        assert!(
+            joined.is_ok(),
+            "{version}: did not join, received {:?}",
+            client.received
+        );
+        assert_eq!(
+            client.received,
+            scenario.expected_packets(protocol_version),
+            "{version}"
+        );
+        let expected_unique_id = match scenario.forwarding {
+            Forwarding::Disabled => Some(offline_unique_id()),
+            Forwarding::Modern if !protocol_version.is_modern() => None,
+            Forwarding::Legacy | Forwarding::BungeeGuard | Forwarding::Modern => {
+                Some(FORWARDED_UUID)
+            }
+        };
+        assert_eq!(client.unique_id, expected_unique_id, "{version}");
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_every_version_joins_without_forwarding() {
+    assert_every_version_joins(Scenario::new(Forwarding::Disabled)).await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_every_version_joins_with_compression() {
+    assert_every_version_joins(Scenario::new(Forwarding::Disabled).with_compression()).await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_every_version_joins_with_legacy_forwarding() {
+    assert_every_version_joins(Scenario::new(Forwarding::Legacy)).await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_every_version_joins_with_bungee_guard_forwarding() {
+    assert_every_version_joins(Scenario::new(Forwarding::BungeeGuard)).await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_every_version_joins_with_modern_forwarding() {
+    assert_every_version_joins(Scenario::new(Forwarding::Modern)).await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_every_version_joins_with_modern_forwarding_and_compression() {
+    assert_every_version_joins(Scenario::new(Forwarding::Modern).with_compression()).await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_every_version_joins_with_modern_forwarding_of_chat_signing_keys() {
+    assert_every_version_joins(Scenario::new(Forwarding::Modern).with_chat_signing_key()).await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_every_version_joins_with_schematic() {
+    assert_every_version_joins(Scenario::new(Forwarding::Disabled).with_schematic()).await;
+}
@@ -0,0 +1,390 @@
+use crate::server_state::ConnectionLimits;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RejectReason {
+    /// The address opened too many connections during the current window.
+    RateLimited,
+    /// The address already has too many connections open.
+    TooManyConnections,
+    /// Too many connections have not reached the PLAY state yet.
+    TooManyPendingConnections,
+}
+
+impl RejectReason {
+    /// Short name of the reason, used to label the rejections in the metrics.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::RateLimited => "rate_limited",
+            Self::TooManyConnections => "too_many_connections",
+            Self::TooManyPendingConnections => "too_many_pending_connections",
+        }
+    }
+}
+
+impl Display for RejectReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::RateLimited => f.write_str("rate limited"),
+            Self::TooManyConnections => f.write_str("too many connections"),
+            Self::TooManyPendingConnections => f.write_str("too many pending connections"),
+        }
+    }
+}
+
+struct AddressEntry {
+    window_start: Instant,
+    connections_in_window: u32,
+    open_connections: u32,
+}
+
+#[derive(Default)]
+struct RejectionCounters {
+    rate_limited: AtomicU64,
+    too_many_connections: AtomicU64,
+    too_many_pending_connections: AtomicU64,
+}
+
+/// Tracks connections per address to throttle floods before any packet is processed.
+#[derive(Default)]
+pub struct ConnectionLimiter {
+    addresses: Mutex<HashMap<IpAddr, AddressEntry>>,
+    pending_connections: AtomicUsize,
+    rejections: RejectionCounters,
+}
+
+impl ConnectionLimiter {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// Registers a new connection, or tells why it must be rejected.
+    /// The returned permit must be kept for as long as the connection is open.
+    pub fn try_acquire(
+        self: &Arc<Self>,
+        ip: Option<IpAddr>,
+        limits: &ConnectionLimits,
+    ) -> Result<ConnectionPermit, RejectReason> {
+        let result = self.check(ip, limits, Instant::now());
+        if let Err(reason) = result {
+            let counter = match reason {
+                RejectReason::RateLimited => &self.rejections.rate_limited,
+                RejectReason::TooManyConnections => &self.rejections.too_many_connections,
+                RejectReason::TooManyPendingConnections => {
+                    &self.rejections.too_many_pending_connections
+                }
+            };
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+        result.map(|ip| ConnectionPermit {
+            limiter: Arc::clone(self),
+            ip,
+            is_pending: true,
+        })
+    }
+
+    fn check(
+        &self,
+        ip: Option<IpAddr>,
+        limits: &ConnectionLimits,
+        now: Instant,
+    ) -> Result<Option<IpAddr>, RejectReason> {
+        // Reserved at once so that concurrent accepts cannot exceed the limit
+        self.pending_connections
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |pending_connections| {
+                (limits.max_pending_connections == 0
+                    || pending_connections < limits.max_pending_connections)
+                    .then_some(pending_connections + 1)
+            })
+            .map_err(|_| RejectReason::TooManyPendingConnections)?;
+
+        let ip = ip.map(|ip| ip.to_canonical());
+        if let Some(ip) = ip
+            && let Err(reason) = self.check_address(ip, limits, now)
+        {
+            self.pending_connections.fetch_sub(1, Ordering::SeqCst);
+            return Err(reason);
+        }
+        Ok(ip)
+    }
+
+    fn check_address(
+        &self,
+        ip: IpAddr,
+        limits: &ConnectionLimits,
+        now: Instant,
+    ) -> Result<(), RejectReason> {
+        let mut addresses = self.addresses();
+        let entry = addresses.entry(ip).or_insert(AddressEntry {
+            window_start: now,
+            connections_in_window: 0,
+            open_connections: 0,
+        });
+
+        if now.duration_since(entry.window_start) >= limits.window {
+            entry.window_start = now;
+            entry.connections_in_window = 0;
+        }
+
+        if limits.connections_per_window > 0
+            && entry.connections_in_window >= limits.connections_per_window
+        {
+            return Err(RejectReason::RateLimited);
+        }
+        if limits.max_connections_per_address > 0
+            && entry.open_connections >= limits.max_connections_per_address
+        {
+            return Err(RejectReason::TooManyConnections);
+        }
+
+        entry.connections_in_window += 1;
+        entry.open_connections += 1;
+        drop(addresses);
+        Ok(())
+    }
+
+    /// Returns the number of connections rejected since the last call, per reason.
+    pub fn take_rejections(&self) -> [(RejectReason, u64); 3] {
+        [
+            (
+                RejectReason::RateLimited,
+                self.rejections.rate_limited.swap(0, Ordering::Relaxed),
+            ),
+            (
+                RejectReason::TooManyConnections,
+                self.rejections
+                    .too_many_connections
+                    .swap(0, Ordering::Relaxed),
+            ),
+            (
+                RejectReason::TooManyPendingConnections,
+                self.rejections
+                    .too_many_pending_connections
+                    .swap(0, Ordering::Relaxed),
+            ),
+        ]
+    }
+
+    /// Forgets addresses without open connections whose window is over.
+    pub fn remove_expired(&self, window: Duration) {
+        let now = Instant::now();
+        self.addresses().retain(|_, entry| {
+            entry.open_connections > 0 || now.duration_since(entry.window_start) < window
+        });
+    }
+
+    fn release(&self, ip: Option<IpAddr>, is_pending: bool) {
+        if is_pending {
+            self.pending_connections.fetch_sub(1, Ordering::SeqCst);
+        }
+        if let Some(ip) = ip
+            && let Some(entry) = self.addresses().get_mut(&ip)
+        {
+            entry.open_connections = entry.open_connections.saturating_sub(1);
+        }
+    }
+
+    fn addresses(&self) -> MutexGuard<'_, HashMap<IpAddr, AddressEntry>> {
+        self.addresses
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Releases the connection slot when dropped.
+pub struct ConnectionPermit {
+    limiter: Arc<ConnectionLimiter>,
+    ip: Option<IpAddr>,
+    is_pending: bool,
+}
+
+impl ConnectionPermit {
+    /// Stops counting this connection as pending, once the player reached the PLAY state.
+    pub fn mark_joined(&mut self) {
+        if self.is_pending {
+            self.is_pending = false;
+            self.limiter
+                .pending_connections
+                .fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+impl Drop for ConnectionPermit {
+    fn drop(&mut self) {
+        self.limiter.release(self.ip, self.is_pending);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits() -> ConnectionLimits {
+        ConnectionLimits {
+            window: Duration::from_secs(10),
+            connections_per_window: 3,
+            max_connections_per_address: 2,
+            max_pending_connections: 0,
+        }
+    }
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn test_rejects_too_many_open_connections_per_address() {
+        // Given
+        let limiter = ConnectionLimiter::new();
+        let limits = limits();
+        let _first = limiter.try_acquire(Some(ip("10.0.0.1")), &limits).unwrap();
+        let second = limiter.try_acquire(Some(ip("10.0.0.1")), &limits).unwrap();
+
+        // When
+        let third = limiter.try_acquire(Some(ip("10.0.0.1")), &limits);
+        let other_address = limiter.try_acquire(Some(ip("10.0.0.2")), &limits);
+
+        // Then
+        assert!(matches!(third, Err(RejectReason::TooManyConnections)));
+        assert!(other_address.is_ok());
+        drop(second);
+        assert!(limiter.try_acquire(Some(ip("10.0.0.1")), &limits).is_ok());
+    }
+
+    #[test]
+    fn test_rate_limits_within_window() {
+        // Given
+        let limiter = ConnectionLimiter::new();
+        let limits = limits();
+        let now = Instant::now();
+        for _ in 0..3 {
+            limiter.check(Some(ip("10.0.0.1")), &limits, now).unwrap();
+            limiter.release(Some(ip("10.0.0.1")), true);
+        }
+
+        // When
+        let rejected = limiter.check(Some(ip("10.0.0.1")), &limits, now);
+        let next_window = limiter.check(Some(ip("10.0.0.1")), &limits, now + limits.window);
+
+        // Then
+        assert!(matches!(rejected, Err(RejectReason::RateLimited)));
+        assert!(next_window.is_ok());
+    }
+
+    #[test]
+    fn test_ipv4_mapped_addresses_share_limits() {
+        // Given
+        let limiter = ConnectionLimiter::new();
+        let limits = limits();
+        let _first = limiter.try_acquire(Some(ip("10.0.0.1")), &limits).unwrap();
+        let _second = limiter
+            .try_acquire(Some(ip("::ffff:10.0.0.1")), &limits)
+            .unwrap();
+
+        // When
+        let third = limiter.try_acquire(Some(ip("10.0.0.1")), &limits);
+
+        // Then
+        assert!(matches!(third, Err(RejectReason::TooManyConnections)));
+    }
+
+    #[test]
+    fn test_rejects_when_too_many_pending_connections() {
+        // Given
+        let limiter = ConnectionLimiter::new();
+        let limits = ConnectionLimits {
+            max_pending_connections: 1,
+            ..limits()
+        };
+        let mut first = limiter.try_acquire(Some(ip("10.0.0.1")), &limits).unwrap();
+
+        // When
+        let rejected = limiter.try_acquire(Some(ip("10.0.0.2")), &limits);
+        first.mark_joined();
+        let accepted = limiter.try_acquire(Some(ip("10.0.0.2")), &limits);
+
+        // Then
+        assert!(matches!(
+            rejected,
+            Err(RejectReason::TooManyPendingConnections)
+        ));
+        assert!(accepted.is_ok());
+        assert_eq!(
+            limiter.take_rejections()[2],
+            (RejectReason::TooManyPendingConnections, 1)
+        );
+    }
+
+    // Every thread is spawned before the first one is joined
+    #[allow(clippy::needless_collect)]
+    #[test]
+    fn test_concurrent_connections_do_not_exceed_max_pending_connections() {
+        // Given
+        let limiter = ConnectionLimiter::new();
+        let limits = ConnectionLimits {
+            max_pending_connections: 1,
+            ..limits()
+        };
+        let addresses: Vec<IpAddr> = (0..8)
+            .map(|index| IpAddr::from([10, 0, 0, index]))
+            .collect();
+
+        // When
+        let permits: Vec<ConnectionPermit> = std::thread::scope(|scope| {
+            let attempts: Vec<_> = addresses
+                .iter()
+                .map(|address| scope.spawn(|| limiter.try_acquire(Some(*address), &limits)))
+                .collect();
+            attempts
+                .into_iter()
+                .filter_map(|attempt| attempt.join().unwrap().ok())
+                .collect()
+        });
+
+        // Then
+        assert_eq!(permits.len(), 1);
+        assert_eq!(limiter.pending_connections.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_rejected_address_releases_its_pending_connection() {
+        // Given
+        let limiter = ConnectionLimiter::new();
+        let limits = ConnectionLimits {
+            max_pending_connections: 3,
+            ..limits()
+        };
+        let _first = limiter.try_acquire(Some(ip("10.0.0.1")), &limits).unwrap();
+        let _second = limiter.try_acquire(Some(ip("10.0.0.1")), &limits).unwrap();
+
+        // When
+        let rejected = limiter.try_acquire(Some(ip("10.0.0.1")), &limits);
+        let other_address = limiter.try_acquire(Some(ip("10.0.0.2")), &limits);
+
+        // Then
+        assert!(matches!(rejected, Err(RejectReason::TooManyConnections)));
+        assert!(other_address.is_ok());
+    }
+
+    #[test]
+    fn test_remove_expired_keeps_open_connections() {
+        // Given
+        let limiter = ConnectionLimiter::new();
+        let limits = limits();
+        let _open = limiter.try_acquire(Some(ip("10.0.0.1")), &limits).unwrap();
+        drop(limiter.try_acquire(Some(ip("10.0.0.2")), &limits).unwrap());
+
+        // When
+        limiter.remove_expired(Duration::ZERO);
+
+        // Then
+        assert!(limiter.addresses().contains_key(&ip("10.0.0.1")));
+        assert!(!limiter.addresses().contains_key(&ip("10.0.0.2")));
+    }
+}
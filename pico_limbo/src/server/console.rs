@@ -0,0 +1,168 @@
+use crate::server::admin::{Admin, COMMANDS};
+use crate::server::player_registry::PlayerRegistry;
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+/// Runs the commands typed by the operator on the standard input.
+pub struct Console {
+    admin: Arc<Admin>,
+    stop: CancellationToken,
+}
+
+impl Console {
+    pub const fn new(admin: Arc<Admin>, stop: CancellationToken) -> Self {
+        Self { admin, stop }
+    }
+
+    /// Reads lines on a dedicated thread, since reading the standard input blocks.
+    pub async fn run(self) {
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let players = self.admin.players();
+        let stop = self.stop.clone();
+        let spawned = std::thread::Builder::new()
+            .name("console".to_string())
+            .spawn(move || read_lines(&sender, players, &stop));
+        if let Err(err) = spawned {
+            error!("Failed to start the console: {err}");
+            return;
+        }
+
+        while let Some(line) = receiver.recv().await {
+            match self.admin.run(&line).await {
+                Some(Ok(reply)) => info!("{}", reply.to_plain_text()),
+                Some(Err(err)) => warn!("{err}"),
+                None => {}
+            }
+        }
+    }
+}
+
+fn read_lines(
+    sender: &mpsc::UnboundedSender<String>,
+    players: Arc<PlayerRegistry>,
+    stop: &CancellationToken,
+) {
+    let mut editor = match Editor::<ConsoleHelper, DefaultHistory>::new() {
+        Ok(editor) => editor,
+        Err(err) => {
+            error!("Failed to start the console: {err}");
+            return;
+        }
+    };
+    editor.set_helper(Some(ConsoleHelper { players }));
+
+    while !stop.is_cancelled() {
+        match editor.readline("") {
+            Ok(line) => {
+                // Reading again would leave the terminal in raw mode once the process exits
+                let is_stop = line.trim() == "stop";
+                if !line.trim().is_empty() {
+                    let _ = editor.add_history_entry(line.as_str());
+                }
+                if sender.send(line).is_err() || is_stop {
+                    break;
+                }
+            }
+            Err(ReadlineError::Interrupted) => {
+                // The terminal does not raise SIGINT while a line is being read
+                info!("Stopping the server");
+                stop.cancel();
+                break;
+            }
+            Err(ReadlineError::Eof) => break,
+            Err(err) => {
+                error!("Failed to read from the console: {err}");
+                break;
+            }
+        }
+    }
+}
+
+/// Completes command names, then player names for the commands targeting a player.
+struct ConsoleHelper {
+    players: Arc<PlayerRegistry>,
+}
+
+impl ConsoleHelper {
+    fn candidates(&self, line: &str) -> (usize, Vec<String>) {
+        let start = line.rfind(' ').map_or(0, |index| index + 1);
+        let word = &line[start..];
+        let previous_words: Vec<&str> = line[..start].split_whitespace().collect();
+        let candidates: Vec<String> = match previous_words.as_slice() {
+            [] => COMMANDS.iter().map(ToString::to_string).collect(),
+            ["kick" | "transfer"] => self.players.usernames(),
+            _ => Vec::new(),
+        };
+        let word = word.to_lowercase();
+        let candidates = candidates
+            .into_iter()
+            .filter(|candidate| candidate.to_lowercase().starts_with(&word))
+            .collect();
+        (start, candidates)
+    }
+}
+
+impl Completer for ConsoleHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let (start, candidates) = self.candidates(&line[..pos]);
+        let pairs = candidates
+            .into_iter()
+            .map(|candidate| Pair {
+                display: candidate.clone(),
+                replacement: format!("{candidate} "),
+            })
+            .collect();
+        Ok((start, pairs))
+    }
+}
+
+impl Hinter for ConsoleHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ConsoleHelper {}
+
+impl Validator for ConsoleHelper {}
+
+impl Helper for ConsoleHelper {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use minecraft_protocol::prelude::{ProtocolVersion, Uuid};
+
+    #[test]
+    fn test_completes_commands_then_player_names() {
+        // Given
+        let players = PlayerRegistry::new();
+        let _notch = players.register(Uuid::from_u128(1), "Notch", ProtocolVersion::V1_21_2);
+        let _jeb = players.register(Uuid::from_u128(2), "jeb_", ProtocolVersion::V1_21_2);
+        let helper = ConsoleHelper { players };
+
+        // When
+        let commands = helper.candidates("k");
+        let usernames = helper.candidates("kick n");
+        let nothing = helper.candidates("broadcast n");
+
+        // Then
+        assert_eq!(commands, (0, vec!["kick".to_string()]));
+        assert_eq!(usernames, (5, vec!["Notch".to_string()]));
+        assert_eq!(nothing, (10, Vec::new()));
+    }
+}
@@ -9,6 +9,19 @@ pub struct GameProfile {
     username: String,
     uuid: Uuid,
     textures: Option<Property>,
+    /// Chat signing key of 1.19 to 1.19.2 players, when forwarded by Velocity.
+    public_key: Option<PlayerPublicKey>,
+}
+
+/// Chat signing key issued by Mojang to a player.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlayerPublicKey {
+    /// Milliseconds since the Unix epoch.
+    pub expires_at: i64,
+    pub key: Vec<u8>,
+    pub signature: Vec<u8>,
+    /// UUID of the player the key was issued to, forwarded since 1.19.1.
+    pub signature_holder: Option<Uuid>,
 }
 
 impl GameProfile {
@@ -20,6 +33,7 @@ impl GameProfile {
             username,
             uuid,
             textures,
+            public_key: None,
         }
     }
 
@@ -28,6 +42,7 @@ impl GameProfile {
             username: String::new(),
             uuid,
             textures,
+            public_key: None,
         }
     }
 
@@ -53,6 +68,14 @@ impl GameProfile {
     pub const fn textures(&self) -> Option<&Property> {
         self.textures.as_ref()
     }
+
+    pub const fn public_key(&self) -> Option<&PlayerPublicKey> {
+        self.public_key.as_ref()
+    }
+
+    pub fn set_public_key(&mut self, public_key: Option<PlayerPublicKey>) {
+        self.public_key = public_key;
+    }
 }
 
 impl From<&LoginStartPacket> for GameProfile {
@@ -71,6 +94,7 @@ impl From<&LoginStartPacket> for GameProfile {
             username,
             uuid,
             textures: None,
+            public_key: None,
         }
     }
 }
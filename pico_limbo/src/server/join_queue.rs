@@ -0,0 +1,412 @@
+use crate::handlers::configuration::action_bar_packet;
+use crate::kick_messages::QUEUE_RELEASED_KICK_REASON;
+use crate::server::backend_watcher::proxy_connect_packet;
+use crate::server::packet_registry::PacketRegistry;
+use crate::server::player_registry::{PlayerHandle, PlayerRegistry};
+use crate::server::server_ping::ping;
+use crate::server_state::{JoinQueue, QueueDisplay, QueueMethod, ServerState};
+use minecraft_packets::play::boss_bar_packet::{BossBarDivision, BossBarPacket};
+use minecraft_packets::play::transfer_packet::TransferPacket;
+use minecraft_protocol::prelude::{ProtocolVersion, Uuid, VarInt};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tracing::{debug, error, info};
+
+/// How often the positions are recomputed and displayed.
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+/// Boss bar displaying the position, distinct from the configured boss bar.
+const QUEUE_BOSS_BAR_UUID: Uuid = Uuid::from_u128(0x7069_636f_6c69_6d62_6f00_0000_7175_6575);
+
+/// Holds the players in game in a queue and releases them in batches.
+pub struct QueueManager {
+    state: Arc<RwLock<ServerState>>,
+    players: Arc<PlayerRegistry>,
+}
+
+/// What was last displayed to a player.
+struct Displayed {
+    position: usize,
+    has_boss_bar: bool,
+}
+
+impl QueueManager {
+    pub const fn new(state: Arc<RwLock<ServerState>>, players: Arc<PlayerRegistry>) -> Self {
+        Self { state, players }
+    }
+
+    /// Runs the queue until the task is aborted.
+    pub async fn run(self) {
+        let mut interval = tokio::time::interval(TICK_INTERVAL);
+        let mut next_batch = Instant::now();
+        let mut is_target_online = false;
+        let mut displayed: HashMap<Uuid, Displayed> = HashMap::new();
+        // Released players stay in game until they are moved, they must not be released again
+        let mut released: HashSet<Uuid> = HashSet::new();
+        let mut pending_ping: Option<JoinHandle<bool>> = None;
+
+        loop {
+            interval.tick().await;
+            let Some(queue) = self.state.read().await.join_queue() else {
+                self.clear(&mut displayed);
+                continue;
+            };
+
+            self.forget_departed(&mut released);
+            let mut players = self.queued_players(&queue, &released);
+            // The target is pinged in the background, to keep displaying the positions meanwhile
+            if !players.is_empty() && pending_ping.is_none() && Instant::now() >= next_batch {
+                next_batch = Instant::now() + queue.batch_interval;
+                pending_ping = Some(tokio::spawn(is_online(queue.host.clone(), queue.port)));
+            }
+            if let Some(ping) = pending_ping.take_if(|ping| ping.is_finished()) {
+                is_target_online = ping.await.unwrap_or(false);
+                if is_target_online {
+                    let batch_size = queue.batch_size.min(players.len());
+                    for player in players.drain(..batch_size) {
+                        release(&queue, &player, displayed.remove(&player.uuid()));
+                        released.insert(player.uuid());
+                    }
+                }
+            }
+
+            let until_next_batch =
+                is_target_online.then(|| next_batch.saturating_duration_since(Instant::now()));
+            displayed.retain(|uuid, _| players.iter().any(|player| player.uuid() == *uuid));
+            for (index, player) in players.iter().enumerate() {
+                let previous = displayed.get(&player.uuid());
+                let packets = display_packets(
+                    &queue,
+                    player,
+                    index + 1,
+                    players.len(),
+                    until_next_batch,
+                    previous,
+                );
+                let has_boss_bar = shows_boss_bar(&queue, player.protocol_version())
+                    && (!packets.is_empty()
+                        || previous.is_some_and(|previous| previous.has_boss_bar));
+                for packet in packets {
+                    player.queue(packet);
+                }
+                displayed.insert(
+                    player.uuid(),
+                    Displayed {
+                        position: index + 1,
+                        has_boss_bar,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Players in game and not released yet, ordered by tier, then by the time they entered the game.
+    fn queued_players(&self, queue: &JoinQueue, released: &HashSet<Uuid>) -> Vec<PlayerHandle> {
+        let mut players = self.players.in_join_order();
+        players.retain(|player| !released.contains(&player.uuid()));
+        players.sort_by_key(|player| queue.tier(player.uuid()));
+        players
+    }
+
+    /// Forgets the released players who left, so that they are queued again when they come back.
+    fn forget_departed(&self, released: &mut HashSet<Uuid>) {
+        released.retain(|uuid| self.players.get(*uuid).is_some());
+    }
+
+    /// Removes the boss bars once the queue is disabled by a reload.
+    fn clear(&self, displayed: &mut HashMap<Uuid, Displayed>) {
+        for (uuid, displayed) in displayed.drain() {
+            if displayed.has_boss_bar
+                && let Some(player) = self.players.get(uuid)
+            {
+                player.queue(PacketRegistry::BossBar(BossBarPacket::remove(
+                    QUEUE_BOSS_BAR_UUID,
+                )));
+            }
+        }
+    }
+}
+
+/// Pings the target of the queue, logging why it is offline.
+async fn is_online(host: String, port: u16) -> bool {
+    match ping(&host, port).await {
+        Ok(()) => true,
+        Err(err) => {
+            debug!("Queue target {host}:{port} did not answer: {err}");
+            false
+        }
+    }
+}
+
+/// Transfers the player, or asks the proxy to move them, and kicks the players who can be moved neither way.
+/// The boss bar is removed first, the player may stay in game until the proxy moves them.
+fn release(queue: &JoinQueue, player: &PlayerHandle, previous: Option<Displayed>) {
+    info!("Releasing {} from the queue", player.username());
+    if previous.is_some_and(|previous| previous.has_boss_bar) {
+        player.queue(PacketRegistry::BossBar(BossBarPacket::remove(
+            QUEUE_BOSS_BAR_UUID,
+        )));
+    }
+    let protocol_version = player.protocol_version();
+    let can_transfer = queue.method == QueueMethod::Transfer
+        && protocol_version.is_after_inclusive(ProtocolVersion::V1_20_5);
+    if can_transfer {
+        player.queue(PacketRegistry::Transfer(TransferPacket::new(
+            &queue.host,
+            &VarInt::from(i32::from(queue.port)),
+        )));
+    } else if let Some(payload) = &queue.proxy_connect {
+        player.queue(proxy_connect_packet(payload, protocol_version));
+    } else {
+        player.kick(queue.kick_message.clone(), QUEUE_RELEASED_KICK_REASON);
+    }
+}
+
+/// Builds the packets displaying the position, the boss bar is updated once added and
+/// clients prior to 1.8 only receive a chat message when their position changes.
+fn display_packets(
+    queue: &JoinQueue,
+    player: &PlayerHandle,
+    position: usize,
+    size: usize,
+    until_next_batch: Option<Duration>,
+    previous: Option<&Displayed>,
+) -> Vec<PacketRegistry> {
+    let protocol_version = player.protocol_version();
+    if protocol_version.is_before_inclusive(ProtocolVersion::V1_7_6)
+        && previous.is_some_and(|previous| previous.position == position)
+    {
+        return Vec::new();
+    }
+
+    let batches_ahead = u32::try_from((position - 1) / queue.batch_size).unwrap_or(u32::MAX);
+    let eta = until_next_batch.map(|until_next_batch| {
+        until_next_batch + queue.batch_interval.saturating_mul(batches_ahead)
+    });
+    let message = match queue.message(position, size, eta) {
+        Ok(message) => message,
+        Err(err) => {
+            error!("Failed to format the queue message: {err}");
+            return Vec::new();
+        }
+    };
+
+    let has_boss_bar = previous.is_some_and(|previous| previous.has_boss_bar);
+    if shows_boss_bar(queue, protocol_version) {
+        let health = progress(position, size);
+        if has_boss_bar {
+            vec![
+                PacketRegistry::BossBar(BossBarPacket::update_title(QUEUE_BOSS_BAR_UUID, &message)),
+                PacketRegistry::BossBar(BossBarPacket::update_health(QUEUE_BOSS_BAR_UUID, health)),
+            ]
+        } else {
+            vec![PacketRegistry::BossBar(BossBarPacket::add(
+                QUEUE_BOSS_BAR_UUID,
+                &message,
+                health,
+                queue.color,
+                BossBarDivision::NoDivision,
+            ))]
+        }
+    } else {
+        let mut packets = Vec::with_capacity(2);
+        if has_boss_bar {
+            packets.push(PacketRegistry::BossBar(BossBarPacket::remove(
+                QUEUE_BOSS_BAR_UUID,
+            )));
+        }
+        packets.push(action_bar_packet(&message, protocol_version));
+        packets
+    }
+}
+
+/// The boss bar falls back to the action bar before 1.9.
+fn shows_boss_bar(queue: &JoinQueue, protocol_version: ProtocolVersion) -> bool {
+    queue.display == QueueDisplay::BossBar
+        && protocol_version.is_after_inclusive(ProtocolVersion::V1_9)
+}
+
+/// Fills up as the player moves to the front of the queue.
+#[allow(clippy::cast_precision_loss)]
+fn progress(position: usize, size: usize) -> f32 {
+    (size + 1 - position) as f32 / size as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configuration::queue::{QueueConfig, QueueDisplayConfig, QueueTierConfig};
+    use crate::server::player_registry::Outbound;
+
+    fn join_queue(display: QueueDisplayConfig, proxy_server: Option<&str>) -> JoinQueue {
+        JoinQueue::from_config(QueueConfig {
+            enabled: true,
+            display,
+            proxy_server: proxy_server.map(ToString::to_string),
+            batch_size: 2,
+            tiers: vec![QueueTierConfig {
+                uuids: vec![Uuid::from_u128(3).to_string()],
+            }],
+            ..QueueConfig::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_priority_tiers_are_queued_first() {
+        // Given
+        let players = PlayerRegistry::new();
+        let _notch = players.register(Uuid::from_u128(1), "Notch", ProtocolVersion::V1_21_2);
+        let _jeb = players.register(Uuid::from_u128(2), "jeb_", ProtocolVersion::V1_21_2);
+        let _dinnerbone =
+            players.register(Uuid::from_u128(3), "Dinnerbone", ProtocolVersion::V1_21_2);
+        let state = Arc::new(RwLock::new(ServerState::builder().build().unwrap()));
+        let manager = QueueManager::new(state, players);
+        let queue = join_queue(QueueDisplayConfig::ActionBar, None);
+
+        // When
+        let queued = manager.queued_players(&queue, &HashSet::new());
+
+        // Then
+        let usernames: Vec<&str> = queued.iter().map(PlayerHandle::username).collect();
+        assert_eq!(usernames, vec!["Dinnerbone", "Notch", "jeb_"]);
+    }
+
+    #[test]
+    fn test_released_players_are_not_queued_until_they_leave() {
+        // Given
+        let players = PlayerRegistry::new();
+        let _notch = players.register(Uuid::from_u128(1), "Notch", ProtocolVersion::V1_21_2);
+        let jeb = players.register(Uuid::from_u128(2), "jeb_", ProtocolVersion::V1_21_2);
+        let state = Arc::new(RwLock::new(ServerState::builder().build().unwrap()));
+        let manager = QueueManager::new(state, players);
+        let queue = join_queue(QueueDisplayConfig::ActionBar, None);
+        let mut released = HashSet::from([Uuid::from_u128(1), Uuid::from_u128(2)]);
+
+        // When
+        let queued_before_leaving = manager.queued_players(&queue, &released);
+        drop(jeb);
+        manager.forget_departed(&mut released);
+
+        // Then
+        assert!(queued_before_leaving.is_empty());
+        assert_eq!(released, HashSet::from([Uuid::from_u128(1)]));
+    }
+
+    #[tokio::test]
+    async fn test_target_is_offline_when_the_ping_fails() {
+        // Given
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+
+        // When
+        let ping = tokio::spawn(is_online("127.0.0.1".to_string(), port));
+
+        // Then
+        assert!(!ping.await.unwrap());
+    }
+
+    #[test]
+    fn test_release_depends_on_the_version() {
+        // Given
+        let players = PlayerRegistry::new();
+        let (_notch, mut notch) =
+            players.register(Uuid::from_u128(1), "Notch", ProtocolVersion::V1_21_2);
+        let (_jeb, mut jeb) = players.register(Uuid::from_u128(2), "jeb_", ProtocolVersion::V1_8);
+        let without_proxy = join_queue(QueueDisplayConfig::ActionBar, None);
+        let with_proxy = join_queue(QueueDisplayConfig::ActionBar, Some("lobby"));
+
+        // When
+        release(
+            &without_proxy,
+            &players.get(Uuid::from_u128(1)).unwrap(),
+            None,
+        );
+        release(
+            &without_proxy,
+            &players.get(Uuid::from_u128(2)).unwrap(),
+            None,
+        );
+        release(&with_proxy, &players.get(Uuid::from_u128(2)).unwrap(), None);
+
+        // Then
+        assert!(matches!(
+            notch.try_recv().unwrap(),
+            Outbound::Packet(packet) if matches!(*packet, PacketRegistry::Transfer(_))
+        ));
+        assert!(matches!(
+            jeb.try_recv().unwrap(),
+            Outbound::Kick(_, QUEUE_RELEASED_KICK_REASON)
+        ));
+        assert!(matches!(
+            jeb.try_recv().unwrap(),
+            Outbound::Packet(packet) if matches!(*packet, PacketRegistry::PlayClientBoundPluginMessage(_))
+        ));
+    }
+
+    #[test]
+    fn test_boss_bar_is_removed_on_release() {
+        // Given
+        let players = PlayerRegistry::new();
+        let (_notch, mut notch) =
+            players.register(Uuid::from_u128(1), "Notch", ProtocolVersion::V1_21_2);
+        let queue = join_queue(QueueDisplayConfig::BossBar, Some("lobby"));
+        let previous = Displayed {
+            position: 1,
+            has_boss_bar: true,
+        };
+
+        // When
+        release(
+            &queue,
+            &players.get(Uuid::from_u128(1)).unwrap(),
+            Some(previous),
+        );
+
+        // Then
+        assert!(matches!(
+            notch.try_recv().unwrap(),
+            Outbound::Packet(packet) if matches!(*packet, PacketRegistry::BossBar(_))
+        ));
+        assert!(matches!(
+            notch.try_recv().unwrap(),
+            Outbound::Packet(packet) if matches!(*packet, PacketRegistry::Transfer(_))
+        ));
+    }
+
+    #[test]
+    fn test_boss_bar_is_added_then_updated() {
+        // Given
+        let players = PlayerRegistry::new();
+        let _notch = players.register(Uuid::from_u128(1), "Notch", ProtocolVersion::V1_21_2);
+        let notch = players.get(Uuid::from_u128(1)).unwrap();
+        let queue = join_queue(QueueDisplayConfig::BossBar, None);
+        let eta = Some(Duration::from_secs(5));
+
+        // When
+        let added = display_packets(&queue, &notch, 2, 3, eta, None);
+        let previous = Displayed {
+            position: 2,
+            has_boss_bar: true,
+        };
+        let updated = display_packets(&queue, &notch, 1, 2, eta, Some(&previous));
+
+        // Then
+        assert!(matches!(added.as_slice(), [PacketRegistry::BossBar(_)]));
+        assert!(matches!(
+            updated.as_slice(),
+            [PacketRegistry::BossBar(_), PacketRegistry::BossBar(_)]
+        ));
+        assert!((progress(1, 2) - 1.0).abs() < f32::EPSILON);
+        assert!(
+            queue.message(3, 3, Some(Duration::from_secs(25))).unwrap()
+                == queue
+                    .message(3, 3, eta.map(|eta| eta + Duration::from_secs(20)))
+                    .unwrap()
+        );
+    }
+}
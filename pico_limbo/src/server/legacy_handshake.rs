@@ -0,0 +1,199 @@
+use crate::server_state::ServerState;
+use minecraft_protocol::prelude::ProtocolVersion;
+
+/// Kick packet id used by clients before 1.7, also used to answer server list pings.
+const LEGACY_KICK_PACKET_ID: u8 = 0xFF;
+const LEGACY_PING_PACKET_ID: u8 = 0xFE;
+const LEGACY_PLUGIN_MESSAGE_PACKET_ID: u8 = 0xFA;
+const LEGACY_LOGIN_PACKET_ID: u8 = 0x02;
+/// Protocol number that is never compatible, so that the client shows our version name.
+const INCOMPATIBLE_PROTOCOL: i32 = 127;
+
+/// A request from a client older than 1.7, before the Netty framing was introduced.
+#[derive(Debug, PartialEq, Eq)]
+pub enum LegacyRequest {
+    /// Server list ping from Beta 1.8 to 1.3, answered with `motd§online§max`.
+    BetaPing,
+    /// Server list ping from 1.4 to 1.6, answered with a `§1`-prefixed response.
+    Ping,
+    Login,
+}
+
+impl LegacyRequest {
+    /// Detects a legacy request from the first bytes received on a connection.
+    ///
+    /// A handshake is at least 6 bytes long, so a modern client never starts with
+    /// a length of 2. A length of 254 starts with `0xFE 0x01` too, which is why
+    /// the 1.6 ping is only recognised with the `0xFA` plugin message after it.
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            [LEGACY_PING_PACKET_ID] => Some(Self::BetaPing),
+            [LEGACY_PING_PACKET_ID, 0x01]
+            | [
+                LEGACY_PING_PACKET_ID,
+                0x01,
+                LEGACY_PLUGIN_MESSAGE_PACKET_ID,
+                ..,
+            ] => Some(Self::Ping),
+            [LEGACY_LOGIN_PACKET_ID, ..] => Some(Self::Login),
+            _ => None,
+        }
+    }
+
+    /// Whether the bytes received are too short to be told apart: a lone `0xFE`
+    /// is a Beta ping only if nothing follows it.
+    pub fn needs_more_bytes(bytes: &[u8]) -> bool {
+        bytes == [LEGACY_PING_PACKET_ID]
+    }
+
+    /// Builds the bytes sent back to the client, the connection must be closed afterward.
+    /// Returns `None` when nothing should be answered.
+    pub fn response(&self, server_state: &ServerState) -> Option<Vec<u8>> {
+        match self {
+            Self::BetaPing if server_state.reply_to_status() => {
+                let motd = server_state.motd().to_plain_text().replace('§', "");
+                Some(legacy_kick(&format!(
+                    "{motd}§{}§{}",
+                    server_state.online_players(),
+                    server_state.max_players()
+                )))
+            }
+            Self::Ping if server_state.reply_to_status() => {
+                let oldest = ProtocolVersion::oldest().humanize();
+                let latest = ProtocolVersion::latest().humanize();
+                Some(legacy_kick(&format!(
+                    "§1\0{INCOMPATIBLE_PROTOCOL}\0PicoLimbo {oldest}-{latest}\0{}\0{}\0{}",
+                    server_state.motd().to_legacy_text(),
+                    server_state.online_players(),
+                    server_state.max_players()
+                )))
+            }
+            Self::BetaPing | Self::Ping => None,
+            Self::Login => Some(legacy_kick(&outdated_client_kick_message())),
+        }
+    }
+}
+
+pub fn outdated_client_kick_message() -> String {
+    format!(
+        "Outdated client! Please use {} or newer.",
+        ProtocolVersion::oldest().humanize()
+    )
+}
+
+/// Encodes a legacy kick packet, its reason being a UTF-16 string prefixed by its length.
+fn legacy_kick(reason: &str) -> Vec<u8> {
+    let utf16: Vec<u16> = reason.encode_utf16().collect();
+    let length = u16::try_from(utf16.len()).unwrap_or(u16::MAX);
+
+    let mut bytes = Vec::with_capacity(3 + utf16.len() * 2);
+    bytes.push(LEGACY_KICK_PACKET_ID);
+    bytes.extend(length.to_be_bytes());
+    for code_unit in utf16.iter().take(usize::from(length)) {
+        bytes.extend(code_unit.to_be_bytes());
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server_state() -> ServerState {
+        let mut builder = ServerState::builder();
+        builder
+            .description_text("<red>A Minecraft Server</red>")
+            .max_players(20)
+            .show_online_player_count(true)
+            .set_reply_to_status(true);
+        builder.build().unwrap()
+    }
+
+    fn decode_legacy_kick(bytes: &[u8]) -> String {
+        assert_eq!(bytes[0], LEGACY_KICK_PACKET_ID);
+        let length = usize::from(u16::from_be_bytes([bytes[1], bytes[2]]));
+        let utf16: Vec<u16> = bytes[3..]
+            .chunks(2)
+            .map(|chunk| u16::from_be_bytes([chunk[0], chunk[1]]))
+            .collect();
+        assert_eq!(utf16.len(), length);
+        String::from_utf16(&utf16).unwrap()
+    }
+
+    #[test]
+    fn test_detect_legacy_requests() {
+        assert_eq!(
+            LegacyRequest::detect(&[0xFE]),
+            Some(LegacyRequest::BetaPing)
+        );
+        assert_eq!(
+            LegacyRequest::detect(&[0xFE, 0x01]),
+            Some(LegacyRequest::Ping)
+        );
+        assert_eq!(
+            LegacyRequest::detect(&[0xFE, 0x01, 0xFA, 0x00, 0x0B]),
+            Some(LegacyRequest::Ping)
+        );
+        assert_eq!(
+            LegacyRequest::detect(&[0x02, 0x4E, 0x00, 0x05]),
+            Some(LegacyRequest::Login)
+        );
+    }
+
+    #[test]
+    fn test_detect_modern_handshakes() {
+        // Handshake of 16 bytes
+        assert_eq!(LegacyRequest::detect(&[0x10, 0x00, 0xF2, 0x05]), None);
+        // Handshake of 254 bytes
+        assert_eq!(LegacyRequest::detect(&[0xFE, 0x01, 0x00, 0xF2]), None);
+        assert_eq!(LegacyRequest::detect(&[]), None);
+    }
+
+    #[test]
+    fn test_beta_ping_response() {
+        // Given
+        let server_state = server_state();
+
+        // When
+        let response = LegacyRequest::BetaPing.response(&server_state).unwrap();
+
+        // Then
+        assert_eq!(decode_legacy_kick(&response), "A Minecraft Server§0§20");
+    }
+
+    #[test]
+    fn test_ping_response() {
+        // Given
+        let server_state = server_state();
+
+        // When
+        let response = LegacyRequest::Ping.response(&server_state).unwrap();
+
+        // Then
+        let fields: Vec<String> = decode_legacy_kick(&response)
+            .split('\0')
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(fields[0], "§1");
+        assert_eq!(fields[1], "127");
+        assert_eq!(fields[3], "§r§cA Minecraft Server");
+        assert_eq!(fields[4], "0");
+        assert_eq!(fields[5], "20");
+    }
+
+    #[test]
+    fn test_ping_ignored_when_status_is_disabled() {
+        let server_state = ServerState::builder().build().unwrap();
+        assert!(LegacyRequest::Ping.response(&server_state).is_none());
+    }
+
+    #[test]
+    fn test_login_is_kicked() {
+        let server_state = server_state();
+        let response = LegacyRequest::Login.response(&server_state).unwrap();
+        assert_eq!(
+            decode_legacy_kick(&response),
+            "Outdated client! Please use 1.7.2 or newer."
+        );
+    }
+}
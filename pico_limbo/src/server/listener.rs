@@ -0,0 +1,331 @@
+use crate::server::server_address::{ParseServerAddressError, ServerAddress};
+use socket2::{Domain, Protocol, Socket, Type};
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::io;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, TcpStream, lookup_host};
+
+/// Same backlog as the listeners created by Tokio.
+const BACKLOG: i32 = 1024;
+const UNIX_PREFIX: &str = "unix:";
+/// Clients of a Unix domain socket run on the same host, like a proxy connecting through the loopback.
+#[cfg(unix)]
+pub const UNIX_PEER_ADDRESS: SocketAddr =
+    SocketAddr::new(std::net::IpAddr::V4(std::net::Ipv4Addr::LOCALHOST), 0);
+
+/// Byte stream a client is connected through.
+pub trait Transport: AsyncRead + AsyncWrite + Unpin + Send + 'static {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send + 'static> Transport for T {}
+
+/// Address the server listens on, either a TCP address or the path of a Unix domain socket.
+#[derive(Debug, Clone)]
+pub enum BindAddress {
+    Tcp(ServerAddress),
+    Unix(PathBuf),
+}
+
+impl BindAddress {
+    pub fn parse(bind: &str) -> Result<Self, ParseServerAddressError> {
+        match bind.strip_prefix(UNIX_PREFIX) {
+            Some("") => Err(ParseServerAddressError),
+            Some(path) => Ok(Self::Unix(PathBuf::from(path))),
+            None => ServerAddress::parse(bind).map(Self::Tcp),
+        }
+    }
+}
+
+impl Display for BindAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Tcp(address) => address.fmt(f),
+            Self::Unix(path) => write!(f, "{UNIX_PREFIX}{}", path.display()),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("Failed to bind to {address}: {source}")]
+pub struct BindError {
+    address: BindAddress,
+    source: io::Error,
+}
+
+#[derive(Debug)]
+pub enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(unix::UnixSocketListener),
+}
+
+pub enum Connection {
+    Tcp(TcpStream, SocketAddr),
+    #[cfg(unix)]
+    Unix(tokio::net::UnixStream),
+}
+
+impl Listener {
+    pub async fn accept(&self) -> io::Result<Connection> {
+        match self {
+            Self::Tcp(listener) => {
+                let (socket, address) = listener.accept().await?;
+                Ok(Connection::Tcp(socket, address))
+            }
+            #[cfg(unix)]
+            Self::Unix(listener) => listener.accept().await.map(Connection::Unix),
+        }
+    }
+}
+
+/// Binds a listener for every address, in order.
+///
+/// An IPv6 wildcard address also accepts IPv4 clients,
+/// unless an IPv4 address is listed with the same port.
+/// Unix domain sockets are created with the given permissions.
+pub async fn bind_listeners(
+    addresses: &[BindAddress],
+    unix_socket_permissions: u32,
+) -> Result<Vec<Listener>, BindError> {
+    let mut resolved = Vec::with_capacity(addresses.len());
+    for address in addresses {
+        let socket_addresses: Vec<SocketAddr> = match address {
+            BindAddress::Tcp(server_address) => lookup_host(server_address.tuple())
+                .await
+                .map_err(|source| BindError {
+                    address: address.clone(),
+                    source,
+                })?
+                .collect(),
+            BindAddress::Unix(_) => Vec::new(),
+        };
+        resolved.push((address, socket_addresses));
+    }
+
+    let ipv4_ports: HashSet<u16> = resolved
+        .iter()
+        .flat_map(|(_, socket_addresses)| socket_addresses)
+        .filter(|socket_address| socket_address.is_ipv4())
+        .map(SocketAddr::port)
+        .collect();
+
+    resolved
+        .into_iter()
+        .map(|(address, socket_addresses)| {
+            let listener = match address {
+                BindAddress::Tcp(_) => {
+                    bind_first(&socket_addresses, &ipv4_ports).map(Listener::Tcp)
+                }
+                BindAddress::Unix(path) => bind_unix(path, unix_socket_permissions),
+            };
+            listener.map_err(|source| BindError {
+                address: address.clone(),
+                source,
+            })
+        })
+        .collect()
+}
+
+/// Binds the first address that can be bound, like [`TcpListener::bind`].
+fn bind_first(
+    socket_addresses: &[SocketAddr],
+    ipv4_ports: &HashSet<u16>,
+) -> io::Result<TcpListener> {
+    let mut last_error = io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "could not resolve to any address",
+    );
+    for socket_address in socket_addresses {
+        let only_v6 = ipv4_ports.contains(&socket_address.port());
+        match bind(*socket_address, only_v6) {
+            Ok(listener) => return Ok(listener),
+            Err(err) => last_error = err,
+        }
+    }
+    Err(last_error)
+}
+
+fn bind(socket_address: SocketAddr, only_v6: bool) -> io::Result<TcpListener> {
+    let socket = Socket::new(
+        Domain::for_address(socket_address),
+        Type::STREAM,
+        Some(Protocol::TCP),
+    )?;
+    if socket_address.is_ipv6() {
+        socket.set_only_v6(only_v6)?;
+    }
+    // Allows restarting right away while the previous connections are in TIME_WAIT
+    #[cfg(unix)]
+    socket.set_reuse_address(true)?;
+    socket.bind(&socket_address.into())?;
+    socket.listen(BACKLOG)?;
+    socket.set_nonblocking(true)?;
+    TcpListener::from_std(socket.into())
+}
+
+#[cfg(unix)]
+fn bind_unix(path: &std::path::Path, permissions: u32) -> io::Result<Listener> {
+    unix::UnixSocketListener::bind(path, permissions).map(Listener::Unix)
+}
+
+#[cfg(not(unix))]
+fn bind_unix(_path: &std::path::Path, _permissions: u32) -> io::Result<Listener> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Unix domain sockets are not supported on this platform",
+    ))
+}
+
+#[cfg(unix)]
+mod unix {
+    use std::fs::Permissions;
+    use std::io;
+    use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+    use std::path::{Path, PathBuf};
+    use tokio::net::{UnixListener, UnixStream};
+
+    /// Listens on a Unix domain socket and removes the socket file once dropped.
+    #[derive(Debug)]
+    pub struct UnixSocketListener {
+        listener: UnixListener,
+        path: PathBuf,
+    }
+
+    impl UnixSocketListener {
+        pub fn bind(path: &Path, permissions: u32) -> io::Result<Self> {
+            remove_stale_socket(path)?;
+            let listener = UnixListener::bind(path)?;
+            let unix_socket_listener = Self {
+                listener,
+                path: path.to_path_buf(),
+            };
+            std::fs::set_permissions(path, Permissions::from_mode(permissions))?;
+            Ok(unix_socket_listener)
+        }
+
+        pub async fn accept(&self) -> io::Result<UnixStream> {
+            self.listener.accept().await.map(|(socket, _)| socket)
+        }
+    }
+
+    impl Drop for UnixSocketListener {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+
+    /// Removes the socket left behind by a server that did not stop cleanly,
+    /// a socket that still accepts connections belongs to a running server.
+    fn remove_stale_socket(path: &Path) -> io::Result<()> {
+        let metadata = match std::fs::symlink_metadata(path) {
+            Ok(metadata) => metadata,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(err),
+        };
+        if !metadata.file_type().is_socket() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "the path already exists and is not a socket",
+            ));
+        }
+        match std::os::unix::net::UnixStream::connect(path) {
+            Ok(_) => Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                "another server is listening on this socket",
+            )),
+            Err(err) if err.kind() == io::ErrorKind::ConnectionRefused => {
+                std::fs::remove_file(path)
+            }
+            Err(err) => Err(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tcp(bind: &str) -> BindAddress {
+        BindAddress::Tcp(ServerAddress::parse(bind).unwrap())
+    }
+
+    #[test]
+    fn test_parse_unix_address() {
+        // Given
+        let bind = "unix:/run/pico_limbo.sock";
+
+        // When
+        let address = BindAddress::parse(bind).unwrap();
+
+        // Then
+        assert!(
+            matches!(&address, BindAddress::Unix(path) if path == &PathBuf::from("/run/pico_limbo.sock"))
+        );
+        assert_eq!(address.to_string(), bind);
+        assert!(BindAddress::parse("unix:").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_binds_every_address() {
+        // Given
+        let addresses = vec![tcp("127.0.0.1:0"), tcp("127.0.0.1:0")];
+
+        // When
+        let listeners = bind_listeners(&addresses, 0o660).await.unwrap();
+
+        // Then
+        let ports: Vec<u16> = listeners
+            .iter()
+            .map(|listener| match listener {
+                Listener::Tcp(listener) => listener.local_addr().unwrap().port(),
+                #[cfg(unix)]
+                Listener::Unix(_) => unreachable!(),
+            })
+            .collect();
+        assert_eq!(ports.len(), 2);
+        assert_ne!(ports[0], ports[1]);
+    }
+
+    #[tokio::test]
+    async fn test_reports_the_address_in_use() {
+        // Given
+        let taken = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = taken.local_addr().unwrap().port();
+        let addresses = vec![tcp("127.0.0.1:0"), tcp(&format!("127.0.0.1:{port}"))];
+
+        // When
+        let result = bind_listeners(&addresses, 0o660).await;
+
+        // Then
+        let err = result.unwrap_err();
+        assert_eq!(err.address.to_string(), format!("127.0.0.1:{port}"));
+        assert_eq!(err.source.kind(), io::ErrorKind::AddrInUse);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_unix_socket_replaces_stale_socket() {
+        use std::os::unix::fs::PermissionsExt;
+
+        // Given
+        let path = std::env::temp_dir().join(format!("pico_limbo_{}.sock", std::process::id()));
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+        let addresses = vec![BindAddress::Unix(path.clone())];
+
+        // When
+        let listeners = bind_listeners(&addresses, 0o600).await.unwrap();
+        let client = tokio::net::UnixStream::connect(&path).await;
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        let in_use = bind_listeners(&addresses, 0o600).await;
+        drop(listeners);
+
+        // Then
+        assert!(client.is_ok());
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(in_use.unwrap_err().source.kind(), io::ErrorKind::AddrInUse);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,464 @@
+use crate::server::player_registry::PlayerRegistry;
+use crate::server::server_address::ServerAddress;
+use minecraft_protocol::prelude::State;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::timeout;
+use tracing::{debug, error, info};
+
+/// Upper bounds of the keep alive latency histogram buckets, in seconds.
+const LATENCY_BUCKETS: [f64; 10] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0];
+const STATES: [State; 6] = [
+    State::Handshake,
+    State::Status,
+    State::Login,
+    State::Configuration,
+    State::Play,
+    State::Transfer,
+];
+/// How long a scraper has to send its request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+/// Request lines and headers longer than this are rejected.
+const MAX_REQUEST_LENGTH: usize = 8 * 1024;
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::default);
+
+/// Counters shared by every connection.
+pub fn metrics() -> &'static Metrics {
+    &METRICS
+}
+
+#[derive(Default)]
+pub struct Metrics {
+    connections_accepted: AtomicU64,
+    connections_rejected: LabeledCounter,
+    logins: LabeledCounter,
+    kicks: LabeledCounter,
+    received: Traffic,
+    sent: Traffic,
+    compression_input_bytes: AtomicU64,
+    compression_output_bytes: AtomicU64,
+    keep_alive_latency: Histogram,
+}
+
+impl Metrics {
+    pub fn connection_accepted(&self) {
+        self.connections_accepted.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn connection_rejected(&self, reason: &'static str) {
+        self.connections_rejected.increment(reason);
+    }
+
+    pub fn login(&self, forwarding: &'static str) {
+        self.logins.increment(forwarding);
+    }
+
+    pub fn kick(&self, reason: &'static str) {
+        self.kicks.increment(reason);
+    }
+
+    pub fn packet_received(&self, state: State, size: usize) {
+        self.received.record(state, size);
+    }
+
+    pub fn packet_sent(&self, state: State, size: usize) {
+        self.sent.record(state, size);
+    }
+
+    /// Records a packet sent while compression is enabled, with its size before
+    /// and after compression.
+    pub fn compressed_packet_sent(&self, size: usize, compressed_size: u64) {
+        self.compression_input_bytes
+            .fetch_add(size as u64, Ordering::Relaxed);
+        self.compression_output_bytes
+            .fetch_add(compressed_size, Ordering::Relaxed);
+    }
+
+    pub fn keep_alive_latency(&self, latency: Duration) {
+        self.keep_alive_latency.observe(latency);
+    }
+
+    /// Renders every metric in the Prometheus text format.
+    pub fn render(&self, players: &PlayerRegistry) -> String {
+        let mut output = String::new();
+        render_online_players(&mut output, players);
+
+        counter(
+            &mut output,
+            "pico_limbo_connections_accepted_total",
+            "Connections accepted.",
+            self.connections_accepted.load(Ordering::Relaxed),
+        );
+        self.connections_rejected.render(
+            &mut output,
+            "pico_limbo_connections_rejected_total",
+            "Connections rejected before any packet was processed, by reason.",
+            "reason",
+        );
+        self.logins.render(
+            &mut output,
+            "pico_limbo_logins_total",
+            "Successful logins by forwarding mode.",
+            "forwarding",
+        );
+        self.kicks.render(
+            &mut output,
+            "pico_limbo_kicks_total",
+            "Players kicked by reason.",
+            "reason",
+        );
+
+        header(
+            &mut output,
+            "pico_limbo_packets_total",
+            "counter",
+            "Packets by direction and state.",
+        );
+        self.received.render_packets(&mut output, "in");
+        self.sent.render_packets(&mut output, "out");
+        header(
+            &mut output,
+            "pico_limbo_packet_bytes_total",
+            "counter",
+            "Packet bytes before compression and encryption, by direction and state.",
+        );
+        self.received.render_bytes(&mut output, "in");
+        self.sent.render_bytes(&mut output, "out");
+
+        self.render_compression(&mut output);
+        self.keep_alive_latency.render(
+            &mut output,
+            "pico_limbo_keep_alive_latency_seconds",
+            "Round-trip latency measured with keep alive packets.",
+        );
+        output
+    }
+
+    fn render_compression(&self, output: &mut String) {
+        let input = self.compression_input_bytes.load(Ordering::Relaxed);
+        let compressed_output = self.compression_output_bytes.load(Ordering::Relaxed);
+        counter(
+            output,
+            "pico_limbo_compression_input_bytes_total",
+            "Bytes of the packets sent with compression enabled, before compression.",
+            input,
+        );
+        counter(
+            output,
+            "pico_limbo_compression_output_bytes_total",
+            "Bytes of the packets sent with compression enabled, as written on the wire.",
+            compressed_output,
+        );
+        if input > 0 {
+            header(
+                output,
+                "pico_limbo_compression_ratio",
+                "gauge",
+                "Bytes written on the wire for every byte sent with compression enabled.",
+            );
+            #[allow(clippy::cast_precision_loss)]
+            let ratio = compressed_output as f64 / input as f64;
+            let _ = writeln!(output, "pico_limbo_compression_ratio {ratio}");
+        }
+    }
+}
+
+fn render_online_players(output: &mut String, players: &PlayerRegistry) {
+    let mut online_players: BTreeMap<i32, (&'static str, u64)> = BTreeMap::new();
+    for player in players.all() {
+        let protocol_version = player.protocol_version();
+        online_players
+            .entry(protocol_version.version_number())
+            .or_insert_with(|| (protocol_version.humanize(), 0))
+            .1 += 1;
+    }
+    header(
+        output,
+        "pico_limbo_online_players",
+        "gauge",
+        "Players in game by protocol version.",
+    );
+    for (version, count) in online_players.values() {
+        let _ = writeln!(
+            output,
+            "pico_limbo_online_players{{version=\"{version}\"}} {count}"
+        );
+    }
+}
+
+fn counter(output: &mut String, name: &str, help: &str, value: u64) {
+    header(output, name, "counter", help);
+    let _ = writeln!(output, "{name} {value}");
+}
+
+fn header(output: &mut String, name: &str, metric_type: &str, help: &str) {
+    let _ = writeln!(output, "# HELP {name} {help}");
+    let _ = writeln!(output, "# TYPE {name} {metric_type}");
+}
+
+#[derive(Default)]
+struct LabeledCounter {
+    values: Mutex<BTreeMap<&'static str, u64>>,
+}
+
+impl LabeledCounter {
+    fn increment(&self, label: &'static str) {
+        *self.values().entry(label).or_default() += 1;
+    }
+
+    fn render(&self, output: &mut String, name: &str, help: &str, label_name: &str) {
+        header(output, name, "counter", help);
+        for (label, value) in self.values().iter() {
+            let _ = writeln!(output, "{name}{{{label_name}=\"{label}\"}} {value}");
+        }
+    }
+
+    fn values(&self) -> MutexGuard<'_, BTreeMap<&'static str, u64>> {
+        self.values.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Packets and bytes of a single direction, by state.
+#[derive(Default)]
+struct Traffic {
+    packets: [AtomicU64; STATES.len()],
+    bytes: [AtomicU64; STATES.len()],
+}
+
+impl Traffic {
+    fn record(&self, state: State, size: usize) {
+        let index = state_index(state);
+        self.packets[index].fetch_add(1, Ordering::Relaxed);
+        self.bytes[index].fetch_add(size as u64, Ordering::Relaxed);
+    }
+
+    fn render_packets(&self, output: &mut String, direction: &str) {
+        render_by_state(output, "pico_limbo_packets_total", direction, &self.packets);
+    }
+
+    fn render_bytes(&self, output: &mut String, direction: &str) {
+        render_by_state(
+            output,
+            "pico_limbo_packet_bytes_total",
+            direction,
+            &self.bytes,
+        );
+    }
+}
+
+fn render_by_state(output: &mut String, name: &str, direction: &str, values: &[AtomicU64]) {
+    for (state, value) in STATES.iter().zip(values) {
+        let value = value.load(Ordering::Relaxed);
+        if value > 0 {
+            let _ = writeln!(
+                output,
+                "{name}{{direction=\"{direction}\",state=\"{state}\"}} {value}"
+            );
+        }
+    }
+}
+
+const fn state_index(state: State) -> usize {
+    match state {
+        State::Handshake => 0,
+        State::Status => 1,
+        State::Login => 2,
+        State::Configuration => 3,
+        State::Play => 4,
+        State::Transfer => 5,
+    }
+}
+
+#[derive(Default)]
+struct Histogram {
+    /// Cumulative count of the observations below each bound of `LATENCY_BUCKETS`.
+    buckets: [AtomicU64; LATENCY_BUCKETS.len()],
+    count: AtomicU64,
+    sum_micros: AtomicU64,
+}
+
+impl Histogram {
+    fn observe(&self, value: Duration) {
+        let seconds = value.as_secs_f64();
+        for (bound, bucket) in LATENCY_BUCKETS.iter().zip(&self.buckets) {
+            if seconds <= *bound {
+                bucket.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.count.fetch_add(1, Ordering::Relaxed);
+        let micros = u64::try_from(value.as_micros()).unwrap_or(u64::MAX);
+        self.sum_micros.fetch_add(micros, Ordering::Relaxed);
+    }
+
+    fn render(&self, output: &mut String, name: &str, help: &str) {
+        header(output, name, "histogram", help);
+        for (bound, bucket) in LATENCY_BUCKETS.iter().zip(&self.buckets) {
+            let _ = writeln!(
+                output,
+                "{name}_bucket{{le=\"{bound}\"}} {}",
+                bucket.load(Ordering::Relaxed)
+            );
+        }
+        let count = self.count.load(Ordering::Relaxed);
+        let sum = Duration::from_micros(self.sum_micros.load(Ordering::Relaxed)).as_secs_f64();
+        let _ = writeln!(output, "{name}_bucket{{le=\"+Inf\"}} {count}");
+        let _ = writeln!(output, "{name}_sum {sum}");
+        let _ = writeln!(output, "{name}_count {count}");
+    }
+}
+
+/// Serves the metrics over HTTP until the task is aborted.
+pub async fn serve_metrics(bind: ServerAddress, players: Arc<PlayerRegistry>) {
+    let listener = match TcpListener::bind(&bind.tuple()).await {
+        Ok(listener) => listener,
+        Err(err) => {
+            error!("Failed to bind the metrics endpoint to {}: {}", bind, err);
+            return;
+        }
+    };
+    info!("Serving metrics on http://{}/metrics", bind);
+
+    loop {
+        match listener.accept().await {
+            Ok((socket, _)) => {
+                let players = Arc::clone(&players);
+                tokio::spawn(async move {
+                    if let Err(err) = respond(socket, &players).await {
+                        debug!("Failed to answer a metrics request: {}", err);
+                    }
+                });
+            }
+            Err(err) => {
+                error!("Failed to accept a metrics connection: {:?}", err);
+            }
+        }
+    }
+}
+
+async fn respond(socket: TcpStream, players: &PlayerRegistry) -> std::io::Result<()> {
+    let mut stream = BufReader::new(socket);
+    let Ok(request_line) = timeout(REQUEST_TIMEOUT, read_request(&mut stream)).await else {
+        return Ok(());
+    };
+    let request_line = request_line?;
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let path = parts.next().unwrap_or_default();
+    let path = path.split_once('?').map_or(path, |(path, _)| path);
+    let (status, body) = match (method, path) {
+        ("GET", "/metrics") => ("200 OK", metrics().render(players)),
+        ("GET", _) => ("404 Not Found", "Not Found\n".to_string()),
+        _ => ("405 Method Not Allowed", "Method Not Allowed\n".to_string()),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: text/plain; version=0.0.4; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// Reads the request line, then skips the headers.
+async fn read_request(stream: &mut BufReader<TcpStream>) -> std::io::Result<String> {
+    let mut request_line = String::new();
+    let mut line = String::new();
+    let mut length = 0;
+    loop {
+        line.clear();
+        let read = stream.read_line(&mut line).await?;
+        length += read;
+        if length > MAX_REQUEST_LENGTH {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "request too long",
+            ));
+        }
+        if read == 0 || line.trim_end().is_empty() {
+            return Ok(request_line);
+        }
+        if request_line.is_empty() {
+            request_line = line.trim_end().to_string();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use minecraft_protocol::prelude::{ProtocolVersion, Uuid};
+
+    #[test]
+    fn test_render_online_players_by_version() {
+        // Given
+        let metrics = Metrics::default();
+        let players = PlayerRegistry::new();
+        let _notch = players.register(Uuid::from_u128(1), "Notch", ProtocolVersion::V1_21_2);
+        let _jeb = players.register(Uuid::from_u128(2), "jeb_", ProtocolVersion::V1_21_2);
+        let _dinnerbone = players.register(Uuid::from_u128(3), "Dinnerbone", ProtocolVersion::V1_8);
+
+        // When
+        let output = metrics.render(&players);
+
+        // Then
+        assert!(output.contains("pico_limbo_online_players{version=\"1.8\"} 1\n"));
+        assert!(output.contains("pico_limbo_online_players{version=\"1.21.2\"} 2\n"));
+    }
+
+    #[test]
+    fn test_render_counters() {
+        // Given
+        let metrics = Metrics::default();
+        metrics.connection_accepted();
+        metrics.connection_rejected("rate_limited");
+        metrics.kick("timed_out");
+        metrics.kick("timed_out");
+        metrics.packet_received(State::Play, 10);
+        metrics.packet_sent(State::Login, 3);
+        metrics.packet_sent(State::Login, 5);
+        metrics.compressed_packet_sent(200, 50);
+
+        // When
+        let output = metrics.render(&PlayerRegistry::new());
+
+        // Then
+        assert!(output.contains("pico_limbo_connections_accepted_total 1\n"));
+        assert!(
+            output.contains("pico_limbo_connections_rejected_total{reason=\"rate_limited\"} 1\n")
+        );
+        assert!(output.contains("pico_limbo_kicks_total{reason=\"timed_out\"} 2\n"));
+        assert!(output.contains("pico_limbo_packets_total{direction=\"in\",state=\"play\"} 1\n"));
+        assert!(output.contains("pico_limbo_packets_total{direction=\"out\",state=\"login\"} 2\n"));
+        assert!(
+            output.contains("pico_limbo_packet_bytes_total{direction=\"out\",state=\"login\"} 8\n")
+        );
+        assert!(output.contains("pico_limbo_compression_ratio 0.25\n"));
+    }
+
+    #[test]
+    fn test_latency_histogram_is_cumulative() {
+        // Given
+        let metrics = Metrics::default();
+        metrics.keep_alive_latency(Duration::from_millis(20));
+        metrics.keep_alive_latency(Duration::from_millis(300));
+
+        // When
+        let output = metrics.render(&PlayerRegistry::new());
+
+        // Then
+        let name = "pico_limbo_keep_alive_latency_seconds";
+        assert!(output.contains(&format!("{name}_bucket{{le=\"0.01\"}} 0\n")));
+        assert!(output.contains(&format!("{name}_bucket{{le=\"0.025\"}} 1\n")));
+        assert!(output.contains(&format!("{name}_bucket{{le=\"0.5\"}} 2\n")));
+        assert!(output.contains(&format!("{name}_bucket{{le=\"+Inf\"}} 2\n")));
+        assert!(output.contains(&format!("{name}_sum 0.32\n")));
+        assert!(output.contains(&format!("{name}_count 2\n")));
+    }
+}
@@ -1,12 +1,32 @@
+mod admin;
+mod announcer;
+mod backend_watcher;
 pub mod batch;
 mod client_data;
 pub mod client_state;
+#[cfg(test)]
+mod conformance;
+mod connection_limiter;
+mod console;
 mod controllable_interval;
 pub mod game_mode;
 pub mod game_profile;
+mod join_queue;
+mod legacy_handshake;
+mod listener;
+pub mod metrics;
 pub mod network;
 pub mod packet_handler;
 pub mod packet_registry;
+mod player_registry;
+mod proxy_protocol;
+mod query;
+mod rcon;
+mod recording;
+pub mod reload;
+pub mod replay;
 pub mod server_address;
+mod server_ping;
+mod session_timeouts;
 mod shutdown_signal;
 pub mod start_server;
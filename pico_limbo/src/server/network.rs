@@ -1,69 +1,162 @@
+use crate::handlers::configuration::send_reloaded_packets;
+use crate::handlers::login::login_start::fire_login_success;
+use crate::handlers::login::session_server::has_joined;
+use crate::handlers::play::commands::execute_command;
+use crate::kick_messages::{
+    AUTHENTICATION_SERVERS_DOWN_KICK_MESSAGE, CONNECTION_THROTTLED_KICK_MESSAGE,
+    FAILED_TO_VERIFY_USERNAME_KICK_MESSAGE, TIMED_OUT_KICK_MESSAGE,
+    TOO_MANY_CONNECTIONS_KICK_MESSAGE, kick_reason,
+};
 use crate::server::batch::{Batch, BatchItem};
 use crate::server::client_data::ClientData;
+use crate::server::client_state::PendingAuthentication;
+use crate::server::connection_limiter::{ConnectionLimiter, ConnectionPermit, RejectReason};
+use crate::server::legacy_handshake::LegacyRequest;
+#[cfg(unix)]
+use crate::server::listener::UNIX_PEER_ADDRESS;
+use crate::server::listener::{
+    BindAddress, BindError, Connection, Listener, Transport, bind_listeners,
+};
+use crate::server::metrics::metrics;
 use crate::server::packet_handler::{PacketHandler, PacketHandlerError};
 use crate::server::packet_registry::{
     PacketRegistry, PacketRegistryDecodeError, PacketRegistryEncodeError,
 };
-use crate::server::server_address::ServerAddress;
+use crate::server::player_registry::{Outbound, PlayerRegistration, PlayerRegistry};
+use crate::server::proxy_protocol::{ProxyProtocolError, read_proxy_header};
+use crate::server::recording::ConnectionRecorder;
+use crate::server::reload::ConfigReloader;
+use crate::server::session_timeouts::check_session_timeouts;
 use crate::server::shutdown_signal::shutdown_signal;
-use crate::server_state::ServerState;
+use crate::server_state::{ReloadedSections, ServerState};
 use futures::StreamExt;
+use minecraft_packets::handshaking::handshake_packet::HandshakePacket;
 use minecraft_packets::login::login_disconnect_packet::LoginDisconnectPacket;
 use minecraft_packets::play::client_bound_keep_alive_packet::ClientBoundKeepAlivePacket;
 use minecraft_packets::play::disconnect_packet::DisconnectPacket;
-use minecraft_protocol::prelude::State;
-use net::packet_stream::PacketStreamError;
+use minecraft_protocol::prelude::{BinaryReader, DecodePacket, ProtocolVersion, State};
+use net::packet_stream::{PacketStream, PacketStreamError};
 use net::raw_packet::RawPacket;
+use pico_text_component::prelude::Component;
+use std::net::{IpAddr, SocketAddr};
 use std::num::TryFromIntError;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
-use tokio::net::{TcpListener, TcpStream};
-use tokio::sync::RwLock;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::{RwLock, broadcast};
+use tokio::time::timeout;
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, trace, warn};
 
+/// How long a trusted proxy has to send the PROXY protocol header.
+const PROXY_HEADER_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long a throttled client has to send its handshake before being dropped.
+const REJECTED_CONNECTION_TIMEOUT: Duration = Duration::from_secs(1);
+/// How long to wait for the byte following a lone `0xFE` before answering a Beta ping.
+const LEGACY_PING_GRACE: Duration = Duration::from_millis(200);
+const HANDSHAKE_PACKET_ID: u8 = 0x00;
+/// How often rejected connections are summarized in the logs.
+const REJECTION_REPORT_INTERVAL: Duration = Duration::from_mins(1);
+/// How many reloads a connection may fall behind before receiving every section again.
+const RELOAD_CHANNEL_CAPACITY: usize = 4;
+/// How long the session server has to answer when the login timeout is disabled.
+const DEFAULT_SESSION_SERVER_TIMEOUT: Duration = Duration::from_secs(10);
+
 pub struct Server {
     state: Arc<RwLock<ServerState>>,
-    listen_address: ServerAddress,
+    listen_addresses: Vec<BindAddress>,
+    unix_socket_permissions: u32,
+    connection_limiter: Arc<ConnectionLimiter>,
+    reloads: broadcast::Sender<ReloadedSections>,
+    players: Arc<PlayerRegistry>,
+    stop: CancellationToken,
 }
 
 impl Server {
-    pub fn new(listen_address: &ServerAddress, state: ServerState) -> Self {
+    pub fn new(
+        listen_addresses: Vec<BindAddress>,
+        unix_socket_permissions: u32,
+        state: ServerState,
+    ) -> Self {
+        let (reloads, _) = broadcast::channel(RELOAD_CHANNEL_CAPACITY);
         Self {
             state: Arc::new(RwLock::new(state)),
-            listen_address: listen_address.clone(),
+            listen_addresses,
+            unix_socket_permissions,
+            connection_limiter: ConnectionLimiter::new(),
+            reloads,
+            players: PlayerRegistry::new(),
+            stop: CancellationToken::new(),
         }
     }
 
-    pub async fn run(self, cancellation_token: Option<&CancellationToken>) {
-        let listener = match TcpListener::bind(&self.listen_address.tuple()).await {
-            Ok(sock) => sock,
-            Err(err) => {
-                error!("Failed to bind to {}: {}", self.listen_address, err);
-                std::process::exit(1);
-            }
-        };
+    pub fn state(&self) -> Arc<RwLock<ServerState>> {
+        Arc::clone(&self.state)
+    }
+
+    pub fn players(&self) -> Arc<PlayerRegistry> {
+        Arc::clone(&self.players)
+    }
+
+    /// Token stopping the server once cancelled.
+    pub fn stop_token(&self) -> CancellationToken {
+        self.stop.clone()
+    }
+
+    /// Creates a reloader that swaps the state of this server and updates its players.
+    pub fn reloader(&self, config_path: PathBuf, watch_config_file: bool) -> Arc<ConfigReloader> {
+        ConfigReloader::new(
+            config_path,
+            Arc::clone(&self.state),
+            self.reloads.clone(),
+            watch_config_file,
+        )
+    }
+
+    /// Binds every address before accepting connections, nothing is accepted if one fails.
+    pub async fn run(
+        self,
+        cancellation_token: Option<&CancellationToken>,
+    ) -> Result<(), BindError> {
+        let listeners =
+            bind_listeners(&self.listen_addresses, self.unix_socket_permissions).await?;
+        for listen_address in &self.listen_addresses {
+            info!("Listening on: {}", listen_address);
+        }
+
+        let rejection_report = tokio::spawn(report_rejected_connections(
+            Arc::clone(&self.connection_limiter),
+            Arc::clone(&self.state),
+        ));
+        self.accept(&listeners, cancellation_token).await;
+        rejection_report.abort();
+        Ok(())
+    }
 
-        info!("Listening on: {}", self.listen_address);
-        self.accept(&listener, cancellation_token).await;
+    fn spawn_connection<S: Transport>(&self, socket: S, addr: SocketAddr) {
+        let state_clone = Arc::clone(&self.state);
+        let limiter_clone = Arc::clone(&self.connection_limiter);
+        let reloads = self.reloads.subscribe();
+        let players = Arc::clone(&self.players);
+        tokio::spawn(async move {
+            handle_connection(socket, addr, state_clone, limiter_clone, reloads, players).await;
+        });
     }
 
     pub async fn accept(
         self,
-        listener: &TcpListener,
+        listeners: &[Listener],
         cancellation_token: Option<&CancellationToken>,
     ) {
         loop {
             tokio::select! {
-                 accept_result = listener.accept() => {
+                 accept_result = accept_any(listeners) => {
                     match accept_result {
-                        Ok((socket, addr)) => {
-                            debug!("Accepted connection from {}", addr);
-                        let state_clone = Arc::clone(&self.state);
-                            tokio::spawn(async move {
-                                handle_client(socket, state_clone).await;
-                            });
-                        }
+                        Ok(Connection::Tcp(socket, addr)) => self.spawn_connection(socket, addr),
+                        #[cfg(unix)]
+                        Ok(Connection::Unix(socket)) => self.spawn_connection(socket, UNIX_PEER_ADDRESS),
                         Err(e) => {
                             error!("Failed to accept a connection: {:?}", e);
                         }
@@ -74,11 +167,21 @@ impl Server {
                     info!("Shutdown signal received, shutting down gracefully.");
                     break;
                 }
+
+                 () = self.stop.cancelled() => {
+                    break;
+                }
             }
         }
     }
 }
 
+/// Accepts the next connection of whichever listener receives one first.
+async fn accept_any(listeners: &[Listener]) -> std::io::Result<Connection> {
+    let accepts = listeners.iter().map(|listener| Box::pin(listener.accept()));
+    futures::future::select_all(accepts).await.0
+}
+
 #[derive(Debug, Error)]
 pub enum PacketProcessingError {
     #[error("Client disconnected")]
@@ -144,8 +247,8 @@ impl From<PacketStreamError> for PacketProcessingError {
     }
 }
 
-async fn process_packet(
-    client_data: &ClientData,
+async fn process_packet<S: Transport>(
+    client_data: &ClientData<S>,
     server_state: &Arc<RwLock<ServerState>>,
     raw_packet: RawPacket,
     was_in_play_state: &mut bool,
@@ -157,6 +260,7 @@ async fn process_packet(
             client_state.serverbound_state(),
         )
     };
+    metrics().packet_received(state, raw_packet.size());
     let decoded_packet = PacketRegistry::decode_packet(protocol_version, state, raw_packet)?;
     trace!(
         packet_name = decoded_packet.packet_name(),
@@ -179,19 +283,54 @@ async fn process_packet(
 
     if !*was_in_play_state && state == State::Play {
         *was_in_play_state = true;
-        server_state.write().await.increment();
-        let username = {
+        let (username, remote_address) = {
             let client_state = client_data.client().await;
-            client_state.get_username()
+            (client_state.get_username(), client_state.remote_address())
         };
+        let origin = remote_address
+            .map(|remote_address| format!(" from {remote_address}"))
+            .unwrap_or_default();
         debug!(
-            "{} joined using version {}",
+            "{}{} joined using version {}",
             username,
+            origin,
             protocol_version.humanize()
         );
         info!("{} joined the game", username);
     }
 
+    send_batch(client_data, server_state, batch, protocol_version).await?;
+
+    let pending_authentication = client_data.client().await.take_pending_authentication();
+    if let Some(pending_authentication) = pending_authentication {
+        authenticate(client_data, server_state, pending_authentication).await?;
+    }
+
+    let should_kick = {
+        let client_state = client_data.client().await;
+        client_state
+            .should_kick()
+            .map(|kick_message| (kick_message, client_state.kick_reason()))
+    };
+
+    if let Some((kick_message, reason)) = should_kick {
+        kick_client(client_data, &kick_message, reason)
+            .await
+            .map_err(|_| PacketProcessingError::Disconnected)?;
+        return Err(PacketProcessingError::Disconnected);
+    }
+
+    client_data.enable_keep_alive_if_needed().await;
+
+    Ok(())
+}
+
+async fn send_batch<S: Transport>(
+    client_data: &ClientData<S>,
+    server_state: &Arc<RwLock<ServerState>>,
+    batch: Batch,
+    protocol_version: ProtocolVersion,
+) -> Result<(), PacketProcessingError> {
     let mut stream = batch.into_stream();
     while let Some(pending_packet) = stream.next().await {
         match pending_packet {
@@ -217,53 +356,390 @@ async fn process_packet(
                     warn!("compression enabled but no settings were found");
                 }
             }
+            BatchItem::EnableEncryption(shared_secret) => {
+                trace!("Enabling encryption");
+                let mut packet_stream = client_data.stream().await;
+                packet_stream.set_encryption(&shared_secret)?;
+            }
         }
     }
+    Ok(())
+}
 
-    let should_kick = {
-        let client_state = client_data.client().await;
-        client_state.should_kick()
-    };
+/// Confirms the identity of a player with the session server once the
+/// encryption handshake is done, then finishes the login.
+async fn authenticate<S: Transport>(
+    client_data: &ClientData<S>,
+    server_state: &Arc<RwLock<ServerState>>,
+    pending_authentication: PendingAuthentication,
+) -> Result<(), PacketProcessingError> {
+    let has_joined_url = server_state
+        .read()
+        .await
+        .online_mode()
+        .map(|online_mode| online_mode.has_joined_url().to_string())
+        .ok_or_else(|| PacketProcessingError::Custom("Online mode is disabled".to_string()))?;
+    let session_server_timeout = server_state
+        .read()
+        .await
+        .timeouts()
+        .login
+        .unwrap_or(DEFAULT_SESSION_SERVER_TIMEOUT);
 
-    if let Some(reason) = should_kick {
-        kick_client(client_data, reason.clone())
-            .await
-            .map_err(|_| PacketProcessingError::Disconnected)?;
-        return Err(PacketProcessingError::Disconnected);
-    }
+    let result = has_joined(
+        &has_joined_url,
+        &pending_authentication.username,
+        &pending_authentication.server_hash,
+        session_server_timeout,
+    )
+    .await;
 
-    client_data.enable_keep_alive_if_needed().await;
+    let (batch, protocol_version) = {
+        let server_state_guard = server_state.read().await;
+        let mut client_state = client_data.client().await;
+        let mut batch = Batch::new();
+        match result {
+            Ok(Some(game_profile)) => {
+                fire_login_success(
+                    &mut batch,
+                    &mut client_state,
+                    &server_state_guard,
+                    game_profile,
+                )?;
+            }
+            Ok(None) => {
+                info!(
+                    "{} failed to authenticate with the session server",
+                    pending_authentication.username
+                );
+                client_state.kick(FAILED_TO_VERIFY_USERNAME_KICK_MESSAGE);
+            }
+            Err(err) => {
+                error!(
+                    "Could not verify {} with the session server: {}",
+                    pending_authentication.username, err
+                );
+                client_state.kick(AUTHENTICATION_SERVERS_DOWN_KICK_MESSAGE);
+            }
+        }
+        (batch, client_state.protocol_version())
+    };
 
-    Ok(())
+    send_batch(client_data, server_state, batch, protocol_version).await
 }
 
-async fn read(
-    client_data: &ClientData,
+async fn read<S: Transport>(
+    client_data: &ClientData<S>,
     server_state: &Arc<RwLock<ServerState>>,
     was_in_play_state: &mut bool,
 ) -> Result<(), PacketProcessingError> {
+    // Every event is cancel safe, a packet received halfway is read entirely by the next call
     tokio::select! {
         result = client_data.read_packet() => {
             let raw_packet = result?;
-            process_packet(client_data, server_state, raw_packet, was_in_play_state).await?;
+            client_data.record_received(&raw_packet).await;
+            let result = process_packet(client_data, server_state, raw_packet, was_in_play_state).await;
+            client_data.refresh_timeout().await;
+            result?;
         }
         () = client_data.keep_alive_tick() => {
-            send_keep_alive(client_data).await?;
+            send_keep_alive(client_data, server_state).await?;
+        }
+        outbound = client_data.next_outbound() => {
+            send_outbound(client_data, server_state, outbound).await?;
+        }
+        sections = client_data.next_reload() => {
+            send_reloaded_sections(client_data, server_state, sections).await?;
+        }
+        () = client_data.session_tick() => {
+            send_session_timeouts(client_data, server_state).await?;
+        }
+        () = client_data.timeout_tick() => {
+            let state = client_data.client().await.serverbound_state();
+            debug!("Client timed out in {} state", state);
+            kick_client(
+                client_data,
+                &Component::new(TIMED_OUT_KICK_MESSAGE),
+                kick_reason(TIMED_OUT_KICK_MESSAGE),
+            )
+            .await?;
+            return Err(PacketProcessingError::Disconnected);
+        }
+    }
+    Ok(())
+}
+
+pub(super) async fn handle_connection<S: Transport>(
+    mut socket: S,
+    peer_address: SocketAddr,
+    server_state: Arc<RwLock<ServerState>>,
+    connection_limiter: Arc<ConnectionLimiter>,
+    reloads: broadcast::Receiver<ReloadedSections>,
+    players: Arc<PlayerRegistry>,
+) {
+    let remote_address =
+        match resolve_remote_address(&mut socket, peer_address, &server_state).await {
+            Ok(remote_address) => remote_address,
+            Err(err) => {
+                debug!("Rejected connection from {}: {}", peer_address, err);
+                metrics().connection_rejected("proxy_protocol");
+                return;
+            }
+        };
+
+    let connection_limits = server_state.read().await.connection_limits().copied();
+    let permit = connection_limits
+        .map(|limits| connection_limiter.try_acquire(limited_ip(remote_address), &limits))
+        .transpose();
+    match permit {
+        Ok(permit) => {
+            debug!("Accepted connection from {}", remote_address);
+            metrics().connection_accepted();
+            handle_client(
+                socket,
+                remote_address,
+                server_state,
+                permit,
+                reloads,
+                players,
+            )
+            .await;
+        }
+        Err(reason) => {
+            debug!("Rejected connection from {}: {}", remote_address, reason);
+            metrics().connection_rejected(reason.name());
+            if reason != RejectReason::TooManyPendingConnections {
+                reject_client(socket, reason).await;
+            }
+        }
+    }
+}
+
+/// Reads the handshake of a throttled client to kick it with a message
+/// if it is trying to log in, any other connection is simply closed.
+async fn reject_client<S: Transport>(socket: S, reason: RejectReason) {
+    let kick_message = match reason {
+        RejectReason::RateLimited => CONNECTION_THROTTLED_KICK_MESSAGE,
+        RejectReason::TooManyConnections | RejectReason::TooManyPendingConnections => {
+            TOO_MANY_CONNECTIONS_KICK_MESSAGE
         }
+    };
+    let mut stream = PacketStream::new(socket);
+    let _ = timeout(
+        REJECTED_CONNECTION_TIMEOUT,
+        kick_rejected_login(&mut stream, kick_message),
+    )
+    .await;
+    let _ = stream.get_stream().shutdown().await;
+}
+
+async fn kick_rejected_login<S: Transport>(
+    stream: &mut PacketStream<S>,
+    kick_message: &str,
+) -> Result<(), PacketProcessingError> {
+    let raw_packet = stream.read_packet().await?;
+    if raw_packet.packet_id() != Some(HANDSHAKE_PACKET_ID) {
+        return Ok(());
+    }
+    let handshake = HandshakePacket::decode(
+        &mut BinaryReader::new(raw_packet.data()),
+        ProtocolVersion::Any,
+    )
+    .map_err(|err| PacketProcessingError::Custom(err.to_string()))?;
+    // 2 is a login and 3 a transfer, both log in
+    if !matches!(handshake.next_state.inner(), 2 | 3) {
+        return Ok(());
     }
+
+    let protocol_version = ProtocolVersion::try_from(handshake.protocol.inner())
+        .map_err(|_| PacketProcessingError::Custom("Unsupported protocol version".to_string()))?;
+    let packet = PacketRegistry::LoginDisconnect(LoginDisconnectPacket::text(kick_message));
+    let raw_packet = packet.encode_packet(protocol_version)?;
+    metrics().kick(kick_reason(kick_message));
+    stream.write_packet(raw_packet).await?;
     Ok(())
 }
 
-async fn handle_client(socket: TcpStream, server_state: Arc<RwLock<ServerState>>) {
-    let keep_alive_interval = server_state.read().await.keep_alive_interval();
-    let client_data = ClientData::new(socket, keep_alive_interval);
+async fn report_rejected_connections(
+    connection_limiter: Arc<ConnectionLimiter>,
+    server_state: Arc<RwLock<ServerState>>,
+) {
+    let mut interval = tokio::time::interval(REJECTION_REPORT_INTERVAL);
+    loop {
+        interval.tick().await;
+
+        let rejections = connection_limiter.take_rejections();
+        let total: u64 = rejections.iter().map(|(_, count)| count).sum();
+        if total > 0 {
+            let details = rejections
+                .iter()
+                .filter(|(_, count)| *count > 0)
+                .map(|(reason, count)| format!("{count} {reason}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            warn!("Rejected {total} connections in the last minute ({details})");
+        }
+
+        if let Some(limits) = server_state.read().await.connection_limits() {
+            connection_limiter.remove_expired(limits.window);
+        }
+    }
+}
+
+/// Clients of a Unix domain socket share a single address, so they are not limited per address.
+fn limited_ip(remote_address: SocketAddr) -> Option<IpAddr> {
+    #[cfg(unix)]
+    if remote_address == UNIX_PEER_ADDRESS {
+        return None;
+    }
+    Some(remote_address.ip())
+}
+
+/// Reads the PROXY protocol header when the peer is a trusted proxy,
+/// otherwise the peer address is the address of the player.
+async fn resolve_remote_address<S: Transport>(
+    socket: &mut S,
+    peer_address: SocketAddr,
+    server_state: &Arc<RwLock<ServerState>>,
+) -> Result<SocketAddr, ProxyProtocolError> {
+    let is_trusted_proxy = server_state
+        .read()
+        .await
+        .proxy_protocol()
+        .is_some_and(|proxy_protocol| proxy_protocol.is_trusted(peer_address.ip()));
+
+    if !is_trusted_proxy {
+        return Ok(peer_address);
+    }
+
+    let header = timeout(PROXY_HEADER_TIMEOUT, read_proxy_header(socket))
+        .await
+        .map_err(|_| ProxyProtocolError::MissingHeader)??;
+    if let Some(remote_address) = header {
+        trace!("{} is proxying {}", peer_address, remote_address);
+    }
+    Ok(header.unwrap_or(peer_address))
+}
+
+/// Answers clients older than 1.7. Returns `None` once the connection was handled,
+/// or the bytes read ahead that the packet stream must start with.
+async fn handle_legacy_request<S: Transport>(
+    stream: &mut BufReader<S>,
+    remote_address: SocketAddr,
+    server_state: &Arc<RwLock<ServerState>>,
+) -> Option<Vec<u8>> {
+    let handshake_timeout = server_state.read().await.timeouts().handshake;
+    let bytes = match handshake_timeout {
+        Some(handshake_timeout) => {
+            let Ok(bytes) = timeout(handshake_timeout, stream.fill_buf()).await else {
+                debug!(
+                    "Client {} timed out before sending the handshake",
+                    remote_address
+                );
+                let _ = stream.shutdown().await;
+                return None;
+            };
+            bytes
+        }
+        None => stream.fill_buf().await,
+    };
+    let Ok(bytes) = bytes else {
+        return Some(Vec::new());
+    };
+
+    let mut received_bytes = Vec::new();
+    let legacy_request = if LegacyRequest::needs_more_bytes(bytes) {
+        // The rest may still be in flight, silence or EOF means a Beta ping
+        received_bytes.extend_from_slice(bytes);
+        let length = bytes.len();
+        stream.consume(length);
+        if let Ok(Ok(bytes)) = timeout(LEGACY_PING_GRACE, stream.fill_buf()).await {
+            received_bytes.extend_from_slice(bytes);
+            let length = bytes.len();
+            stream.consume(length);
+        }
+        LegacyRequest::detect(&received_bytes)
+    } else {
+        LegacyRequest::detect(bytes)
+    };
+    let Some(legacy_request) = legacy_request else {
+        return Some(received_bytes);
+    };
+
+    debug!(
+        "Received legacy {:?} from {}",
+        legacy_request, remote_address
+    );
+    let response = legacy_request.response(&*server_state.read().await);
+    if let Some(response) = response {
+        let _ = stream.write_all(&response).await;
+    }
+    let _ = stream.shutdown().await;
+    None
+}
+
+async fn handle_client<S: Transport>(
+    socket: S,
+    remote_address: SocketAddr,
+    server_state: Arc<RwLock<ServerState>>,
+    mut permit: Option<ConnectionPermit>,
+    reloads: broadcast::Receiver<ReloadedSections>,
+    players: Arc<PlayerRegistry>,
+) {
+    let mut stream = BufReader::new(socket);
+    let Some(received_bytes) =
+        handle_legacy_request(&mut stream, remote_address, &server_state).await
+    else {
+        return;
+    };
+
+    let (keep_alive_interval, timeouts, recording_directory) = {
+        let server_state = server_state.read().await;
+        (
+            server_state.keep_alive_interval(),
+            server_state.timeouts(),
+            server_state.recording_directory().map(Path::to_path_buf),
+        )
+    };
+    let client_data = ClientData::new(
+        stream,
+        received_bytes,
+        remote_address,
+        keep_alive_interval,
+        timeouts,
+        reloads,
+    );
+    if let Some(recording_directory) = recording_directory {
+        match ConnectionRecorder::create(&recording_directory, remote_address) {
+            Ok(recorder) => client_data.enable_recording(recorder),
+            Err(err) => warn!("Failed to record the connection of {remote_address}: {err}"),
+        }
+    }
+    client_data.refresh_timeout().await;
     let mut was_in_play_state = false;
+    let mut registration: Option<PlayerRegistration> = None;
 
     loop {
-        match read(&client_data, &server_state, &mut was_in_play_state).await {
+        let result = read(&client_data, &server_state, &mut was_in_play_state).await;
+        if was_in_play_state && let Some(permit) = permit.as_mut() {
+            permit.mark_joined();
+        }
+        if was_in_play_state && registration.is_none() {
+            registration = Some(register_player(&client_data, &server_state, &players).await);
+        }
+        match result {
             Ok(()) => {}
             Err(PacketProcessingError::Disconnected) => {
-                debug!("Client disconnected");
+                let latency = client_data.client().await.latency();
+                if let Some(latency) = latency {
+                    debug!(
+                        "Client {} disconnected, last latency was {} ms",
+                        remote_address,
+                        latency.as_millis()
+                    );
+                } else {
+                    debug!("Client {} disconnected", remote_address);
+                }
                 break;
             }
             Err(PacketProcessingError::Custom(e)) => {
@@ -280,15 +756,65 @@ async fn handle_client(socket: TcpStream, server_state: Arc<RwLock<ServerState>>
     let _ = client_data.shutdown().await;
 
     if was_in_play_state {
-        server_state.write().await.decrement();
         let username = client_data.client().await.get_username();
         info!("{} left the game", username);
     }
 }
 
-async fn kick_client(
-    client_data: &ClientData,
-    reason: String,
+async fn register_player<S: Transport>(
+    client_data: &ClientData<S>,
+    server_state: &Arc<RwLock<ServerState>>,
+    players: &Arc<PlayerRegistry>,
+) -> PlayerRegistration {
+    let (registration, receiver) = {
+        let client_state = client_data.client().await;
+        players.register(
+            client_state.get_unique_id(),
+            &client_state.get_username(),
+            client_state.protocol_version(),
+        )
+    };
+    client_data.set_outbound(receiver).await;
+    if server_state.read().await.has_session_timeouts() {
+        client_data.enable_session_checks().await;
+    }
+    registration
+}
+
+/// Sends a packet, a kick or a command queued for this player by another task.
+async fn send_outbound<S: Transport>(
+    client_data: &ClientData<S>,
+    server_state: &Arc<RwLock<ServerState>>,
+    outbound: Outbound,
+) -> Result<(), PacketProcessingError> {
+    match outbound {
+        Outbound::Packet(packet) => {
+            let protocol_version = client_data.protocol_version().await;
+            let mut batch = Batch::new();
+            batch.queue(|| *packet);
+            send_batch(client_data, server_state, batch, protocol_version).await
+        }
+        Outbound::Kick(kick_message, reason) => {
+            kick_client(client_data, &kick_message, reason).await?;
+            Err(PacketProcessingError::Disconnected)
+        }
+        Outbound::Command(command) => {
+            let (batch, protocol_version) = {
+                let server_state_guard = server_state.read().await;
+                let mut client_state = client_data.client().await;
+                let mut batch = Batch::new();
+                execute_command(&mut client_state, &server_state_guard, &command, &mut batch);
+                (batch, client_state.protocol_version())
+            };
+            send_batch(client_data, server_state, batch, protocol_version).await
+        }
+    }
+}
+
+async fn kick_client<S: Transport>(
+    client_data: &ClientData<S>,
+    kick_message: &Component,
+    reason: &'static str,
 ) -> Result<(), PacketProcessingError> {
     let (protocol_version, state) = {
         let state = client_data.client().await;
@@ -297,21 +823,22 @@ async fn kick_client(
     let packet = match state {
         State::Login => {
             debug!("Login disconnect");
-            PacketRegistry::LoginDisconnect(LoginDisconnectPacket::text(reason))
+            PacketRegistry::LoginDisconnect(LoginDisconnectPacket::component(kick_message))
         }
         State::Configuration => {
             debug!("Configuration disconnect");
-            PacketRegistry::ConfigurationDisconnect(DisconnectPacket::text(reason))
+            PacketRegistry::ConfigurationDisconnect(DisconnectPacket::component(kick_message))
         }
         State::Play => {
             debug!("Play disconnect");
-            PacketRegistry::PlayDisconnect(DisconnectPacket::text(reason))
+            PacketRegistry::PlayDisconnect(DisconnectPacket::component(kick_message))
         }
         _ => {
             debug!("A user was disconnected from a state where no packet can be sent");
             return Err(PacketProcessingError::Disconnected);
         }
     };
+    metrics().kick(reason);
     if let Ok(raw_packet) = packet.encode_packet(protocol_version) {
         client_data.write_packet(raw_packet).await?;
         client_data.shutdown().await?;
@@ -320,23 +847,89 @@ async fn kick_client(
     Ok(())
 }
 
-async fn send_keep_alive(client_data: &ClientData) -> Result<(), PacketProcessingError> {
+/// Pushes the tab list, boss bar and title of the reloaded configuration to a player in game.
+async fn send_reloaded_sections<S: Transport>(
+    client_data: &ClientData<S>,
+    server_state: &Arc<RwLock<ServerState>>,
+    sections: ReloadedSections,
+) -> Result<(), PacketProcessingError> {
+    let (batch, protocol_version) = {
+        let server_state_guard = server_state.read().await;
+        let mut client_state = client_data.client().await;
+        if client_state.clientbound_state() != State::Play {
+            return Ok(());
+        }
+        let mut batch = Batch::new();
+        send_reloaded_packets(&mut batch, &mut client_state, &server_state_guard, sections);
+        (batch, client_state.protocol_version())
+    };
+
+    send_batch(client_data, server_state, batch, protocol_version).await
+}
+
+/// Sends the AFK and session limit warnings, transfers or kicks.
+async fn send_session_timeouts<S: Transport>(
+    client_data: &ClientData<S>,
+    server_state: &Arc<RwLock<ServerState>>,
+) -> Result<(), PacketProcessingError> {
+    let (batch, protocol_version, should_kick) = {
+        let server_state_guard = server_state.read().await;
+        let mut client_state = client_data.client().await;
+        let batch = check_session_timeouts(
+            &mut client_state,
+            &server_state_guard,
+            std::time::Instant::now(),
+        );
+        let should_kick = client_state
+            .should_kick()
+            .map(|kick_message| (kick_message, client_state.kick_reason()));
+        (batch, client_state.protocol_version(), should_kick)
+    };
+
+    send_batch(client_data, server_state, batch, protocol_version).await?;
+    if let Some((kick_message, reason)) = should_kick {
+        kick_client(client_data, &kick_message, reason).await?;
+        return Err(PacketProcessingError::Disconnected);
+    }
+    Ok(())
+}
+
+async fn send_keep_alive<S: Transport>(
+    client_data: &ClientData<S>,
+    server_state: &Arc<RwLock<ServerState>>,
+) -> Result<(), PacketProcessingError> {
     let (protocol_version, state) = {
         let client = client_data.client().await;
         (client.protocol_version(), client.clientbound_state())
     };
 
+    let keep_alive = ClientBoundKeepAlivePacket::random()?;
+    let id = i64::from(keep_alive.id());
     let packet = match state {
         State::Configuration => Some(PacketRegistry::ConfigurationClientBoundKeepAlive(
-            ClientBoundKeepAlivePacket::random()?,
-        )),
-        State::Play => Some(PacketRegistry::ClientBoundKeepAlive(
-            ClientBoundKeepAlivePacket::random()?,
+            keep_alive,
         )),
+        State::Play => Some(PacketRegistry::ClientBoundKeepAlive(keep_alive)),
         _ => None,
     };
 
     if let Some(packet) = packet {
+        let max_missed_keep_alives = server_state.read().await.max_missed_keep_alives();
+        let missed_keep_alives = client_data
+            .client()
+            .await
+            .keep_alive_sent(id, std::time::Instant::now());
+        if max_missed_keep_alives > 0 && missed_keep_alives >= max_missed_keep_alives {
+            debug!("Client missed {} keep alive in a row", missed_keep_alives);
+            kick_client(
+                client_data,
+                &Component::new(TIMED_OUT_KICK_MESSAGE),
+                kick_reason(TIMED_OUT_KICK_MESSAGE),
+            )
+            .await?;
+            return Err(PacketProcessingError::Disconnected);
+        }
+
         trace!(?state, "sending keep alive");
         let raw_packet = packet.encode_packet(protocol_version)?;
         client_data.write_packet(raw_packet).await?;
@@ -344,3 +937,216 @@ async fn send_keep_alive(client_data: &ClientData) -> Result<(), PacketProcessin
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server_state::Timeouts;
+    use minecraft_packets::play::system_chat_message_packet::SystemChatMessagePacket;
+    use minecraft_protocol::prelude::{BinaryWriter, Direction, EncodePacket, VarInt};
+    use tokio::io::{AsyncReadExt, DuplexStream};
+    use tokio::sync::mpsc;
+
+    const UNKNOWN_PACKET_ID: u8 = 0x7F;
+
+    async fn client_in_play() -> (
+        ClientData<DuplexStream>,
+        DuplexStream,
+        mpsc::UnboundedSender<Outbound>,
+    ) {
+        let (client, server) = tokio::io::duplex(16 * 1024);
+        let (_, reloads) = broadcast::channel(RELOAD_CHANNEL_CAPACITY);
+        let client_data = ClientData::new(
+            BufReader::new(server),
+            Vec::new(),
+            SocketAddr::from(([127, 0, 0, 1], 25565)),
+            Duration::from_secs(10),
+            Timeouts::default(),
+            reloads,
+        );
+        {
+            let mut client_state = client_data.client().await;
+            client_state.set_protocol_version(ProtocolVersion::V1_21_4);
+            client_state.set_state(Direction::Clientbound, State::Play);
+            client_state.set_state(Direction::Serverbound, State::Play);
+        }
+        let (sender, receiver) = mpsc::unbounded_channel();
+        client_data.set_outbound(receiver).await;
+        (client_data, client, sender)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unix_peers_are_not_limited_per_address() {
+        // Given
+        let tcp_peer = SocketAddr::from(([127, 0, 0, 1], 25565));
+
+        // When
+        let tcp_ip = limited_ip(tcp_peer);
+        let unix_ip = limited_ip(UNIX_PEER_ADDRESS);
+
+        // Then
+        assert_eq!(tcp_ip, Some(tcp_peer.ip()));
+        assert_eq!(unix_ip, None);
+    }
+
+    fn login_handshake() -> RawPacket {
+        let handshake = HandshakePacket {
+            protocol: VarInt::from(ProtocolVersion::V1_21_4.version_number()),
+            hostname: "localhost".to_string(),
+            port: 25565,
+            next_state: VarInt::new(2),
+        };
+        let mut writer = BinaryWriter::new();
+        writer.write(&HANDSHAKE_PACKET_ID).unwrap();
+        handshake.encode(&mut writer, ProtocolVersion::Any).unwrap();
+        RawPacket::new(writer.into_inner()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_rejected_login_is_kicked_then_closed() {
+        // Given
+        let (client, server) = tokio::io::duplex(16 * 1024);
+        let rejection = tokio::spawn(reject_client(server, RejectReason::RateLimited));
+        let mut client = PacketStream::new(client);
+
+        // When
+        client.write_packet(login_handshake()).await.unwrap();
+        let kick = client.read_packet().await.unwrap();
+        let closed = client.read_packet().await;
+
+        // Then
+        assert_eq!(kick.packet_id(), Some(0x00));
+        assert!(closed.is_err());
+        rejection.await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_silent_rejected_connection_is_closed_promptly() {
+        // Given
+        let (mut client, server) = tokio::io::duplex(16 * 1024);
+        tokio::spawn(reject_client(server, RejectReason::TooManyConnections));
+        let started_at = tokio::time::Instant::now();
+
+        // When
+        let read = client.read(&mut [0; 16]).await.unwrap();
+
+        // Then
+        assert_eq!(read, 0);
+        assert!(started_at.elapsed() <= REJECTED_CONNECTION_TIMEOUT);
+    }
+
+    #[tokio::test]
+    async fn test_outbound_packet_does_not_interrupt_a_partial_read() {
+        // Given
+        let (client_data, mut client, sender) = client_in_play().await;
+        let server_state = Arc::new(RwLock::new(ServerState::builder().build().unwrap()));
+        let mut was_in_play_state = true;
+        client.write_all(&[3, UNKNOWN_PACKET_ID]).await.unwrap();
+        sender
+            .send(Outbound::Packet(Box::new(
+                PacketRegistry::SystemChatMessage(SystemChatMessagePacket::component(
+                    &Component::new("Hello"),
+                )),
+            )))
+            .unwrap();
+
+        // When
+        let outbound = read(&client_data, &server_state, &mut was_in_play_state).await;
+        client.write_all(&[0, 0]).await.unwrap();
+        let received = read(&client_data, &server_state, &mut was_in_play_state).await;
+
+        // Then
+        assert!(outbound.is_ok());
+        assert!(PacketStream::new(&mut client).read_packet().await.is_ok());
+        assert!(matches!(
+            received,
+            Err(PacketProcessingError::DecodePacketError(
+                _,
+                State::Play,
+                UNKNOWN_PACKET_ID
+            ))
+        ));
+    }
+
+    fn legacy_server_state() -> Arc<RwLock<ServerState>> {
+        let mut builder = ServerState::builder();
+        builder.set_reply_to_status(true);
+        Arc::new(RwLock::new(builder.build().unwrap()))
+    }
+
+    async fn detect_split_request(first: &[u8], rest: Option<&[u8]>) -> (Option<Vec<u8>>, Vec<u8>) {
+        let (mut client, server) = tokio::io::duplex(16 * 1024);
+        let mut stream = BufReader::new(server);
+        let server_state = legacy_server_state();
+        client.write_all(first).await.unwrap();
+        let remote_address = SocketAddr::from(([127, 0, 0, 1], 25565));
+        let (received_bytes, ()) = tokio::join!(
+            handle_legacy_request(&mut stream, remote_address, &server_state),
+            async {
+                if let Some(rest) = rest {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                    client.write_all(rest).await.unwrap();
+                }
+            }
+        );
+        drop(stream);
+        let mut response = Vec::new();
+        client.read_to_end(&mut response).await.unwrap();
+        (received_bytes, response)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_lone_legacy_ping_byte_is_a_beta_ping() {
+        // Given
+        let expected = LegacyRequest::BetaPing.response(&*legacy_server_state().read().await);
+
+        // When
+        let (received_bytes, response) = detect_split_request(&[0xFE], None).await;
+
+        // Then
+        assert_eq!(received_bytes, None);
+        assert_eq!(Some(response), expected);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_split_legacy_ping_is_not_a_beta_ping() {
+        // Given
+        let expected = LegacyRequest::Ping.response(&*legacy_server_state().read().await);
+
+        // When
+        let (received_bytes, response) =
+            detect_split_request(&[0xFE], Some(&[0x01, 0xFA, 0x00, 0x0B])).await;
+
+        // Then
+        assert_eq!(received_bytes, None);
+        assert_eq!(Some(response), expected);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_split_handshake_of_254_bytes_is_kept() {
+        // Given
+        let mut rest = vec![0x01, HANDSHAKE_PACKET_ID];
+        rest.resize(255, 0);
+        let (mut client, server) = tokio::io::duplex(16 * 1024);
+        let mut stream = BufReader::new(server);
+        let server_state = legacy_server_state();
+        client.write_all(&[0xFE]).await.unwrap();
+        let remote_address = SocketAddr::from(([127, 0, 0, 1], 25565));
+
+        // When
+        let (received_bytes, ()) = tokio::join!(
+            handle_legacy_request(&mut stream, remote_address, &server_state),
+            async {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                client.write_all(&rest).await.unwrap();
+            }
+        );
+        let mut packet_stream = PacketStream::with_received_bytes(stream, received_bytes.unwrap());
+        let packet = packet_stream.read_packet().await.unwrap();
+
+        // Then
+        assert_eq!(packet.packet_id(), Some(HANDSHAKE_PACKET_ID));
+        assert_eq!(packet.size(), 254);
+    }
+}
@@ -13,6 +13,8 @@ use minecraft_packets::configuration::update_tags_packet::UpdateTagsPacket;
 use minecraft_packets::handshaking::handshake_packet::HandshakePacket;
 use minecraft_packets::login::custom_query_answer_packet::CustomQueryAnswerPacket;
 use minecraft_packets::login::custom_query_packet::CustomQueryPacket;
+use minecraft_packets::login::encryption_request_packet::EncryptionRequestPacket;
+use minecraft_packets::login::encryption_response_packet::EncryptionResponsePacket;
 use minecraft_packets::login::game_profile_packet::GameProfilePacket;
 use minecraft_packets::login::login_acknowledged_packet::LoginAcknowledgedPacket;
 use minecraft_packets::login::login_disconnect_packet::LoginDisconnectPacket;
@@ -33,6 +35,7 @@ use minecraft_packets::play::legacy_chat_message_packet::LegacyChatMessagePacket
 use minecraft_packets::play::legacy_set_title_packet::LegacySetTitlePacket;
 use minecraft_packets::play::login_packet::LoginPacket;
 use minecraft_packets::play::player_info_update_packet::PlayerInfoUpdatePacket;
+use minecraft_packets::play::server_bound_keep_alive_packet::ServerBoundKeepAlivePacket;
 use minecraft_packets::play::server_bound_player_abilities_packet::ServerBoundPlayerAbilitiesPacket;
 use minecraft_packets::play::set_action_bar_text_packet::SetActionBarTextPacket;
 use minecraft_packets::play::set_chunk_cache_center_packet::SetCenterChunkPacket;
@@ -98,6 +101,9 @@ pub enum PacketRegistry {
     #[protocol_id(state = "login", bound = "serverbound", name = "minecraft:hello")]
     LoginStart(LoginStartPacket),
 
+    #[protocol_id(state = "login", bound = "serverbound", name = "minecraft:key")]
+    EncryptionResponse(EncryptionResponsePacket),
+
     #[protocol_id(
         state = "login",
         bound = "serverbound",
@@ -112,6 +118,9 @@ pub enum PacketRegistry {
     )]
     CustomQueryAnswer(CustomQueryAnswerPacket),
 
+    #[protocol_id(state = "login", bound = "clientbound", name = "minecraft:hello")]
+    EncryptionRequest(EncryptionRequestPacket),
+
     #[protocol_id(
         state = "login",
         bound = "clientbound",
@@ -197,6 +206,13 @@ pub enum PacketRegistry {
     )]
     ConfigurationClientBoundKeepAlive(ClientBoundKeepAlivePacket),
 
+    #[protocol_id(
+        state = "configuration",
+        bound = "serverbound",
+        name = "minecraft:keep_alive"
+    )]
+    ConfigurationServerBoundKeepAlive(ServerBoundKeepAlivePacket),
+
     #[protocol_id(
         state = "configuration",
         bound = "clientbound",
@@ -289,6 +305,9 @@ pub enum PacketRegistry {
     #[protocol_id(state = "play", bound = "clientbound", name = "minecraft:keep_alive")]
     ClientBoundKeepAlive(ClientBoundKeepAlivePacket),
 
+    #[protocol_id(state = "play", bound = "serverbound", name = "minecraft:keep_alive")]
+    ServerBoundKeepAlive(ServerBoundKeepAlivePacket),
+
     #[protocol_id(state = "play", bound = "clientbound", name = "minecraft:disconnect")]
     PlayDisconnect(DisconnectPacket),
 
@@ -379,6 +398,7 @@ impl PacketHandler for PacketRegistry {
             Self::StatusRequest(packet) => packet.handle(client_state, server_state),
             Self::PingRequest(packet) => packet.handle(client_state, server_state),
             Self::LoginStart(packet) => packet.handle(client_state, server_state),
+            Self::EncryptionResponse(packet) => packet.handle(client_state, server_state),
             Self::CustomQueryAnswer(packet) => packet.handle(client_state, server_state),
             Self::LoginAcknowledged(packet) => packet.handle(client_state, server_state),
             Self::AcknowledgeConfiguration(packet) => packet.handle(client_state, server_state),
@@ -388,6 +408,8 @@ impl PacketHandler for PacketRegistry {
             Self::ChatMessage(packet) => packet.handle(client_state, server_state),
             Self::ServerBoundPlayerAbilities(packet) => packet.handle(client_state, server_state),
             Self::ServerBoundKnownPacks(packet) => packet.handle(client_state, server_state),
+            Self::ConfigurationServerBoundKeepAlive(packet)
+            | Self::ServerBoundKeepAlive(packet) => packet.handle(client_state, server_state),
             _ => Err(PacketHandlerError::custom("Unhandled packet")),
         }
     }
@@ -0,0 +1,287 @@
+use crate::kick_messages::{DUPLICATE_LOGIN_KICK_MESSAGE, kick_reason};
+use crate::server::packet_registry::PacketRegistry;
+use minecraft_protocol::prelude::{ProtocolVersion, Uuid};
+use pico_text_component::prelude::Component;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use tokio::sync::mpsc;
+
+/// Sent to a connection from outside of its task.
+pub enum Outbound {
+    Packet(Box<PacketRegistry>),
+    /// Kick message, and the reason labelling the kick in the metrics.
+    Kick(Component, &'static str),
+    /// In-game command run on behalf of the player.
+    Command(String),
+}
+
+/// Player in game, as seen from other tasks.
+#[derive(Clone)]
+pub struct PlayerHandle {
+    uuid: Uuid,
+    username: String,
+    protocol_version: ProtocolVersion,
+    sender: mpsc::UnboundedSender<Outbound>,
+}
+
+impl PlayerHandle {
+    pub const fn uuid(&self) -> Uuid {
+        self.uuid
+    }
+
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    pub const fn protocol_version(&self) -> ProtocolVersion {
+        self.protocol_version
+    }
+
+    /// Queues a packet, returns false when the player already disconnected.
+    pub fn queue(&self, packet: PacketRegistry) -> bool {
+        self.sender.send(Outbound::Packet(Box::new(packet))).is_ok()
+    }
+
+    /// Runs an in-game command as if the player typed it, returns false when the player already disconnected.
+    pub fn run_command(&self, command: String) -> bool {
+        self.sender.send(Outbound::Command(command)).is_ok()
+    }
+
+    /// Disconnects the player, returns false when the player already disconnected.
+    pub fn kick(&self, kick_message: Component, reason: &'static str) -> bool {
+        self.sender
+            .send(Outbound::Kick(kick_message, reason))
+            .is_ok()
+    }
+}
+
+struct PlayerEntry {
+    id: u64,
+    handle: PlayerHandle,
+}
+
+#[derive(Default)]
+struct Players {
+    by_uuid: HashMap<Uuid, PlayerEntry>,
+    /// Lowercase username to UUID.
+    by_username: HashMap<String, Uuid>,
+}
+
+/// Players currently in game, shared by every connection.
+#[derive(Default)]
+pub struct PlayerRegistry {
+    players: Mutex<Players>,
+    next_id: AtomicU64,
+}
+
+impl PlayerRegistry {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// Adds a player that just entered the PLAY state, a player already
+    /// connected with the same UUID is kicked.
+    /// The player is removed when the returned registration is dropped.
+    pub fn register(
+        self: &Arc<Self>,
+        uuid: Uuid,
+        username: &str,
+        protocol_version: ProtocolVersion,
+    ) -> (PlayerRegistration, mpsc::UnboundedReceiver<Outbound>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let entry = PlayerEntry {
+            id,
+            handle: PlayerHandle {
+                uuid,
+                username: username.to_string(),
+                protocol_version,
+                sender,
+            },
+        };
+
+        let previous = self.insert(entry);
+        if let Some(previous) = previous {
+            previous.handle.kick(
+                Component::new(DUPLICATE_LOGIN_KICK_MESSAGE),
+                kick_reason(DUPLICATE_LOGIN_KICK_MESSAGE),
+            );
+        }
+
+        let registration = PlayerRegistration {
+            registry: Arc::clone(self),
+            uuid,
+            id,
+        };
+        (registration, receiver)
+    }
+
+    fn insert(&self, entry: PlayerEntry) -> Option<PlayerEntry> {
+        let mut players = self.players();
+        let uuid = entry.handle.uuid;
+        players
+            .by_username
+            .insert(entry.handle.username.to_lowercase(), uuid);
+        let previous = players.by_uuid.insert(uuid, entry);
+        if let Some(previous) = &previous {
+            let previous_username = previous.handle.username.to_lowercase();
+            if players.by_username.get(&previous_username) != Some(&uuid) {
+                players.by_username.remove(&previous_username);
+            }
+        }
+        drop(players);
+        previous
+    }
+
+    /// Finds a player by UUID.
+    pub fn get(&self, uuid: Uuid) -> Option<PlayerHandle> {
+        self.players()
+            .by_uuid
+            .get(&uuid)
+            .map(|entry| entry.handle.clone())
+    }
+
+    /// Finds a player by username, ignoring case.
+    pub fn find(&self, username: &str) -> Option<PlayerHandle> {
+        let players = self.players();
+        players
+            .by_username
+            .get(&username.to_lowercase())
+            .and_then(|uuid| players.by_uuid.get(uuid))
+            .map(|entry| entry.handle.clone())
+    }
+
+    /// Returns every player in game, sorted by username.
+    pub fn all(&self) -> Vec<PlayerHandle> {
+        let mut players: Vec<PlayerHandle> = self
+            .players()
+            .by_uuid
+            .values()
+            .map(|entry| entry.handle.clone())
+            .collect();
+        players.sort_by(|a, b| a.username.cmp(&b.username));
+        players
+    }
+
+    /// Returns every player in game, in the order they entered the game.
+    pub fn in_join_order(&self) -> Vec<PlayerHandle> {
+        let mut entries: Vec<(u64, PlayerHandle)> = self
+            .players()
+            .by_uuid
+            .values()
+            .map(|entry| (entry.id, entry.handle.clone()))
+            .collect();
+        entries.sort_by_key(|(id, _)| *id);
+        entries.into_iter().map(|(_, handle)| handle).collect()
+    }
+
+    /// Returns the username of every player in game, sorted.
+    pub fn usernames(&self) -> Vec<String> {
+        self.all()
+            .into_iter()
+            .map(|handle| handle.username)
+            .collect()
+    }
+
+    fn unregister(&self, uuid: Uuid, id: u64) {
+        let mut players = self.players();
+        if players
+            .by_uuid
+            .get(&uuid)
+            .is_some_and(|entry| entry.id == id)
+            && let Some(entry) = players.by_uuid.remove(&uuid)
+        {
+            let username = entry.handle.username.to_lowercase();
+            if players.by_username.get(&username) == Some(&uuid) {
+                players.by_username.remove(&username);
+            }
+        }
+    }
+
+    fn players(&self) -> MutexGuard<'_, Players> {
+        self.players.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Removes the player from the registry when dropped.
+pub struct PlayerRegistration {
+    registry: Arc<PlayerRegistry>,
+    uuid: Uuid,
+    id: u64,
+}
+
+impl Drop for PlayerRegistration {
+    fn drop(&mut self) {
+        self.registry.unregister(self.uuid, self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uuid(value: u128) -> Uuid {
+        Uuid::from_u128(value)
+    }
+
+    #[test]
+    fn test_register_and_find_by_uuid_or_username() {
+        // Given
+        let registry = PlayerRegistry::new();
+        let (_registration, mut receiver) =
+            registry.register(uuid(1), "Notch", ProtocolVersion::V1_21_2);
+
+        // When
+        let handle = registry.find("notch").unwrap();
+        let kicked = handle.kick(Component::new("Bye"), "other");
+
+        // Then
+        assert!(kicked);
+        assert_eq!(handle.username(), "Notch");
+        assert_eq!(registry.get(uuid(1)).unwrap().username(), "Notch");
+        assert!(matches!(
+            receiver.try_recv().unwrap(),
+            Outbound::Kick(kick_message, "other") if kick_message == Component::new("Bye")
+        ));
+    }
+
+    #[test]
+    fn test_dropping_registration_removes_player() {
+        // Given
+        let registry = PlayerRegistry::new();
+        let (registration, _receiver) =
+            registry.register(uuid(1), "Notch", ProtocolVersion::V1_21_2);
+        let (_other, _other_receiver) = registry.register(uuid(2), "jeb_", ProtocolVersion::V1_8);
+
+        // When
+        drop(registration);
+
+        // Then
+        assert!(registry.find("Notch").is_none());
+        assert!(registry.get(uuid(1)).is_none());
+        assert_eq!(registry.usernames(), vec!["jeb_".to_string()]);
+    }
+
+    #[test]
+    fn test_duplicate_login_kicks_previous_player() {
+        // Given
+        let registry = PlayerRegistry::new();
+        let (first, mut first_receiver) =
+            registry.register(uuid(1), "Notch", ProtocolVersion::V1_21_2);
+        let (_second, _second_receiver) =
+            registry.register(uuid(1), "Notch", ProtocolVersion::V1_8);
+
+        // When
+        drop(first);
+
+        // Then
+        assert!(matches!(
+            first_receiver.try_recv().unwrap(),
+            Outbound::Kick(_, "duplicate_login")
+        ));
+        assert_eq!(registry.usernames(), vec!["Notch".to_string()]);
+        let handle = registry.find("Notch").unwrap();
+        assert_eq!(handle.protocol_version(), ProtocolVersion::V1_8);
+    }
+}
@@ -0,0 +1,248 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+const V1_PREFIX: &[u8] = b"PROXY ";
+/// Longest possible v1 header, CRLF included.
+const V1_MAX_LENGTH: usize = 107;
+const V2_SIGNATURE: [u8; 12] = *b"\r\n\r\n\0\r\nQUIT\n";
+
+const V2_COMMAND_LOCAL: u8 = 0x0;
+const V2_COMMAND_PROXY: u8 = 0x1;
+const V2_FAMILY_INET: u8 = 0x1;
+const V2_FAMILY_INET6: u8 = 0x2;
+
+#[derive(Debug, Error)]
+pub enum ProxyProtocolError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("missing PROXY protocol header")]
+    MissingHeader,
+    #[error("malformed PROXY protocol header: {0}")]
+    Malformed(&'static str),
+}
+
+/// Reads a PROXY protocol v1 or v2 header from the start of the stream.
+///
+/// Returns the original client address, or `None` when the header does not
+/// carry one (v1 `UNKNOWN`, v2 `LOCAL` or a non-IP address family), in which
+/// case the peer address should be used.
+/// Exactly the header is consumed, the Minecraft protocol starts right after.
+pub async fn read_proxy_header<S>(stream: &mut S) -> Result<Option<SocketAddr>, ProxyProtocolError>
+where
+    S: AsyncRead + Unpin,
+{
+    let first_byte = stream.read_u8().await?;
+    match first_byte {
+        b'P' => read_v1_header(stream).await,
+        b'\r' => read_v2_header(stream).await,
+        _ => Err(ProxyProtocolError::MissingHeader),
+    }
+}
+
+async fn read_v1_header<S>(stream: &mut S) -> Result<Option<SocketAddr>, ProxyProtocolError>
+where
+    S: AsyncRead + Unpin,
+{
+    let mut line = vec![b'P'];
+    while !line.ends_with(b"\r\n") {
+        if line.len() >= V1_MAX_LENGTH {
+            return Err(ProxyProtocolError::Malformed("v1 header is too long"));
+        }
+        line.push(stream.read_u8().await?);
+    }
+
+    if !line.starts_with(V1_PREFIX) {
+        return Err(ProxyProtocolError::MissingHeader);
+    }
+
+    let line = std::str::from_utf8(&line[V1_PREFIX.len()..line.len() - 2])
+        .map_err(|_| ProxyProtocolError::Malformed("v1 header is not ASCII"))?;
+    let mut parts = line.split(' ');
+    match parts.next() {
+        Some("UNKNOWN") => Ok(None),
+        Some("TCP4" | "TCP6") => {
+            let source_ip = parts
+                .next()
+                .and_then(|ip| ip.parse::<IpAddr>().ok())
+                .ok_or(ProxyProtocolError::Malformed("invalid v1 source address"))?;
+            let _destination_ip = parts.next();
+            let source_port = parts
+                .next()
+                .and_then(|port| port.parse::<u16>().ok())
+                .ok_or(ProxyProtocolError::Malformed("invalid v1 source port"))?;
+            Ok(Some(SocketAddr::new(source_ip, source_port)))
+        }
+        _ => Err(ProxyProtocolError::Malformed("unknown v1 protocol")),
+    }
+}
+
+async fn read_v2_header<S>(stream: &mut S) -> Result<Option<SocketAddr>, ProxyProtocolError>
+where
+    S: AsyncRead + Unpin,
+{
+    let mut header = [0u8; 16];
+    header[0] = b'\r';
+    stream.read_exact(&mut header[1..]).await?;
+
+    if header[..12] != V2_SIGNATURE {
+        return Err(ProxyProtocolError::MissingHeader);
+    }
+
+    let version = header[12] >> 4;
+    let command = header[12] & 0x0F;
+    let family = header[13] >> 4;
+    let length = usize::from(u16::from_be_bytes([header[14], header[15]]));
+
+    if version != 2 {
+        return Err(ProxyProtocolError::Malformed("unsupported version"));
+    }
+
+    let mut addresses = vec![0u8; length];
+    stream.read_exact(&mut addresses).await?;
+
+    match command {
+        V2_COMMAND_LOCAL => Ok(None),
+        V2_COMMAND_PROXY => match family {
+            V2_FAMILY_INET => {
+                let bytes: [u8; 12] = addresses
+                    .get(..12)
+                    .and_then(|bytes| bytes.try_into().ok())
+                    .ok_or(ProxyProtocolError::Malformed("truncated v2 IPv4 addresses"))?;
+                let ip = Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3]);
+                let port = u16::from_be_bytes([bytes[8], bytes[9]]);
+                Ok(Some(SocketAddr::new(IpAddr::V4(ip), port)))
+            }
+            V2_FAMILY_INET6 => {
+                let bytes: [u8; 36] = addresses
+                    .get(..36)
+                    .and_then(|bytes| bytes.try_into().ok())
+                    .ok_or(ProxyProtocolError::Malformed("truncated v2 IPv6 addresses"))?;
+                let mut ip = [0u8; 16];
+                ip.copy_from_slice(&bytes[..16]);
+                let port = u16::from_be_bytes([bytes[32], bytes[33]]);
+                Ok(Some(SocketAddr::new(IpAddr::V6(Ipv6Addr::from(ip)), port)))
+            }
+            _ => Ok(None),
+        },
+        _ => Err(ProxyProtocolError::Malformed("unknown v2 command")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_v1_tcp4() {
+        // Given
+        let mut stream: &[u8] = b"PROXY TCP4 192.168.0.1 192.168.0.11 56324 25565\r\n\x10\x00";
+
+        // When
+        let address = read_proxy_header(&mut stream).await.unwrap();
+
+        // Then
+        assert_eq!(address, Some("192.168.0.1:56324".parse().unwrap()));
+        assert_eq!(stream, b"\x10\x00");
+    }
+
+    #[tokio::test]
+    async fn test_v1_tcp6() {
+        // Given
+        let mut stream: &[u8] = b"PROXY TCP6 2001:db8::1 2001:db8::2 4242 25565\r\n";
+
+        // When
+        let address = read_proxy_header(&mut stream).await.unwrap();
+
+        // Then
+        assert_eq!(address, Some("[2001:db8::1]:4242".parse().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn test_v1_unknown() {
+        // Given
+        let mut stream: &[u8] = b"PROXY UNKNOWN\r\n";
+
+        // When
+        let address = read_proxy_header(&mut stream).await.unwrap();
+
+        // Then
+        assert_eq!(address, None);
+    }
+
+    #[tokio::test]
+    async fn test_v1_too_long() {
+        // Given
+        let line = format!("PROXY TCP4 {}\r\n", "1".repeat(200));
+        let mut stream: &[u8] = line.as_bytes();
+
+        // When
+        let result = read_proxy_header(&mut stream).await;
+
+        // Then
+        assert!(matches!(result, Err(ProxyProtocolError::Malformed(_))));
+    }
+
+    #[tokio::test]
+    async fn test_v2_ipv4() {
+        // Given
+        let mut bytes = V2_SIGNATURE.to_vec();
+        bytes.extend([0x21, 0x11, 0x00, 0x0C]);
+        bytes.extend([10, 0, 0, 1, 10, 0, 0, 2, 0x1F, 0x90, 0x63, 0xDD]);
+        bytes.push(0x10);
+        let mut stream: &[u8] = &bytes;
+
+        // When
+        let address = read_proxy_header(&mut stream).await.unwrap();
+
+        // Then
+        assert_eq!(address, Some("10.0.0.1:8080".parse().unwrap()));
+        assert_eq!(stream, &[0x10]);
+    }
+
+    #[tokio::test]
+    async fn test_v2_ipv6() {
+        // Given
+        let mut bytes = V2_SIGNATURE.to_vec();
+        bytes.extend([0x21, 0x21, 0x00, 0x24]);
+        let source: Ipv6Addr = "2001:db8::1".parse().unwrap();
+        let destination: Ipv6Addr = "2001:db8::2".parse().unwrap();
+        bytes.extend(source.octets());
+        bytes.extend(destination.octets());
+        bytes.extend([0x10, 0x92, 0x63, 0xDD]);
+        let mut stream: &[u8] = &bytes;
+
+        // When
+        let address = read_proxy_header(&mut stream).await.unwrap();
+
+        // Then
+        assert_eq!(address, Some("[2001:db8::1]:4242".parse().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn test_v2_local_skips_tlvs() {
+        // Given
+        let mut bytes = V2_SIGNATURE.to_vec();
+        bytes.extend([0x20, 0x00, 0x00, 0x03, 0x01, 0x02, 0x03, 0x10]);
+        let mut stream: &[u8] = &bytes;
+
+        // When
+        let address = read_proxy_header(&mut stream).await.unwrap();
+
+        // Then
+        assert_eq!(address, None);
+        assert_eq!(stream, &[0x10]);
+    }
+
+    #[tokio::test]
+    async fn test_missing_header() {
+        // Given
+        let mut stream: &[u8] = &[0x10, 0x00, 0xFF];
+
+        // When
+        let result = read_proxy_header(&mut stream).await;
+
+        // Then
+        assert!(matches!(result, Err(ProxyProtocolError::MissingHeader)));
+    }
+}
@@ -0,0 +1,446 @@
+use crate::server::player_registry::PlayerRegistry;
+use crate::server::server_address::ServerAddress;
+use crate::server_state::ServerState;
+use minecraft_protocol::prelude::ProtocolVersion;
+use rand::RngExt;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::net::UdpSocket;
+use tokio::sync::RwLock;
+use tracing::{debug, error, info};
+
+const MAGIC: [u8; 2] = [0xFE, 0xFD];
+const HANDSHAKE_TYPE: u8 = 9;
+const STAT_TYPE: u8 = 0;
+/// Only the lower 4 bits of each byte of the session id are used by clients.
+const SESSION_ID_MASK: i32 = 0x0F0F_0F0F;
+/// How long a challenge token stays valid, same as vanilla.
+const CHALLENGE_LIFETIME: Duration = Duration::from_secs(30);
+const FULL_STAT_PADDING: &[u8] = b"splitnum\0\x80\0";
+const PLAYERS_PADDING: &[u8] = b"\x01player_\0\0";
+/// Requests are at most 15 bytes long, anything larger is ignored.
+const MAX_REQUEST_SIZE: usize = 64;
+
+#[derive(Debug, PartialEq, Eq)]
+enum QueryRequest {
+    Handshake { session_id: i32 },
+    BasicStat { session_id: i32, challenge: i32 },
+    FullStat { session_id: i32, challenge: i32 },
+}
+
+impl QueryRequest {
+    fn parse(bytes: &[u8]) -> Option<Self> {
+        let (magic, rest) = bytes.split_first_chunk::<2>()?;
+        let (request_type, rest) = rest.split_first()?;
+        let (session_id, payload) = rest.split_first_chunk::<4>()?;
+        if *magic != MAGIC {
+            return None;
+        }
+        let session_id = i32::from_be_bytes(*session_id) & SESSION_ID_MASK;
+        let challenge = payload
+            .first_chunk()
+            .map(|bytes| i32::from_be_bytes(*bytes));
+        match (*request_type, payload.len(), challenge) {
+            (HANDSHAKE_TYPE, 0, _) => Some(Self::Handshake { session_id }),
+            (STAT_TYPE, 4, Some(challenge)) => Some(Self::BasicStat {
+                session_id,
+                challenge,
+            }),
+            // The full stat is requested with 4 bytes of padding after the challenge
+            (STAT_TYPE, 8, Some(challenge)) => Some(Self::FullStat {
+                session_id,
+                challenge,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Challenge tokens handed out to each address during the handshake.
+#[derive(Default)]
+struct Challenges {
+    tokens: HashMap<SocketAddr, (i32, Instant)>,
+}
+
+impl Challenges {
+    fn issue(&mut self, address: SocketAddr, now: Instant) -> i32 {
+        let token = rand::rng().random::<i32>() & i32::MAX;
+        self.tokens.insert(address, (token, now));
+        token
+    }
+
+    fn verify(&self, address: SocketAddr, challenge: i32, now: Instant) -> bool {
+        self.tokens.get(&address).is_some_and(|(token, issued_at)| {
+            *token == challenge && now.duration_since(*issued_at) < CHALLENGE_LIFETIME
+        })
+    }
+
+    fn remove_expired(&mut self, now: Instant) {
+        self.tokens
+            .retain(|_, (_, issued_at)| now.duration_since(*issued_at) < CHALLENGE_LIFETIME);
+    }
+}
+
+/// What the query protocol tells about the server.
+struct QueryInfo {
+    motd: String,
+    version: String,
+    online_players: u32,
+    max_players: u32,
+    players: Vec<String>,
+    host_ip: String,
+    host_port: u16,
+}
+
+impl QueryInfo {
+    fn new(server_state: &ServerState, players: &PlayerRegistry, address: &ServerAddress) -> Self {
+        let (host_ip, host_port) = address.tuple();
+        let online_players = server_state.online_players();
+        let oldest = ProtocolVersion::oldest().humanize();
+        let latest = ProtocolVersion::latest().humanize();
+        Self {
+            motd: server_state.motd().to_plain_text(),
+            version: format!("{oldest}-{latest}"),
+            online_players,
+            max_players: server_state.max_players(),
+            // The names are hidden along with the count
+            players: if online_players > 0 {
+                players.usernames()
+            } else {
+                Vec::new()
+            },
+            host_ip: host_ip.to_string(),
+            host_port,
+        }
+    }
+
+    fn basic_stat(&self, session_id: i32) -> Vec<u8> {
+        let mut bytes = header(STAT_TYPE, session_id);
+        push_string(&mut bytes, &self.motd);
+        push_string(&mut bytes, "SMP");
+        push_string(&mut bytes, "world");
+        push_string(&mut bytes, &self.online_players.to_string());
+        push_string(&mut bytes, &self.max_players.to_string());
+        bytes.extend(self.host_port.to_le_bytes());
+        push_string(&mut bytes, &self.host_ip);
+        bytes
+    }
+
+    fn full_stat(&self, session_id: i32) -> Vec<u8> {
+        let mut bytes = header(STAT_TYPE, session_id);
+        bytes.extend(FULL_STAT_PADDING);
+        let values = [
+            ("hostname", self.motd.clone()),
+            ("gametype", "SMP".to_string()),
+            ("game_id", "MINECRAFT".to_string()),
+            ("version", self.version.clone()),
+            ("plugins", "PicoLimbo".to_string()),
+            ("map", "world".to_string()),
+            ("numplayers", self.online_players.to_string()),
+            ("maxplayers", self.max_players.to_string()),
+            ("hostport", self.host_port.to_string()),
+            ("hostip", self.host_ip.clone()),
+        ];
+        for (key, value) in values {
+            push_string(&mut bytes, key);
+            push_string(&mut bytes, &value);
+        }
+        bytes.push(0);
+        bytes.extend(PLAYERS_PADDING);
+        for player in &self.players {
+            push_string(&mut bytes, player);
+        }
+        bytes.push(0);
+        bytes
+    }
+}
+
+fn header(response_type: u8, session_id: i32) -> Vec<u8> {
+    let mut bytes = vec![response_type];
+    bytes.extend(session_id.to_be_bytes());
+    bytes
+}
+
+/// Writes a null-terminated string, null bytes inside the value would end it early.
+fn push_string(bytes: &mut Vec<u8>, value: &str) {
+    bytes.extend(value.bytes().filter(|byte| *byte != 0));
+    bytes.push(0);
+}
+
+/// Answers the `GameSpy4` UDP query protocol used by server lists and monitoring tools.
+pub struct QueryServer {
+    bind: ServerAddress,
+    server_address: ServerAddress,
+    state: Arc<RwLock<ServerState>>,
+    players: Arc<PlayerRegistry>,
+}
+
+impl QueryServer {
+    /// The server address is reported as the address players should connect to.
+    pub const fn new(
+        bind: ServerAddress,
+        server_address: ServerAddress,
+        state: Arc<RwLock<ServerState>>,
+        players: Arc<PlayerRegistry>,
+    ) -> Self {
+        Self {
+            bind,
+            server_address,
+            state,
+            players,
+        }
+    }
+
+    /// Answers queries until the task is aborted.
+    pub async fn run(self) {
+        let socket = match UdpSocket::bind(self.bind.tuple()).await {
+            Ok(socket) => socket,
+            Err(err) => {
+                error!(
+                    "Failed to bind the query listener to {}: {}",
+                    self.bind, err
+                );
+                return;
+            }
+        };
+        info!("Listening for queries on: {}", self.bind);
+
+        let mut challenges = Challenges::default();
+        let mut buffer = [0u8; MAX_REQUEST_SIZE];
+        loop {
+            let (length, address) = match socket.recv_from(&mut buffer).await {
+                Ok(received) => received,
+                Err(err) => {
+                    debug!("Failed to receive a query: {}", err);
+                    continue;
+                }
+            };
+            let Some(request) = QueryRequest::parse(&buffer[..length]) else {
+                continue;
+            };
+            let now = Instant::now();
+            challenges.remove_expired(now);
+
+            let response = self.respond(&request, address, &mut challenges, now).await;
+            if let Some(response) = response
+                && let Err(err) = socket.send_to(&response, address).await
+            {
+                debug!("Failed to answer the query of {}: {}", address, err);
+            }
+        }
+    }
+
+    async fn respond(
+        &self,
+        request: &QueryRequest,
+        address: SocketAddr,
+        challenges: &mut Challenges,
+        now: Instant,
+    ) -> Option<Vec<u8>> {
+        match *request {
+            QueryRequest::Handshake { session_id } => {
+                let token = challenges.issue(address, now);
+                let mut bytes = header(HANDSHAKE_TYPE, session_id);
+                push_string(&mut bytes, &token.to_string());
+                Some(bytes)
+            }
+            QueryRequest::BasicStat {
+                session_id,
+                challenge,
+            }
+            | QueryRequest::FullStat {
+                session_id,
+                challenge,
+            } => {
+                if !challenges.verify(address, challenge, now) {
+                    return None;
+                }
+                let server_state = self.state.read().await;
+                if !server_state.reply_to_status() {
+                    return None;
+                }
+                let info = QueryInfo::new(&server_state, &self.players, &self.server_address);
+                drop(server_state);
+                Some(if matches!(request, QueryRequest::FullStat { .. }) {
+                    info.full_stat(session_id)
+                } else {
+                    info.basic_stat(session_id)
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use minecraft_protocol::prelude::Uuid;
+
+    fn address() -> SocketAddr {
+        "127.0.0.1:50000".parse().unwrap()
+    }
+
+    fn query_server() -> QueryServer {
+        let mut builder = ServerState::builder();
+        builder
+            .description_text("<red>A Minecraft Server</red>")
+            .max_players(20)
+            .show_online_player_count(true)
+            .set_reply_to_status(true);
+        let server_state = builder.build().unwrap();
+        server_state.increment();
+        let players = PlayerRegistry::new();
+        QueryServer::new(
+            ServerAddress::parse("0.0.0.0:25565").unwrap(),
+            ServerAddress::parse("127.0.0.1:25565").unwrap(),
+            Arc::new(RwLock::new(server_state)),
+            players,
+        )
+    }
+
+    fn split_strings(bytes: &[u8]) -> Vec<String> {
+        bytes
+            .split(|byte| *byte == 0)
+            .map(|string| String::from_utf8_lossy(string).to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_requests() {
+        assert_eq!(
+            QueryRequest::parse(&[0xFE, 0xFD, 9, 0x7F, 0x7F, 0x7F, 0x7F]),
+            Some(QueryRequest::Handshake {
+                session_id: 0x0F0F_0F0F
+            })
+        );
+        assert_eq!(
+            QueryRequest::parse(&[0xFE, 0xFD, 0, 0, 0, 0, 1, 0, 0, 0, 42]),
+            Some(QueryRequest::BasicStat {
+                session_id: 1,
+                challenge: 42
+            })
+        );
+        assert_eq!(
+            QueryRequest::parse(&[0xFE, 0xFD, 0, 0, 0, 0, 1, 0, 0, 0, 42, 0, 0, 0, 0]),
+            Some(QueryRequest::FullStat {
+                session_id: 1,
+                challenge: 42
+            })
+        );
+        assert_eq!(QueryRequest::parse(&[0xFE, 0xFD, 0, 0, 0, 0, 1]), None);
+        assert_eq!(QueryRequest::parse(&[0xFE, 0x01, 9, 0, 0, 0, 1]), None);
+    }
+
+    #[test]
+    fn test_challenge_expires() {
+        // Given
+        let mut challenges = Challenges::default();
+        let now = Instant::now();
+        let token = challenges.issue(address(), now);
+
+        // When
+        let later = now + CHALLENGE_LIFETIME;
+
+        // Then
+        assert!(challenges.verify(address(), token, now));
+        assert!(!challenges.verify(address(), token.wrapping_add(1), now));
+        assert!(!challenges.verify("127.0.0.2:50000".parse().unwrap(), token, now));
+        assert!(!challenges.verify(address(), token, later));
+        challenges.remove_expired(later);
+        assert!(challenges.tokens.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_basic_stat_after_handshake() {
+        // Given
+        let query_server = query_server();
+        let mut challenges = Challenges::default();
+        let now = Instant::now();
+        let handshake = query_server
+            .respond(
+                &QueryRequest::Handshake { session_id: 1 },
+                address(),
+                &mut challenges,
+                now,
+            )
+            .await
+            .unwrap();
+        let challenge: i32 = split_strings(&handshake[5..])[0].parse().unwrap();
+
+        // When
+        let response = query_server
+            .respond(
+                &QueryRequest::BasicStat {
+                    session_id: 1,
+                    challenge,
+                },
+                address(),
+                &mut challenges,
+                now,
+            )
+            .await
+            .unwrap();
+
+        // Then
+        assert_eq!(handshake[..5], [9, 0, 0, 0, 1]);
+        assert_eq!(response[..5], [0, 0, 0, 0, 1]);
+        let strings = split_strings(&response[5..]);
+        assert_eq!(
+            strings[..5],
+            ["A Minecraft Server", "SMP", "world", "1", "20"]
+        );
+        assert!(response.ends_with(b"127.0.0.1\0"));
+    }
+
+    #[tokio::test]
+    async fn test_stat_with_invalid_challenge_is_ignored() {
+        // Given
+        let query_server = query_server();
+        let mut challenges = Challenges::default();
+
+        // When
+        let response = query_server
+            .respond(
+                &QueryRequest::BasicStat {
+                    session_id: 1,
+                    challenge: 42,
+                },
+                address(),
+                &mut challenges,
+                Instant::now(),
+            )
+            .await;
+
+        // Then
+        assert!(response.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_full_stat_lists_players() {
+        // Given
+        let query_server = query_server();
+        let _notch =
+            query_server
+                .players
+                .register(Uuid::from_u128(1), "Notch", ProtocolVersion::V1_21_2);
+        let info = QueryInfo::new(
+            &*query_server.state.read().await,
+            &query_server.players,
+            &query_server.server_address,
+        );
+
+        // When
+        let response = info.full_stat(1);
+
+        // Then
+        let strings = split_strings(&response[5 + FULL_STAT_PADDING.len()..]);
+        assert_eq!(
+            strings[..4],
+            ["hostname", "A Minecraft Server", "gametype", "SMP"]
+        );
+        assert!(strings.contains(&"numplayers".to_string()));
+        assert!(response.ends_with(b"\x01player_\0\0Notch\0\0"));
+    }
+}
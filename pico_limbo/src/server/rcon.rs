@@ -0,0 +1,312 @@
+use crate::server::admin::Admin;
+use crate::server::server_address::ServerAddress;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use subtle::ConstantTimeEq;
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::time::timeout;
+use tracing::{debug, error, info, warn};
+
+const AUTH_TYPE: i32 = 3;
+const AUTH_RESPONSE_TYPE: i32 = 2;
+const COMMAND_TYPE: i32 = 2;
+const RESPONSE_TYPE: i32 = 0;
+/// Request id sent back when the password is wrong.
+const AUTH_FAILURE_ID: i32 = -1;
+/// Request id, type and the two null bytes ending the body.
+const MIN_PACKET_LENGTH: i32 = 10;
+/// Same limit as vanilla for the packets received.
+const MAX_PACKET_LENGTH: i32 = 1460;
+/// Replies longer than this are split over several packets, like vanilla.
+const MAX_RESPONSE_BODY_LENGTH: usize = 4096;
+/// How long a client has to log in before being disconnected.
+const AUTH_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Error)]
+pub enum RconError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("invalid packet length {0}")]
+    InvalidLength(i32),
+    #[error("authentication timed out")]
+    AuthTimeout,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct RconPacket {
+    request_id: i32,
+    packet_type: i32,
+    body: String,
+}
+
+impl RconPacket {
+    async fn read<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Self, RconError> {
+        let length = reader.read_i32_le().await?;
+        if !(MIN_PACKET_LENGTH..=MAX_PACKET_LENGTH).contains(&length) {
+            return Err(RconError::InvalidLength(length));
+        }
+        let request_id = reader.read_i32_le().await?;
+        let packet_type = reader.read_i32_le().await?;
+        let mut body = vec![0u8; usize::try_from(length - 8).unwrap_or_default()];
+        reader.read_exact(&mut body).await?;
+        let end = body
+            .iter()
+            .position(|byte| *byte == 0)
+            .unwrap_or(body.len());
+        Ok(Self {
+            request_id,
+            packet_type,
+            body: String::from_utf8_lossy(&body[..end]).to_string(),
+        })
+    }
+
+    fn encode(request_id: i32, packet_type: i32, body: &[u8]) -> Vec<u8> {
+        let length = i32::try_from(body.len() + 10).unwrap_or(i32::MAX);
+        let mut bytes = Vec::with_capacity(body.len() + 14);
+        bytes.extend(length.to_le_bytes());
+        bytes.extend(request_id.to_le_bytes());
+        bytes.extend(packet_type.to_le_bytes());
+        bytes.extend(body);
+        bytes.extend([0, 0]);
+        bytes
+    }
+}
+
+/// Runs the commands of remote administration tools through the Source RCON protocol.
+pub struct RconServer {
+    bind: ServerAddress,
+    password: String,
+    admin: Arc<Admin>,
+}
+
+impl RconServer {
+    pub const fn new(bind: ServerAddress, password: String, admin: Arc<Admin>) -> Self {
+        Self {
+            bind,
+            password,
+            admin,
+        }
+    }
+
+    /// Accepts connections until the task is aborted.
+    pub async fn run(self) {
+        let listener = match TcpListener::bind(self.bind.tuple()).await {
+            Ok(listener) => listener,
+            Err(err) => {
+                error!("Failed to bind RCON to {}: {}", self.bind, err);
+                return;
+            }
+        };
+        info!("RCON listening on: {}", self.bind);
+
+        let password: Arc<str> = Arc::from(self.password);
+        loop {
+            match listener.accept().await {
+                Ok((socket, address)) => {
+                    let password = Arc::clone(&password);
+                    let admin = Arc::clone(&self.admin);
+                    tokio::spawn(async move {
+                        if let Err(err) =
+                            handle_connection(socket, address, &password, &admin).await
+                        {
+                            debug!("RCON connection from {} closed: {}", address, err);
+                        }
+                    });
+                }
+                Err(err) => {
+                    error!("Failed to accept an RCON connection: {:?}", err);
+                }
+            }
+        }
+    }
+}
+
+async fn handle_connection<S: AsyncRead + AsyncWrite + Unpin>(
+    mut stream: S,
+    address: SocketAddr,
+    password: &str,
+    admin: &Admin,
+) -> Result<(), RconError> {
+    let login = timeout(AUTH_TIMEOUT, RconPacket::read(&mut stream))
+        .await
+        .map_err(|_| RconError::AuthTimeout)??;
+    if login.packet_type != AUTH_TYPE
+        || login.body.as_bytes().ct_eq(password.as_bytes()).unwrap_u8() != 1
+    {
+        warn!("RCON authentication failed from {}", address);
+        let packet = RconPacket::encode(AUTH_FAILURE_ID, AUTH_RESPONSE_TYPE, &[]);
+        stream.write_all(&packet).await?;
+        return Ok(());
+    }
+    debug!("RCON client {} logged in", address);
+    let packet = RconPacket::encode(login.request_id, AUTH_RESPONSE_TYPE, &[]);
+    stream.write_all(&packet).await?;
+
+    loop {
+        let request = match RconPacket::read(&mut stream).await {
+            Ok(request) => request,
+            Err(RconError::Io(err)) if err.kind() == std::io::ErrorKind::UnexpectedEof => {
+                return Ok(());
+            }
+            Err(err) => return Err(err),
+        };
+        let reply = if request.packet_type == COMMAND_TYPE {
+            info!("RCON {} issued command: {}", address, request.body);
+            match admin.run(&request.body).await {
+                Some(Ok(reply)) => reply.to_plain_text(),
+                Some(Err(err)) => err.to_string(),
+                None => String::new(),
+            }
+        } else {
+            format!("Unknown request {:x}", request.packet_type)
+        };
+        send_reply(&mut stream, request.request_id, &reply).await?;
+    }
+}
+
+async fn send_reply<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    request_id: i32,
+    reply: &str,
+) -> Result<(), RconError> {
+    let bytes = reply.as_bytes();
+    if bytes.is_empty() {
+        writer
+            .write_all(&RconPacket::encode(request_id, RESPONSE_TYPE, &[]))
+            .await?;
+    }
+    for chunk in bytes.chunks(MAX_RESPONSE_BODY_LENGTH) {
+        writer
+            .write_all(&RconPacket::encode(request_id, RESPONSE_TYPE, chunk))
+            .await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::player_registry::PlayerRegistry;
+    use crate::server::reload::ConfigReloader;
+    use crate::server_state::ServerState;
+    use minecraft_protocol::prelude::{ProtocolVersion, Uuid};
+    use std::path::PathBuf;
+    use tokio::io::DuplexStream;
+    use tokio::sync::{RwLock, broadcast};
+    use tokio_util::sync::CancellationToken;
+
+    const PASSWORD: &str = "secret";
+
+    fn admin() -> Arc<Admin> {
+        let state = Arc::new(RwLock::new(ServerState::builder().build().unwrap()));
+        let (reloads, _) = broadcast::channel(1);
+        let reloader = ConfigReloader::new(
+            PathBuf::from("server.toml"),
+            Arc::clone(&state),
+            reloads,
+            false,
+        );
+        Admin::new(
+            state,
+            PlayerRegistry::new(),
+            reloader,
+            CancellationToken::new(),
+        )
+    }
+
+    fn connect(admin: Arc<Admin>) -> DuplexStream {
+        let (client, server) = tokio::io::duplex(16 * 1024);
+        tokio::spawn(async move {
+            let address = "127.0.0.1:50000".parse().unwrap();
+            let _ = handle_connection(server, address, PASSWORD, &admin).await;
+        });
+        client
+    }
+
+    async fn send(client: &mut DuplexStream, request_id: i32, packet_type: i32, body: &str) {
+        let packet = RconPacket::encode(request_id, packet_type, body.as_bytes());
+        client.write_all(&packet).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_login_then_run_command() {
+        // Given
+        let admin = admin();
+        let _notch =
+            admin
+                .players()
+                .register(Uuid::from_u128(1), "Notch", ProtocolVersion::V1_21_2);
+        let mut client = connect(admin);
+
+        // When
+        send(&mut client, 7, AUTH_TYPE, PASSWORD).await;
+        let login = RconPacket::read(&mut client).await.unwrap();
+        send(&mut client, 8, COMMAND_TYPE, "list").await;
+        let reply = RconPacket::read(&mut client).await.unwrap();
+
+        // Then
+        assert_eq!(login.request_id, 7);
+        assert_eq!(login.packet_type, AUTH_RESPONSE_TYPE);
+        assert_eq!(
+            reply,
+            RconPacket {
+                request_id: 8,
+                packet_type: RESPONSE_TYPE,
+                body: "Players online (1): Notch".to_string(),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_wrong_password_is_rejected() {
+        // Given
+        let mut client = connect(admin());
+
+        // When
+        send(&mut client, 7, AUTH_TYPE, "guess").await;
+        let login = RconPacket::read(&mut client).await.unwrap();
+        let mut rest = Vec::new();
+        client.read_to_end(&mut rest).await.unwrap();
+
+        // Then
+        assert_eq!(login.request_id, AUTH_FAILURE_ID);
+        assert!(rest.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_errors_are_sent_as_replies() {
+        // Given
+        let mut client = connect(admin());
+        send(&mut client, 1, AUTH_TYPE, PASSWORD).await;
+        RconPacket::read(&mut client).await.unwrap();
+
+        // When
+        send(&mut client, 2, COMMAND_TYPE, "kick Notch").await;
+        let reply = RconPacket::read(&mut client).await.unwrap();
+
+        // Then
+        assert_eq!(reply.body, "Notch is not online");
+    }
+
+    #[tokio::test]
+    async fn test_long_replies_are_split() {
+        // Given
+        let (mut client, mut server) = tokio::io::duplex(16 * 1024);
+        let reply = "a".repeat(MAX_RESPONSE_BODY_LENGTH + 1);
+
+        // When
+        send_reply(&mut server, 3, &reply).await.unwrap();
+        let first_length = client.read_i32_le().await.unwrap();
+        let mut first = vec![0u8; usize::try_from(first_length).unwrap()];
+        client.read_exact(&mut first).await.unwrap();
+        let second = RconPacket::read(&mut client).await.unwrap();
+
+        // Then
+        assert_eq!(first_length, 4106);
+        assert_eq!(second.request_id, 3);
+        assert_eq!(second.body, "a");
+    }
+}
@@ -0,0 +1,477 @@
+//! Recording of the packets exchanged with a connection.
+//!
+//! A recording starts with the `PLREC` magic and the format version, followed by frames.
+//! Each frame starts with its kind and the milliseconds elapsed since the connection started,
+//! as a `VarLong`:
+//! - protocol version: the version number as a `VarInt`,
+//! - state: the direction and the state, as one byte each,
+//! - serverbound or clientbound packet: the length of the packet as a `VarInt`, followed by its ID and data.
+//!
+//! The protocol version and the state of a direction are only recorded when they change,
+//! right before the packet they apply to.
+//!
+//! What proxies forward after the hostname of the handshake and the answer to the Velocity query
+//! are removed before being written, as they carry the forwarding secrets.
+
+use minecraft_packets::handshaking::handshake_packet::HandshakePacket;
+use minecraft_packets::login::custom_query_answer_packet::CustomQueryAnswerPacket;
+use minecraft_protocol::prelude::{
+    BinaryReader, BinaryReaderError, BinaryWriter, BinaryWriterError, DecodePacket, Direction,
+    EncodePacket, ProtocolVersion, State, VarInt, VarLong,
+};
+use net::raw_packet::RawPacket;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+const MAGIC: &[u8; 5] = b"PLREC";
+const FORMAT_VERSION: u8 = 1;
+
+const PROTOCOL_VERSION_FRAME: u8 = 0;
+const STATE_FRAME: u8 = 1;
+const SERVERBOUND_FRAME: u8 = 2;
+const CLIENTBOUND_FRAME: u8 = 3;
+
+const HANDSHAKE_PACKET_ID: u8 = 0x00;
+const CUSTOM_QUERY_ANSWER_PACKET_ID: u8 = 0x02;
+
+#[derive(Debug, Error)]
+pub enum RecordingError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Write(#[from] BinaryWriterError),
+    #[error("truncated or corrupted frame: {0}")]
+    Read(#[from] BinaryReaderError),
+    #[error("not a recording, or recorded with an unsupported format")]
+    InvalidHeader,
+    #[error("unknown frame kind {0}")]
+    UnknownFrame(u8),
+    #[error("unknown direction {0}")]
+    UnknownDirection(u8),
+    #[error("unknown state {0}")]
+    UnknownState(u8),
+}
+
+/// Writes the packets of a single connection, along with its protocol version and states.
+pub struct ConnectionRecorder<W: Write = BufWriter<File>> {
+    writer: W,
+    started_at: Instant,
+    protocol_version: Option<ProtocolVersion>,
+    serverbound_state: Option<State>,
+    clientbound_state: Option<State>,
+}
+
+impl ConnectionRecorder {
+    /// Creates the recording of a new connection in the directory, created when missing.
+    pub fn create(directory: &Path, remote_address: SocketAddr) -> Result<Self, RecordingError> {
+        fs::create_dir_all(directory)?;
+        let started_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        // Colons are not allowed in file names on every platform
+        let address = remote_address.to_string().replace([':', '[', ']'], "_");
+        let file = File::create(directory.join(format!("{started_at}-{address}.plrec")))?;
+        Self::new(BufWriter::new(file))
+    }
+}
+
+impl<W: Write> ConnectionRecorder<W> {
+    pub fn new(mut writer: W) -> Result<Self, RecordingError> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[FORMAT_VERSION])?;
+        Ok(Self {
+            writer,
+            started_at: Instant::now(),
+            protocol_version: None,
+            serverbound_state: None,
+            clientbound_state: None,
+        })
+    }
+
+    /// Records a packet, preceded by the protocol version and the state when they changed.
+    pub fn record(
+        &mut self,
+        direction: Direction,
+        state: State,
+        protocol_version: ProtocolVersion,
+        raw_packet: &RawPacket,
+    ) -> Result<(), RecordingError> {
+        let elapsed = self.started_at.elapsed();
+        let mut frames = BinaryWriter::new();
+
+        if self.protocol_version.replace(protocol_version) != Some(protocol_version) {
+            write_frame_header(&mut frames, PROTOCOL_VERSION_FRAME, elapsed)?;
+            frames.write(&VarInt::from(protocol_version.version_number()))?;
+        }
+        let recorded_state = match direction {
+            Direction::Serverbound => &mut self.serverbound_state,
+            Direction::Clientbound => &mut self.clientbound_state,
+        };
+        if recorded_state.replace(state) != Some(state) {
+            write_frame_header(&mut frames, STATE_FRAME, elapsed)?;
+            frames.write(&direction_to_byte(direction))?;
+            frames.write(&state_to_byte(state))?;
+        }
+
+        let kind = match direction {
+            Direction::Serverbound => SERVERBOUND_FRAME,
+            Direction::Clientbound => CLIENTBOUND_FRAME,
+        };
+        let redacted_packet = redact(direction, state, protocol_version, raw_packet);
+        let raw_packet = redacted_packet.as_ref().unwrap_or(raw_packet);
+        write_frame_header(&mut frames, kind, elapsed)?;
+        frames.write(&VarInt::try_from(raw_packet.size()).map_err(|_| {
+            io::Error::new(io::ErrorKind::InvalidInput, "packet too large to record")
+        })?)?;
+        frames.write_bytes(raw_packet.bytes())?;
+
+        self.writer.write_all(frames.as_slice())?;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), RecordingError> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Strips the player information and the `BungeeGuard` token a proxy appends to the hostname,
+/// and the signed player information sent by Velocity.
+/// Returns `None` when there is nothing to redact, undecodable packets being rejected anyway.
+fn redact(
+    direction: Direction,
+    state: State,
+    protocol_version: ProtocolVersion,
+    raw_packet: &RawPacket,
+) -> Option<RawPacket> {
+    if direction != Direction::Serverbound {
+        return None;
+    }
+    let packet_id = raw_packet.packet_id()?;
+    let mut reader = BinaryReader::new(raw_packet.data());
+    match (state, packet_id) {
+        (State::Handshake, HANDSHAKE_PACKET_ID) => {
+            let mut packet = HandshakePacket::decode(&mut reader, protocol_version).ok()?;
+            let (hostname, _) = packet.hostname.split_once('\0')?;
+            packet.hostname = hostname.to_string();
+            encode(packet_id, protocol_version, &packet)
+        }
+        (State::Login, CUSTOM_QUERY_ANSWER_PACKET_ID) => {
+            let mut packet = CustomQueryAnswerPacket::decode(&mut reader, protocol_version).ok()?;
+            packet.data.clear();
+            encode(packet_id, protocol_version, &packet)
+        }
+        _ => None,
+    }
+}
+
+fn encode(
+    packet_id: u8,
+    protocol_version: ProtocolVersion,
+    packet: &impl EncodePacket,
+) -> Option<RawPacket> {
+    let mut writer = BinaryWriter::new();
+    writer.write(&packet_id).ok()?;
+    packet.encode(&mut writer, protocol_version).ok()?;
+    RawPacket::new(writer.into_inner()).ok()
+}
+
+fn write_frame_header(
+    writer: &mut BinaryWriter,
+    kind: u8,
+    elapsed: Duration,
+) -> Result<(), BinaryWriterError> {
+    writer.write(&kind)?;
+    let elapsed = u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX);
+    writer.write(&VarLong::from(elapsed))
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Frame {
+    ProtocolVersion(ProtocolVersion),
+    State(Direction, State),
+    Packet(Direction, Vec<u8>),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct RecordedFrame {
+    /// Time elapsed since the connection started.
+    pub elapsed: Duration,
+    pub frame: Frame,
+}
+
+/// Reads the frames of a recording one at a time, so that a truncated recording can be read up to the corrupted frame.
+pub struct RecordingReader<'a> {
+    reader: BinaryReader<'a>,
+    failed: bool,
+}
+
+impl<'a> RecordingReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Result<Self, RecordingError> {
+        let frames = bytes
+            .strip_prefix(MAGIC.as_slice())
+            .and_then(|bytes| bytes.strip_prefix(&[FORMAT_VERSION]))
+            .ok_or(RecordingError::InvalidHeader)?;
+        Ok(Self {
+            reader: BinaryReader::new(frames),
+            failed: false,
+        })
+    }
+
+    fn read_frame(&mut self) -> Result<RecordedFrame, RecordingError> {
+        let kind = self.reader.read::<u8>()?;
+        let elapsed = self.reader.read::<VarLong>()?.inner();
+        let frame = match kind {
+            PROTOCOL_VERSION_FRAME => {
+                Frame::ProtocolVersion(ProtocolVersion::from(self.reader.read::<VarInt>()?.inner()))
+            }
+            STATE_FRAME => {
+                let direction = direction_from_byte(self.reader.read()?)?;
+                Frame::State(direction, state_from_byte(self.reader.read()?)?)
+            }
+            SERVERBOUND_FRAME | CLIENTBOUND_FRAME => {
+                let length = usize::try_from(self.reader.read::<VarInt>()?.inner()).unwrap_or(0);
+                let mut packet = vec![0; length];
+                if self.reader.read_bytes(&mut packet)? < length {
+                    return Err(BinaryReaderError::UnexpectedEof.into());
+                }
+                let direction = if kind == SERVERBOUND_FRAME {
+                    Direction::Serverbound
+                } else {
+                    Direction::Clientbound
+                };
+                Frame::Packet(direction, packet)
+            }
+            kind => return Err(RecordingError::UnknownFrame(kind)),
+        };
+        Ok(RecordedFrame {
+            elapsed: Duration::from_millis(u64::try_from(elapsed).unwrap_or(0)),
+            frame,
+        })
+    }
+}
+
+impl Iterator for RecordingReader<'_> {
+    type Item = Result<RecordedFrame, RecordingError>;
+
+    /// Stops after the first corrupted frame, as the following ones cannot be located.
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.reader.remaining() == 0 {
+            return None;
+        }
+        let frame = self.read_frame();
+        self.failed = frame.is_err();
+        Some(frame)
+    }
+}
+
+const fn direction_to_byte(direction: Direction) -> u8 {
+    match direction {
+        Direction::Serverbound => 0,
+        Direction::Clientbound => 1,
+    }
+}
+
+const fn direction_from_byte(byte: u8) -> Result<Direction, RecordingError> {
+    match byte {
+        0 => Ok(Direction::Serverbound),
+        1 => Ok(Direction::Clientbound),
+        byte => Err(RecordingError::UnknownDirection(byte)),
+    }
+}
+
+const fn state_to_byte(state: State) -> u8 {
+    match state {
+        State::Handshake => 0,
+        State::Status => 1,
+        State::Login => 2,
+        State::Configuration => 3,
+        State::Play => 4,
+        State::Transfer => 5,
+    }
+}
+
+const fn state_from_byte(byte: u8) -> Result<State, RecordingError> {
+    match byte {
+        0 => Ok(State::Handshake),
+        1 => Ok(State::Status),
+        2 => Ok(State::Login),
+        3 => Ok(State::Configuration),
+        4 => Ok(State::Play),
+        5 => Ok(State::Transfer),
+        byte => Err(RecordingError::UnknownState(byte)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recording_round_trip() {
+        // Given
+        let mut recorder = ConnectionRecorder::new(Vec::new()).unwrap();
+        let handshake = RawPacket::from_bytes(0x00, &[1, 2, 3]);
+        let login_start = RawPacket::from_bytes(0x00, &[4]);
+        let login_success = RawPacket::from_bytes(0x02, &[5, 6]);
+
+        // When
+        recorder
+            .record(
+                Direction::Serverbound,
+                State::Handshake,
+                ProtocolVersion::Any,
+                &handshake,
+            )
+            .unwrap();
+        recorder
+            .record(
+                Direction::Serverbound,
+                State::Login,
+                ProtocolVersion::V1_21_4,
+                &login_start,
+            )
+            .unwrap();
+        recorder
+            .record(
+                Direction::Clientbound,
+                State::Login,
+                ProtocolVersion::V1_21_4,
+                &login_success,
+            )
+            .unwrap();
+        let frames: Vec<Frame> = RecordingReader::new(&recorder.writer)
+            .unwrap()
+            .map(|recorded_frame| recorded_frame.unwrap().frame)
+            .collect();
+
+        // Then
+        assert_eq!(
+            frames,
+            vec![
+                Frame::ProtocolVersion(ProtocolVersion::Any),
+                Frame::State(Direction::Serverbound, State::Handshake),
+                Frame::Packet(Direction::Serverbound, vec![0x00, 1, 2, 3]),
+                Frame::ProtocolVersion(ProtocolVersion::V1_21_4),
+                Frame::State(Direction::Serverbound, State::Login),
+                Frame::Packet(Direction::Serverbound, vec![0x00, 4]),
+                Frame::State(Direction::Clientbound, State::Login),
+                Frame::Packet(Direction::Clientbound, vec![0x02, 5, 6]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_forwarding_secrets_are_redacted() {
+        // Given
+        let mut recorder = ConnectionRecorder::new(Vec::new()).unwrap();
+        let handshake = HandshakePacket {
+            protocol: VarInt::new(769),
+            hostname: "localhost\x00127.0.0.1\x00uuid\x00[{\"name\":\"bungeeguard-token\"}]"
+                .to_string(),
+            port: 25565,
+            next_state: VarInt::new(2),
+        };
+        let velocity_answer = CustomQueryAnswerPacket {
+            message_id: VarInt::new(7),
+            is_present: true,
+            data: vec![1, 2, 3],
+        };
+
+        // When
+        recorder
+            .record(
+                Direction::Serverbound,
+                State::Handshake,
+                ProtocolVersion::Any,
+                &encode(HANDSHAKE_PACKET_ID, ProtocolVersion::Any, &handshake).unwrap(),
+            )
+            .unwrap();
+        recorder
+            .record(
+                Direction::Serverbound,
+                State::Login,
+                ProtocolVersion::V1_21_4,
+                &encode(
+                    CUSTOM_QUERY_ANSWER_PACKET_ID,
+                    ProtocolVersion::V1_21_4,
+                    &velocity_answer,
+                )
+                .unwrap(),
+            )
+            .unwrap();
+        let packets: Vec<Vec<u8>> = RecordingReader::new(&recorder.writer)
+            .unwrap()
+            .filter_map(|recorded_frame| match recorded_frame.unwrap().frame {
+                Frame::Packet(_, bytes) => Some(bytes),
+                _ => None,
+            })
+            .collect();
+
+        // Then
+        let redacted_handshake = HandshakePacket {
+            hostname: "localhost".to_string(),
+            ..handshake
+        };
+        let redacted_answer = CustomQueryAnswerPacket {
+            data: Vec::new(),
+            ..velocity_answer
+        };
+        assert_eq!(
+            packets,
+            vec![
+                encode(
+                    HANDSHAKE_PACKET_ID,
+                    ProtocolVersion::Any,
+                    &redacted_handshake
+                )
+                .unwrap()
+                .bytes()
+                .to_vec(),
+                encode(
+                    CUSTOM_QUERY_ANSWER_PACKET_ID,
+                    ProtocolVersion::V1_21_4,
+                    &redacted_answer
+                )
+                .unwrap()
+                .bytes()
+                .to_vec(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rejects_truncated_recording() {
+        // Given
+        let mut recorder = ConnectionRecorder::new(Vec::new()).unwrap();
+        recorder
+            .record(
+                Direction::Serverbound,
+                State::Handshake,
+                ProtocolVersion::Any,
+                &RawPacket::from_bytes(0x00, &[1, 2, 3]),
+            )
+            .unwrap();
+
+        // When
+        let truncated: Vec<_> = RecordingReader::new(&recorder.writer[..recorder.writer.len() - 1])
+            .unwrap()
+            .collect();
+        let not_a_recording = RecordingReader::new(b"not a recording");
+
+        // Then
+        assert!(matches!(
+            truncated.as_slice(),
+            [Ok(_), Ok(_), Err(RecordingError::Read(_))]
+        ));
+        assert!(matches!(
+            not_a_recording,
+            Err(RecordingError::InvalidHeader)
+        ));
+    }
+}
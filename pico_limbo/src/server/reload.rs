@@ -0,0 +1,234 @@
+use crate::configuration::config::{ConfigError, load};
+use crate::server::start_server::build_state;
+use crate::server_state::{ReloadedSections, ServerState, ServerStateBuilderError};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime};
+use thiserror::Error;
+use tokio::sync::{RwLock, broadcast};
+use tokio::task::JoinError;
+use tracing::{error, info, warn};
+
+/// How often the configuration file is checked for modifications when watched.
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Error)]
+pub enum ReloadError {
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+    #[error(transparent)]
+    State(#[from] ServerStateBuilderError),
+    #[error("reload task failed: {0}")]
+    Join(#[from] JoinError),
+}
+
+/// Rebuilds the server state from the configuration file and swaps it in,
+/// then notifies connected players of the sections that changed.
+pub struct ConfigReloader {
+    config_path: PathBuf,
+    state: Arc<RwLock<ServerState>>,
+    reloads: broadcast::Sender<ReloadedSections>,
+    watch_config_file: AtomicBool,
+}
+
+impl ConfigReloader {
+    pub fn new(
+        config_path: PathBuf,
+        state: Arc<RwLock<ServerState>>,
+        reloads: broadcast::Sender<ReloadedSections>,
+        watch_config_file: bool,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            config_path,
+            state,
+            reloads,
+            watch_config_file: AtomicBool::new(watch_config_file),
+        })
+    }
+
+    /// Loads the configuration file and replaces the running state.
+    /// The running state is left untouched when anything goes wrong.
+    pub async fn reload(&self) -> Result<ReloadedSections, ReloadError> {
+        let config_path = self.config_path.clone();
+        let (new_state, watch_config_file) = tokio::task::spawn_blocking(move || {
+            let cfg = load(config_path)?;
+            let watch_config_file = cfg.watch_config_file;
+            Ok::<_, ReloadError>((build_state(cfg)?, watch_config_file))
+        })
+        .await??;
+
+        let sections = swap_state(&self.state, new_state).await;
+        self.watch_config_file
+            .store(watch_config_file, Ordering::Relaxed);
+        if !sections.is_empty() {
+            // Nobody is listening when no player is connected
+            let _ = self.reloads.send(sections);
+        }
+        Ok(sections)
+    }
+
+    /// Reloads the configuration and logs the outcome, returns whether it succeeded.
+    pub async fn reload_and_log(&self) -> bool {
+        match self.reload().await {
+            Ok(_) => {
+                info!("Configuration reloaded");
+                true
+            }
+            Err(err) => {
+                error!("Failed to reload the configuration, keeping the current one: {err}");
+                false
+            }
+        }
+    }
+
+    /// Reloads the whitelist and ban files modified since they were last read.
+    async fn reload_access_lists(&self) {
+        let state = Arc::clone(&self.state);
+        let results = tokio::task::spawn_blocking(move || {
+            state.blocking_read().access_lists().reload_changed_files()
+        })
+        .await;
+        for result in results.unwrap_or_default() {
+            match result {
+                Ok(path) => info!("Reloaded {}", path.display()),
+                Err(err) => error!("Failed to reload an access list, keeping its entries: {err}"),
+            }
+        }
+    }
+
+    /// Reloads on SIGHUP, and whenever the configuration file changes if it is watched.
+    /// The whitelist and ban files are reloaded whenever they change.
+    pub async fn watch(self: Arc<Self>) {
+        let mut hangup = HangupSignal::new();
+        let mut interval = tokio::time::interval(WATCH_INTERVAL);
+        let mut last_modified = modified_time(&self.config_path);
+
+        loop {
+            tokio::select! {
+                () = hangup.recv() => {
+                    info!("SIGHUP received, reloading the configuration");
+                }
+                _ = interval.tick() => {
+                    self.reload_access_lists().await;
+                    if !self.watch_config_file.load(Ordering::Relaxed) {
+                        continue;
+                    }
+                    let modified = modified_time(&self.config_path);
+                    if modified == last_modified {
+                        continue;
+                    }
+                    last_modified = modified;
+                    info!("Configuration file changed, reloading it");
+                }
+            }
+            self.reload_and_log().await;
+        }
+    }
+}
+
+async fn swap_state(state: &RwLock<ServerState>, mut new_state: ServerState) -> ReloadedSections {
+    let mut state = state.write().await;
+    new_state.carry_over(&mut state);
+    let sections = new_state.reloaded_sections(&state);
+    *state = new_state;
+    drop(state);
+    sections
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+struct HangupSignal {
+    #[cfg(unix)]
+    signal: Option<tokio::signal::unix::Signal>,
+}
+
+impl HangupSignal {
+    fn new() -> Self {
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{SignalKind, signal};
+            let signal = signal(SignalKind::hangup())
+                .inspect_err(|err| warn!("Failed to install SIGHUP handler: {err}"))
+                .ok();
+            Self { signal }
+        }
+
+        #[cfg(not(unix))]
+        {
+            Self {}
+        }
+    }
+
+    /// Completes when SIGHUP is received, never on platforms without it.
+    async fn recv(&mut self) {
+        #[cfg(unix)]
+        if let Some(signal) = self.signal.as_mut()
+            && signal.recv().await.is_some()
+        {
+            return;
+        }
+        std::future::pending::<()>().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server_state(tab_list_header: &str) -> ServerState {
+        let mut builder = ServerState::builder();
+        builder.tab_list(tab_list_header, "").unwrap();
+        builder.show_online_player_count(true);
+        builder.build().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_swap_state_keeps_connected_clients() {
+        // Given
+        let state = RwLock::new(server_state("Header"));
+        state.read().await.increment();
+
+        // When
+        let sections = swap_state(&state, server_state("New header")).await;
+
+        // Then
+        assert_eq!(
+            sections,
+            ReloadedSections {
+                tab_list: true,
+                boss_bar: false,
+                title: false,
+            }
+        );
+        assert_eq!(state.read().await.online_players(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_reload_error_keeps_running_state() {
+        // Given
+        let running_state = Arc::new(RwLock::new(server_state("Header")));
+        let (sender, mut receiver) = broadcast::channel(1);
+        let reloader = ConfigReloader::new(
+            PathBuf::from("does/not/exist.toml"),
+            Arc::clone(&running_state),
+            sender,
+            false,
+        );
+
+        // When
+        let result = reloader.reload().await;
+
+        // Then
+        assert!(matches!(
+            result,
+            Err(ReloadError::Config(ConfigError::Io(_)))
+        ));
+        assert!(running_state.read().await.tab_list().is_some());
+        assert!(receiver.try_recv().is_err());
+    }
+}
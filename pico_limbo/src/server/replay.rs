@@ -0,0 +1,161 @@
+use crate::cli::ReplayArgs;
+use crate::server::packet_registry::PacketRegistry;
+use crate::server::recording::{Frame, RecordedFrame, RecordingReader};
+use minecraft_protocol::prelude::{Direction, ProtocolVersion, State};
+use net::raw_packet::RawPacket;
+use std::fs;
+use std::process::ExitCode;
+
+/// Prints every frame of a recording, naming each packet.
+pub fn replay(args: &ReplayArgs) -> ExitCode {
+    let path = args.recording_path.display();
+    let bytes = match fs::read(&args.recording_path) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            eprintln!("Failed to read {path}: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let reader = match RecordingReader::new(&bytes) {
+        Ok(reader) => reader,
+        Err(err) => {
+            eprintln!("Failed to read {path}: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut transcript = Transcript::default();
+    for frame in reader {
+        match frame {
+            Ok(frame) => println!("{}", transcript.describe(&frame, args.hex)),
+            Err(err) => {
+                eprintln!("Failed to read {path}: {err}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+/// Follows the protocol version and the states of the connection to decode its packets.
+#[derive(Default)]
+struct Transcript {
+    protocol_version: ProtocolVersion,
+    serverbound_state: State,
+    clientbound_state: State,
+}
+
+impl Transcript {
+    fn describe(&mut self, recorded_frame: &RecordedFrame, hex: bool) -> String {
+        let elapsed = recorded_frame.elapsed.as_secs_f64();
+        match &recorded_frame.frame {
+            Frame::ProtocolVersion(protocol_version) => {
+                self.protocol_version = *protocol_version;
+                format!(
+                    "{elapsed:>9.3}s  protocol version {} ({})",
+                    protocol_version.humanize(),
+                    protocol_version.version_number()
+                )
+            }
+            Frame::State(direction, state) => {
+                match direction {
+                    Direction::Serverbound => self.serverbound_state = *state,
+                    Direction::Clientbound => self.clientbound_state = *state,
+                }
+                format!("{elapsed:>9.3}s  {direction} state is now {state}")
+            }
+            Frame::Packet(direction, bytes) => {
+                let state = match direction {
+                    Direction::Serverbound => self.serverbound_state,
+                    Direction::Clientbound => self.clientbound_state,
+                };
+                // Display implementations ignore the width, hence the strings
+                let prefix = format!(
+                    "{elapsed:>9.3}s  {:<11}  {:<13}",
+                    direction.to_string(),
+                    state.to_string()
+                );
+                let Some((&packet_id, data)) = bytes.split_first() else {
+                    return format!("{prefix}  <empty packet>");
+                };
+                let raw_packet = RawPacket::from_bytes(packet_id, data);
+                let mut line = format!(
+                    "{prefix}  0x{packet_id:02X}  {}  ({} bytes)",
+                    self.packet_name(*direction, state, &raw_packet),
+                    bytes.len()
+                );
+                if hex && !data.is_empty() {
+                    line.push_str("\n            ");
+                    line.push_str(raw_packet.to_string().trim_end());
+                }
+                line
+            }
+        }
+    }
+
+    /// Serverbound packets are decoded to catch malformed packets, clientbound packets
+    /// are only named since the server never decodes them.
+    fn packet_name(&self, direction: Direction, state: State, raw_packet: &RawPacket) -> String {
+        let packet_id = raw_packet.packet_id().unwrap_or_default();
+        match direction {
+            Direction::Serverbound => {
+                let raw_packet = RawPacket::from_bytes(packet_id, raw_packet.data());
+                match PacketRegistry::decode_packet(self.protocol_version, state, raw_packet) {
+                    Ok(packet) => packet.packet_name().to_string(),
+                    Err(err) => format!("<{err}>"),
+                }
+            }
+            Direction::Clientbound => {
+                PacketRegistry::clientbound_packet_name(self.protocol_version, state, packet_id)
+                    .unwrap_or("<unknown packet>")
+                    .to_string()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn frame(frame: Frame) -> RecordedFrame {
+        RecordedFrame {
+            elapsed: Duration::from_millis(1500),
+            frame,
+        }
+    }
+
+    #[test]
+    fn test_packets_are_named_in_their_state() {
+        // Given
+        let handshake = vec![
+            0x00, 129, 6, 9, 108, 111, 99, 97, 108, 104, 111, 115, 116, 99, 221, 1,
+        ];
+        let mut transcript = Transcript::default();
+
+        // When
+        let lines: Vec<String> = [
+            Frame::ProtocolVersion(ProtocolVersion::V1_21_4),
+            Frame::State(Direction::Serverbound, State::Handshake),
+            Frame::Packet(Direction::Serverbound, handshake),
+            Frame::State(Direction::Serverbound, State::Login),
+            Frame::Packet(Direction::Serverbound, vec![0x00]),
+            Frame::State(Direction::Clientbound, State::Login),
+            Frame::Packet(Direction::Clientbound, vec![0x02, 0xAB]),
+        ]
+        .into_iter()
+        .map(|recorded_frame| transcript.describe(&frame(recorded_frame), true))
+        .collect();
+
+        // Then
+        assert_eq!(lines[0], "    1.500s  protocol version 1.21.4 (769)");
+        assert_eq!(lines[1], "    1.500s  serverbound state is now handshake");
+        assert!(lines[2].contains("0x00  minecraft:intention  (16 bytes)"));
+        assert!(lines[4].contains("0x00  <Failed to read packet>"));
+        assert_eq!(
+            lines[6],
+            "    1.500s  clientbound  login          0x02  minecraft:login_finished  (2 bytes)\n            AB"
+        );
+    }
+}
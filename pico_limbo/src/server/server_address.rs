@@ -19,7 +19,11 @@ impl Default for ServerAddress {
 
 impl Display for ServerAddress {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}:{}", self.host, self.port)
+        if self.host.contains(':') {
+            write!(f, "[{}]:{}", self.host, self.port)
+        } else {
+            write!(f, "{}:{}", self.host, self.port)
+        }
     }
 }
 
@@ -124,6 +128,12 @@ mod tests {
         assert_eq!(addr.port, 25565);
     }
 
+    #[test]
+    fn test_ip_v6_display() {
+        let addr = ServerAddress::parse("[::]:25565").unwrap();
+        assert_eq!(addr.to_string(), "[::]:25565");
+    }
+
     #[test]
     fn test_empty_string() {
         let addr = ServerAddress::parse("");
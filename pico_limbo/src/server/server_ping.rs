@@ -0,0 +1,99 @@
+use minecraft_protocol::prelude::{
+    BinaryReader, BinaryReaderError, BinaryWriter, BinaryWriterError, DecodePacket, EncodePacket,
+    ProtocolVersion, VarInt,
+};
+use net::packet_stream::{PacketStream, PacketStreamError};
+use net::raw_packet::RawPacket;
+use std::io;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+/// How long a server has to answer the status request.
+const PING_TIMEOUT: Duration = Duration::from_secs(5);
+const STATUS_NEXT_STATE: i32 = 1;
+
+#[derive(Debug, Error)]
+pub enum PingError {
+    #[error("timed out")]
+    Timeout,
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    PacketStream(#[from] PacketStreamError),
+    #[error(transparent)]
+    BinaryWriter(#[from] BinaryWriterError),
+    #[error(transparent)]
+    BinaryReader(#[from] BinaryReaderError),
+    #[error("invalid status response")]
+    InvalidResponse,
+}
+
+/// Sends a status request, the target is online once it answers with a JSON response.
+pub async fn ping(host: &str, port: u16) -> Result<(), PingError> {
+    timeout(PING_TIMEOUT, async {
+        let protocol_version = ProtocolVersion::latest();
+        let mut stream = PacketStream::new(TcpStream::connect((host, port)).await?);
+
+        let mut handshake = BinaryWriter::new();
+        VarInt::from(protocol_version.version_number()).encode(&mut handshake, protocol_version)?;
+        host.to_string().encode(&mut handshake, protocol_version)?;
+        port.encode(&mut handshake, protocol_version)?;
+        VarInt::from(STATUS_NEXT_STATE).encode(&mut handshake, protocol_version)?;
+        stream
+            .write_packet(RawPacket::from_bytes(0, &handshake.into_inner()))
+            .await?;
+        stream.write_packet(RawPacket::from_bytes(0, &[])).await?;
+
+        let response = stream.read_packet().await?;
+        if response.packet_id() != Some(0) {
+            return Err(PingError::InvalidResponse);
+        }
+        let json = String::decode(&mut BinaryReader::new(response.data()), protocol_version)?;
+        serde_json::from_str::<serde_json::Value>(&json)
+            .map(|_| ())
+            .map_err(|_| PingError::InvalidResponse)
+    })
+    .await
+    .map_err(|_| PingError::Timeout)?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_ping_reads_the_status_response() {
+        // Given
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let backend = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut stream = PacketStream::new(stream);
+            let handshake = stream.read_packet().await.unwrap();
+            let status_request = stream.read_packet().await.unwrap();
+            let mut response = BinaryWriter::new();
+            r#"{"version":{"name":"1.21.11","protocol":774}}"#
+                .to_string()
+                .encode(&mut response, ProtocolVersion::Any)
+                .unwrap();
+            stream
+                .write_packet(RawPacket::from_bytes(0, &response.into_inner()))
+                .await
+                .unwrap();
+            (handshake, status_request)
+        });
+
+        // When
+        let result = ping("127.0.0.1", port).await;
+
+        // Then
+        assert!(result.is_ok());
+        let (handshake, status_request) = backend.await.unwrap();
+        assert_eq!(handshake.packet_id(), Some(0));
+        assert_eq!(handshake.data().last(), Some(&1));
+        assert_eq!(status_request.bytes(), &[0]);
+    }
+}
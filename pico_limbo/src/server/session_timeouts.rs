@@ -0,0 +1,256 @@
+use crate::handlers::configuration::{send_message, title_packets};
+use crate::kick_messages::{AFK_KICK_REASON, SESSION_LIMIT_KICK_REASON};
+use crate::server::backend_watcher::proxy_connect_packet;
+use crate::server::batch::Batch;
+use crate::server::client_state::ClientState;
+use crate::server::packet_registry::PacketRegistry;
+use crate::server_state::{
+    ServerState, SessionTimeout, TimeoutStep, TimeoutTransfer, Title, TitleType, WarningDisplay,
+};
+use minecraft_packets::play::transfer_packet::TransferPacket;
+use minecraft_protocol::prelude::{ProtocolVersion, VarInt};
+use std::time::{Duration, Instant};
+use tracing::{error, info};
+
+/// Warns, then kicks or transfers the player once idle or in game for too long.
+pub fn check_session_timeouts(
+    client_state: &mut ClientState,
+    server_state: &ServerState,
+    now: Instant,
+) -> Batch {
+    let mut batch = Batch::new();
+
+    if let Some(session_limit) = server_state.session_limit()
+        && let Some(session_duration) = client_state.session_duration(now)
+    {
+        match session_limit.step(session_duration, client_state.is_session_limit_warned()) {
+            Some(TimeoutStep::Warn(remaining)) => {
+                warn(session_limit, remaining, client_state, &mut batch);
+                client_state.set_session_limit_warned();
+            }
+            Some(TimeoutStep::Expire) => {
+                info!("Session of {} has ended", client_state.get_username());
+                if expire(
+                    session_limit,
+                    SESSION_LIMIT_KICK_REASON,
+                    client_state,
+                    &mut batch,
+                ) {
+                    client_state.start_session(now);
+                }
+                return batch;
+            }
+            None => {}
+        }
+    }
+
+    if let Some(afk_timeout) = server_state.afk_timeout() {
+        let idle_duration = client_state.idle_duration(now);
+        match afk_timeout.step(idle_duration, client_state.is_afk_warned()) {
+            Some(TimeoutStep::Warn(remaining)) => {
+                warn(afk_timeout, remaining, client_state, &mut batch);
+                client_state.set_afk_warned();
+            }
+            Some(TimeoutStep::Expire) => {
+                info!("{} was idle for too long", client_state.get_username());
+                if expire(afk_timeout, AFK_KICK_REASON, client_state, &mut batch) {
+                    client_state.record_activity(now);
+                }
+            }
+            None => {}
+        }
+    }
+
+    batch
+}
+
+/// Displays the warning in a title since 1.8, or in the chat.
+fn warn(
+    timeout: &SessionTimeout,
+    remaining: Duration,
+    client_state: &ClientState,
+    batch: &mut Batch,
+) {
+    let Some(warning) = &timeout.warning else {
+        return;
+    };
+    let message = match warning.message(remaining) {
+        Ok(message) => message,
+        Err(err) => {
+            error!("Failed to format the warning: {err}");
+            return;
+        }
+    };
+
+    let protocol_version = client_state.protocol_version();
+    if warning.display == WarningDisplay::Title
+        && protocol_version.is_after_inclusive(ProtocolVersion::V1_8)
+    {
+        let title = Title {
+            content: TitleType::Subtitle(message),
+            fade_in: 10,
+            stay: 70,
+            fade_out: 20,
+        };
+        for packet in title_packets(&title, protocol_version) {
+            batch.queue(|| packet);
+        }
+    } else {
+        send_message(batch, &message, protocol_version);
+    }
+}
+
+/// Transfers the player, or asks the proxy to move them, and kicks the players who can be moved
+/// neither way. Returns whether the player was sent away without being kicked.
+fn expire(
+    timeout: &SessionTimeout,
+    reason: &'static str,
+    client_state: &mut ClientState,
+    batch: &mut Batch,
+) -> bool {
+    let protocol_version = client_state.protocol_version();
+    match &timeout.transfer {
+        Some(transfer) if protocol_version.is_after_inclusive(ProtocolVersion::V1_20_5) => {
+            let packet =
+                TransferPacket::new(&transfer.host, &VarInt::from(i32::from(transfer.port)));
+            batch.queue(|| PacketRegistry::Transfer(packet));
+            true
+        }
+        Some(TimeoutTransfer {
+            proxy_connect: Some(payload),
+            ..
+        }) => {
+            let packet = proxy_connect_packet(payload, protocol_version);
+            batch.queue(|| packet);
+            true
+        }
+        _ => {
+            client_state.kick_component(timeout.kick_message.clone(), reason);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configuration::session_timeouts::{
+        AfkConfig, SessionLimitConfig, TimeoutActionConfig,
+    };
+    use crate::server::batch::BatchItem;
+    use futures::StreamExt;
+
+    fn server_state(action: TimeoutActionConfig) -> ServerState {
+        let mut builder = ServerState::builder();
+        builder
+            .afk(AfkConfig {
+                timeout_seconds: 60,
+                warning_seconds: 10,
+                action,
+                ..AfkConfig::default()
+            })
+            .unwrap()
+            .session_limit(SessionLimitConfig {
+                max_duration_seconds: 600,
+                warning_seconds: 0,
+                action,
+                ..SessionLimitConfig::default()
+            })
+            .unwrap();
+        builder.build().unwrap()
+    }
+
+    fn client_state(protocol_version: ProtocolVersion, now: Instant) -> ClientState {
+        let mut client_state = ClientState::default();
+        client_state.set_protocol_version(protocol_version);
+        client_state.start_session(now);
+        client_state
+    }
+
+    async fn packets(batch: Batch) -> Vec<BatchItem> {
+        batch.into_stream().collect().await
+    }
+
+    #[tokio::test]
+    async fn test_idle_player_is_warned_then_kicked() {
+        // Given
+        let now = Instant::now();
+        let server_state = server_state(TimeoutActionConfig::Kick);
+        let mut client_state = client_state(ProtocolVersion::V1_21_2, now);
+        client_state.record_position(0.0, 0.0, now);
+        client_state.record_position(0.0, 0.0, now + Duration::from_secs(30));
+
+        // When
+        let warning = check_session_timeouts(
+            &mut client_state,
+            &server_state,
+            now + Duration::from_secs(55),
+        );
+        let nothing = check_session_timeouts(
+            &mut client_state,
+            &server_state,
+            now + Duration::from_secs(56),
+        );
+        let kick = check_session_timeouts(
+            &mut client_state,
+            &server_state,
+            now + Duration::from_mins(1),
+        );
+
+        // Then
+        assert!(matches!(
+            packets(warning).await.as_slice(),
+            [BatchItem::Packet(PacketRegistry::SystemChatMessage(_))]
+        ));
+        assert!(packets(nothing).await.is_empty());
+        assert!(packets(kick).await.is_empty());
+        assert!(client_state.should_kick().is_some());
+        assert_eq!(client_state.kick_reason(), AFK_KICK_REASON);
+    }
+
+    #[tokio::test]
+    async fn test_moving_player_is_not_idle() {
+        // Given
+        let now = Instant::now();
+        let server_state = server_state(TimeoutActionConfig::Kick);
+        let mut client_state = client_state(ProtocolVersion::V1_21_2, now);
+        client_state.record_position(0.0, 0.0, now);
+        client_state.record_position(1.0, 0.0, now + Duration::from_secs(30));
+
+        // When
+        let batch = check_session_timeouts(
+            &mut client_state,
+            &server_state,
+            now + Duration::from_mins(1),
+        );
+
+        // Then
+        assert!(packets(batch).await.is_empty());
+        assert!(client_state.should_kick().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_session_limit_transfers_or_kicks() {
+        // Given
+        let now = Instant::now();
+        let server_state = server_state(TimeoutActionConfig::Transfer);
+        let mut modern = client_state(ProtocolVersion::V1_21_2, now);
+        let mut legacy = client_state(ProtocolVersion::V1_8, now);
+        let end = now + Duration::from_mins(10);
+        modern.record_activity(end);
+        legacy.record_activity(end);
+
+        // When
+        let transfer = check_session_timeouts(&mut modern, &server_state, end);
+        let kick = check_session_timeouts(&mut legacy, &server_state, end);
+
+        // Then
+        assert!(matches!(
+            packets(transfer).await.as_slice(),
+            [BatchItem::Packet(PacketRegistry::Transfer(_))]
+        ));
+        assert_eq!(modern.session_duration(end), Some(Duration::ZERO));
+        assert!(packets(kick).await.is_empty());
+        assert_eq!(legacy.kick_reason(), SESSION_LIMIT_KICK_REASON);
+    }
+}
@@ -1,17 +1,30 @@
 use crate::banner;
 use crate::configuration::TaggedForwarding;
+use crate::configuration::access_lists::{BansConfig, WhitelistConfig};
+use crate::configuration::bind::BindConfig;
 use crate::configuration::boss_bar::BossBarConfig;
 use crate::configuration::config::{Config, ConfigError, load_or_create};
+use crate::configuration::session_timeouts::{AfkConfig, SessionLimitConfig};
 use crate::configuration::tab_list::TabListMode;
 use crate::configuration::title::TitleConfig;
 use crate::configuration::world_config::boundaries::BoundariesConfig;
+use crate::server::admin::Admin;
+use crate::server::announcer::Announcer;
+use crate::server::backend_watcher::BackendWatcher;
+use crate::server::console::Console;
+use crate::server::join_queue::QueueManager;
+use crate::server::listener::BindAddress;
+use crate::server::metrics::serve_metrics;
 use crate::server::network::Server;
+use crate::server::query::QueryServer;
+use crate::server::rcon::RconServer;
 use crate::server::server_address::ServerAddress;
-use crate::server_state::{ServerState, ServerStateBuilderError};
+use crate::server_state::{ServerState, ServerStateBuilder, ServerStateBuilderError};
 use std::path::PathBuf;
 use std::process::ExitCode;
+use std::sync::Arc;
 use tokio_util::sync::CancellationToken;
-use tracing::{Level, debug, error};
+use tracing::{Level, debug, error, warn};
 use tracing_subscriber::EnvFilter;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
@@ -25,19 +38,90 @@ pub async fn start_server(
         return ExitCode::FAILURE;
     };
 
-    let mut bind = ServerAddress::parse(&cfg.bind).unwrap_or_default();
-    if let Some(port) = cli.port {
-        bind.set_port(port);
-    }
+    let Some(binds) = parse_bind_addresses(&cfg.bind, cli.port) else {
+        return ExitCode::FAILURE;
+    };
+    let Some(unix_socket_permissions) = cfg.unix_socket.mode() else {
+        error!(
+            "Invalid Unix socket permissions: {:?}",
+            cfg.unix_socket.permissions
+        );
+        return ExitCode::FAILURE;
+    };
 
+    let watch_config_file = cfg.watch_config_file;
+    let metrics_bind = parse_optional_bind(cfg.metrics.enabled, &cfg.metrics.bind, "metrics");
+    let query_bind = parse_optional_bind(cfg.query.enabled, &cfg.query.bind, "query");
+    let rcon = if cfg.rcon.enabled && cfg.rcon.password.is_empty() {
+        error!("RCON is enabled without a password, it will not be started");
+        None
+    } else {
+        parse_optional_bind(cfg.rcon.enabled, &cfg.rcon.bind, "RCON")
+            .map(|bind| (bind, cfg.rcon.password.clone()))
+    };
     match build_state(cfg) {
         Ok(server_state) => {
             if !cli.skip_banner {
                 banner::display_banner();
             }
-            Server::new(&bind, server_state)
-                .run(cancellation_token)
-                .await;
+            // Answers queries with the first TCP address, like the server list of a single server
+            let query_address = binds
+                .iter()
+                .find_map(|bind| match bind {
+                    BindAddress::Tcp(address) => Some(address.clone()),
+                    BindAddress::Unix(_) => None,
+                })
+                .unwrap_or_default();
+            let server = Server::new(binds, unix_socket_permissions, server_state);
+            let reloader = server.reloader(cli.config_path.clone(), watch_config_file);
+            let reload_watcher = tokio::spawn(Arc::clone(&reloader).watch());
+            let announcer = tokio::spawn(Announcer::new(server.state(), server.players()).run());
+            let backend_watcher =
+                tokio::spawn(BackendWatcher::new(server.state(), server.players()).run());
+            let queue = tokio::spawn(QueueManager::new(server.state(), server.players()).run());
+            let metrics_server =
+                metrics_bind.map(|bind| tokio::spawn(serve_metrics(bind, server.players())));
+            let query_server = query_bind.map(|query_bind| {
+                let query_server =
+                    QueryServer::new(query_bind, query_address, server.state(), server.players());
+                tokio::spawn(query_server.run())
+            });
+            let admin = Admin::new(
+                server.state(),
+                server.players(),
+                reloader,
+                server.stop_token(),
+            );
+            let rcon_server = rcon.map(|(rcon_bind, password)| {
+                let rcon_server = RconServer::new(rcon_bind, password, Arc::clone(&admin));
+                tokio::spawn(rcon_server.run())
+            });
+            // The standard input belongs to the host application when embedded
+            let console = (!cli.no_console && cancellation_token.is_none()).then(|| {
+                let console = Console::new(admin, server.stop_token());
+                tokio::spawn(console.run())
+            });
+            let result = server.run(cancellation_token).await;
+            reload_watcher.abort();
+            announcer.abort();
+            backend_watcher.abort();
+            queue.abort();
+            if let Some(metrics_server) = metrics_server {
+                metrics_server.abort();
+            }
+            if let Some(query_server) = query_server {
+                query_server.abort();
+            }
+            if let Some(rcon_server) = rcon_server {
+                rcon_server.abort();
+            }
+            if let Some(console) = console {
+                console.abort();
+            }
+            if let Err(err) = result {
+                error!("{err}");
+                return ExitCode::FAILURE;
+            }
             ExitCode::SUCCESS
         }
         Err(err) => {
@@ -47,6 +131,38 @@ pub async fn start_server(
     }
 }
 
+fn parse_bind_addresses(bind: &BindConfig, port: Option<u16>) -> Option<Vec<BindAddress>> {
+    let addresses = bind.addresses();
+    if addresses.is_empty() {
+        error!("No bind address configured");
+        return None;
+    }
+    addresses
+        .into_iter()
+        .map(|address| {
+            let mut bind = BindAddress::parse(address)
+                .inspect_err(|_| error!("Invalid bind address: {:?}", address))
+                .ok()?;
+            if let BindAddress::Tcp(bind) = &mut bind
+                && let Some(port) = port
+            {
+                bind.set_port(port);
+            }
+            Some(bind)
+        })
+        .collect()
+}
+
+/// Parses the address of an optional listener, returns `None` when it is disabled or invalid.
+fn parse_optional_bind(enabled: bool, bind: &str, name: &str) -> Option<ServerAddress> {
+    if !enabled {
+        return None;
+    }
+    ServerAddress::parse(bind)
+        .inspect_err(|_| error!("Invalid {name} bind address: {bind}"))
+        .ok()
+}
+
 fn load_configuration(config_path: &PathBuf) -> Option<Config> {
     let cfg = load_or_create(config_path);
     match cfg {
@@ -67,28 +183,39 @@ fn load_configuration(config_path: &PathBuf) -> Option<Config> {
     None
 }
 
-fn build_state(cfg: Config) -> Result<ServerState, ServerStateBuilderError> {
+pub fn build_state(cfg: Config) -> Result<ServerState, ServerStateBuilderError> {
     let mut server_state_builder = ServerState::builder();
 
     let forwarding: TaggedForwarding = cfg.forwarding.into();
+    let is_forwarding_disabled = matches!(forwarding, TaggedForwarding::None);
 
-    match forwarding {
-        TaggedForwarding::None => {
-            server_state_builder.disable_forwarding();
-        }
-        TaggedForwarding::Legacy => {
-            debug!("Enabling legacy forwarding");
-            server_state_builder.enable_legacy_forwarding();
-        }
-        TaggedForwarding::BungeeGuard { tokens } => {
-            server_state_builder.enable_bungee_guard_forwarding(tokens);
-        }
-        TaggedForwarding::Modern { secret } => {
-            debug!("Enabling modern forwarding");
-            server_state_builder.enable_modern_forwarding(secret);
+    enable_forwarding(&mut server_state_builder, forwarding)?;
+
+    if cfg.online_mode.enabled {
+        if is_forwarding_disabled {
+            debug!("Enabling online mode");
+            server_state_builder.enable_online_mode(cfg.online_mode.has_joined_url);
+        } else {
+            warn!("Online mode is ignored because forwarding is enabled");
         }
     }
 
+    if cfg.proxy_protocol.enabled {
+        debug!("Enabling PROXY protocol");
+        server_state_builder.enable_proxy_protocol(&cfg.proxy_protocol.trusted_sources)?;
+    }
+
+    if cfg.connection_limits.enabled {
+        debug!("Enabling connection limits");
+        let limits = &cfg.connection_limits;
+        server_state_builder.connection_limits(
+            limits.window_seconds,
+            limits.connections_per_window,
+            limits.max_connections_per_address,
+            limits.max_pending_connections,
+        );
+    }
+
     if let BoundariesConfig::Enabled(boundaries) = cfg.world.boundaries {
         if cfg.world.spawn_position.1 < f64::from(boundaries.min_y) {
             return Err(ServerStateBuilderError::InvalidSpawnPosition);
@@ -114,6 +241,31 @@ fn build_state(cfg: Config) -> Result<ServerState, ServerStateBuilderError> {
         )?;
     }
 
+    if cfg.server_list.enforce_max_players {
+        server_state_builder.enforce_max_players(
+            &cfg.server_list.full_server_message,
+            &cfg.server_list.max_players_bypass,
+        )?;
+    }
+
+    enable_access_lists(&mut server_state_builder, &cfg.whitelist, &cfg.bans)?;
+
+    if cfg.announcements.enabled {
+        for announcement in cfg.announcements.entries {
+            server_state_builder.announcement(announcement)?;
+        }
+    }
+
+    if cfg.backend_watcher.enabled {
+        server_state_builder.backend_watcher(cfg.backend_watcher)?;
+    }
+
+    if cfg.queue.enabled {
+        server_state_builder.queue(cfg.queue)?;
+    }
+
+    enable_session_timeouts(&mut server_state_builder, cfg.afk, cfg.session_limit)?;
+
     let server_icon = cfg.server_list.server_icon;
     if std::fs::exists(&server_icon)? {
         server_state_builder.fav_icon(server_icon)?;
@@ -134,13 +286,16 @@ fn build_state(cfg: Config) -> Result<ServerState, ServerStateBuilderError> {
         .spawn_rotation(cfg.world.spawn_rotation)
         .view_distance(cfg.world.experimental.view_distance)
         .schematic(cfg.world.experimental.schematic_file)
+        .record_connections(cfg.recording.into())
         .enable_compression(cfg.compression.threshold, cfg.compression.level)?
         .keep_alive_interval_secs(cfg.connection.keep_alive_interval_seconds)
+        .max_missed_keep_alives(cfg.connection.max_missed_keep_alives)
         .fetch_player_skins(cfg.fetch_player_skins)
         .reduced_debug_info(cfg.reduced_debug_info)
         .set_player_listed(cfg.tab_list.player_listed)
         .set_reply_to_status(cfg.server_list.reply_to_status)
         .set_allow_unsupported_versions(cfg.connection.allow_unsupported_versions)
+        .set_timeouts(&cfg.connection.timeouts)
         .set_fly(&cfg.fly)
         .set_accept_transfers(cfg.accept_transfers)
         .server_commands(cfg.commands);
@@ -148,6 +303,88 @@ fn build_state(cfg: Config) -> Result<ServerState, ServerStateBuilderError> {
     server_state_builder.build()
 }
 
+fn enable_forwarding(
+    server_state_builder: &mut ServerStateBuilder,
+    forwarding: TaggedForwarding,
+) -> Result<(), ServerStateBuilderError> {
+    match forwarding {
+        TaggedForwarding::None => {
+            server_state_builder.disable_forwarding();
+        }
+        TaggedForwarding::Legacy => {
+            debug!("Enabling legacy forwarding");
+            server_state_builder.enable_legacy_forwarding();
+        }
+        TaggedForwarding::BungeeGuard { tokens } => {
+            server_state_builder.enable_bungee_guard_forwarding(tokens);
+        }
+        TaggedForwarding::Modern {
+            secret,
+            secrets,
+            secret_files,
+            bungee_guard_tokens,
+        } => {
+            let mut secrets: Vec<String> = std::iter::once(secret).chain(secrets).collect();
+            for secret_file in secret_files {
+                // Line breaks are ignored, like Velocity does when reading its secret file
+                let secret = std::fs::read_to_string(&secret_file).map_err(|source| {
+                    ServerStateBuilderError::ForwardingSecretFile {
+                        path: secret_file.clone(),
+                        source,
+                    }
+                })?;
+                secrets.push(secret.lines().collect());
+            }
+            secrets.retain(|secret| !secret.is_empty());
+            if secrets.is_empty() {
+                return Err(ServerStateBuilderError::MissingForwardingSecret);
+            }
+            if bungee_guard_tokens.is_empty() {
+                debug!("Enabling modern forwarding");
+                server_state_builder.enable_modern_forwarding(secrets);
+            } else {
+                debug!("Enabling modern and BungeeGuard forwarding");
+                server_state_builder
+                    .enable_modern_and_bungee_guard_forwarding(secrets, bungee_guard_tokens);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn enable_access_lists(
+    server_state_builder: &mut ServerStateBuilder,
+    whitelist: &WhitelistConfig,
+    bans: &BansConfig,
+) -> Result<(), ServerStateBuilderError> {
+    if whitelist.enabled {
+        server_state_builder.whitelist(&whitelist.file, &whitelist.kick_message)?;
+    }
+    if bans.enabled {
+        server_state_builder.bans(
+            &bans.players_file,
+            &bans.ips_file,
+            &bans.kick_message,
+            &bans.ip_kick_message,
+        )?;
+    }
+    Ok(())
+}
+
+fn enable_session_timeouts(
+    server_state_builder: &mut ServerStateBuilder,
+    afk: AfkConfig,
+    session_limit: SessionLimitConfig,
+) -> Result<(), ServerStateBuilderError> {
+    if afk.enabled {
+        server_state_builder.afk(afk)?;
+    }
+    if session_limit.enabled {
+        server_state_builder.session_limit(session_limit)?;
+    }
+    Ok(())
+}
+
 fn enable_logging(verbose: u8) {
     let log_level = match verbose {
         0 => Level::INFO,
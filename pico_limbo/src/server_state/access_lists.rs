@@ -0,0 +1,562 @@
+use crate::kick_messages::{
+    BANNED_KICK_REASON, IP_BANNED_KICK_REASON, NOT_WHITELISTED_KICK_REASON,
+};
+use crate::server::game_profile::GameProfile;
+use crate::server_state::{ServerStateBuilderError, parse_ip_net};
+use ipnet::IpNet;
+use minecraft_protocol::prelude::Uuid;
+use pico_text_component::prelude::{Component, MiniMessageError, parse_mini_message};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::{fs, io};
+use thiserror::Error;
+
+const DEFAULT_BAN_REASON: &str = "Banned by an operator.";
+const PERMANENT_BAN_EXPIRY: &str = "Never";
+#[allow(clippy::literal_string_with_formatting_args)]
+const REASON_PLACEHOLDER: &str = "{reason}";
+#[allow(clippy::literal_string_with_formatting_args)]
+const EXPIRES_PLACEHOLDER: &str = "{expires}";
+/// Expiry written by vanilla servers for permanent bans.
+const FOREVER: &str = "forever";
+
+#[derive(Debug, Error)]
+pub enum AccessListError {
+    #[error("failed to read {0}: {1}")]
+    Io(PathBuf, io::Error),
+    #[error("failed to parse {0}: {1}")]
+    Json(PathBuf, serde_json::Error),
+}
+
+/// Whitelist and ban lists loaded from JSON files, checked when players log in.
+#[derive(Default)]
+pub struct AccessLists {
+    whitelist: Option<Whitelist>,
+    bans: Option<Bans>,
+}
+
+struct Whitelist {
+    players: ListFile<PlayerEntry>,
+    kick_message: Component,
+}
+
+struct Bans {
+    players: ListFile<PlayerBan>,
+    ips: ListFile<IpBan>,
+    kick_message: String,
+    ip_kick_message: String,
+}
+
+impl AccessLists {
+    pub fn enable_whitelist(
+        &mut self,
+        path: &Path,
+        kick_message: &str,
+    ) -> Result<(), ServerStateBuilderError> {
+        self.whitelist = Some(Whitelist {
+            players: ListFile::load(path)?,
+            kick_message: parse_mini_message(kick_message)?,
+        });
+        Ok(())
+    }
+
+    pub fn enable_bans(
+        &mut self,
+        players_path: &Path,
+        ips_path: &Path,
+        kick_message: &str,
+        ip_kick_message: &str,
+    ) -> Result<(), ServerStateBuilderError> {
+        // Fails early on invalid templates rather than when a banned player joins
+        ban_kick_message(kick_message, &Ban::default())?;
+        ban_kick_message(ip_kick_message, &Ban::default())?;
+        self.bans = Some(Bans {
+            players: ListFile::load(players_path)?,
+            ips: ListFile::load(ips_path)?,
+            kick_message: kick_message.to_string(),
+            ip_kick_message: ip_kick_message.to_string(),
+        });
+        Ok(())
+    }
+
+    /// Returns the kick message and its label when the player or the address is banned.
+    /// The address is checked alone when the player is not known yet.
+    pub fn check_bans(
+        &self,
+        game_profile: Option<&GameProfile>,
+        address: Option<IpAddr>,
+    ) -> Option<(Component, &'static str)> {
+        let bans = self.bans.as_ref()?;
+        let now = SystemTime::now();
+        if let Some(address) = address.map(|address| address.to_canonical())
+            && let Some(ip_ban) = bans.ips.find(|ip_ban| ip_ban.matches(address, now))
+        {
+            let kick_message = ban_kick_message(&bans.ip_kick_message, &ip_ban.ban)
+                .unwrap_or_else(|_| Component::new(&bans.ip_kick_message));
+            return Some((kick_message, IP_BANNED_KICK_REASON));
+        }
+        let player_ban = bans.players.find(|player_ban| {
+            game_profile.is_some_and(|game_profile| player_ban.matches(game_profile, now))
+        })?;
+        let kick_message = ban_kick_message(&bans.kick_message, &player_ban.ban)
+            .unwrap_or_else(|_| Component::new(&bans.kick_message));
+        Some((kick_message, BANNED_KICK_REASON))
+    }
+
+    /// Returns the kick message and its label when the whitelist is enabled and the player is not on it.
+    pub fn check_whitelist(&self, game_profile: &GameProfile) -> Option<(Component, &'static str)> {
+        self.whitelist
+            .as_ref()
+            .filter(|whitelist| {
+                whitelist
+                    .players
+                    .find(|player| player.matches(game_profile))
+                    .is_none()
+            })
+            .map(|whitelist| (whitelist.kick_message.clone(), NOT_WHITELISTED_KICK_REASON))
+    }
+
+    /// Reloads the files modified since they were last read, returns the reloaded files.
+    /// A file failing to load keeps its previous entries.
+    pub fn reload_changed_files(&self) -> Vec<Result<PathBuf, AccessListError>> {
+        let mut results = Vec::new();
+        if let Some(whitelist) = &self.whitelist {
+            results.extend(whitelist.players.reload_if_changed());
+        }
+        if let Some(bans) = &self.bans {
+            results.extend(bans.players.reload_if_changed());
+            results.extend(bans.ips.reload_if_changed());
+        }
+        results
+    }
+}
+
+fn ban_kick_message(template: &str, ban: &Ban) -> Result<Component, MiniMessageError> {
+    let reason = ban.reason.as_deref().unwrap_or(DEFAULT_BAN_REASON);
+    let expires = ban
+        .expires
+        .as_ref()
+        .map_or(PERMANENT_BAN_EXPIRY, |expiry| expiry.text.as_str());
+    parse_mini_message(
+        &template
+            .replace(REASON_PLACEHOLDER, reason)
+            .replace(EXPIRES_PLACEHOLDER, expires),
+    )
+}
+
+/// Entries of a JSON file, reloaded when the file is modified.
+struct ListFile<T> {
+    path: PathBuf,
+    entries: RwLock<Vec<T>>,
+    modified: Mutex<Option<SystemTime>>,
+}
+
+impl<T: DeserializeOwned + Clone> ListFile<T> {
+    /// Loads the file, an empty list is created when it does not exist.
+    fn load(path: &Path) -> Result<Self, AccessListError> {
+        if !path.exists() {
+            fs::write(path, "[]\n").map_err(|err| AccessListError::Io(path.to_path_buf(), err))?;
+        }
+        let modified = modified_time(path);
+        Ok(Self {
+            path: path.to_path_buf(),
+            entries: RwLock::new(read_entries(path)?),
+            modified: Mutex::new(modified),
+        })
+    }
+
+    fn find(&self, predicate: impl Fn(&T) -> bool) -> Option<T> {
+        self.entries
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .iter()
+            .find(|entry| predicate(entry))
+            .cloned()
+    }
+
+    fn reload_if_changed(&self) -> Option<Result<PathBuf, AccessListError>> {
+        let modified = modified_time(&self.path);
+        {
+            let mut last_modified = self
+                .modified
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner);
+            if *last_modified == modified {
+                return None;
+            }
+            *last_modified = modified;
+        }
+        let result = read_entries(&self.path).map(|entries| {
+            *self
+                .entries
+                .write()
+                .unwrap_or_else(std::sync::PoisonError::into_inner) = entries;
+            self.path.clone()
+        });
+        Some(result)
+    }
+}
+
+fn read_entries<T: DeserializeOwned>(path: &Path) -> Result<Vec<T>, AccessListError> {
+    let content =
+        fs::read_to_string(path).map_err(|err| AccessListError::Io(path.to_path_buf(), err))?;
+    serde_json::from_str(&content).map_err(|err| AccessListError::Json(path.to_path_buf(), err))
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Player designated by UUID, by name, or both. Unknown fields are ignored,
+/// so that the files of vanilla servers can be used as they are.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(try_from = "RawPlayerEntry")]
+struct PlayerEntry {
+    uuid: Option<Uuid>,
+    name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RawPlayerEntry {
+    uuid: Option<String>,
+    name: Option<String>,
+}
+
+impl TryFrom<RawPlayerEntry> for PlayerEntry {
+    type Error = String;
+
+    fn try_from(raw: RawPlayerEntry) -> Result<Self, Self::Error> {
+        let uuid = raw
+            .uuid
+            .map(|uuid| Uuid::parse_str(&uuid).map_err(|_| format!("invalid UUID {uuid}")))
+            .transpose()?;
+        if uuid.is_none() && raw.name.is_none() {
+            return Err("an entry must have a uuid or a name".to_string());
+        }
+        Ok(Self {
+            uuid,
+            name: raw.name,
+        })
+    }
+}
+
+impl PlayerEntry {
+    /// Names can be taken over by another account, so they are only compared without a UUID.
+    fn matches(&self, game_profile: &GameProfile) -> bool {
+        match (self.uuid, &self.name) {
+            (Some(uuid), _) => uuid == game_profile.uuid(),
+            (None, Some(name)) => name.eq_ignore_ascii_case(game_profile.username()),
+            (None, None) => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(try_from = "RawBan")]
+struct Ban {
+    reason: Option<String>,
+    /// Permanent when `None`.
+    expires: Option<Expiry>,
+}
+
+#[derive(Deserialize)]
+struct RawBan {
+    reason: Option<String>,
+    expires: Option<String>,
+}
+
+impl TryFrom<RawBan> for Ban {
+    type Error = String;
+
+    fn try_from(raw: RawBan) -> Result<Self, Self::Error> {
+        let expires = raw
+            .expires
+            .filter(|expires| !expires.eq_ignore_ascii_case(FOREVER))
+            .map(|text| {
+                parse_date(&text)
+                    .map(|at| Expiry {
+                        text: text.clone(),
+                        at,
+                    })
+                    .ok_or_else(|| format!("invalid expiry date {text}"))
+            })
+            .transpose()?;
+        Ok(Self {
+            reason: raw.reason.filter(|reason| !reason.is_empty()),
+            expires,
+        })
+    }
+}
+
+impl Ban {
+    fn is_active(&self, now: SystemTime) -> bool {
+        self.expires.as_ref().is_none_or(|expiry| expiry.at > now)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Expiry {
+    /// Date as written in the file, shown to the banned player.
+    text: String,
+    at: SystemTime,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct PlayerBan {
+    #[serde(flatten)]
+    player: PlayerEntry,
+    #[serde(flatten)]
+    ban: Ban,
+}
+
+impl PlayerBan {
+    fn matches(&self, game_profile: &GameProfile, now: SystemTime) -> bool {
+        self.ban.is_active(now) && self.player.matches(game_profile)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct IpBan {
+    #[serde(deserialize_with = "deserialize_ip_net")]
+    ip: IpNet,
+    #[serde(flatten)]
+    ban: Ban,
+}
+
+impl IpBan {
+    fn matches(&self, address: IpAddr, now: SystemTime) -> bool {
+        self.ban.is_active(now) && self.ip.contains(&address)
+    }
+}
+
+fn deserialize_ip_net<'de, D>(deserializer: D) -> Result<IpNet, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = String::deserialize(deserializer)?;
+    parse_ip_net(&value).map_err(serde::de::Error::custom)
+}
+
+/// Parses `2026-12-31`, `2026-12-31T12:00:00Z` or the `2026-12-31 12:00:00 +0000` format of vanilla.
+fn parse_date(text: &str) -> Option<SystemTime> {
+    let text = text.trim();
+    let mut date = text.get(..10)?.split('-');
+    let year: i64 = date.next()?.parse().ok()?;
+    let month: i64 = date.next()?.parse().ok()?;
+    let day: i64 = date.next()?.parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    let rest = &text[10..];
+    let (seconds_of_day, offset) = if rest.is_empty() {
+        (0, 0)
+    } else {
+        let rest = rest.strip_prefix(['T', ' '])?;
+        let mut time = rest.get(..8)?.split(':');
+        let hours: i64 = time.next()?.parse().ok()?;
+        let minutes: i64 = time.next()?.parse().ok()?;
+        let seconds: i64 = time.next()?.parse().ok()?;
+        if hours > 23 || minutes > 59 || seconds > 59 {
+            return None;
+        }
+        (
+            hours * 3600 + minutes * 60 + seconds,
+            parse_offset(rest[8..].trim())?,
+        )
+    };
+
+    let timestamp = days_from_civil(year, month, day) * 86_400 + seconds_of_day - offset;
+    let timestamp = u64::try_from(timestamp).unwrap_or_default();
+    Some(UNIX_EPOCH + Duration::from_secs(timestamp))
+}
+
+/// Parses `Z`, `+0100` or `+01:00` into seconds east of UTC.
+fn parse_offset(offset: &str) -> Option<i64> {
+    if offset.is_empty() || offset == "Z" {
+        return Some(0);
+    }
+    let sign = match offset.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    let digits = offset[1..].replace(':', "");
+    if digits.len() != 4 {
+        return None;
+    }
+    let hours: i64 = digits[..2].parse().ok()?;
+    let minutes: i64 = digits[2..].parse().ok()?;
+    Some(sign * (hours * 3600 + minutes * 60))
+}
+
+/// Number of days since 1970-01-01 in the proleptic Gregorian calendar.
+const fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn profile(name: &str, uuid: u128) -> GameProfile {
+        GameProfile::new(name, Uuid::from_u128(uuid), None)
+    }
+
+    fn write_list(directory: &TempDir, name: &str, content: &str) -> PathBuf {
+        let path = directory.path().join(name);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_parse_date() {
+        let expected = UNIX_EPOCH + Duration::from_hours(264_408);
+        assert_eq!(parse_date("2000-03-01"), Some(expected));
+        assert_eq!(parse_date("2000-03-01T00:00:00Z"), Some(expected));
+        assert_eq!(parse_date("2000-03-01 01:00:00 +0100"), Some(expected));
+        assert_eq!(parse_date("2000-02-29T19:00:00-05:00"), Some(expected));
+        assert_eq!(parse_date("2000-13-01"), None);
+        assert_eq!(parse_date("tomorrow"), None);
+    }
+
+    #[test]
+    fn test_bans_and_whitelist() {
+        // Given
+        let directory = TempDir::new().unwrap();
+        let whitelist = write_list(
+            &directory,
+            "whitelist.json",
+            r#"[{"uuid": "00000000-0000-0000-0000-000000000001", "name": "Notch"}, {"name": "jeb_"}]"#,
+        );
+        let players = write_list(
+            &directory,
+            "banned-players.json",
+            r#"[
+                {"name": "jeb_", "reason": "Griefing", "expires": "2999-01-01 00:00:00 +0000", "source": "Server"},
+                {"name": "Dinnerbone", "expires": "2000-01-01"}
+            ]"#,
+        );
+        let ips = write_list(
+            &directory,
+            "banned-ips.json",
+            r#"[{"ip": "203.0.113.0/24", "expires": "forever"}]"#,
+        );
+        let mut access_lists = AccessLists::default();
+        access_lists
+            .enable_whitelist(&whitelist, "Not whitelisted")
+            .unwrap();
+        access_lists
+            .enable_bans(
+                &players,
+                &ips,
+                "Banned: {reason} until {expires}",
+                "IP banned: {reason}",
+            )
+            .unwrap();
+
+        // When
+        let check = |game_profile: &GameProfile| {
+            access_lists
+                .check_bans(Some(game_profile), None)
+                .or_else(|| access_lists.check_whitelist(game_profile))
+        };
+        let notch = check(&profile("Notch", 1));
+        let jeb = check(&profile("JEB_", 2));
+        let dinnerbone = check(&profile("Dinnerbone", 3));
+        let address = access_lists.check_bans(None, Some("::ffff:203.0.113.7".parse().unwrap()));
+
+        // Then
+        let kick = |result: Option<(Component, &'static str)>| {
+            result.map(|(kick_message, reason)| (kick_message.to_plain_text(), reason))
+        };
+        assert_eq!(kick(notch), None);
+        assert_eq!(
+            kick(jeb),
+            Some((
+                "Banned: Griefing until 2999-01-01 00:00:00 +0000".to_string(),
+                BANNED_KICK_REASON
+            ))
+        );
+        assert_eq!(
+            kick(dinnerbone),
+            Some(("Not whitelisted".to_string(), NOT_WHITELISTED_KICK_REASON))
+        );
+        assert_eq!(
+            kick(address),
+            Some((
+                format!("IP banned: {DEFAULT_BAN_REASON}"),
+                IP_BANNED_KICK_REASON
+            ))
+        );
+    }
+
+    #[test]
+    fn test_entry_with_uuid_does_not_match_same_name_with_another_uuid() {
+        // Given
+        let entry = PlayerEntry {
+            uuid: Some(Uuid::from_u128(1)),
+            name: Some("Notch".to_string()),
+        };
+
+        // When
+        let same_uuid = entry.matches(&profile("Renamed", 1));
+        let same_name = entry.matches(&profile("Notch", 2));
+
+        // Then
+        assert!(same_uuid);
+        assert!(!same_name);
+    }
+
+    #[test]
+    fn test_reloads_modified_files_and_keeps_entries_on_error() {
+        // Given
+        let directory = TempDir::new().unwrap();
+        let path = write_list(&directory, "reload.json", r#"[{"name": "Notch"}]"#);
+        let list: ListFile<PlayerEntry> = ListFile::load(&path).unwrap();
+        let notch = profile("Notch", 1);
+
+        // When
+        let unchanged = list.reload_if_changed();
+        fs::write(&path, "[{").unwrap();
+        list.modified.lock().unwrap().take();
+        let invalid = list.reload_if_changed();
+        let still_listed = list.find(|player| player.matches(&notch)).is_some();
+        fs::write(&path, "[]").unwrap();
+        list.modified.lock().unwrap().take();
+        let reloaded = list.reload_if_changed();
+
+        // Then
+        assert!(unchanged.is_none());
+        assert!(matches!(invalid, Some(Err(AccessListError::Json(..)))));
+        assert!(still_listed);
+        assert!(matches!(reloaded, Some(Ok(_))));
+        assert!(list.find(|player| player.matches(&notch)).is_none());
+    }
+
+    #[test]
+    fn test_creates_missing_files() {
+        // Given
+        let directory = TempDir::new().unwrap();
+        let path = directory.path().join("missing.json");
+
+        // When
+        let list: Result<ListFile<PlayerEntry>, _> = ListFile::load(&path);
+
+        // Then
+        assert!(list.is_ok());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "[]\n");
+    }
+}
@@ -0,0 +1,336 @@
+use crate::configuration::announcements::{
+    AnnouncementConfig, AnnouncementTargetConfig, RotationConfig,
+};
+use crate::server_state::{
+    BossBar, ServerStateBuilderError, Title, TitleType, optional_mini_message,
+};
+use minecraft_packets::play::boss_bar_packet::{BossBarColor, BossBarDivision};
+use pico_text_component::prelude::{Component, parse_mini_message};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const DEFAULT_FADE_IN: i32 = 10;
+const DEFAULT_STAY: i32 = 70;
+const DEFAULT_FADE_OUT: i32 = 20;
+const DEFAULT_BOSS_BAR_DURATION: Duration = Duration::from_secs(10);
+const MINUTES_PER_DAY: u64 = 24 * 60;
+/// Long enough to find the next 29th of February falling on a given day of the week.
+const MAX_CRON_SEARCH_DAYS: u64 = 28 * 366;
+
+/// Messages sent to every player in game on a schedule.
+#[derive(PartialEq)]
+pub struct Announcement {
+    pub schedule: Schedule,
+    pub rotation: Rotation,
+    pub messages: Vec<AnnouncementMessage>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rotation {
+    Sequential,
+    Random,
+}
+
+#[derive(PartialEq)]
+pub enum AnnouncementMessage {
+    Chat(Component),
+    ActionBar(Component),
+    Title(Title),
+    /// Boss bar removed after the duration.
+    BossBar(BossBar, Duration),
+}
+
+impl Announcement {
+    pub fn from_config(config: AnnouncementConfig) -> Result<Self, ServerStateBuilderError> {
+        let schedule = match (config.interval_seconds, config.schedule) {
+            (Some(interval_seconds), None) if interval_seconds > 0 => {
+                Schedule::Every(Duration::from_secs(interval_seconds))
+            }
+            (None, Some(expression)) => {
+                Schedule::Cron(CronSchedule::parse(&expression).ok_or_else(|| {
+                    ServerStateBuilderError::InvalidAnnouncement(format!(
+                        "invalid schedule {expression}"
+                    ))
+                })?)
+            }
+            _ => {
+                return Err(ServerStateBuilderError::InvalidAnnouncement(
+                    "an announcement requires either a positive interval_seconds or a schedule"
+                        .to_string(),
+                ));
+            }
+        };
+        if config.messages.is_empty() {
+            return Err(ServerStateBuilderError::InvalidAnnouncement(
+                "an announcement requires at least one message".to_string(),
+            ));
+        }
+
+        let color: BossBarColor = config.color.unwrap_or_default().into();
+        let division: BossBarDivision = config.division.unwrap_or_default().into();
+        let messages = config
+            .messages
+            .iter()
+            .map(
+                |message| -> Result<AnnouncementMessage, ServerStateBuilderError> {
+                    Ok(match config.target {
+                        AnnouncementTargetConfig::Chat => {
+                            AnnouncementMessage::Chat(parse_mini_message(message)?)
+                        }
+                        AnnouncementTargetConfig::ActionBar => {
+                            AnnouncementMessage::ActionBar(parse_mini_message(message)?)
+                        }
+                        AnnouncementTargetConfig::Title => AnnouncementMessage::Title(Title {
+                            content: title_content(message)?,
+                            fade_in: config.fade_in.unwrap_or(DEFAULT_FADE_IN),
+                            stay: config.stay.unwrap_or(DEFAULT_STAY),
+                            fade_out: config.fade_out.unwrap_or(DEFAULT_FADE_OUT),
+                        }),
+                        AnnouncementTargetConfig::BossBar => AnnouncementMessage::BossBar(
+                            BossBar {
+                                title: parse_mini_message(message)?,
+                                health: 1.0,
+                                color,
+                                division,
+                            },
+                            config
+                                .duration_seconds
+                                .map_or(DEFAULT_BOSS_BAR_DURATION, Duration::from_secs),
+                        ),
+                    })
+                },
+            )
+            .collect::<Result<_, _>>()?;
+
+        let rotation = match config.rotation {
+            RotationConfig::Sequential => Rotation::Sequential,
+            RotationConfig::Random => Rotation::Random,
+        };
+        Ok(Self {
+            schedule,
+            rotation,
+            messages,
+        })
+    }
+}
+
+/// Splits the message in a title and a subtitle on the first line break.
+fn title_content(message: &str) -> Result<TitleType, ServerStateBuilderError> {
+    let (title, subtitle) = message.split_once('\n').unwrap_or((message, ""));
+    match (
+        optional_mini_message(title)?,
+        optional_mini_message(subtitle)?,
+    ) {
+        (Some(title), Some(subtitle)) => Ok(TitleType::Both { title, subtitle }),
+        (Some(title), None) => Ok(TitleType::Title(title)),
+        (None, Some(subtitle)) => Ok(TitleType::Subtitle(subtitle)),
+        (None, None) => Err(ServerStateBuilderError::InvalidAnnouncement(
+            "a title announcement cannot be empty".to_string(),
+        )),
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Schedule {
+    Every(Duration),
+    Cron(CronSchedule),
+}
+
+impl Schedule {
+    /// Returns when the announcement is due next.
+    pub fn next_after(&self, after: SystemTime) -> Option<SystemTime> {
+        match self {
+            Self::Every(interval) => Some(after + *interval),
+            Self::Cron(cron) => cron.next_after(after),
+        }
+    }
+}
+
+/// Subset of the cron syntax, with `*`, lists, ranges and steps, evaluated in UTC.
+#[derive(Debug, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    /// Like cron, a day matches either field when both days are restricted.
+    is_day_of_month_restricted: bool,
+    is_day_of_week_restricted: bool,
+}
+
+impl CronSchedule {
+    pub fn parse(expression: &str) -> Option<Self> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minutes, hours, days_of_month, months, days_of_week] = fields.as_slice() else {
+            return None;
+        };
+        let days_of_week_set = parse_field(days_of_week, 0, 7)?;
+        Some(Self {
+            minutes: parse_field(minutes, 0, 59)?,
+            hours: parse_field(hours, 0, 23)?,
+            days_of_month: parse_field(days_of_month, 1, 31)?,
+            months: parse_field(months, 1, 12)?,
+            // Sunday is both 0 and 7
+            days_of_week: (days_of_week_set | (days_of_week_set >> 7)) & 0x7f,
+            is_day_of_month_restricted: !days_of_month.starts_with('*'),
+            is_day_of_week_restricted: !days_of_week.starts_with('*'),
+        })
+    }
+
+    /// Returns the first matching minute strictly after the given time.
+    fn next_after(&self, after: SystemTime) -> Option<SystemTime> {
+        let seconds = after.duration_since(UNIX_EPOCH).ok()?.as_secs();
+        let mut minute = seconds / 60 + 1;
+        let limit = minute + MAX_CRON_SEARCH_DAYS * MINUTES_PER_DAY;
+        while minute < limit {
+            let days = minute / MINUTES_PER_DAY;
+            if !self.matches_day(days) {
+                minute = (days + 1) * MINUTES_PER_DAY;
+                continue;
+            }
+            let minute_of_day = minute % MINUTES_PER_DAY;
+            if !contains(self.hours, minute_of_day / 60) {
+                minute = (minute / 60 + 1) * 60;
+                continue;
+            }
+            if contains(self.minutes, minute_of_day % 60) {
+                return Some(UNIX_EPOCH + Duration::from_secs(minute * 60));
+            }
+            minute += 1;
+        }
+        None
+    }
+
+    const fn matches_day(&self, days_since_epoch: u64) -> bool {
+        let (month, day) = month_and_day(days_since_epoch);
+        if !contains(self.months, month) {
+            return false;
+        }
+        // 1970-01-01 was a Thursday
+        let day_of_week = (days_since_epoch + 4) % 7;
+        let matches_day_of_month = contains(self.days_of_month, day);
+        let matches_day_of_week = contains(self.days_of_week, day_of_week);
+        if self.is_day_of_month_restricted && self.is_day_of_week_restricted {
+            matches_day_of_month || matches_day_of_week
+        } else {
+            matches_day_of_month && matches_day_of_week
+        }
+    }
+}
+
+const fn contains(set: u64, value: u64) -> bool {
+    value < 64 && set & (1 << value) != 0
+}
+
+/// Parses a comma separated list of `*`, `value`, `start-end`, each optionally followed by `/step`.
+fn parse_field(field: &str, min: u64, max: u64) -> Option<u64> {
+    let mut set = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u64>().ok().filter(|step| *step > 0)?),
+            None => (part, 1),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (start.parse().ok()?, end.parse().ok()?)
+        } else {
+            let start = range.parse().ok()?;
+            // `5/15` means from 5 to the maximum, every 15
+            (start, if part.contains('/') { max } else { start })
+        };
+        if start < min || end > max || start > end {
+            return None;
+        }
+        for value in (start..=end).step_by(usize::try_from(step).ok()?) {
+            set |= 1 << value;
+        }
+    }
+    Some(set)
+}
+
+/// Month and day of month of a number of days since 1970-01-01, in the proleptic Gregorian calendar.
+const fn month_and_day(days_since_epoch: u64) -> (u64, u64) {
+    let shifted = days_since_epoch + 719_468;
+    let day_of_era = shifted % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    (month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(seconds: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    /// 2026-10-17T12:34:56Z, a Saturday.
+    const NOW: u64 = 1_792_240_496;
+
+    #[test]
+    fn test_month_and_day() {
+        assert_eq!(month_and_day(0), (1, 1));
+        assert_eq!(month_and_day(11_016), (2, 29));
+        assert_eq!(month_and_day(NOW / 86_400), (10, 17));
+    }
+
+    #[test]
+    fn test_cron_next_after() {
+        let next = |expression: &str| {
+            CronSchedule::parse(expression)
+                .unwrap()
+                .next_after(at(NOW))
+                .map(|next| next.duration_since(UNIX_EPOCH).unwrap().as_secs())
+        };
+        let minute = NOW / 60 * 60;
+
+        assert_eq!(next("* * * * *"), Some(minute + 60));
+        assert_eq!(next("*/15 * * * *"), Some(minute - 34 * 60 + 45 * 60));
+        assert_eq!(next("0 18 * * *"), Some(NOW / 86_400 * 86_400 + 18 * 3600));
+        // Next Monday
+        assert_eq!(
+            next("30 9 * * 1"),
+            Some((NOW / 86_400 + 2) * 86_400 + 9 * 3600 + 1800)
+        );
+        // Either the 1st or a Sunday
+        assert_eq!(next("0 0 1 * 7"), Some((NOW / 86_400 + 1) * 86_400));
+    }
+
+    #[test]
+    fn test_cron_rejects_invalid_expressions() {
+        assert!(CronSchedule::parse("* * * *").is_none());
+        assert!(CronSchedule::parse("60 * * * *").is_none());
+        assert!(CronSchedule::parse("* * 0 * *").is_none());
+        assert!(CronSchedule::parse("*/0 * * * *").is_none());
+        assert!(CronSchedule::parse("5-1 * * * *").is_none());
+        assert!(CronSchedule::parse("a * * * *").is_none());
+    }
+
+    #[test]
+    fn test_title_content_is_split_on_the_first_line_break() {
+        // Given
+        let message = "<gold>Maintenance</gold>\nBack at 18:00";
+
+        // When
+        let content = title_content(message).unwrap();
+
+        // Then
+        assert!(
+            content
+                == TitleType::Both {
+                    title: parse_mini_message("<gold>Maintenance</gold>").unwrap(),
+                    subtitle: parse_mini_message("Back at 18:00").unwrap(),
+                }
+        );
+    }
+}
@@ -0,0 +1,101 @@
+use crate::configuration::backend_watcher::{BackendWatcherConfig, OlderClientsConfig};
+use crate::server_state::ServerStateBuilderError;
+use pico_text_component::prelude::{Component, parse_mini_message};
+use std::time::Duration;
+
+#[allow(clippy::literal_string_with_formatting_args)]
+const HOST_PLACEHOLDER: &str = "{host}";
+#[allow(clippy::literal_string_with_formatting_args)]
+const PORT_PLACEHOLDER: &str = "{port}";
+
+/// Servers pinged periodically, players are moved to the first one online.
+pub struct BackendTransfer {
+    pub interval: Duration,
+    /// Delay between two players being moved, so that the target is not flooded with logins.
+    pub transfer_delay: Duration,
+    pub older_clients: OlderClients,
+    pub targets: Vec<BackendTarget>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OlderClients {
+    Chat,
+    ProxyConnect,
+}
+
+pub struct BackendTarget {
+    pub host: String,
+    pub port: u16,
+    /// Payload of the `BungeeCord` `Connect` message moving the player to the proxy server.
+    pub proxy_connect: Option<Vec<u8>>,
+    pub chat_prompt: Component,
+}
+
+impl BackendTransfer {
+    pub fn from_config(config: BackendWatcherConfig) -> Result<Self, ServerStateBuilderError> {
+        if config.interval_seconds == 0 {
+            return Err(invalid("interval_seconds must be positive"));
+        }
+        if config.transfers_per_second == 0 {
+            return Err(invalid("transfers_per_second must be positive"));
+        }
+        if config.targets.is_empty() {
+            return Err(invalid("at least one target is required"));
+        }
+
+        let older_clients = match config.older_clients {
+            OlderClientsConfig::Chat => OlderClients::Chat,
+            OlderClientsConfig::ProxyConnect => OlderClients::ProxyConnect,
+        };
+        let targets = config
+            .targets
+            .into_iter()
+            .map(|target| {
+                if older_clients == OlderClients::ProxyConnect && target.proxy_server.is_none() {
+                    return Err(invalid(&format!(
+                        "target {}:{} requires a proxy_server to connect older clients through the proxy",
+                        target.host, target.port
+                    )));
+                }
+                let chat_prompt = parse_mini_message(
+                    &config
+                        .chat_prompt
+                        .replace(HOST_PLACEHOLDER, &target.host)
+                        .replace(PORT_PLACEHOLDER, &target.port.to_string()),
+                )?;
+                let proxy_connect = target
+                    .proxy_server
+                    .as_deref()
+                    .map(proxy_connect_payload)
+                    .transpose()?;
+                Ok(BackendTarget {
+                    host: target.host,
+                    port: target.port,
+                    proxy_connect,
+                    chat_prompt,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            interval: Duration::from_secs(config.interval_seconds),
+            transfer_delay: Duration::from_secs(1) / config.transfers_per_second,
+            older_clients,
+            targets,
+        })
+    }
+}
+
+/// Writes the `Connect` sub-channel and the server name as Java's `DataOutput::writeUTF` would.
+pub(super) fn proxy_connect_payload(server: &str) -> Result<Vec<u8>, ServerStateBuilderError> {
+    let mut payload = Vec::with_capacity(server.len() + 11);
+    for value in ["Connect", server] {
+        payload.extend_from_slice(&u16::try_from(value.len())?.to_be_bytes());
+        payload.extend_from_slice(value.as_bytes());
+    }
+    Ok(payload)
+}
+
+fn invalid(message: &str) -> ServerStateBuilderError {
+    ServerStateBuilderError::InvalidBackendWatcher(message.to_string())
+}
@@ -0,0 +1,188 @@
+use crate::configuration::queue::{QueueConfig, QueueDisplayConfig, QueueMethodConfig};
+use crate::server_state::ServerStateBuilderError;
+use crate::server_state::backend_transfer::proxy_connect_payload;
+use minecraft_packets::play::boss_bar_packet::BossBarColor;
+use minecraft_protocol::prelude::Uuid;
+use pico_text_component::prelude::{Component, MiniMessageError, parse_mini_message};
+use std::time::Duration;
+
+#[allow(clippy::literal_string_with_formatting_args)]
+const POSITION_PLACEHOLDER: &str = "{position}";
+#[allow(clippy::literal_string_with_formatting_args)]
+const SIZE_PLACEHOLDER: &str = "{size}";
+#[allow(clippy::literal_string_with_formatting_args)]
+const ETA_PLACEHOLDER: &str = "{eta}";
+#[allow(clippy::literal_string_with_formatting_args)]
+const HOST_PLACEHOLDER: &str = "{host}";
+#[allow(clippy::literal_string_with_formatting_args)]
+const PORT_PLACEHOLDER: &str = "{port}";
+const UNKNOWN_ETA: &str = "unknown";
+
+/// Players wait in the order they entered the game, by tier, and are released in batches.
+pub struct JoinQueue {
+    pub host: String,
+    pub port: u16,
+    /// Payload of the `BungeeCord` `Connect` message moving the player to the target.
+    pub proxy_connect: Option<Vec<u8>>,
+    pub method: QueueMethod,
+    pub batch_size: usize,
+    pub batch_interval: Duration,
+    pub display: QueueDisplay,
+    pub color: BossBarColor,
+    pub kick_message: Component,
+    message: String,
+    tiers: Vec<Vec<Uuid>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueueMethod {
+    Transfer,
+    ProxyConnect,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueueDisplay {
+    ActionBar,
+    BossBar,
+}
+
+impl JoinQueue {
+    pub fn from_config(config: QueueConfig) -> Result<Self, ServerStateBuilderError> {
+        if config.batch_size == 0 {
+            return Err(invalid("batch_size must be positive"));
+        }
+        if config.batch_interval_seconds == 0 {
+            return Err(invalid("batch_interval_seconds must be positive"));
+        }
+        let method = match config.method {
+            QueueMethodConfig::Transfer => QueueMethod::Transfer,
+            QueueMethodConfig::ProxyConnect => QueueMethod::ProxyConnect,
+        };
+        if method == QueueMethod::ProxyConnect && config.proxy_server.is_none() {
+            return Err(invalid("the proxy_connect method requires a proxy_server"));
+        }
+        // Fails early on an invalid message rather than on every update
+        parse_mini_message(&config.message)?;
+
+        let tiers = config
+            .tiers
+            .iter()
+            .map(|tier| {
+                tier.uuids
+                    .iter()
+                    .map(|uuid| {
+                        Uuid::parse_str(uuid)
+                            .map_err(|_| invalid(&format!("invalid UUID {uuid} in a tier")))
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<_, _>>()?;
+        let kick_message = parse_mini_message(
+            &config
+                .kick_message
+                .replace(HOST_PLACEHOLDER, &config.host)
+                .replace(PORT_PLACEHOLDER, &config.port.to_string()),
+        )?;
+
+        Ok(Self {
+            proxy_connect: config
+                .proxy_server
+                .as_deref()
+                .map(proxy_connect_payload)
+                .transpose()?,
+            host: config.host,
+            port: config.port,
+            method,
+            batch_size: usize::try_from(config.batch_size)?,
+            batch_interval: Duration::from_secs(config.batch_interval_seconds),
+            display: match config.display {
+                QueueDisplayConfig::ActionBar => QueueDisplay::ActionBar,
+                QueueDisplayConfig::BossBar => QueueDisplay::BossBar,
+            },
+            color: config.color.into(),
+            kick_message,
+            message: config.message,
+            tiers,
+        })
+    }
+
+    /// Index of the first tier listing the player, players in no tier come last.
+    pub fn tier(&self, uuid: Uuid) -> usize {
+        self.tiers
+            .iter()
+            .position(|tier| tier.contains(&uuid))
+            .unwrap_or(self.tiers.len())
+    }
+
+    /// Message displayed to a player, the ETA is unknown while the target is offline.
+    pub fn message(
+        &self,
+        position: usize,
+        size: usize,
+        eta: Option<Duration>,
+    ) -> Result<Component, MiniMessageError> {
+        let eta = eta.map_or_else(|| UNKNOWN_ETA.to_string(), format_eta);
+        parse_mini_message(
+            &self
+                .message
+                .replace(POSITION_PLACEHOLDER, &position.to_string())
+                .replace(SIZE_PLACEHOLDER, &size.to_string())
+                .replace(ETA_PLACEHOLDER, &eta),
+        )
+    }
+}
+
+/// Formats a duration as `1h 2m`, `2m 5s` or `5s`.
+fn format_eta(eta: Duration) -> String {
+    let seconds = eta.as_secs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{hours}h {minutes}m")
+    } else if minutes > 0 {
+        format!("{minutes}m {seconds}s")
+    } else {
+        format!("{seconds}s")
+    }
+}
+
+fn invalid(message: &str) -> ServerStateBuilderError {
+    ServerStateBuilderError::InvalidQueue(message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configuration::queue::QueueTierConfig;
+
+    #[test]
+    fn test_players_in_no_tier_come_last() {
+        // Given
+        let queue = JoinQueue::from_config(QueueConfig {
+            tiers: vec![
+                QueueTierConfig {
+                    uuids: vec!["00000000-0000-0000-0000-000000000001".into()],
+                },
+                QueueTierConfig {
+                    uuids: vec!["00000000-0000-0000-0000-000000000002".into()],
+                },
+            ],
+            ..QueueConfig::default()
+        })
+        .unwrap();
+
+        // When
+        let tiers: Vec<usize> = (1..=3)
+            .map(|uuid| queue.tier(Uuid::from_u128(uuid)))
+            .collect();
+
+        // Then
+        assert_eq!(tiers, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_format_eta() {
+        assert_eq!(format_eta(Duration::from_secs(5)), "5s");
+        assert_eq!(format_eta(Duration::from_secs(125)), "2m 5s");
+        assert_eq!(format_eta(Duration::from_mins(62)), "1h 2m");
+    }
+}
@@ -1,16 +1,32 @@
+use crate::configuration::announcements::AnnouncementConfig;
+use crate::configuration::backend_watcher::BackendWatcherConfig;
 use crate::configuration::boss_bar::EnabledBossBarConfig;
 use crate::configuration::commands::CommandsConfig;
+use crate::configuration::connection_config::TimeoutsConfig;
 use crate::configuration::fly_config::FlyConfig;
+use crate::configuration::queue::QueueConfig;
+use crate::configuration::session_timeouts::{AfkConfig, SessionLimitConfig};
 use crate::server::game_mode::GameMode;
+use crate::server::game_profile::GameProfile;
+pub use access_lists::{AccessListError, AccessLists};
+pub use announcements::{Announcement, AnnouncementMessage, Rotation};
+pub use backend_transfer::{BackendTarget, BackendTransfer, OlderClients};
 use base64::engine::general_purpose;
 use base64::{Engine, alphabet, engine};
+use ipnet::IpNet;
+pub use join_queue::{JoinQueue, QueueDisplay, QueueMethod};
 use minecraft_packets::play::boss_bar_packet::{BossBarColor, BossBarDivision};
-use minecraft_protocol::prelude::{BinaryReaderError, Dimension};
+use minecraft_protocol::prelude::{BinaryReaderError, Dimension, State, Uuid};
+#[cfg(test)]
+pub use online_mode::sign_verify_token;
+pub use online_mode::{OnlineMode, OnlineModeError, verify_token_signature};
 use pico_structures::prelude::{Schematic, SchematicError, World, WorldLoadingError};
 use pico_text_component::prelude::{Component, MiniMessageError, parse_mini_message};
 pub use server_commands::{ServerCommand, ServerCommands};
+pub use session_timeouts::{SessionTimeout, TimeoutStep, TimeoutTransfer, WarningDisplay};
 use std::fs::File;
 use std::io::Read;
+use std::net::IpAddr;
 use std::num::TryFromIntError;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -19,7 +35,13 @@ use std::time::Duration;
 use thiserror::Error;
 use tracing::debug;
 
+mod access_lists;
+mod announcements;
+mod backend_transfer;
+mod join_queue;
+mod online_mode;
 mod server_commands;
+mod session_timeouts;
 
 #[derive(Clone)]
 pub struct CompressionSettings {
@@ -36,10 +58,34 @@ pub enum ForwardingMode {
         tokens: Vec<String>,
     },
     Modern {
-        secret: Vec<u8>,
+        /// Every accepted secret, to rotate them without downtime.
+        secrets: Vec<Vec<u8>>,
+        /// Tokens of the `BungeeGuard` proxies accepted alongside the Velocity ones.
+        bungee_guard_tokens: Vec<String>,
     },
 }
 
+impl ForwardingMode {
+    /// Short name of the mode, used to label the logins in the metrics.
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::Disabled => "none",
+            Self::Legacy => "legacy",
+            Self::BungeeGuard { .. } => "bungee_guard",
+            Self::Modern { .. } => "modern",
+        }
+    }
+
+    /// Name of the forwarding a player logged in with, players forwarded in the handshake
+    /// alongside modern forwarding were let in by `BungeeGuard`.
+    pub const fn login_name(&self, forwarded_by_handshake: bool) -> &'static str {
+        match self {
+            Self::Modern { .. } if forwarded_by_handshake => "bungee_guard",
+            _ => self.name(),
+        }
+    }
+}
+
 #[derive(Debug, Error)]
 #[error("secret key not set")]
 pub struct MisconfiguredForwardingError;
@@ -50,12 +96,63 @@ pub struct Boundaries {
     pub teleport_message: Option<Component>,
 }
 
-#[derive(Default)]
+pub struct ProxyProtocol {
+    trusted_sources: Vec<IpNet>,
+}
+
+impl ProxyProtocol {
+    /// Whether the given peer is allowed to send a PROXY protocol header.
+    pub fn is_trusted(&self, ip: IpAddr) -> bool {
+        let ip = ip.to_canonical();
+        self.trusted_sources
+            .iter()
+            .any(|trusted_source| trusted_source.contains(&ip))
+    }
+}
+
+/// Limits applied to incoming connections, a value of 0 disables the matching limit.
+#[derive(Debug, Clone, Copy)]
+pub struct ConnectionLimits {
+    pub window: Duration,
+    pub connections_per_window: u32,
+    pub max_connections_per_address: u32,
+    pub max_pending_connections: usize,
+}
+
+pub struct MaxPlayersEnforcement {
+    kick_message: Component,
+    bypass_uuids: Vec<Uuid>,
+    bypass_usernames: Vec<String>,
+}
+
+impl MaxPlayersEnforcement {
+    fn can_bypass(&self, game_profile: &GameProfile) -> bool {
+        self.bypass_uuids.contains(&game_profile.uuid())
+            || self
+                .bypass_usernames
+                .iter()
+                .any(|username| username.eq_ignore_ascii_case(game_profile.username()))
+    }
+}
+
+/// Slot of a player who logged in, counted as online until dropped.
+pub struct PlayerSlot {
+    connected_clients: Arc<AtomicU32>,
+}
+
+impl Drop for PlayerSlot {
+    fn drop(&mut self) {
+        self.connected_clients.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[derive(Default, PartialEq)]
 pub struct TabList {
     pub header: Component,
     pub footer: Component,
 }
 
+#[derive(PartialEq)]
 pub struct BossBar {
     pub title: Component,
     pub health: f32,
@@ -63,6 +160,7 @@ pub struct BossBar {
     pub division: BossBarDivision,
 }
 
+#[derive(PartialEq)]
 pub enum TitleType {
     Title(Component),
     Subtitle(Component),
@@ -72,6 +170,7 @@ pub enum TitleType {
     },
 }
 
+#[derive(PartialEq)]
 pub struct Title {
     pub content: TitleType,
     pub fade_in: i32,
@@ -79,6 +178,50 @@ pub struct Title {
     pub fade_out: i32,
 }
 
+/// Sections displayed to players in game that changed after a reload.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ReloadedSections {
+    pub tab_list: bool,
+    pub boss_bar: bool,
+    pub title: bool,
+}
+
+impl ReloadedSections {
+    pub const fn all() -> Self {
+        Self {
+            tab_list: true,
+            boss_bar: true,
+            title: true,
+        }
+    }
+
+    pub const fn is_empty(self) -> bool {
+        !self.tab_list && !self.boss_bar && !self.title
+    }
+}
+
+/// Maximum time a client may spend in each state, `None` when disabled.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Timeouts {
+    pub handshake: Option<Duration>,
+    pub status: Option<Duration>,
+    pub login: Option<Duration>,
+    pub configuration: Option<Duration>,
+    pub play_read: Option<Duration>,
+}
+
+impl Timeouts {
+    pub const fn for_state(&self, state: State) -> Option<Duration> {
+        match state {
+            State::Handshake => self.handshake,
+            State::Status => self.status,
+            State::Login | State::Transfer => self.login,
+            State::Configuration => self.configuration,
+            State::Play => self.play_read,
+        }
+    }
+}
+
 pub struct Fly {
     pub allow_flight: bool,
     pub flying: bool,
@@ -99,11 +242,15 @@ impl Default for Fly {
 #[allow(clippy::struct_excessive_bools)]
 pub struct ServerState {
     forwarding_mode: ForwardingMode,
+    online_mode: Option<OnlineMode>,
+    proxy_protocol: Option<ProxyProtocol>,
+    connection_limits: Option<ConnectionLimits>,
     spawn_dimension: Dimension,
     motd: Component,
     time_world: i64,
     lock_time: bool,
     max_players: u32,
+    max_players_enforcement: Option<MaxPlayersEnforcement>,
     welcome_message: Option<Component>,
     connected_clients: Arc<AtomicU32>,
     show_online_player_count: bool,
@@ -129,6 +276,15 @@ pub struct ServerState {
     fly: Fly,
     server_commands: ServerCommands,
     keep_alive_interval_secs: u64,
+    max_missed_keep_alives: u32,
+    timeouts: Timeouts,
+    access_lists: AccessLists,
+    announcements: Arc<[Announcement]>,
+    backend_transfer: Option<Arc<BackendTransfer>>,
+    join_queue: Option<Arc<JoinQueue>>,
+    afk_timeout: Option<SessionTimeout>,
+    session_limit: Option<SessionTimeout>,
+    recording_directory: Option<PathBuf>,
 }
 
 impl ServerState {
@@ -137,6 +293,10 @@ impl ServerState {
         ServerStateBuilder::default()
     }
 
+    pub const fn forwarding_mode(&self) -> &ForwardingMode {
+        &self.forwarding_mode
+    }
+
     pub const fn is_legacy_forwarding(&self) -> bool {
         matches!(self.forwarding_mode, ForwardingMode::Legacy)
     }
@@ -145,24 +305,49 @@ impl ServerState {
         matches!(self.forwarding_mode, ForwardingMode::Modern { .. })
     }
 
-    pub fn secret_key(&self) -> Result<Vec<u8>, MisconfiguredForwardingError> {
+    pub fn secret_keys(&self) -> Result<&[Vec<u8>], MisconfiguredForwardingError> {
         match &self.forwarding_mode {
-            ForwardingMode::Modern { secret } => Ok(secret.clone()),
+            ForwardingMode::Modern { secrets, .. } if !secrets.is_empty() => Ok(secrets),
             _ => Err(MisconfiguredForwardingError),
         }
     }
 
+    /// Whether `BungeeGuard` forwarding is accepted, alone or alongside modern forwarding.
     pub const fn is_bungee_guard_forwarding(&self) -> bool {
-        matches!(self.forwarding_mode, ForwardingMode::BungeeGuard { .. })
+        match &self.forwarding_mode {
+            ForwardingMode::BungeeGuard { .. } => true,
+            ForwardingMode::Modern {
+                bungee_guard_tokens,
+                ..
+            } => !bungee_guard_tokens.is_empty(),
+            ForwardingMode::Disabled | ForwardingMode::Legacy => false,
+        }
     }
 
     pub fn tokens(&self) -> Result<Vec<String>, MisconfiguredForwardingError> {
         match &self.forwarding_mode {
-            ForwardingMode::BungeeGuard { tokens } => Ok(tokens.clone()),
+            ForwardingMode::BungeeGuard { tokens }
+            | ForwardingMode::Modern {
+                bungee_guard_tokens: tokens,
+                ..
+            } => Ok(tokens.clone()),
             _ => Err(MisconfiguredForwardingError),
         }
     }
 
+    /// Returns the online mode settings, only set when forwarding is disabled.
+    pub const fn online_mode(&self) -> Option<&OnlineMode> {
+        self.online_mode.as_ref()
+    }
+
+    pub const fn proxy_protocol(&self) -> Option<&ProxyProtocol> {
+        self.proxy_protocol.as_ref()
+    }
+
+    pub const fn connection_limits(&self) -> Option<&ConnectionLimits> {
+        self.connection_limits.as_ref()
+    }
+
     pub const fn motd(&self) -> &Component {
         &self.motd
     }
@@ -171,6 +356,31 @@ impl ServerState {
         self.max_players
     }
 
+    /// Counts the player as online, unless the server is full and the player may not bypass the limit.
+    /// The check and the reservation are a single atomic operation, so that concurrent logins
+    /// cannot exceed `max_players`.
+    pub fn reserve_player_slot(
+        &self,
+        game_profile: &GameProfile,
+    ) -> Result<PlayerSlot, &Component> {
+        let enforcement = self
+            .max_players_enforcement
+            .as_ref()
+            .filter(|enforcement| !enforcement.can_bypass(game_profile));
+        if let Some(enforcement) = enforcement {
+            self.connected_clients
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |connected_clients| {
+                    (connected_clients < self.max_players).then_some(connected_clients + 1)
+                })
+                .map_err(|_| &enforcement.kick_message)?;
+        } else {
+            self.connected_clients.fetch_add(1, Ordering::SeqCst);
+        }
+        Ok(PlayerSlot {
+            connected_clients: Arc::clone(&self.connected_clients),
+        })
+    }
+
     pub const fn welcome_message(&self) -> Option<&Component> {
         self.welcome_message.as_ref()
     }
@@ -219,6 +429,14 @@ impl ServerState {
         Duration::from_secs(self.keep_alive_interval_secs)
     }
 
+    pub const fn max_missed_keep_alives(&self) -> u32 {
+        self.max_missed_keep_alives
+    }
+
+    pub const fn timeouts(&self) -> Timeouts {
+        self.timeouts
+    }
+
     pub fn world(&self) -> Option<Arc<World>> {
         self.world.clone()
     }
@@ -287,12 +505,71 @@ impl ServerState {
         &self.server_commands
     }
 
-    pub fn increment(&self) {
-        self.connected_clients.fetch_add(1, Ordering::SeqCst);
+    pub const fn access_lists(&self) -> &AccessLists {
+        &self.access_lists
     }
 
-    pub fn decrement(&self) {
-        self.connected_clients.fetch_sub(1, Ordering::SeqCst);
+    /// Shared so that the schedule of unchanged announcements survives a reload.
+    pub fn announcements(&self) -> Arc<[Announcement]> {
+        Arc::clone(&self.announcements)
+    }
+
+    pub fn backend_transfer(&self) -> Option<Arc<BackendTransfer>> {
+        self.backend_transfer.clone()
+    }
+
+    pub fn join_queue(&self) -> Option<Arc<JoinQueue>> {
+        self.join_queue.clone()
+    }
+
+    pub const fn afk_timeout(&self) -> Option<&SessionTimeout> {
+        self.afk_timeout.as_ref()
+    }
+
+    pub const fn session_limit(&self) -> Option<&SessionTimeout> {
+        self.session_limit.as_ref()
+    }
+
+    /// Whether the players must be checked for the AFK timeout or the session limit.
+    pub const fn has_session_timeouts(&self) -> bool {
+        self.afk_timeout.is_some() || self.session_limit.is_some()
+    }
+
+    /// Directory where the connections are recorded, `None` when recording is disabled.
+    pub fn recording_directory(&self) -> Option<&Path> {
+        self.recording_directory.as_deref()
+    }
+
+    /// Keeps what must survive a reload from the state being replaced:
+    /// the player count, the announcements when they did not change and, when the
+    /// session server did not change, the key pair used by players currently logging in.
+    pub fn carry_over(&mut self, previous: &mut Self) {
+        self.connected_clients = Arc::clone(&previous.connected_clients);
+        if self.announcements == previous.announcements {
+            self.announcements = Arc::clone(&previous.announcements);
+        }
+        let is_same_session_server = matches!(
+            (&self.online_mode, &previous.online_mode),
+            (Some(online_mode), Some(previous_online_mode))
+                if online_mode.has_joined_url() == previous_online_mode.has_joined_url()
+        );
+        if is_same_session_server {
+            self.online_mode = previous.online_mode.take();
+        }
+    }
+
+    /// Compares the sections pushed to players in game with the previous state.
+    pub fn reloaded_sections(&self, previous: &Self) -> ReloadedSections {
+        ReloadedSections {
+            tab_list: self.tab_list != previous.tab_list,
+            boss_bar: self.boss_bar != previous.boss_bar,
+            title: self.title != previous.title,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn increment(&self) {
+        self.connected_clients.fetch_add(1, Ordering::SeqCst);
     }
 }
 
@@ -300,11 +577,15 @@ impl ServerState {
 #[allow(clippy::struct_excessive_bools)]
 pub struct ServerStateBuilder {
     forwarding_mode: ForwardingMode,
+    has_joined_url: Option<String>,
+    proxy_protocol: Option<ProxyProtocol>,
+    connection_limits: Option<ConnectionLimits>,
     dimension: Option<Dimension>,
     time_world: i64,
     lock_time: bool,
     description_text: String,
     max_players: u32,
+    max_players_enforcement: Option<MaxPlayersEnforcement>,
     welcome_message: String,
     show_online_player_count: bool,
     game_mode: GameMode,
@@ -329,6 +610,15 @@ pub struct ServerStateBuilder {
     accept_transfers: bool,
     server_commands: ServerCommands,
     keep_alive_interval_secs: Option<u64>,
+    max_missed_keep_alives: u32,
+    timeouts: Timeouts,
+    access_lists: AccessLists,
+    announcements: Vec<Announcement>,
+    backend_transfer: Option<BackendTransfer>,
+    join_queue: Option<JoinQueue>,
+    afk_timeout: Option<SessionTimeout>,
+    session_limit: Option<SessionTimeout>,
+    recording_directory: Option<PathBuf>,
 }
 
 #[derive(Debug, Error)]
@@ -347,6 +637,27 @@ pub enum ServerStateBuilderError {
     Io(#[from] std::io::Error),
     #[error(transparent)]
     TryFromInt(#[from] TryFromIntError),
+    #[error(transparent)]
+    OnlineMode(#[from] OnlineModeError),
+    #[error("invalid IP address or CIDR range: {0}")]
+    InvalidIpNet(String),
+    #[error(transparent)]
+    AccessList(#[from] AccessListError),
+    #[error("invalid announcement: {0}")]
+    InvalidAnnouncement(String),
+    #[error("invalid backend watcher: {0}")]
+    InvalidBackendWatcher(String),
+    #[error("invalid queue: {0}")]
+    InvalidQueue(String),
+    #[error("invalid session timeout: {0}")]
+    InvalidSessionTimeout(String),
+    #[error("modern forwarding requires at least one secret")]
+    MissingForwardingSecret,
+    #[error("failed to read the forwarding secret file {}: {source}", path.display())]
+    ForwardingSecretFile {
+        path: PathBuf,
+        source: std::io::Error,
+    },
 }
 
 impl ServerStateBuilder {
@@ -360,11 +671,35 @@ impl ServerStateBuilder {
         self
     }
 
-    pub fn enable_modern_forwarding<K>(&mut self, key: K) -> &mut Self
+    /// Accepts the players forwarded by Velocity with any of the given secrets.
+    pub fn enable_modern_forwarding<I, K>(&mut self, secrets: I) -> &mut Self
+    where
+        I: IntoIterator<Item = K>,
+        K: Into<Vec<u8>>,
+    {
+        self.forwarding_mode = ForwardingMode::Modern {
+            secrets: secrets.into_iter().map(Into::into).collect(),
+            bungee_guard_tokens: Vec::new(),
+        };
+        self
+    }
+
+    /// Accepts the players forwarded by Velocity with any of the given secrets,
+    /// and the players forwarded by `BungeeGuard` with any of the given tokens.
+    /// The forwarding is chosen by what the handshake carries.
+    pub fn enable_modern_and_bungee_guard_forwarding<I, K>(
+        &mut self,
+        secrets: I,
+        tokens: Vec<String>,
+    ) -> &mut Self
     where
+        I: IntoIterator<Item = K>,
         K: Into<Vec<u8>>,
     {
-        self.forwarding_mode = ForwardingMode::Modern { secret: key.into() };
+        self.forwarding_mode = ForwardingMode::Modern {
+            secrets: secrets.into_iter().map(Into::into).collect(),
+            bungee_guard_tokens: tokens,
+        };
         self
     }
 
@@ -373,6 +708,48 @@ impl ServerStateBuilder {
         self
     }
 
+    /// Authenticate players against the given session server `hasJoined` URL.
+    pub fn enable_online_mode<S>(&mut self, has_joined_url: S) -> &mut Self
+    where
+        S: Into<String>,
+    {
+        self.has_joined_url = Some(has_joined_url.into());
+        self
+    }
+
+    /// Expect a PROXY protocol header from the given addresses or CIDR ranges.
+    pub fn enable_proxy_protocol<S>(
+        &mut self,
+        trusted_sources: &[S],
+    ) -> Result<&mut Self, ServerStateBuilderError>
+    where
+        S: AsRef<str>,
+    {
+        let trusted_sources = trusted_sources
+            .iter()
+            .map(|source| parse_ip_net(source.as_ref()))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.proxy_protocol = Some(ProxyProtocol { trusted_sources });
+        Ok(self)
+    }
+
+    /// Throttle connections per address and cap connections that did not join yet.
+    pub const fn connection_limits(
+        &mut self,
+        window_secs: u64,
+        connections_per_window: u32,
+        max_connections_per_address: u32,
+        max_pending_connections: usize,
+    ) -> &mut Self {
+        self.connection_limits = Some(ConnectionLimits {
+            window: Duration::from_secs(window_secs),
+            connections_per_window,
+            max_connections_per_address,
+            max_pending_connections,
+        });
+        self
+    }
+
     /// Set the spawn dimension
     pub const fn dimension(&mut self, dimension: Dimension) -> &mut Self {
         self.dimension = Some(dimension);
@@ -403,6 +780,33 @@ impl ServerStateBuilder {
         self
     }
 
+    /// Refuse logins once `max_players` are online, except for the given UUIDs or usernames.
+    pub fn enforce_max_players<S>(
+        &mut self,
+        kick_message: &str,
+        bypass: &[S],
+    ) -> Result<&mut Self, ServerStateBuilderError>
+    where
+        S: AsRef<str>,
+    {
+        let (bypass_uuids, bypass_usernames) = bypass.iter().map(AsRef::as_ref).fold(
+            (Vec::new(), Vec::new()),
+            |(mut uuids, mut usernames), entry| {
+                match Uuid::parse_str(entry) {
+                    Ok(uuid) => uuids.push(uuid),
+                    Err(_) => usernames.push(entry.to_string()),
+                }
+                (uuids, usernames)
+            },
+        );
+        self.max_players_enforcement = Some(MaxPlayersEnforcement {
+            kick_message: parse_mini_message(kick_message)?,
+            bypass_uuids,
+            bypass_usernames,
+        });
+        Ok(self)
+    }
+
     pub fn welcome_message<S>(&mut self, message: S) -> &mut Self
     where
         S: Into<String>,
@@ -461,6 +865,24 @@ impl ServerStateBuilder {
         self
     }
 
+    pub const fn set_timeouts(&mut self, timeouts: &TimeoutsConfig) -> &mut Self {
+        const fn seconds(secs: u64) -> Option<Duration> {
+            if secs == 0 {
+                None
+            } else {
+                Some(Duration::from_secs(secs))
+            }
+        }
+        self.timeouts = Timeouts {
+            handshake: seconds(timeouts.handshake),
+            status: seconds(timeouts.status),
+            login: seconds(timeouts.login),
+            configuration: seconds(timeouts.configuration),
+            play_read: seconds(timeouts.play_read),
+        };
+        self
+    }
+
     pub const fn set_accept_transfers(&mut self, accept_transfers: bool) -> &mut Self {
         self.accept_transfers = accept_transfers;
         self
@@ -493,6 +915,11 @@ impl ServerStateBuilder {
         self
     }
 
+    pub const fn max_missed_keep_alives(&mut self, max_missed_keep_alives: u32) -> &mut Self {
+        self.max_missed_keep_alives = max_missed_keep_alives;
+        self
+    }
+
     pub fn schematic(&mut self, schematic_file_path: String) -> &mut Self {
         self.schematic_file_path = schematic_file_path;
         self
@@ -611,6 +1038,90 @@ impl ServerStateBuilder {
         self
     }
 
+    /// Adds messages sent to every player in game on a schedule.
+    pub fn announcement(
+        &mut self,
+        announcement_config: AnnouncementConfig,
+    ) -> Result<&mut Self, ServerStateBuilderError> {
+        self.announcements
+            .push(Announcement::from_config(announcement_config)?);
+        Ok(self)
+    }
+
+    /// Moves the players to the first configured backend server found online.
+    pub fn backend_watcher(
+        &mut self,
+        backend_watcher_config: BackendWatcherConfig,
+    ) -> Result<&mut Self, ServerStateBuilderError> {
+        self.backend_transfer = Some(BackendTransfer::from_config(backend_watcher_config)?);
+        Ok(self)
+    }
+
+    /// Holds the players in a queue and releases them in batches to the target.
+    pub fn queue(
+        &mut self,
+        queue_config: QueueConfig,
+    ) -> Result<&mut Self, ServerStateBuilderError> {
+        self.join_queue = Some(JoinQueue::from_config(queue_config)?);
+        Ok(self)
+    }
+
+    /// Kicks or transfers the players who neither moved nor chatted for a while.
+    pub fn afk(&mut self, afk_config: AfkConfig) -> Result<&mut Self, ServerStateBuilderError> {
+        self.afk_timeout = Some(SessionTimeout::afk(afk_config)?);
+        Ok(self)
+    }
+
+    /// Kicks or transfers the players once they stayed in game for the maximum duration.
+    pub fn session_limit(
+        &mut self,
+        session_limit_config: SessionLimitConfig,
+    ) -> Result<&mut Self, ServerStateBuilderError> {
+        self.session_limit = Some(SessionTimeout::session_limit(session_limit_config)?);
+        Ok(self)
+    }
+
+    /// Records the packets of every connection to a file in the given directory, when set.
+    pub fn record_connections(&mut self, directory: Option<PathBuf>) -> &mut Self {
+        self.recording_directory = directory;
+        self
+    }
+
+    /// Only let the players listed in the JSON file join, the file is created when missing.
+    pub fn whitelist<P>(
+        &mut self,
+        file_path: P,
+        kick_message: &str,
+    ) -> Result<&mut Self, ServerStateBuilderError>
+    where
+        P: AsRef<Path>,
+    {
+        self.access_lists
+            .enable_whitelist(file_path.as_ref(), kick_message)?;
+        Ok(self)
+    }
+
+    /// Refuse the players and addresses banned in the JSON files, the files are created when missing.
+    /// `{reason}` and `{expires}` are replaced in the kick messages.
+    pub fn bans<P>(
+        &mut self,
+        players_file_path: P,
+        ips_file_path: P,
+        kick_message: &str,
+        ip_kick_message: &str,
+    ) -> Result<&mut Self, ServerStateBuilderError>
+    where
+        P: AsRef<Path>,
+    {
+        self.access_lists.enable_bans(
+            players_file_path.as_ref(),
+            ips_file_path.as_ref(),
+            kick_message,
+            ip_kick_message,
+        )?;
+        Ok(self)
+    }
+
     /// Finish building, returning an error if any required fields are missing.
     pub fn build(self) -> Result<ServerState, ServerStateBuilderError> {
         let world = if self.schematic_file_path.is_empty() {
@@ -625,13 +1136,22 @@ impl ServerStateBuilder {
             Some(Arc::new(world))
         };
 
+        let online_mode = self
+            .has_joined_url
+            .map(|url| time_operation("Generating RSA key pair", || OnlineMode::new(url)))
+            .transpose()?;
+
         Ok(ServerState {
             forwarding_mode: self.forwarding_mode,
+            online_mode,
+            proxy_protocol: self.proxy_protocol,
+            connection_limits: self.connection_limits,
             spawn_dimension: self.dimension.unwrap_or_default(),
             motd: parse_mini_message(&self.description_text)?,
             time_world: self.time_world,
             lock_time: self.lock_time,
             max_players: self.max_players,
+            max_players_enforcement: self.max_players_enforcement,
             welcome_message: optional_mini_message(&self.welcome_message)?,
             action_bar: self.action_bar,
             connected_clients: Arc::new(AtomicU32::new(0)),
@@ -657,10 +1177,28 @@ impl ServerStateBuilder {
             accept_transfers: self.accept_transfers,
             server_commands: self.server_commands,
             keep_alive_interval_secs: self.keep_alive_interval_secs.unwrap_or(15),
+            max_missed_keep_alives: self.max_missed_keep_alives,
+            timeouts: self.timeouts,
+            access_lists: self.access_lists,
+            announcements: self.announcements.into(),
+            backend_transfer: self.backend_transfer.map(Arc::new),
+            join_queue: self.join_queue.map(Arc::new),
+            afk_timeout: self.afk_timeout,
+            session_limit: self.session_limit,
+            recording_directory: self.recording_directory,
         })
     }
 }
 
+/// Parses a CIDR range, a single address being treated as a range of one.
+pub fn parse_ip_net(value: &str) -> Result<IpNet, ServerStateBuilderError> {
+    let value = value.trim();
+    value
+        .parse::<IpNet>()
+        .or_else(|_| value.parse::<IpAddr>().map(IpNet::from))
+        .map_err(|_| ServerStateBuilderError::InvalidIpNet(value.to_string()))
+}
+
 fn optional_mini_message(content: &str) -> Result<Option<Component>, MiniMessageError> {
     let component = if content.is_empty() {
         None
@@ -691,3 +1229,104 @@ where
     debug!("Time elapsed: {}", format_duration(elapsed));
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ip_net_accepts_single_address() {
+        let ip_net = parse_ip_net("10.0.0.1").unwrap();
+        assert_eq!(ip_net, "10.0.0.1/32".parse::<IpNet>().unwrap());
+    }
+
+    #[test]
+    fn test_parse_ip_net_rejects_garbage() {
+        assert!(matches!(
+            parse_ip_net("not an ip"),
+            Err(ServerStateBuilderError::InvalidIpNet(_))
+        ));
+    }
+
+    #[test]
+    fn test_proxy_protocol_trusts_ipv4_mapped_addresses() {
+        // Given
+        let mut builder = ServerState::builder();
+        builder.enable_proxy_protocol(&["10.0.0.0/8"]).unwrap();
+        let server_state = builder.build().unwrap();
+        let proxy_protocol = server_state.proxy_protocol().unwrap();
+
+        // Then
+        assert!(proxy_protocol.is_trusted("10.1.2.3".parse().unwrap()));
+        assert!(proxy_protocol.is_trusted("::ffff:10.1.2.3".parse().unwrap()));
+        assert!(!proxy_protocol.is_trusted("192.168.1.1".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_timeouts_zero_disables_state() {
+        // Given
+        let mut builder = ServerState::builder();
+        builder.set_timeouts(&TimeoutsConfig {
+            handshake: 5,
+            status: 0,
+            login: 30,
+            configuration: 30,
+            play_read: 60,
+        });
+        let server_state = builder.build().unwrap();
+
+        // When
+        let timeouts = server_state.timeouts();
+
+        // Then
+        assert_eq!(
+            timeouts.for_state(State::Handshake),
+            Some(Duration::from_secs(5))
+        );
+        assert_eq!(timeouts.for_state(State::Status), None);
+        assert_eq!(
+            timeouts.for_state(State::Transfer),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            timeouts.for_state(State::Play),
+            Some(Duration::from_mins(1))
+        );
+    }
+
+    #[test]
+    fn test_session_timeouts_are_only_checked_when_configured() {
+        // Given
+        let disabled = ServerState::builder().build().unwrap();
+        let mut builder = ServerState::builder();
+        builder
+            .afk(AfkConfig {
+                timeout_seconds: 60,
+                warning_seconds: 10,
+                ..AfkConfig::default()
+            })
+            .unwrap();
+
+        // When
+        let enabled = builder.build().unwrap();
+
+        // Then
+        assert!(!disabled.has_session_timeouts());
+        assert!(enabled.has_session_timeouts());
+    }
+
+    #[test]
+    fn test_login_name_is_the_forwarding_used_by_the_player() {
+        // Given
+        let mut builder = ServerState::builder();
+        builder.enable_modern_and_bungee_guard_forwarding(["secret"], vec!["token".to_string()]);
+        let server_state = builder.build().unwrap();
+
+        // When
+        let forwarding_mode = server_state.forwarding_mode();
+
+        // Then
+        assert_eq!(forwarding_mode.login_name(false), "modern");
+        assert_eq!(forwarding_mode.login_name(true), "bungee_guard");
+    }
+}
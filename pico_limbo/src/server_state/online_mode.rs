@@ -0,0 +1,203 @@
+use rsa::pkcs8::{DecodePublicKey, EncodePublicKey};
+use rsa::rand_core::OsRng;
+use rsa::{Pkcs1v15Encrypt, Pkcs1v15Sign, RsaPrivateKey, RsaPublicKey};
+use sha1::{Digest, Sha1};
+use sha2::Sha256;
+use std::fmt::Write;
+use thiserror::Error;
+
+/// Same key size as the vanilla server.
+const KEY_SIZE: usize = 1024;
+/// `DigestInfo` prefix of a SHA-256 hash in a PKCS#1 v1.5 signature.
+const SHA256_DIGEST_INFO_PREFIX: [u8; 19] = [
+    0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01, 0x05,
+    0x00, 0x04, 0x20,
+];
+
+pub struct OnlineMode {
+    private_key: RsaPrivateKey,
+    public_key_der: Vec<u8>,
+    has_joined_url: String,
+}
+
+impl OnlineMode {
+    /// Generates the RSA key pair used for the encryption handshake.
+    pub fn new(has_joined_url: String) -> Result<Self, OnlineModeError> {
+        let private_key = RsaPrivateKey::new(&mut OsRng, KEY_SIZE)?;
+        let public_key_der = RsaPublicKey::from(&private_key)
+            .to_public_key_der()?
+            .into_vec();
+        Ok(Self {
+            private_key,
+            public_key_der,
+            has_joined_url,
+        })
+    }
+
+    /// The public key in ASN.1 DER format, as sent in the encryption request.
+    pub fn public_key(&self) -> &[u8] {
+        &self.public_key_der
+    }
+
+    pub fn has_joined_url(&self) -> &str {
+        &self.has_joined_url
+    }
+
+    /// Decrypts a value the client encrypted with our public key.
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, rsa::Error> {
+        self.private_key.decrypt(Pkcs1v15Encrypt, data)
+    }
+
+    /// Computes the `serverId` sent to the session server, the server id being always empty.
+    pub fn server_hash(&self, shared_secret: &[u8]) -> String {
+        let digest = Sha1::new()
+            .chain_update(shared_secret)
+            .chain_update(&self.public_key_der)
+            .finalize();
+        minecraft_hex_digest(digest.into())
+    }
+}
+
+/// Checks the verify token a 1.19 to 1.19.2 client signed with its profile key instead of
+/// encrypting it, the signature covering the verify token followed by the salt.
+pub fn verify_token_signature(
+    profile_public_key: &[u8],
+    verify_token: &[u8],
+    salt: i64,
+    signature: &[u8],
+) -> bool {
+    let Ok(public_key) = RsaPublicKey::from_public_key_der(profile_public_key) else {
+        return false;
+    };
+    let digest = verify_token_digest(verify_token, salt);
+    public_key
+        .verify(sha256_signature_scheme(), &digest, signature)
+        .is_ok()
+}
+
+/// Signs the verify token the way a 1.19 client does.
+#[cfg(test)]
+pub fn sign_verify_token(profile_key: &RsaPrivateKey, verify_token: &[u8], salt: i64) -> Vec<u8> {
+    let digest = verify_token_digest(verify_token, salt);
+    profile_key
+        .sign(sha256_signature_scheme(), &digest)
+        .unwrap()
+}
+
+fn verify_token_digest(verify_token: &[u8], salt: i64) -> Vec<u8> {
+    Sha256::new()
+        .chain_update(verify_token)
+        .chain_update(salt.to_be_bytes())
+        .finalize()
+        .to_vec()
+}
+
+fn sha256_signature_scheme() -> Pkcs1v15Sign {
+    Pkcs1v15Sign {
+        hash_len: Some(32),
+        prefix: Box::new(SHA256_DIGEST_INFO_PREFIX),
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum OnlineModeError {
+    #[error("failed to generate the RSA key pair: {0}")]
+    Rsa(#[from] rsa::Error),
+    #[error("failed to encode the RSA public key: {0}")]
+    PublicKeyEncoding(#[from] rsa::pkcs8::spki::Error),
+}
+
+/// Formats a SHA-1 digest as a signed big-endian number in hexadecimal,
+/// the way Java's `BigInteger.toString(16)` does.
+fn minecraft_hex_digest(mut digest: [u8; 20]) -> String {
+    let negative = digest[0] & 0x80 != 0;
+    if negative {
+        let mut carry = true;
+        for byte in digest.iter_mut().rev() {
+            *byte = !*byte;
+            if carry {
+                (*byte, carry) = byte.overflowing_add(1);
+            }
+        }
+    }
+
+    let hex = digest.iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{byte:02x}");
+        hex
+    });
+    let hex = hex.trim_start_matches('0');
+    if negative {
+        format!("-{hex}")
+    } else {
+        hex.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex_digest(name: &str) -> String {
+        minecraft_hex_digest(Sha1::digest(name.as_bytes()).into())
+    }
+
+    #[test]
+    fn test_minecraft_hex_digest() {
+        assert_eq!(
+            hex_digest("Notch"),
+            "4ed1f46bbe04bc756bcb17c0c7ce3e4632f06a48"
+        );
+        assert_eq!(
+            hex_digest("jeb_"),
+            "-7c9d5b0044c130109a5d7b5fb5c317c02b4e28c1"
+        );
+        assert_eq!(
+            hex_digest("simon"),
+            "88e16a1019277b15d58faf0541e11910eb756f6"
+        );
+    }
+
+    #[test]
+    fn test_decrypt_what_the_client_encrypted() {
+        // Given
+        let online_mode = OnlineMode::new(String::new()).unwrap();
+        let public_key = RsaPublicKey::from(&online_mode.private_key);
+        let encrypted = public_key
+            .encrypt(&mut OsRng, Pkcs1v15Encrypt, &[1, 2, 3, 4])
+            .unwrap();
+
+        // When
+        let decrypted = online_mode.decrypt(&encrypted).unwrap();
+
+        // Then
+        assert_eq!(decrypted, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_verify_token_signature() {
+        // Given
+        let profile_key = RsaPrivateKey::new(&mut OsRng, KEY_SIZE).unwrap();
+        let profile_public_key = RsaPublicKey::from(&profile_key)
+            .to_public_key_der()
+            .unwrap()
+            .into_vec();
+
+        // When
+        let signature = sign_verify_token(&profile_key, &[1, 2, 3, 4], 42);
+
+        // Then
+        assert!(verify_token_signature(
+            &profile_public_key,
+            &[1, 2, 3, 4],
+            42,
+            &signature
+        ));
+        assert!(!verify_token_signature(
+            &profile_public_key,
+            &[1, 2, 3, 4],
+            43,
+            &signature
+        ));
+        assert!(!verify_token_signature(&[], &[1, 2, 3, 4], 42, &signature));
+    }
+}
@@ -0,0 +1,228 @@
+use crate::configuration::session_timeouts::{
+    AfkConfig, SessionLimitConfig, TimeoutActionConfig, WarningDisplayConfig,
+};
+use crate::server_state::ServerStateBuilderError;
+use crate::server_state::backend_transfer::proxy_connect_payload;
+use pico_text_component::prelude::{Component, MiniMessageError, parse_mini_message};
+use std::time::Duration;
+
+#[allow(clippy::literal_string_with_formatting_args)]
+const SECONDS_PLACEHOLDER: &str = "{seconds}";
+
+/// Kicks or transfers a player once a duration elapsed, after an optional warning.
+pub struct SessionTimeout {
+    pub after: Duration,
+    pub warning: Option<TimeoutWarning>,
+    pub transfer: Option<TimeoutTransfer>,
+    pub kick_message: Component,
+}
+
+pub struct TimeoutWarning {
+    pub before: Duration,
+    pub display: WarningDisplay,
+    message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WarningDisplay {
+    Chat,
+    Title,
+}
+
+pub struct TimeoutTransfer {
+    pub host: String,
+    pub port: u16,
+    /// Payload of the `BungeeCord` `Connect` message moving the player to the proxy server.
+    pub proxy_connect: Option<Vec<u8>>,
+}
+
+/// What is due once some time elapsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeoutStep {
+    /// Warns the player, with the time left.
+    Warn(Duration),
+    Expire,
+}
+
+/// Settings shared by the AFK timeout and the session limit.
+struct TimeoutSettings {
+    section: &'static str,
+    after_seconds: u64,
+    warning_seconds: u64,
+    warning_display: WarningDisplayConfig,
+    warning_message: String,
+    action: TimeoutActionConfig,
+    kick_message: String,
+    host: String,
+    port: u16,
+    proxy_server: Option<String>,
+}
+
+impl SessionTimeout {
+    pub fn afk(config: AfkConfig) -> Result<Self, ServerStateBuilderError> {
+        Self::from_settings(TimeoutSettings {
+            section: "afk",
+            after_seconds: config.timeout_seconds,
+            warning_seconds: config.warning_seconds,
+            warning_display: config.warning_display,
+            warning_message: config.warning_message,
+            action: config.action,
+            kick_message: config.kick_message,
+            host: config.host,
+            port: config.port,
+            proxy_server: config.proxy_server,
+        })
+    }
+
+    pub fn session_limit(config: SessionLimitConfig) -> Result<Self, ServerStateBuilderError> {
+        Self::from_settings(TimeoutSettings {
+            section: "session_limit",
+            after_seconds: config.max_duration_seconds,
+            warning_seconds: config.warning_seconds,
+            warning_display: config.warning_display,
+            warning_message: config.warning_message,
+            action: config.action,
+            kick_message: config.kick_message,
+            host: config.host,
+            port: config.port,
+            proxy_server: config.proxy_server,
+        })
+    }
+
+    fn from_settings(settings: TimeoutSettings) -> Result<Self, ServerStateBuilderError> {
+        let invalid = |message: &str| {
+            ServerStateBuilderError::InvalidSessionTimeout(format!(
+                "{}: {message}",
+                settings.section
+            ))
+        };
+        if settings.after_seconds == 0 {
+            return Err(invalid("the duration must be positive"));
+        }
+        if settings.warning_seconds >= settings.after_seconds {
+            return Err(invalid("warning_seconds must be shorter than the duration"));
+        }
+
+        let warning = if settings.warning_seconds == 0 {
+            None
+        } else {
+            // Fails early on an invalid message rather than on every warning
+            parse_mini_message(&settings.warning_message)?;
+            Some(TimeoutWarning {
+                before: Duration::from_secs(settings.warning_seconds),
+                display: match settings.warning_display {
+                    WarningDisplayConfig::Chat => WarningDisplay::Chat,
+                    WarningDisplayConfig::Title => WarningDisplay::Title,
+                },
+                message: settings.warning_message,
+            })
+        };
+        let transfer = match settings.action {
+            TimeoutActionConfig::Kick => None,
+            TimeoutActionConfig::Transfer => Some(TimeoutTransfer {
+                proxy_connect: settings
+                    .proxy_server
+                    .as_deref()
+                    .map(proxy_connect_payload)
+                    .transpose()?,
+                host: settings.host,
+                port: settings.port,
+            }),
+        };
+
+        Ok(Self {
+            after: Duration::from_secs(settings.after_seconds),
+            warning,
+            transfer,
+            kick_message: parse_mini_message(&settings.kick_message)?,
+        })
+    }
+
+    /// What is due after the given time elapsed, the warning is only due when not already displayed.
+    pub fn step(&self, elapsed: Duration, warned: bool) -> Option<TimeoutStep> {
+        let Some(remaining) = self
+            .after
+            .checked_sub(elapsed)
+            .filter(|remaining| !remaining.is_zero())
+        else {
+            return Some(TimeoutStep::Expire);
+        };
+        self.warning
+            .as_ref()
+            .filter(|warning| !warned && remaining <= warning.before)
+            .map(|_| TimeoutStep::Warn(remaining))
+    }
+}
+
+impl TimeoutWarning {
+    /// Message displayed to the player, the time left is rounded up to the second.
+    pub fn message(&self, remaining: Duration) -> Result<Component, MiniMessageError> {
+        let seconds = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+        parse_mini_message(
+            &self
+                .message
+                .replace(SECONDS_PLACEHOLDER, &seconds.to_string()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_warns_once_then_expires() {
+        // Given
+        let timeout = SessionTimeout::afk(AfkConfig {
+            timeout_seconds: 60,
+            warning_seconds: 10,
+            ..AfkConfig::default()
+        })
+        .unwrap();
+
+        // When
+        let idle = timeout.step(Duration::from_secs(45), false);
+        let warned = timeout.step(Duration::from_millis(50_500), false);
+        let already_warned = timeout.step(Duration::from_secs(55), true);
+        let expired = timeout.step(Duration::from_mins(1), true);
+
+        // Then
+        assert_eq!(idle, None);
+        assert_eq!(
+            warned,
+            Some(TimeoutStep::Warn(Duration::from_millis(9_500)))
+        );
+        assert_eq!(already_warned, None);
+        assert_eq!(expired, Some(TimeoutStep::Expire));
+        assert!(
+            timeout
+                .warning
+                .unwrap()
+                .message(Duration::from_millis(9_500))
+                .unwrap()
+                == parse_mini_message(
+                    "<yellow>You are idle and will be disconnected in 10 seconds.</yellow>"
+                )
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_warning_must_be_shorter_than_the_limit() {
+        // Given
+        let config = SessionLimitConfig {
+            max_duration_seconds: 60,
+            warning_seconds: 60,
+            ..SessionLimitConfig::default()
+        };
+
+        // When
+        let result = SessionTimeout::session_limit(config);
+
+        // Then
+        assert!(matches!(
+            result,
+            Err(ServerStateBuilderError::InvalidSessionTimeout(_))
+        ));
+    }
+}